@@ -0,0 +1,758 @@
+//! Multi-head attention, positional encodings and Transformer encoder blocks over
+//! `[batch, time, features]` sequences.
+
+use crate::error::ComputeError;
+use crate::graph::Graph;
+use crate::init::Init;
+use crate::layers::{Layer, Linear};
+use crate::module::Module;
+use crate::norm::LayerNorm;
+use crate::ops::{AddOp, Op, ReluOp};
+use crate::shape::{self, Dim};
+use crate::slicing::{ConcatOp, NarrowOp};
+use crate::tensor::Tensor;
+
+/// Softmax attention `softmax(q k^T / sqrt(d)) v`, batched over the leading axis.
+///
+/// Inputs are `[q, k, v]` or `[q, k, v, key_mask]` with q `[batch, tq, d]`, k
+/// `[batch, tk, d]`, v `[batch, tk, dv]` and an optional key mask `[batch, tk]` whose
+/// non-zero entries mark real (non-padding) keys. The output is `[batch, tq, dv]`.
+///
+/// With `causal`, query `i` only sees keys up to `i + tk - tq`, i.e. the present and past
+/// when queries and keys cover the same steps. Hidden keys get exactly zero weight; a query
+/// that sees no key at all outputs zeros. The mask receives no gradient.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ScaledDotProductAttentionOp {
+    pub causal: bool,
+}
+
+/// Sizes of one attention call.
+struct AttentionDims {
+    batch: usize,
+    tq: usize,
+    tk: usize,
+    d: usize,
+    dv: usize,
+}
+
+impl ScaledDotProductAttentionOp {
+    fn dims(&self, inputs: &[Tensor]) -> Result<AttentionDims, ComputeError> {
+        if inputs.len() != 3 && inputs.len() != 4 {
+            return Err(ComputeError::InputCountError {
+                expected: 4,
+                got: inputs.len(),
+            });
+        }
+        let (q, k, v) = (inputs[0].shape(), inputs[1].shape(), inputs[2].shape());
+        let valid = matches!((q, k, v), ([b, _, d], [b2, tk, d2], [b3, tk2, _])
+            if b == b2 && b == b3 && d == d2 && tk == tk2);
+        if !valid {
+            return Err(ComputeError::DimensionError {
+                message: format!(
+                    "attention expects q [batch, tq, d], k [batch, tk, d], v [batch, tk, dv], got {q:?}, {k:?}, {v:?}"
+                ),
+            });
+        }
+        let dims = AttentionDims {
+            batch: q[0],
+            tq: q[1],
+            tk: k[1],
+            d: q[2],
+            dv: v[2],
+        };
+        if let Some(mask) = inputs.get(3) {
+            if mask.shape() != [dims.batch, dims.tk] {
+                return Err(ComputeError::DimensionError {
+                    message: format!(
+                        "key mask must be [{}, {}], got {:?}",
+                        dims.batch,
+                        dims.tk,
+                        mask.shape()
+                    ),
+                });
+            }
+        }
+        Ok(dims)
+    }
+
+    fn visible(
+        &self,
+        inputs: &[Tensor],
+        dims: &AttentionDims,
+        b: usize,
+        i: usize,
+        j: usize,
+    ) -> bool {
+        let causal_ok = !self.causal || j + dims.tq <= i + dims.tk;
+        let key_ok = inputs
+            .get(3)
+            .is_none_or(|mask| mask.data()[b * dims.tk + j] != 0.0);
+        causal_ok && key_ok
+    }
+
+    /// Attention weights `[batch, tq, tk]`, zero for hidden keys.
+    fn weights(&self, inputs: &[Tensor], dims: &AttentionDims) -> Vec<f32> {
+        let (q, k) = (inputs[0].data(), inputs[1].data());
+        let scale = 1.0 / (dims.d as f32).sqrt();
+        let mut weights = vec![0.0; dims.batch * dims.tq * dims.tk];
+        for b in 0..dims.batch {
+            for i in 0..dims.tq {
+                let row = &mut weights[(b * dims.tq + i) * dims.tk..][..dims.tk];
+                let qi = &q[(b * dims.tq + i) * dims.d..][..dims.d];
+                let mut max = f32::NEG_INFINITY;
+                for (j, w) in row.iter_mut().enumerate() {
+                    if !self.visible(inputs, dims, b, i, j) {
+                        *w = f32::NEG_INFINITY;
+                        continue;
+                    }
+                    let kj = &k[(b * dims.tk + j) * dims.d..][..dims.d];
+                    *w = qi.iter().zip(kj).map(|(a, c)| a * c).sum::<f32>() * scale;
+                    max = max.max(*w);
+                }
+                if max == f32::NEG_INFINITY {
+                    row.fill(0.0);
+                    continue;
+                }
+                let mut sum = 0.0;
+                for w in row.iter_mut() {
+                    *w = (*w - max).exp();
+                    sum += *w;
+                }
+                for w in row.iter_mut() {
+                    *w /= sum;
+                }
+            }
+        }
+        weights
+    }
+}
+
+impl Op for ScaledDotProductAttentionOp {
+    fn forward(&self, inputs: &[Tensor]) -> Result<Tensor, ComputeError> {
+        let dims = self.dims(inputs)?;
+        let weights = self.weights(inputs, &dims);
+        let v = inputs[2].data();
+        let mut out = vec![0.0; dims.batch * dims.tq * dims.dv];
+        for b in 0..dims.batch {
+            for i in 0..dims.tq {
+                let o = &mut out[(b * dims.tq + i) * dims.dv..][..dims.dv];
+                for j in 0..dims.tk {
+                    let p = weights[(b * dims.tq + i) * dims.tk + j];
+                    if p == 0.0 {
+                        continue;
+                    }
+                    for (o, v) in o
+                        .iter_mut()
+                        .zip(&v[(b * dims.tk + j) * dims.dv..][..dims.dv])
+                    {
+                        *o += p * v;
+                    }
+                }
+            }
+        }
+        Tensor::new(out, vec![dims.batch, dims.tq, dims.dv])
+    }
+
+    fn backward(
+        &self,
+        inputs: &[Tensor],
+        grad_output: &Tensor,
+    ) -> Result<Vec<Tensor>, ComputeError> {
+        let dims = self.dims(inputs)?;
+        let weights = self.weights(inputs, &dims);
+        let (q, k, v, g) = (
+            inputs[0].data(),
+            inputs[1].data(),
+            inputs[2].data(),
+            grad_output.data(),
+        );
+        let scale = 1.0 / (dims.d as f32).sqrt();
+        let mut gq = vec![0.0; q.len()];
+        let mut gk = vec![0.0; k.len()];
+        let mut gv = vec![0.0; v.len()];
+        let mut gp = vec![0.0; dims.tk];
+        for b in 0..dims.batch {
+            for i in 0..dims.tq {
+                let row = (b * dims.tq + i) * dims.tk;
+                let gi = &g[(b * dims.tq + i) * dims.dv..][..dims.dv];
+                // dP_j = dO_i . v_j and dV_j += p_j dO_i
+                for j in 0..dims.tk {
+                    let vj = (b * dims.tk + j) * dims.dv;
+                    gp[j] = gi
+                        .iter()
+                        .zip(&v[vj..vj + dims.dv])
+                        .map(|(a, c)| a * c)
+                        .sum();
+                    let p = weights[row + j];
+                    for (gv, gi) in gv[vj..vj + dims.dv].iter_mut().zip(gi) {
+                        *gv += p * gi;
+                    }
+                }
+                // Softmax backward: dS_j = p_j (dP_j - sum_l p_l dP_l)
+                let dot: f32 = (0..dims.tk).map(|j| weights[row + j] * gp[j]).sum();
+                let qi = (b * dims.tq + i) * dims.d;
+                for j in 0..dims.tk {
+                    let ds = weights[row + j] * (gp[j] - dot) * scale;
+                    if ds == 0.0 {
+                        continue;
+                    }
+                    let kj = (b * dims.tk + j) * dims.d;
+                    for c in 0..dims.d {
+                        gq[qi + c] += ds * k[kj + c];
+                        gk[kj + c] += ds * q[qi + c];
+                    }
+                }
+            }
+        }
+        let mut grads = vec![
+            Tensor::new(gq, inputs[0].shape().to_vec())?,
+            Tensor::new(gk, inputs[1].shape().to_vec())?,
+            Tensor::new(gv, inputs[2].shape().to_vec())?,
+        ];
+        if let Some(mask) = inputs.get(3) {
+            grads.push(Tensor::zeros_like(mask)?);
+        }
+        Ok(grads)
+    }
+
+    fn infer_shape(&self, inputs: &[Vec<Dim>]) -> Result<Vec<Dim>, ComputeError> {
+        if inputs.len() != 3 && inputs.len() != 4 {
+            return Err(ComputeError::InputCountError {
+                expected: 4,
+                got: inputs.len(),
+            });
+        }
+        let (q, k, v) = (&inputs[0], &inputs[1], &inputs[2]);
+        if q.len() != 3 || k.len() != 3 || v.len() != 3 {
+            return Err(ComputeError::DimensionError {
+                message: format!(
+                    "attention expects rank 3 q, k and v, got {}, {} and {}",
+                    shape::format_shape(q),
+                    shape::format_shape(k),
+                    shape::format_shape(v)
+                ),
+            });
+        }
+        let batch = shape::unify(&shape::unify(&q[0], &k[0])?, &v[0])?;
+        shape::unify(&q[2], &k[2])?;
+        let tk = shape::unify(&k[1], &v[1])?;
+        if let Some(mask) = inputs.get(3) {
+            if mask.len() != 2 {
+                return Err(ComputeError::DimensionError {
+                    message: format!("key mask must be 2D, got {}", shape::format_shape(mask)),
+                });
+            }
+            shape::unify(&mask[0], &batch)?;
+            shape::unify(&mask[1], &tk)?;
+        }
+        Ok(vec![batch, q[1].clone(), v[2].clone()])
+    }
+
+    fn name(&self) -> &str {
+        "ScaledDotProductAttentionOp"
+    }
+
+    fn attributes(&self) -> Option<Vec<(&'static str, String)>> {
+        Some(vec![("causal", self.causal.to_string())])
+    }
+}
+
+/// Which keys each query may attend to.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct AttentionMask {
+    /// Hide future steps (see `ScaledDotProductAttentionOp`).
+    pub causal: bool,
+    /// Node of a `[batch, time]` tensor, non-zero for real steps and zero for padding.
+    pub key_padding: Option<usize>,
+}
+
+/// Multi-head attention: project queries, keys and values, attend in `num_heads` slices of
+/// `d_model / num_heads` features, then concatenate the heads and project back.
+pub struct MultiHeadAttention {
+    q_proj: Linear,
+    k_proj: Linear,
+    v_proj: Linear,
+    out_proj: Linear,
+    pub d_model: usize,
+    pub num_heads: usize,
+}
+
+impl MultiHeadAttention {
+    /// Four `d_model x d_model` projections initialized like `Linear::new`, seeded from
+    /// `seed` onwards.
+    pub fn new(
+        graph: &mut Graph,
+        d_model: usize,
+        num_heads: usize,
+        seed: u32,
+    ) -> Result<Self, ComputeError> {
+        let k = 1.0 / (d_model as f32).sqrt();
+        Self::with_init(
+            graph,
+            d_model,
+            num_heads,
+            &Init::Uniform { low: -k, high: k },
+            &Init::Constant(0.0),
+            seed,
+        )
+    }
+
+    /// Projections built with `Linear::with_init`, seeded from `seed` onwards.
+    pub fn with_init(
+        graph: &mut Graph,
+        d_model: usize,
+        num_heads: usize,
+        weight_init: &Init,
+        bias_init: &Init,
+        seed: u32,
+    ) -> Result<Self, ComputeError> {
+        if num_heads == 0 || !d_model.is_multiple_of(num_heads) {
+            return Err(ComputeError::InvalidOperation {
+                message: format!("{num_heads} heads must divide model width {d_model}"),
+            });
+        }
+        let mut proj = |offset| {
+            let seed = seed.wrapping_add(offset);
+            Linear::with_init(graph, d_model, d_model, weight_init, bias_init, seed)
+        };
+        Ok(Self {
+            q_proj: proj(0)?,
+            k_proj: proj(1)?,
+            v_proj: proj(2)?,
+            out_proj: proj(3)?,
+            d_model,
+            num_heads,
+        })
+    }
+
+    /// Attend from `query` (`[batch, tq, d_model]`) to `key` and `value`
+    /// (`[batch, tk, d_model]`).
+    pub fn attend(
+        &self,
+        graph: &mut Graph,
+        query: usize,
+        key: usize,
+        value: usize,
+        mask: &AttentionMask,
+    ) -> Result<usize, ComputeError> {
+        let q = self.q_proj.forward_batched(graph, query);
+        let k = self.k_proj.forward_batched(graph, key);
+        let v = self.v_proj.forward_batched(graph, value);
+        let head_dim = self.d_model / self.num_heads;
+        let op = ScaledDotProductAttentionOp {
+            causal: mask.causal,
+        };
+        let heads: Vec<usize> = (0..self.num_heads)
+            .map(|h| {
+                let slice = NarrowOp {
+                    axis: 2,
+                    start: h * head_dim,
+                    len: head_dim,
+                };
+                let mut args = vec![
+                    graph.apply_op(slice, &[q]),
+                    graph.apply_op(slice, &[k]),
+                    graph.apply_op(slice, &[v]),
+                ];
+                args.extend(mask.key_padding);
+                graph.apply_op(op, &args)
+            })
+            .collect();
+        let merged = match heads[..] {
+            [only] => only,
+            _ => graph.apply_op(ConcatOp { axis: 2 }, &heads),
+        };
+        Ok(self.out_proj.forward_batched(graph, merged))
+    }
+}
+
+impl Module for MultiHeadAttention {
+    fn children(&self) -> Vec<(String, &dyn Module)> {
+        vec![
+            ("q_proj".to_string(), &self.q_proj as &dyn Module),
+            ("k_proj".to_string(), &self.k_proj),
+            ("v_proj".to_string(), &self.v_proj),
+            ("out_proj".to_string(), &self.out_proj),
+        ]
+    }
+}
+
+impl Layer for MultiHeadAttention {
+    /// Unmasked self-attention.
+    fn forward(&self, graph: &mut Graph, input_idx: usize) -> Result<usize, ComputeError> {
+        self.attend(
+            graph,
+            input_idx,
+            input_idx,
+            input_idx,
+            &AttentionMask::default(),
+        )
+    }
+}
+
+/// Sinusoidal features of real-valued timestamps: `[batch, time]` to
+/// `[batch, time, dim]`.
+///
+/// Feature pair `i` is `sin(t * f_i), cos(t * f_i)` with frequencies
+/// `f_i = max_period^(-2i / dim)`, so periods range from `2 * pi` to about
+/// `2 * pi * max_period` timestamp units. Timestamps need not be evenly spaced, which suits
+/// irregularly sampled machine data; measure them relative to the window start so the
+/// values stay small.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SinusoidalEncodingOp {
+    pub dim: usize,
+    pub max_period: f32,
+}
+
+impl SinusoidalEncodingOp {
+    fn frequencies(&self) -> Result<Vec<f32>, ComputeError> {
+        if self.dim == 0 || !self.dim.is_multiple_of(2) {
+            return Err(ComputeError::InvalidOperation {
+                message: format!("sinusoidal encoding width {} must be even", self.dim),
+            });
+        }
+        Ok((0..self.dim / 2)
+            .map(|i| self.max_period.powf(-2.0 * i as f32 / self.dim as f32))
+            .collect())
+    }
+
+    fn timestamps<'a>(&self, inputs: &'a [Tensor]) -> Result<&'a Tensor, ComputeError> {
+        match inputs {
+            [t] if t.shape().len() == 2 => Ok(t),
+            [t] => Err(ComputeError::DimensionError {
+                message: format!("timestamps must be [batch, time], got {:?}", t.shape()),
+            }),
+            _ => Err(ComputeError::InputCountError {
+                expected: 1,
+                got: inputs.len(),
+            }),
+        }
+    }
+}
+
+impl Op for SinusoidalEncodingOp {
+    fn forward(&self, inputs: &[Tensor]) -> Result<Tensor, ComputeError> {
+        let t = self.timestamps(inputs)?;
+        let freqs = self.frequencies()?;
+        let mut out = Vec::with_capacity(t.data().len() * self.dim);
+        for &time in t.data() {
+            for f in &freqs {
+                out.push((time * f).sin());
+                out.push((time * f).cos());
+            }
+        }
+        let mut shape = t.shape().to_vec();
+        shape.push(self.dim);
+        Tensor::new(out, shape)
+    }
+
+    fn backward(
+        &self,
+        inputs: &[Tensor],
+        grad_output: &Tensor,
+    ) -> Result<Vec<Tensor>, ComputeError> {
+        let t = self.timestamps(inputs)?;
+        let freqs = self.frequencies()?;
+        let grad: Vec<f32> = t
+            .data()
+            .iter()
+            .zip(grad_output.data().chunks(self.dim))
+            .map(|(&time, g)| {
+                freqs
+                    .iter()
+                    .zip(g.chunks(2))
+                    .map(|(f, g)| f * (g[0] * (time * f).cos() - g[1] * (time * f).sin()))
+                    .sum()
+            })
+            .collect();
+        Ok(vec![Tensor::new(grad, t.shape().to_vec())?])
+    }
+
+    fn infer_shape(&self, inputs: &[Vec<Dim>]) -> Result<Vec<Dim>, ComputeError> {
+        shape::expect_inputs(inputs, 1)?;
+        if inputs[0].len() != 2 {
+            return Err(ComputeError::DimensionError {
+                message: format!(
+                    "timestamps must be [batch, time], got {}",
+                    shape::format_shape(&inputs[0])
+                ),
+            });
+        }
+        self.frequencies()?;
+        let mut out = inputs[0].clone();
+        out.push(Dim::Fixed(self.dim));
+        Ok(out)
+    }
+
+    fn name(&self) -> &str {
+        "SinusoidalEncodingOp"
+    }
+
+    fn attributes(&self) -> Option<Vec<(&'static str, String)>> {
+        Some(vec![
+            ("dim", self.dim.to_string()),
+            ("max_period", self.max_period.to_string()),
+        ])
+    }
+}
+
+/// Evenly spaced positions `0, 1, ..., time - 1` for each of `batch` sequences, for use as
+/// `SinusoidalEncodingOp` timestamps when the samples are regular.
+pub fn positions(batch: usize, time: usize) -> Result<Tensor, ComputeError> {
+    let row = (0..time).map(|t| t as f32);
+    Tensor::new(
+        (0..batch).flat_map(|_| row.clone()).collect(),
+        vec![batch, time],
+    )
+}
+
+/// Adds `table[t]` to step `t` of a `[batch, time, dim]` input, for a `[max_len, dim]`
+/// table of learned position embeddings. Inputs are `[x, table]`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct AddPositionsOp;
+
+impl AddPositionsOp {
+    fn check(inputs: &[Tensor]) -> Result<(usize, usize), ComputeError> {
+        let [x, table] = inputs else {
+            return Err(ComputeError::InputCountError {
+                expected: 2,
+                got: inputs.len(),
+            });
+        };
+        match (x.shape(), table.shape()) {
+            ([_, time, dim], [max_len, table_dim]) if dim == table_dim && time <= max_len => {
+                Ok((*time, *dim))
+            }
+            (x, table) => Err(ComputeError::DimensionError {
+                message: format!(
+                    "positions need x [batch, time, dim] and a table [max_len >= time, dim], got {x:?} and {table:?}"
+                ),
+            }),
+        }
+    }
+}
+
+impl Op for AddPositionsOp {
+    fn forward(&self, inputs: &[Tensor]) -> Result<Tensor, ComputeError> {
+        let (time, dim) = Self::check(inputs)?;
+        let table = &inputs[1].data()[..time * dim];
+        let mut out = inputs[0].clone();
+        for step in out.data_mut().chunks_mut(time * dim) {
+            for (v, p) in step.iter_mut().zip(table) {
+                *v += p;
+            }
+        }
+        Ok(out)
+    }
+
+    fn backward(
+        &self,
+        inputs: &[Tensor],
+        grad_output: &Tensor,
+    ) -> Result<Vec<Tensor>, ComputeError> {
+        let (time, dim) = Self::check(inputs)?;
+        let mut table = Tensor::zeros_like(&inputs[1])?;
+        for sequence in grad_output.data().chunks(time * dim) {
+            for (t, g) in table.data_mut().iter_mut().zip(sequence) {
+                *t += g;
+            }
+        }
+        Ok(vec![grad_output.clone(), table])
+    }
+
+    fn infer_shape(&self, inputs: &[Vec<Dim>]) -> Result<Vec<Dim>, ComputeError> {
+        shape::expect_inputs(inputs, 2)?;
+        let (x, table) = (&inputs[0], &inputs[1]);
+        if x.len() != 3 || table.len() != 2 {
+            return Err(ComputeError::DimensionError {
+                message: format!(
+                    "positions need x [batch, time, dim] and a table [max_len, dim], got {} and {}",
+                    shape::format_shape(x),
+                    shape::format_shape(table)
+                ),
+            });
+        }
+        if let (Dim::Fixed(time), Dim::Fixed(max_len)) = (&x[1], &table[0]) {
+            if time > max_len {
+                return Err(ComputeError::DimensionError {
+                    message: format!("sequence length {time} exceeds {max_len} learned positions"),
+                });
+            }
+        }
+        let mut out = x.clone();
+        out[2] = shape::unify(&x[2], &table[1])?;
+        Ok(out)
+    }
+
+    fn name(&self) -> &str {
+        "AddPositionsOp"
+    }
+
+    fn attributes(&self) -> Option<Vec<(&'static str, String)>> {
+        Some(Vec::new())
+    }
+}
+
+/// A learned `[max_len, dim]` embedding per step index, added to the input.
+pub struct LearnedPositionalEncoding {
+    table_idx: usize,
+    pub max_len: usize,
+    pub dim: usize,
+}
+
+impl LearnedPositionalEncoding {
+    /// Embeddings drawn from a normal distribution with std 0.02.
+    pub fn new(
+        graph: &mut Graph,
+        max_len: usize,
+        dim: usize,
+        seed: u32,
+    ) -> Result<Self, ComputeError> {
+        let init = Init::Normal {
+            mean: 0.0,
+            std: 0.02,
+        };
+        let table_idx = graph.add_parameter(init.tensor(vec![max_len, dim], seed)?, true);
+        Ok(Self {
+            table_idx,
+            max_len,
+            dim,
+        })
+    }
+}
+
+impl Module for LearnedPositionalEncoding {
+    fn own_parameters(&self) -> Vec<(String, usize)> {
+        vec![("weight".to_string(), self.table_idx)]
+    }
+}
+
+impl Layer for LearnedPositionalEncoding {
+    fn forward(&self, graph: &mut Graph, input_idx: usize) -> Result<usize, ComputeError> {
+        Ok(graph.apply_op(AddPositionsOp, &[input_idx, self.table_idx]))
+    }
+}
+
+/// One Transformer encoder block: self-attention and a ReLU feed-forward network, each
+/// wrapped in a residual connection and layer normalization.
+///
+/// By default normalization follows each residual sum (`x = norm(x + f(x))`, the original
+/// post-norm layout); with `norm_first` it precedes each sublayer
+/// (`x = x + f(norm(x))`), which tends to train more stably in deep stacks.
+pub struct TransformerEncoderLayer {
+    self_attn: MultiHeadAttention,
+    linear1: Linear,
+    linear2: Linear,
+    norm1: LayerNorm,
+    norm2: LayerNorm,
+    pub norm_first: bool,
+}
+
+impl TransformerEncoderLayer {
+    /// A post-norm block of width `d_model` with a `ff_dim` hidden layer, seeded from `seed`
+    /// onwards.
+    pub fn new(
+        graph: &mut Graph,
+        d_model: usize,
+        num_heads: usize,
+        ff_dim: usize,
+        seed: u32,
+    ) -> Result<Self, ComputeError> {
+        Ok(Self {
+            self_attn: MultiHeadAttention::new(graph, d_model, num_heads, seed)?,
+            linear1: Linear::new(graph, d_model, ff_dim, seed.wrapping_add(4))?,
+            linear2: Linear::new(graph, ff_dim, d_model, seed.wrapping_add(5))?,
+            norm1: LayerNorm::new(graph, d_model)?,
+            norm2: LayerNorm::new(graph, d_model)?,
+            norm_first: false,
+        })
+    }
+
+    /// A post-norm block whose attention and feed-forward weights and biases are drawn from
+    /// `weight_init` and `bias_init`, seeded from `seed` onwards. Layer norms start at the
+    /// identity as in `new`.
+    #[allow(clippy::too_many_arguments)]
+    pub fn with_init(
+        graph: &mut Graph,
+        d_model: usize,
+        num_heads: usize,
+        ff_dim: usize,
+        weight_init: &Init,
+        bias_init: &Init,
+        seed: u32,
+    ) -> Result<Self, ComputeError> {
+        let linear = |graph: &mut Graph, input, output, offset| {
+            let seed = seed.wrapping_add(offset);
+            Linear::with_init(graph, input, output, weight_init, bias_init, seed)
+        };
+        Ok(Self {
+            self_attn: MultiHeadAttention::with_init(
+                graph,
+                d_model,
+                num_heads,
+                weight_init,
+                bias_init,
+                seed,
+            )?,
+            linear1: linear(graph, d_model, ff_dim, 4)?,
+            linear2: linear(graph, ff_dim, d_model, 5)?,
+            norm1: LayerNorm::new(graph, d_model)?,
+            norm2: LayerNorm::new(graph, d_model)?,
+            norm_first: false,
+        })
+    }
+
+    pub fn self_attn(&self) -> &MultiHeadAttention {
+        &self.self_attn
+    }
+
+    fn feed_forward(&self, graph: &mut Graph, x: usize) -> usize {
+        let hidden = self.linear1.forward_batched(graph, x);
+        let hidden = graph.apply_op(ReluOp, &[hidden]);
+        self.linear2.forward_batched(graph, hidden)
+    }
+
+    /// Encode a `[batch, time, d_model]` sequence under `mask`.
+    pub fn forward_masked(
+        &self,
+        graph: &mut Graph,
+        input_idx: usize,
+        mask: &AttentionMask,
+    ) -> Result<usize, ComputeError> {
+        let x = input_idx;
+        if self.norm_first {
+            let normed = self.norm1.forward(graph, x)?;
+            let attended = self.self_attn.attend(graph, normed, normed, normed, mask)?;
+            let x = graph.apply_op(AddOp, &[x, attended]);
+            let normed = self.norm2.forward(graph, x)?;
+            let ff = self.feed_forward(graph, normed);
+            Ok(graph.apply_op(AddOp, &[x, ff]))
+        } else {
+            let attended = self.self_attn.attend(graph, x, x, x, mask)?;
+            let sum = graph.apply_op(AddOp, &[x, attended]);
+            let x = self.norm1.forward(graph, sum)?;
+            let ff = self.feed_forward(graph, x);
+            let sum = graph.apply_op(AddOp, &[x, ff]);
+            self.norm2.forward(graph, sum)
+        }
+    }
+}
+
+impl Module for TransformerEncoderLayer {
+    fn children(&self) -> Vec<(String, &dyn Module)> {
+        vec![
+            ("self_attn".to_string(), &self.self_attn as &dyn Module),
+            ("linear1".to_string(), &self.linear1),
+            ("linear2".to_string(), &self.linear2),
+            ("norm1".to_string(), &self.norm1),
+            ("norm2".to_string(), &self.norm2),
+        ]
+    }
+}
+
+impl Layer for TransformerEncoderLayer {
+    /// Unmasked encoding.
+    fn forward(&self, graph: &mut Graph, input_idx: usize) -> Result<usize, ComputeError> {
+        self.forward_masked(graph, input_idx, &AttentionMask::default())
+    }
+}
@@ -0,0 +1,444 @@
+//! Control-flow ops whose bodies are `MacroOp` subgraphs.
+//!
+//! - `CondOp` evaluates one of two branches depending on a scalar predicate.
+//! - `WhileOp` repeats a body on a carried state while a condition holds.
+//! - `ScanOp` applies a cell along one axis of a sequence, carrying state between steps.
+//!
+//! All three differentiate through the steps they actually took, so `WhileOp` and `ScanOp`
+//! implement backpropagation through time.
+
+use crate::custom::MacroOp;
+use crate::error::ComputeError;
+use crate::ops::{self, Op, OpContext};
+use crate::shape::{self, Dim};
+use crate::tensor::Tensor;
+
+fn expect_inputs(inputs: usize, expected: usize) -> Result<(), ComputeError> {
+    if inputs != expected {
+        return Err(ComputeError::InputCountError {
+            expected,
+            got: inputs,
+        });
+    }
+    Ok(())
+}
+
+fn expect_arity(what: &str, op: &MacroOp, expected: usize) -> Result<(), ComputeError> {
+    if op.arity() != expected {
+        return Err(ComputeError::InvalidOperation {
+            message: format!("{what} takes {} inputs, expected {expected}", op.arity()),
+        });
+    }
+    Ok(())
+}
+
+fn unify_shapes(a: &[Dim], b: &[Dim]) -> Result<Vec<Dim>, ComputeError> {
+    if a.len() != b.len() {
+        return Err(ComputeError::DimensionError {
+            message: format!(
+                "shapes {} and {} differ in rank",
+                shape::format_shape(a),
+                shape::format_shape(b)
+            ),
+        });
+    }
+    a.iter().zip(b).map(|(x, y)| shape::unify(x, y)).collect()
+}
+
+fn accumulate(total: &mut [Tensor], grads: &[Tensor]) -> Result<(), ComputeError> {
+    for (t, g) in total.iter_mut().zip(grads) {
+        *t = t.add(g)?;
+    }
+    Ok(())
+}
+
+/// Scalar truth value: a one-element tensor that is non-zero.
+fn truth(predicate: &Tensor) -> Result<bool, ComputeError> {
+    match predicate.data() {
+        [v] => Ok(*v != 0.0),
+        data => Err(ComputeError::ShapeMismatch {
+            expected: 1,
+            got: data.len(),
+        }),
+    }
+}
+
+/// `inputs[0]` is a one-element predicate; the remaining inputs go to `then_branch` if it
+/// is non-zero and to `else_branch` otherwise. The predicate gets a zero gradient.
+pub struct CondOp {
+    then_branch: MacroOp,
+    else_branch: MacroOp,
+}
+
+impl CondOp {
+    pub fn new(then_branch: MacroOp, else_branch: MacroOp) -> Result<Self, ComputeError> {
+        expect_arity("else branch", &else_branch, then_branch.arity())?;
+        Ok(Self {
+            then_branch,
+            else_branch,
+        })
+    }
+
+    fn branch(&self, inputs: &[Tensor]) -> Result<&MacroOp, ComputeError> {
+        expect_inputs(inputs.len(), self.then_branch.arity() + 1)?;
+        Ok(if truth(&inputs[0])? {
+            &self.then_branch
+        } else {
+            &self.else_branch
+        })
+    }
+}
+
+impl Op for CondOp {
+    fn forward(&self, inputs: &[Tensor]) -> Result<Tensor, ComputeError> {
+        self.forward_with(inputs, &OpContext::default())
+    }
+
+    fn backward(
+        &self,
+        inputs: &[Tensor],
+        grad_output: &Tensor,
+    ) -> Result<Vec<Tensor>, ComputeError> {
+        self.backward_with(inputs, grad_output, &OpContext::default())
+    }
+
+    fn forward_into_with(
+        &self,
+        inputs: &[&Tensor],
+        out: &mut Tensor,
+        ctx: &OpContext,
+    ) -> Result<(), ComputeError> {
+        ops::forward_into_owned(self, inputs, out, ctx)
+    }
+
+    fn forward_with(&self, inputs: &[Tensor], ctx: &OpContext) -> Result<Tensor, ComputeError> {
+        self.branch(inputs)?.forward_with(&inputs[1..], ctx)
+    }
+
+    fn backward_with(
+        &self,
+        inputs: &[Tensor],
+        grad_output: &Tensor,
+        ctx: &OpContext,
+    ) -> Result<Vec<Tensor>, ComputeError> {
+        let branch_grads = self
+            .branch(inputs)?
+            .backward_with(&inputs[1..], grad_output, ctx)?;
+        let mut grads = vec![Tensor::zeros_like(&inputs[0])?];
+        grads.extend(branch_grads);
+        Ok(grads)
+    }
+
+    /// Both branches must agree on the output shape.
+    fn infer_shape(&self, inputs: &[Vec<Dim>]) -> Result<Vec<Dim>, ComputeError> {
+        shape::expect_inputs(inputs, self.then_branch.arity() + 1)?;
+        let then_shape = self.then_branch.infer_shape(&inputs[1..])?;
+        let else_shape = self.else_branch.infer_shape(&inputs[1..])?;
+        unify_shapes(&then_shape, &else_shape)
+    }
+
+    fn name(&self) -> &str {
+        "CondOp"
+    }
+}
+
+/// Repeats `state = body(state, extra...)` while `condition(state, extra...)` is non-zero.
+///
+/// Inputs are `[initial_state, extra...]`; the body must preserve the state's shape. Fails
+/// if the condition still holds after `max_iterations` steps. Gradients flow back through
+/// every iteration to the initial state and the extra inputs.
+pub struct WhileOp {
+    condition: MacroOp,
+    body: MacroOp,
+    max_iterations: usize,
+}
+
+impl WhileOp {
+    pub fn new(
+        condition: MacroOp,
+        body: MacroOp,
+        max_iterations: usize,
+    ) -> Result<Self, ComputeError> {
+        if body.arity() == 0 {
+            return Err(ComputeError::InvalidOperation {
+                message: "while body must take the loop state".to_string(),
+            });
+        }
+        expect_arity("while condition", &condition, body.arity())?;
+        Ok(Self {
+            condition,
+            body,
+            max_iterations,
+        })
+    }
+
+    /// Every state the loop passes through, starting with the initial one.
+    fn states(&self, inputs: &[Tensor], ctx: &OpContext) -> Result<Vec<Tensor>, ComputeError> {
+        expect_inputs(inputs.len(), self.body.arity())?;
+        let mut args = inputs.to_vec();
+        let mut states = vec![inputs[0].clone()];
+        while truth(&self.condition.forward_with(&args, ctx)?)? {
+            if states.len() > self.max_iterations {
+                return Err(ComputeError::InvalidOperation {
+                    message: format!(
+                        "while loop did not terminate within {} iterations",
+                        self.max_iterations
+                    ),
+                });
+            }
+            let next = self.body.forward_with(&args, ctx)?;
+            if next.shape() != args[0].shape() {
+                return Err(ComputeError::DimensionError {
+                    message: format!(
+                        "while body changed the state shape from {:?} to {:?}",
+                        args[0].shape(),
+                        next.shape()
+                    ),
+                });
+            }
+            args[0] = next.clone();
+            states.push(next);
+        }
+        Ok(states)
+    }
+}
+
+impl Op for WhileOp {
+    fn forward(&self, inputs: &[Tensor]) -> Result<Tensor, ComputeError> {
+        self.forward_with(inputs, &OpContext::default())
+    }
+
+    fn backward(
+        &self,
+        inputs: &[Tensor],
+        grad_output: &Tensor,
+    ) -> Result<Vec<Tensor>, ComputeError> {
+        self.backward_with(inputs, grad_output, &OpContext::default())
+    }
+
+    fn forward_into_with(
+        &self,
+        inputs: &[&Tensor],
+        out: &mut Tensor,
+        ctx: &OpContext,
+    ) -> Result<(), ComputeError> {
+        ops::forward_into_owned(self, inputs, out, ctx)
+    }
+
+    fn forward_with(&self, inputs: &[Tensor], ctx: &OpContext) -> Result<Tensor, ComputeError> {
+        let mut states = self.states(inputs, ctx)?;
+        Ok(states.pop().expect("initial state"))
+    }
+
+    fn backward_with(
+        &self,
+        inputs: &[Tensor],
+        grad_output: &Tensor,
+        ctx: &OpContext,
+    ) -> Result<Vec<Tensor>, ComputeError> {
+        let states = self.states(inputs, ctx)?;
+        let mut args = inputs.to_vec();
+        let mut grad_state = grad_output.clone();
+        let mut extra_grads = inputs[1..]
+            .iter()
+            .map(Tensor::zeros_like)
+            .collect::<Result<Vec<_>, _>>()?;
+        for state in states[..states.len() - 1].iter().rev() {
+            args[0] = state.clone();
+            let grads = self.body.backward_with(&args, &grad_state, ctx)?;
+            accumulate(&mut extra_grads, &grads[1..])?;
+            grad_state = grads.into_iter().next().expect("state gradient");
+        }
+        let mut grads = vec![grad_state];
+        grads.extend(extra_grads);
+        Ok(grads)
+    }
+
+    fn infer_shape(&self, inputs: &[Vec<Dim>]) -> Result<Vec<Dim>, ComputeError> {
+        shape::expect_inputs(inputs, self.body.arity())?;
+        let next = self.body.infer_shape(inputs)?;
+        unify_shapes(&inputs[0], &next)
+    }
+
+    fn name(&self) -> &str {
+        "WhileOp"
+    }
+}
+
+/// Applies `state = cell(x_t, state, extra...)` for each step `t` along `axis` of a sequence.
+///
+/// Inputs are `[sequence, initial_state, extra...]`. The output stacks every new state
+/// along `axis`, so a `[time, features]` sequence with a `[hidden]` state gives
+/// `[time, hidden]`. Backward runs backpropagation through time, accumulating the gradients
+/// of the extra inputs (typically the cell's weights) over all steps.
+pub struct ScanOp {
+    cell: MacroOp,
+    axis: usize,
+}
+
+impl ScanOp {
+    /// Scan over the leading axis.
+    pub fn new(cell: MacroOp) -> Result<Self, ComputeError> {
+        if cell.arity() < 2 {
+            return Err(ComputeError::InvalidOperation {
+                message: "scan cell must take the step input and the carried state".to_string(),
+            });
+        }
+        Ok(Self { cell, axis: 0 })
+    }
+
+    /// Scan over `axis` instead, e.g. `1` for `[batch, time, features]` sequences.
+    pub fn with_axis(mut self, axis: usize) -> Self {
+        self.axis = axis;
+        self
+    }
+
+    fn steps(&self, inputs: &[Tensor]) -> Result<usize, ComputeError> {
+        expect_inputs(inputs.len(), self.cell.arity())?;
+        inputs[0]
+            .shape()
+            .get(self.axis)
+            .copied()
+            .ok_or_else(|| ComputeError::DimensionError {
+                message: format!(
+                    "invalid scan axis {} for rank {}",
+                    self.axis,
+                    inputs[0].shape().len()
+                ),
+            })
+    }
+
+    /// Step `t` of `sequence`. Steps of a 1-D sequence are `[1]` tensors, since `select`
+    /// cannot produce a zero-rank one.
+    fn step(&self, sequence: &Tensor, t: usize) -> Result<Tensor, ComputeError> {
+        if sequence.shape().len() == 1 && self.axis == 0 {
+            return Tensor::new(vec![sequence.data()[t]], vec![1]);
+        }
+        sequence.select(self.axis, t)
+    }
+
+    /// The state before each step followed by the final state.
+    fn states(&self, inputs: &[Tensor], ctx: &OpContext) -> Result<Vec<Tensor>, ComputeError> {
+        let steps = self.steps(inputs)?;
+        let mut args = inputs.to_vec();
+        let mut states = vec![inputs[1].clone()];
+        for t in 0..steps {
+            args[0] = self.step(&inputs[0], t)?;
+            args[1] = self.cell.forward_with(&args, ctx)?;
+            if args[1].shape() != inputs[1].shape() {
+                return Err(ComputeError::DimensionError {
+                    message: format!(
+                        "scan cell changed the state shape from {:?} to {:?}",
+                        inputs[1].shape(),
+                        args[1].shape()
+                    ),
+                });
+            }
+            states.push(args[1].clone());
+        }
+        Ok(states)
+    }
+}
+
+impl Op for ScanOp {
+    fn forward(&self, inputs: &[Tensor]) -> Result<Tensor, ComputeError> {
+        self.forward_with(inputs, &OpContext::default())
+    }
+
+    fn backward(
+        &self,
+        inputs: &[Tensor],
+        grad_output: &Tensor,
+    ) -> Result<Vec<Tensor>, ComputeError> {
+        self.backward_with(inputs, grad_output, &OpContext::default())
+    }
+
+    fn forward_into_with(
+        &self,
+        inputs: &[&Tensor],
+        out: &mut Tensor,
+        ctx: &OpContext,
+    ) -> Result<(), ComputeError> {
+        ops::forward_into_owned(self, inputs, out, ctx)
+    }
+
+    fn forward_with(&self, inputs: &[Tensor], ctx: &OpContext) -> Result<Tensor, ComputeError> {
+        let states = self.states(inputs, ctx)?;
+        if states.len() == 1 {
+            return Err(ComputeError::InvalidOperation {
+                message: "cannot scan over an empty sequence".to_string(),
+            });
+        }
+        Tensor::stack(&states[1..], self.axis)
+    }
+
+    fn backward_with(
+        &self,
+        inputs: &[Tensor],
+        grad_output: &Tensor,
+        ctx: &OpContext,
+    ) -> Result<Vec<Tensor>, ComputeError> {
+        let states = self.states(inputs, ctx)?;
+        let steps = states.len() - 1;
+        let mut args = inputs.to_vec();
+        let mut grad_state = Tensor::zeros_like(&inputs[1])?;
+        let mut extra_grads = inputs[2..]
+            .iter()
+            .map(Tensor::zeros_like)
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut step_grads = Vec::with_capacity(steps);
+        for t in (0..steps).rev() {
+            args[0] = self.step(&inputs[0], t)?;
+            args[1] = states[t].clone();
+            let grad = grad_output.select(self.axis, t)?.add(&grad_state)?;
+            let mut grads = self.cell.backward_with(&args, &grad, ctx)?.into_iter();
+            step_grads.push(grads.next().expect("step input gradient"));
+            grad_state = grads.next().expect("state gradient");
+            accumulate(&mut extra_grads, grads.as_slice())?;
+        }
+        step_grads.reverse();
+        let mut sequence_grad = Tensor::stack(&step_grads, self.axis)?;
+        if inputs[0].shape().len() == 1 {
+            sequence_grad = Tensor::new(sequence_grad.data().to_vec(), inputs[0].shape().to_vec())?;
+        }
+
+        let mut grads = vec![sequence_grad, grad_state];
+        grads.extend(extra_grads);
+        Ok(grads)
+    }
+
+    fn infer_shape(&self, inputs: &[Vec<Dim>]) -> Result<Vec<Dim>, ComputeError> {
+        shape::expect_inputs(inputs, self.cell.arity())?;
+        if self.axis >= inputs[0].len() {
+            return Err(ComputeError::DimensionError {
+                message: format!(
+                    "invalid scan axis {} for rank {}",
+                    self.axis,
+                    inputs[0].len()
+                ),
+            });
+        }
+        let mut args = inputs.to_vec();
+        let steps = args[0].remove(self.axis);
+        if args[0].is_empty() {
+            args[0].push(Dim::Fixed(1));
+        }
+        let next = self.cell.infer_shape(&args)?;
+        let mut out = unify_shapes(&inputs[1], &next)?;
+        if self.axis > out.len() {
+            return Err(ComputeError::DimensionError {
+                message: format!(
+                    "invalid scan axis {} for state rank {}",
+                    self.axis,
+                    out.len()
+                ),
+            });
+        }
+        out.insert(self.axis, steps);
+        Ok(out)
+    }
+
+    fn name(&self) -> &str {
+        "ScanOp"
+    }
+}
@@ -0,0 +1,777 @@
+//! Convolution ops over `[batch, channels, ...spatial]` tensors.
+//!
+//! Every op runs on one direct 2D kernel; 1D problems use a spatial height of 1.
+//! Convolution weights are `[out_channels, in_channels / groups, ...kernel]`, transposed
+//! convolution weights `[in_channels, out_channels / groups, ...kernel]`, and the optional
+//! bias is `[out_channels]`.
+
+use crate::error::ComputeError;
+use crate::ops::Op;
+use crate::shape::{self, Dim};
+use crate::tensor::Tensor;
+
+/// Sizes of one convolution, with 1D problems stored as height 1.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct ConvGeometry {
+    pub batch: usize,
+    pub in_channels: usize,
+    pub out_channels: usize,
+    pub groups: usize,
+    pub input: [usize; 2],
+    pub kernel: [usize; 2],
+    pub output: [usize; 2],
+    pub stride: [usize; 2],
+    pub padding: [usize; 2],
+    pub dilation: [usize; 2],
+}
+
+/// Length of a convolution output along one axis.
+pub(crate) fn output_size(
+    input: usize,
+    kernel: usize,
+    stride: usize,
+    padding: usize,
+    dilation: usize,
+) -> Result<usize, ComputeError> {
+    if stride == 0 || dilation == 0 || kernel == 0 {
+        return Err(ComputeError::InvalidOperation {
+            message: "stride, dilation and kernel size must be positive".to_string(),
+        });
+    }
+    let span = dilation * (kernel - 1) + 1;
+    let padded = input + 2 * padding;
+    if padded < span {
+        return Err(ComputeError::DimensionError {
+            message: format!(
+                "kernel span {span} exceeds padded input length {padded} (input {input}, padding {padding})"
+            ),
+        });
+    }
+    Ok((padded - span) / stride + 1)
+}
+
+/// Length of a transposed convolution output along one axis.
+pub(crate) fn transposed_output_size(
+    input: usize,
+    kernel: usize,
+    stride: usize,
+    padding: usize,
+    output_padding: usize,
+    dilation: usize,
+) -> Result<usize, ComputeError> {
+    let full = input.saturating_sub(1) * stride + dilation * kernel.saturating_sub(1) + 1;
+    let out = (full + output_padding)
+        .checked_sub(2 * padding)
+        .filter(|&n| n > 0)
+        .ok_or_else(|| ComputeError::DimensionError {
+            message: format!("padding {padding} leaves no transposed convolution output"),
+        })?;
+    // The matching forward convolution must map the output back onto the input.
+    if output_size(out, kernel, stride, padding, dilation)? != input {
+        return Err(ComputeError::InvalidOperation {
+            message: format!(
+                "output padding {output_padding} must be smaller than stride {stride} or dilation {dilation}"
+            ),
+        });
+    }
+    Ok(out)
+}
+
+fn check_groups(
+    groups: usize,
+    in_channels: usize,
+    out_channels: usize,
+) -> Result<(), ComputeError> {
+    if groups == 0 || !in_channels.is_multiple_of(groups) || !out_channels.is_multiple_of(groups) {
+        return Err(ComputeError::InvalidOperation {
+            message: format!(
+                "groups {groups} must divide in channels {in_channels} and out channels {out_channels}"
+            ),
+        });
+    }
+    Ok(())
+}
+
+/// Add `b[c]` to every element of channel `c` in a `[batch, channels, plane]` buffer.
+fn add_channel_bias(data: &mut [f32], channels: usize, plane: usize, b: &[f32]) {
+    for (idx, chunk) in data.chunks_mut(plane.max(1)).enumerate() {
+        let bias = b[idx % channels];
+        for v in chunk {
+            *v += bias;
+        }
+    }
+}
+
+/// Per-channel sums of a `[batch, channels, plane]` buffer.
+fn channel_sums(data: &[f32], channels: usize, plane: usize) -> Vec<f32> {
+    let mut sums = vec![0.0; channels];
+    for (idx, chunk) in data.chunks(plane.max(1)).enumerate() {
+        sums[idx % channels] += chunk.iter().sum::<f32>();
+    }
+    sums
+}
+
+impl ConvGeometry {
+    pub fn input_len(&self) -> usize {
+        self.batch * self.in_channels * self.input[0] * self.input[1]
+    }
+
+    pub fn output_len(&self) -> usize {
+        self.batch * self.out_channels * self.output[0] * self.output[1]
+    }
+
+    pub fn weight_len(&self) -> usize {
+        self.out_channels * (self.in_channels / self.groups) * self.kernel[0] * self.kernel[1]
+    }
+
+    /// Call `f(output, input, weight)` with flat indices for every multiply-add of the
+    /// convolution, skipping taps that land in the padding.
+    pub fn for_each_tap(&self, mut f: impl FnMut(usize, usize, usize)) {
+        let [ih, iw] = self.input;
+        let [kh, kw] = self.kernel;
+        let [oh, ow] = self.output;
+        let in_per_group = self.in_channels / self.groups;
+        let out_per_group = self.out_channels / self.groups;
+        for n in 0..self.batch {
+            for oc in 0..self.out_channels {
+                let group = oc / out_per_group;
+                for oy in 0..oh {
+                    for ox in 0..ow {
+                        let o = ((n * self.out_channels + oc) * oh + oy) * ow + ox;
+                        for icg in 0..in_per_group {
+                            let ic = group * in_per_group + icg;
+                            for ky in 0..kh {
+                                let iy = (oy * self.stride[0] + ky * self.dilation[0])
+                                    .checked_sub(self.padding[0]);
+                                let Some(iy) = iy.filter(|&y| y < ih) else {
+                                    continue;
+                                };
+                                for kx in 0..kw {
+                                    let ix = (ox * self.stride[1] + kx * self.dilation[1])
+                                        .checked_sub(self.padding[1]);
+                                    let Some(ix) = ix.filter(|&x| x < iw) else {
+                                        continue;
+                                    };
+                                    let i = ((n * self.in_channels + ic) * ih + iy) * iw + ix;
+                                    let k = ((oc * in_per_group + icg) * kh + ky) * kw + kx;
+                                    f(o, i, k);
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Settings shared by the convolution ops, lifted to two spatial axes.
+#[derive(Clone, Copy, Debug)]
+struct ConvParams {
+    /// Number of spatial axes, 1 or 2.
+    spatial: usize,
+    stride: [usize; 2],
+    padding: [usize; 2],
+    output_padding: [usize; 2],
+    dilation: [usize; 2],
+    groups: usize,
+}
+
+impl ConvParams {
+    fn one(
+        stride: usize,
+        padding: usize,
+        output_padding: usize,
+        dilation: usize,
+        groups: usize,
+    ) -> Self {
+        Self {
+            spatial: 1,
+            stride: [1, stride],
+            padding: [0, padding],
+            output_padding: [0, output_padding],
+            dilation: [1, dilation],
+            groups,
+        }
+    }
+
+    fn two(
+        stride: [usize; 2],
+        padding: [usize; 2],
+        output_padding: [usize; 2],
+        dilation: [usize; 2],
+        groups: usize,
+    ) -> Self {
+        Self {
+            spatial: 2,
+            stride,
+            padding,
+            output_padding,
+            dilation,
+            groups,
+        }
+    }
+
+    fn rank(&self) -> usize {
+        self.spatial + 2
+    }
+
+    /// The trailing spatial sizes of a shape as `[height, width]`.
+    fn plane(&self, spatial: &[usize]) -> [usize; 2] {
+        match spatial {
+            [w] => [1, *w],
+            [h, w] => [*h, *w],
+            _ => unreachable!("ranks are checked before lifting"),
+        }
+    }
+
+    fn shape(&self, batch: usize, channels: usize, plane: [usize; 2]) -> Vec<usize> {
+        let mut shape = vec![batch, channels];
+        shape.extend_from_slice(&plane[2 - self.spatial..]);
+        shape
+    }
+
+    fn check_ranks(&self, x: &Tensor, w: &Tensor) -> Result<(), ComputeError> {
+        for (role, t) in [("input", x), ("weight", w)] {
+            if t.shape().len() != self.rank() {
+                return Err(ComputeError::DimensionError {
+                    message: format!(
+                        "conv{}d {role} must have rank {}, got shape {:?}",
+                        self.spatial,
+                        self.rank(),
+                        t.shape()
+                    ),
+                });
+            }
+        }
+        Ok(())
+    }
+
+    fn output_plane(
+        &self,
+        input: [usize; 2],
+        kernel: [usize; 2],
+    ) -> Result<[usize; 2], ComputeError> {
+        let mut out = [1; 2];
+        for axis in 2 - self.spatial..2 {
+            out[axis] = output_size(
+                input[axis],
+                kernel[axis],
+                self.stride[axis],
+                self.padding[axis],
+                self.dilation[axis],
+            )?;
+        }
+        Ok(out)
+    }
+
+    fn transposed_plane(
+        &self,
+        input: [usize; 2],
+        kernel: [usize; 2],
+    ) -> Result<[usize; 2], ComputeError> {
+        let mut out = [1; 2];
+        for axis in 2 - self.spatial..2 {
+            out[axis] = transposed_output_size(
+                input[axis],
+                kernel[axis],
+                self.stride[axis],
+                self.padding[axis],
+                self.output_padding[axis],
+                self.dilation[axis],
+            )?;
+        }
+        Ok(out)
+    }
+
+    fn geometry(&self, x: &Tensor, w: &Tensor) -> Result<ConvGeometry, ComputeError> {
+        self.check_ranks(x, w)?;
+        let input = self.plane(&x.shape()[2..]);
+        let kernel = self.plane(&w.shape()[2..]);
+        let (in_channels, out_channels) = (x.shape()[1], w.shape()[0]);
+        check_groups(self.groups, in_channels, out_channels)?;
+        if w.shape()[1] * self.groups != in_channels {
+            return Err(ComputeError::ShapeMismatch {
+                expected: in_channels / self.groups,
+                got: w.shape()[1],
+            });
+        }
+        Ok(ConvGeometry {
+            batch: x.shape()[0],
+            in_channels,
+            out_channels,
+            groups: self.groups,
+            input,
+            kernel,
+            output: self.output_plane(input, kernel)?,
+            stride: self.stride,
+            padding: self.padding,
+            dilation: self.dilation,
+        })
+    }
+
+    /// The forward convolution whose input gradient is this transposed convolution: its
+    /// input is the transposed output and its output the transposed input.
+    fn transposed_geometry(&self, x: &Tensor, w: &Tensor) -> Result<ConvGeometry, ComputeError> {
+        self.check_ranks(x, w)?;
+        let input = self.plane(&x.shape()[2..]);
+        let kernel = self.plane(&w.shape()[2..]);
+        let in_channels = x.shape()[1];
+        if w.shape()[0] != in_channels {
+            return Err(ComputeError::ShapeMismatch {
+                expected: in_channels,
+                got: w.shape()[0],
+            });
+        }
+        let out_channels = w.shape()[1] * self.groups;
+        check_groups(self.groups, in_channels, out_channels)?;
+        Ok(ConvGeometry {
+            batch: x.shape()[0],
+            in_channels: out_channels,
+            out_channels: in_channels,
+            groups: self.groups,
+            input: self.transposed_plane(input, kernel)?,
+            kernel,
+            output: input,
+            stride: self.stride,
+            padding: self.padding,
+            dilation: self.dilation,
+        })
+    }
+
+    fn forward(&self, inputs: &[Tensor]) -> Result<Tensor, ComputeError> {
+        expect_conv_inputs(inputs.len())?;
+        let (x, w) = (&inputs[0], &inputs[1]);
+        let g = self.geometry(x, w)?;
+        let mut y = vec![0.0; g.output_len()];
+        let (xd, wd) = (x.data(), w.data());
+        g.for_each_tap(|o, i, k| y[o] += xd[i] * wd[k]);
+        if let Some(b) = inputs.get(2) {
+            check_bias(b, g.out_channels)?;
+            add_channel_bias(&mut y, g.out_channels, g.output[0] * g.output[1], b.data());
+        }
+        Tensor::new(y, self.shape(g.batch, g.out_channels, g.output))
+    }
+
+    fn backward(&self, inputs: &[Tensor], grad: &Tensor) -> Result<Vec<Tensor>, ComputeError> {
+        expect_conv_inputs(inputs.len())?;
+        let (x, w) = (&inputs[0], &inputs[1]);
+        let g = self.geometry(x, w)?;
+        expect_len(grad, g.output_len())?;
+        let mut gx = vec![0.0; g.input_len()];
+        let mut gw = vec![0.0; g.weight_len()];
+        let (xd, wd, gd) = (x.data(), w.data(), grad.data());
+        g.for_each_tap(|o, i, k| {
+            gx[i] += gd[o] * wd[k];
+            gw[k] += gd[o] * xd[i];
+        });
+        let mut grads = vec![
+            Tensor::new(gx, x.shape().to_vec())?,
+            Tensor::new(gw, w.shape().to_vec())?,
+        ];
+        if inputs.len() == 3 {
+            let plane = g.output[0] * g.output[1];
+            grads.push(Tensor::new(
+                channel_sums(gd, g.out_channels, plane),
+                vec![g.out_channels],
+            )?);
+        }
+        Ok(grads)
+    }
+
+    fn transposed_forward(&self, inputs: &[Tensor]) -> Result<Tensor, ComputeError> {
+        expect_conv_inputs(inputs.len())?;
+        let (x, w) = (&inputs[0], &inputs[1]);
+        let g = self.transposed_geometry(x, w)?;
+        let mut y = vec![0.0; g.input_len()];
+        let (xd, wd) = (x.data(), w.data());
+        g.for_each_tap(|o, i, k| y[i] += xd[o] * wd[k]);
+        if let Some(b) = inputs.get(2) {
+            check_bias(b, g.in_channels)?;
+            add_channel_bias(&mut y, g.in_channels, g.input[0] * g.input[1], b.data());
+        }
+        Tensor::new(y, self.shape(g.batch, g.in_channels, g.input))
+    }
+
+    fn transposed_backward(
+        &self,
+        inputs: &[Tensor],
+        grad: &Tensor,
+    ) -> Result<Vec<Tensor>, ComputeError> {
+        expect_conv_inputs(inputs.len())?;
+        let (x, w) = (&inputs[0], &inputs[1]);
+        let g = self.transposed_geometry(x, w)?;
+        expect_len(grad, g.input_len())?;
+        let mut gx = vec![0.0; g.output_len()];
+        let mut gw = vec![0.0; g.weight_len()];
+        let (xd, wd, gd) = (x.data(), w.data(), grad.data());
+        g.for_each_tap(|o, i, k| {
+            gx[o] += gd[i] * wd[k];
+            gw[k] += gd[i] * xd[o];
+        });
+        let mut grads = vec![
+            Tensor::new(gx, x.shape().to_vec())?,
+            Tensor::new(gw, w.shape().to_vec())?,
+        ];
+        if inputs.len() == 3 {
+            let plane = g.input[0] * g.input[1];
+            grads.push(Tensor::new(
+                channel_sums(gd, g.in_channels, plane),
+                vec![g.in_channels],
+            )?);
+        }
+        Ok(grads)
+    }
+
+    /// Static output shape; spatial sizes and the kernel must be fixed, while batch and
+    /// channel counts may be symbolic.
+    fn infer_shape(&self, inputs: &[Vec<Dim>], transposed: bool) -> Result<Vec<Dim>, ComputeError> {
+        expect_conv_inputs(inputs.len())?;
+        let (x, w) = (&inputs[0], &inputs[1]);
+        let rank = self.rank();
+        if x.len() != rank || w.len() != rank {
+            return Err(ComputeError::DimensionError {
+                message: format!(
+                    "convolution expects rank {rank} input and weight, got {} and {}",
+                    shape::format_shape(x),
+                    shape::format_shape(w)
+                ),
+            });
+        }
+        let spatial = |dims: &[Dim]| {
+            shape::concrete(&dims[2..])
+                .map(|sizes| self.plane(&sizes))
+                .ok_or_else(|| ComputeError::DimensionError {
+                    message: format!(
+                        "convolution needs fixed spatial and kernel sizes, got {}",
+                        shape::format_shape(dims)
+                    ),
+                })
+        };
+        let (input, kernel) = (spatial(x)?, spatial(w)?);
+        let (channels, plane) = if transposed {
+            shape::unify(&x[1], &w[0])?;
+            let channels = match (&w[1], self.groups) {
+                (Dim::Fixed(n), groups) => Dim::Fixed(n * groups),
+                (symbol, 1) => symbol.clone(),
+                (symbol, groups) => {
+                    return Err(ComputeError::DimensionError {
+                        message: format!(
+                            "grouped transposed convolution needs fixed channels, got {symbol} with groups {groups}"
+                        ),
+                    })
+                }
+            };
+            (channels, self.transposed_plane(input, kernel)?)
+        } else {
+            (w[0].clone(), self.output_plane(input, kernel)?)
+        };
+        let mut out = vec![x[0].clone(), channels];
+        out.extend(plane[2 - self.spatial..].iter().map(|&n| Dim::Fixed(n)));
+        if let Some(b) = inputs.get(2) {
+            if b.len() != 1 {
+                return Err(ComputeError::DimensionError {
+                    message: format!("bias must be 1D, got {}", shape::format_shape(b)),
+                });
+            }
+            out[1] = shape::unify(&out[1], &b[0])?;
+        }
+        Ok(out)
+    }
+}
+
+fn expect_conv_inputs(inputs: usize) -> Result<(), ComputeError> {
+    if inputs != 2 && inputs != 3 {
+        return Err(ComputeError::InputCountError {
+            expected: 3,
+            got: inputs,
+        });
+    }
+    Ok(())
+}
+
+fn expect_len(grad: &Tensor, expected: usize) -> Result<(), ComputeError> {
+    if grad.data().len() != expected {
+        return Err(ComputeError::ShapeMismatch {
+            expected,
+            got: grad.data().len(),
+        });
+    }
+    Ok(())
+}
+
+fn check_bias(bias: &Tensor, channels: usize) -> Result<(), ComputeError> {
+    if bias.shape() != [channels] {
+        return Err(ComputeError::DimensionError {
+            message: format!("bias must have shape [{channels}], got {:?}", bias.shape()),
+        });
+    }
+    Ok(())
+}
+
+/// Render a `[height, width]` setting as `h,w` for `Op::attributes`.
+pub(crate) fn format_pair(pair: [usize; 2]) -> String {
+    format!("{},{}", pair[0], pair[1])
+}
+
+/// 1D convolution (cross-correlation) of `[batch, in_channels, length]` inputs.
+///
+/// Inputs are `[x, weight]` or `[x, weight, bias]` with weight
+/// `[out_channels, in_channels / groups, kernel]` and bias `[out_channels]`. The output is
+/// `[batch, out_channels, (length + 2 * padding - dilation * (kernel - 1) - 1) / stride + 1]`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Conv1dOp {
+    pub stride: usize,
+    pub padding: usize,
+    pub dilation: usize,
+    pub groups: usize,
+}
+
+impl Default for Conv1dOp {
+    fn default() -> Self {
+        Self {
+            stride: 1,
+            padding: 0,
+            dilation: 1,
+            groups: 1,
+        }
+    }
+}
+
+impl Conv1dOp {
+    fn params(&self) -> ConvParams {
+        ConvParams::one(self.stride, self.padding, 0, self.dilation, self.groups)
+    }
+}
+
+impl Op for Conv1dOp {
+    fn forward(&self, inputs: &[Tensor]) -> Result<Tensor, ComputeError> {
+        self.params().forward(inputs)
+    }
+
+    fn backward(
+        &self,
+        inputs: &[Tensor],
+        grad_output: &Tensor,
+    ) -> Result<Vec<Tensor>, ComputeError> {
+        self.params().backward(inputs, grad_output)
+    }
+
+    fn infer_shape(&self, inputs: &[Vec<Dim>]) -> Result<Vec<Dim>, ComputeError> {
+        self.params().infer_shape(inputs, false)
+    }
+
+    fn name(&self) -> &str {
+        "Conv1dOp"
+    }
+
+    fn attributes(&self) -> Option<Vec<(&'static str, String)>> {
+        Some(vec![
+            ("stride", self.stride.to_string()),
+            ("padding", self.padding.to_string()),
+            ("dilation", self.dilation.to_string()),
+            ("groups", self.groups.to_string()),
+        ])
+    }
+}
+
+/// 2D convolution of `[batch, in_channels, height, width]` inputs, the 2D analogue of
+/// `Conv1dOp` with `[height, width]` settings.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Conv2dOp {
+    pub stride: [usize; 2],
+    pub padding: [usize; 2],
+    pub dilation: [usize; 2],
+    pub groups: usize,
+}
+
+impl Default for Conv2dOp {
+    fn default() -> Self {
+        Self {
+            stride: [1, 1],
+            padding: [0, 0],
+            dilation: [1, 1],
+            groups: 1,
+        }
+    }
+}
+
+impl Conv2dOp {
+    fn params(&self) -> ConvParams {
+        ConvParams::two(
+            self.stride,
+            self.padding,
+            [0, 0],
+            self.dilation,
+            self.groups,
+        )
+    }
+}
+
+impl Op for Conv2dOp {
+    fn forward(&self, inputs: &[Tensor]) -> Result<Tensor, ComputeError> {
+        self.params().forward(inputs)
+    }
+
+    fn backward(
+        &self,
+        inputs: &[Tensor],
+        grad_output: &Tensor,
+    ) -> Result<Vec<Tensor>, ComputeError> {
+        self.params().backward(inputs, grad_output)
+    }
+
+    fn infer_shape(&self, inputs: &[Vec<Dim>]) -> Result<Vec<Dim>, ComputeError> {
+        self.params().infer_shape(inputs, false)
+    }
+
+    fn name(&self) -> &str {
+        "Conv2dOp"
+    }
+
+    fn attributes(&self) -> Option<Vec<(&'static str, String)>> {
+        Some(vec![
+            ("stride", format_pair(self.stride)),
+            ("padding", format_pair(self.padding)),
+            ("dilation", format_pair(self.dilation)),
+            ("groups", self.groups.to_string()),
+        ])
+    }
+}
+
+/// Transposed 1D convolution (the input gradient of `Conv1dOp`), used to upsample.
+///
+/// Inputs are `[x, weight]` or `[x, weight, bias]` with x `[batch, in_channels, length]`,
+/// weight `[in_channels, out_channels / groups, kernel]` and bias `[out_channels]`. The
+/// output length is `(length - 1) * stride - 2 * padding + dilation * (kernel - 1) +
+/// output_padding + 1`; `output_padding` must be smaller than `stride` or `dilation`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ConvTranspose1dOp {
+    pub stride: usize,
+    pub padding: usize,
+    pub output_padding: usize,
+    pub dilation: usize,
+    pub groups: usize,
+}
+
+impl Default for ConvTranspose1dOp {
+    fn default() -> Self {
+        Self {
+            stride: 1,
+            padding: 0,
+            output_padding: 0,
+            dilation: 1,
+            groups: 1,
+        }
+    }
+}
+
+impl ConvTranspose1dOp {
+    fn params(&self) -> ConvParams {
+        ConvParams::one(
+            self.stride,
+            self.padding,
+            self.output_padding,
+            self.dilation,
+            self.groups,
+        )
+    }
+}
+
+impl Op for ConvTranspose1dOp {
+    fn forward(&self, inputs: &[Tensor]) -> Result<Tensor, ComputeError> {
+        self.params().transposed_forward(inputs)
+    }
+
+    fn backward(
+        &self,
+        inputs: &[Tensor],
+        grad_output: &Tensor,
+    ) -> Result<Vec<Tensor>, ComputeError> {
+        self.params().transposed_backward(inputs, grad_output)
+    }
+
+    fn infer_shape(&self, inputs: &[Vec<Dim>]) -> Result<Vec<Dim>, ComputeError> {
+        self.params().infer_shape(inputs, true)
+    }
+
+    fn name(&self) -> &str {
+        "ConvTranspose1dOp"
+    }
+
+    fn attributes(&self) -> Option<Vec<(&'static str, String)>> {
+        Some(vec![
+            ("stride", self.stride.to_string()),
+            ("padding", self.padding.to_string()),
+            ("output_padding", self.output_padding.to_string()),
+            ("dilation", self.dilation.to_string()),
+            ("groups", self.groups.to_string()),
+        ])
+    }
+}
+
+/// Transposed 2D convolution, the 2D analogue of `ConvTranspose1dOp`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ConvTranspose2dOp {
+    pub stride: [usize; 2],
+    pub padding: [usize; 2],
+    pub output_padding: [usize; 2],
+    pub dilation: [usize; 2],
+    pub groups: usize,
+}
+
+impl Default for ConvTranspose2dOp {
+    fn default() -> Self {
+        Self {
+            stride: [1, 1],
+            padding: [0, 0],
+            output_padding: [0, 0],
+            dilation: [1, 1],
+            groups: 1,
+        }
+    }
+}
+
+impl ConvTranspose2dOp {
+    fn params(&self) -> ConvParams {
+        ConvParams::two(
+            self.stride,
+            self.padding,
+            self.output_padding,
+            self.dilation,
+            self.groups,
+        )
+    }
+}
+
+impl Op for ConvTranspose2dOp {
+    fn forward(&self, inputs: &[Tensor]) -> Result<Tensor, ComputeError> {
+        self.params().transposed_forward(inputs)
+    }
+
+    fn backward(
+        &self,
+        inputs: &[Tensor],
+        grad_output: &Tensor,
+    ) -> Result<Vec<Tensor>, ComputeError> {
+        self.params().transposed_backward(inputs, grad_output)
+    }
+
+    fn infer_shape(&self, inputs: &[Vec<Dim>]) -> Result<Vec<Dim>, ComputeError> {
+        self.params().infer_shape(inputs, true)
+    }
+
+    fn name(&self) -> &str {
+        "ConvTranspose2dOp"
+    }
+
+    fn attributes(&self) -> Option<Vec<(&'static str, String)>> {
+        Some(vec![
+            ("stride", format_pair(self.stride)),
+            ("padding", format_pair(self.padding)),
+            ("output_padding", format_pair(self.output_padding)),
+            ("dilation", format_pair(self.dilation)),
+            ("groups", self.groups.to_string()),
+        ])
+    }
+}
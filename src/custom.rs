@@ -0,0 +1,202 @@
+//! User-defined ops: `FnOp` wraps forward/backward closures and `MacroOp` packages a
+//! subgraph of existing ops as a single named op.
+
+use crate::error::ComputeError;
+use crate::graph::Graph;
+use crate::ops::{self, Op, OpContext};
+use crate::shape::Dim;
+use crate::tensor::Tensor;
+
+type ForwardFn = dyn Fn(&[Tensor]) -> Result<Tensor, ComputeError> + Send + Sync;
+type BackwardFn = dyn Fn(&[Tensor], &Tensor) -> Result<Vec<Tensor>, ComputeError> + Send + Sync;
+
+fn check_arity(expected: usize, got: usize) -> Result<(), ComputeError> {
+    if got != expected {
+        return Err(ComputeError::InputCountError { expected, got });
+    }
+    Ok(())
+}
+
+/// An op built from closures.
+///
+/// Input counts are checked against `arity` before either closure runs, and `backward`
+/// must return one gradient per input.
+pub struct FnOp {
+    name: String,
+    arity: usize,
+    forward: Box<ForwardFn>,
+    backward: Box<BackwardFn>,
+}
+
+impl FnOp {
+    pub fn new<F, B>(name: &str, arity: usize, forward: F, backward: B) -> Self
+    where
+        F: Fn(&[Tensor]) -> Result<Tensor, ComputeError> + Send + Sync + 'static,
+        B: Fn(&[Tensor], &Tensor) -> Result<Vec<Tensor>, ComputeError> + Send + Sync + 'static,
+    {
+        Self {
+            name: name.to_string(),
+            arity,
+            forward: Box::new(forward),
+            backward: Box::new(backward),
+        }
+    }
+
+    pub fn arity(&self) -> usize {
+        self.arity
+    }
+}
+
+impl Op for FnOp {
+    fn forward(&self, inputs: &[Tensor]) -> Result<Tensor, ComputeError> {
+        check_arity(self.arity, inputs.len())?;
+        (self.forward)(inputs)
+    }
+
+    fn backward(
+        &self,
+        inputs: &[Tensor],
+        grad_output: &Tensor,
+    ) -> Result<Vec<Tensor>, ComputeError> {
+        check_arity(self.arity, inputs.len())?;
+        let grads = (self.backward)(inputs, grad_output)?;
+        if grads.len() != self.arity {
+            return Err(ComputeError::InvalidOperation {
+                message: format!(
+                    "{} backward returned {} gradients for {} inputs",
+                    self.name,
+                    grads.len(),
+                    self.arity
+                ),
+            });
+        }
+        Ok(grads)
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+/// A named op defined by a subgraph of existing ops.
+///
+/// The body is built once against placeholder inputs. Forward evaluates it with the real
+/// inputs substituted; backward differentiates through it, so no gradient code is needed.
+/// Shape inference checks the body against the shapes of the op's inputs, and the body runs
+/// under the settings of the graph applying the op. Parameters created inside the body are
+/// constants of the op: pass trainable tensors as inputs instead.
+pub struct MacroOp {
+    name: String,
+    body: Graph,
+    inputs: Vec<usize>,
+    output: usize,
+}
+
+impl MacroOp {
+    /// Build the body with `build(graph, placeholders)`, which returns the output node.
+    ///
+    /// The placeholders are empty `[0]` tensors; use `with_input_shapes` when `build` needs
+    /// to evaluate or check the body on its own.
+    pub fn new<F>(name: &str, arity: usize, build: F) -> Result<Self, ComputeError>
+    where
+        F: FnOnce(&mut Graph, &[usize]) -> Result<usize, ComputeError>,
+    {
+        Self::with_input_shapes(name, &vec![vec![0]; arity], build)
+    }
+
+    /// `new` with zero placeholders of the given shapes, one per input.
+    pub fn with_input_shapes<F>(
+        name: &str,
+        shapes: &[Vec<usize>],
+        build: F,
+    ) -> Result<Self, ComputeError>
+    where
+        F: FnOnce(&mut Graph, &[usize]) -> Result<usize, ComputeError>,
+    {
+        let mut body = Graph::new();
+        let inputs = shapes
+            .iter()
+            .map(|shape| Ok(body.add_input(Tensor::zeros(shape.clone())?)))
+            .collect::<Result<Vec<usize>, ComputeError>>()?;
+        let output = build(&mut body, &inputs)?;
+        if output >= body.len() {
+            return Err(ComputeError::IndexError {
+                message: format!("macro op {name} output {output} is not a body node"),
+            });
+        }
+        Ok(Self {
+            name: name.to_string(),
+            body,
+            inputs,
+            output,
+        })
+    }
+
+    pub fn arity(&self) -> usize {
+        self.inputs.len()
+    }
+
+    fn feeds(&self, inputs: &[Tensor]) -> Result<Vec<(usize, Tensor)>, ComputeError> {
+        check_arity(self.inputs.len(), inputs.len())?;
+        Ok(self
+            .inputs
+            .iter()
+            .copied()
+            .zip(inputs.iter().cloned())
+            .collect())
+    }
+}
+
+impl Op for MacroOp {
+    fn forward(&self, inputs: &[Tensor]) -> Result<Tensor, ComputeError> {
+        self.forward_with(inputs, &OpContext::default())
+    }
+
+    fn backward(
+        &self,
+        inputs: &[Tensor],
+        grad_output: &Tensor,
+    ) -> Result<Vec<Tensor>, ComputeError> {
+        self.backward_with(inputs, grad_output, &OpContext::default())
+    }
+
+    /// Runs the body with the settings of the outer graph.
+    fn forward_with(&self, inputs: &[Tensor], ctx: &OpContext) -> Result<Tensor, ComputeError> {
+        self.body
+            .forward_with(self.output, &self.feeds(inputs)?, ctx)
+    }
+
+    fn backward_with(
+        &self,
+        inputs: &[Tensor],
+        grad_output: &Tensor,
+        ctx: &OpContext,
+    ) -> Result<Vec<Tensor>, ComputeError> {
+        self.body
+            .input_gradients(self.output, &self.feeds(inputs)?, grad_output, ctx)
+    }
+
+    fn forward_into_with(
+        &self,
+        inputs: &[&Tensor],
+        out: &mut Tensor,
+        ctx: &OpContext,
+    ) -> Result<(), ComputeError> {
+        ops::forward_into_owned(self, inputs, out, ctx)
+    }
+
+    fn infer_shape(&self, inputs: &[Vec<Dim>]) -> Result<Vec<Dim>, ComputeError> {
+        check_arity(self.inputs.len(), inputs.len())?;
+        let overrides: Vec<(usize, Vec<Dim>)> = self
+            .inputs
+            .iter()
+            .copied()
+            .zip(inputs.iter().cloned())
+            .collect();
+        self.body.check_symbolic(self.output, &overrides)
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
@@ -0,0 +1,223 @@
+//! Dropout ops and layers driven by the graph's seeded random generator.
+//!
+//! Masks are drawn from an `XorShift32` seeded by a seed node (`Graph::add_seed_node`), so
+//! they are a pure function of the graph seed and the number of `Graph::advance_seeds`
+//! calls. Runs with the same seed (for example `RunManifest::seed`) reproduce exactly.
+
+use crate::error::ComputeError;
+use crate::graph::Graph;
+use crate::layers::Layer;
+use crate::module::{self, Module};
+use crate::ops::Op;
+use crate::prng::XorShift32;
+use crate::shape::{self, Dim};
+use crate::tensor::Tensor;
+
+/// Check the drop probability and read the `[x, mode, seed]` inputs.
+fn dropout_inputs(p: f32, inputs: &[Tensor]) -> Result<(&Tensor, bool, XorShift32), ComputeError> {
+    if !(0.0..1.0).contains(&p) {
+        return Err(ComputeError::InvalidOperation {
+            message: format!("dropout probability must be in [0, 1), got {p}"),
+        });
+    }
+    let [x, mode, seed] = inputs else {
+        return Err(ComputeError::InputCountError {
+            expected: 3,
+            got: inputs.len(),
+        });
+    };
+    let seed = match seed.data() {
+        [seed] => *seed as u32,
+        _ => {
+            return Err(ComputeError::DimensionError {
+                message: format!("dropout seed must hold one value, got {:?}", seed.shape()),
+            })
+        }
+    };
+    Ok((x, module::is_training(mode)?, XorShift32::new(seed)))
+}
+
+/// Scale factors of `units` independently kept (`1 / (1 - p)`) or dropped (`0`) units.
+fn keep_scales(p: f32, units: usize, rng: &mut XorShift32) -> Vec<f32> {
+    let scale = 1.0 / (1.0 - p);
+    (0..units)
+        .map(|_| if rng.next_f32() < p { 0.0 } else { scale })
+        .collect()
+}
+
+/// Shape check shared by the dropout ops: `[x, [1], [1]]` gives x's shape.
+fn infer_dropout(inputs: &[Vec<Dim>]) -> Result<Vec<Dim>, ComputeError> {
+    shape::expect_inputs(inputs, 3)?;
+    for scalar in &inputs[1..] {
+        if !matches!(&scalar[..], [d] if shape::unify(d, &Dim::Fixed(1)).is_ok()) {
+            return Err(ComputeError::DimensionError {
+                message: format!(
+                    "dropout mode and seed must be [1], got {}",
+                    shape::format_shape(scalar)
+                ),
+            });
+        }
+    }
+    Ok(inputs[0].clone())
+}
+
+/// Zero each element with probability `p` and scale the rest by `1 / (1 - p)`, so the
+/// expected output equals the input.
+///
+/// Inputs are `[x, mode, seed]`: a mode flag (see `module::add_mode_flag`), off meaning
+/// the op is the identity, and a seed node whose value determines the mask. Backward
+/// rebuilds the same mask from the seed, so the mask is stored as that one value rather
+/// than a tensor. The mode and seed receive zero gradients.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DropoutOp {
+    pub p: f32,
+}
+
+impl DropoutOp {
+    /// Per-element scales, or `None` when the op is the identity.
+    fn mask(&self, inputs: &[Tensor]) -> Result<Option<Vec<f32>>, ComputeError> {
+        let (x, training, mut rng) = dropout_inputs(self.p, inputs)?;
+        Ok((training && self.p > 0.0).then(|| keep_scales(self.p, x.data().len(), &mut rng)))
+    }
+}
+
+/// Drop whole channels of a `[batch, channels, ...]` input with probability `p`, scaling
+/// kept channels by `1 / (1 - p)`. Neighbouring steps of a convolution feature map are
+/// strongly correlated, so element-wise dropout barely regularizes it; dropping a channel
+/// for the whole sequence does.
+///
+/// Inputs and mask handling are as for `DropoutOp`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SpatialDropoutOp {
+    pub p: f32,
+}
+
+impl SpatialDropoutOp {
+    fn mask(&self, inputs: &[Tensor]) -> Result<Option<Vec<f32>>, ComputeError> {
+        let (x, training, mut rng) = dropout_inputs(self.p, inputs)?;
+        let [batch, channels, rest @ ..] = x.shape() else {
+            return Err(ComputeError::DimensionError {
+                message: format!(
+                    "spatial dropout expects [batch, channels, ...], got {:?}",
+                    x.shape()
+                ),
+            });
+        };
+        if !training || self.p == 0.0 {
+            return Ok(None);
+        }
+        let spatial: usize = rest.iter().product();
+        let scales = keep_scales(self.p, batch * channels, &mut rng);
+        Ok(Some(
+            scales
+                .iter()
+                .flat_map(|&s| std::iter::repeat_n(s, spatial))
+                .collect(),
+        ))
+    }
+}
+
+macro_rules! dropout_op {
+    ($op:ident) => {
+        impl Op for $op {
+            fn forward(&self, inputs: &[Tensor]) -> Result<Tensor, ComputeError> {
+                let mask = self.mask(inputs)?;
+                let mut out = inputs[0].clone();
+                if let Some(mask) = mask {
+                    for (v, m) in out.data_mut().iter_mut().zip(&mask) {
+                        *v *= m;
+                    }
+                }
+                Ok(out)
+            }
+
+            fn backward(
+                &self,
+                inputs: &[Tensor],
+                grad_output: &Tensor,
+            ) -> Result<Vec<Tensor>, ComputeError> {
+                let mut grad = grad_output.clone();
+                if let Some(mask) = self.mask(inputs)? {
+                    for (g, m) in grad.data_mut().iter_mut().zip(&mask) {
+                        *g *= m;
+                    }
+                }
+                Ok(vec![
+                    grad,
+                    Tensor::zeros_like(&inputs[1])?,
+                    Tensor::zeros_like(&inputs[2])?,
+                ])
+            }
+
+            fn infer_shape(&self, inputs: &[Vec<Dim>]) -> Result<Vec<Dim>, ComputeError> {
+                infer_dropout(inputs)
+            }
+
+            fn name(&self) -> &str {
+                stringify!($op)
+            }
+
+            fn attributes(&self) -> Option<Vec<(&'static str, String)>> {
+                Some(vec![("p", self.p.to_string())])
+            }
+        }
+    };
+}
+
+dropout_op!(DropoutOp);
+dropout_op!(SpatialDropoutOp);
+
+macro_rules! dropout_layer {
+    ($(#[$doc:meta])* $name:ident, $op:ident) => {
+        $(#[$doc])*
+        pub struct $name {
+            mode_idx: usize,
+            seed_idx: usize,
+            pub p: f32,
+        }
+
+        impl $name {
+            pub fn new(graph: &mut Graph, p: f32) -> Result<Self, ComputeError> {
+                if !(0.0..1.0).contains(&p) {
+                    return Err(ComputeError::InvalidOperation {
+                        message: format!("dropout probability must be in [0, 1), got {p}"),
+                    });
+                }
+                Ok(Self {
+                    mode_idx: module::add_mode_flag(graph)?,
+                    seed_idx: graph.add_seed_node(),
+                    p,
+                })
+            }
+
+            /// The seed node feeding this layer's masks.
+            pub fn seed_node(&self) -> usize {
+                self.seed_idx
+            }
+        }
+
+        impl Module for $name {
+            fn own_mode_flags(&self) -> Vec<usize> {
+                vec![self.mode_idx]
+            }
+        }
+
+        impl Layer for $name {
+            fn forward(&self, graph: &mut Graph, input_idx: usize) -> Result<usize, ComputeError> {
+                let op = $op { p: self.p };
+                Ok(graph.apply_op(op, &[input_idx, self.mode_idx, self.seed_idx]))
+            }
+        }
+    };
+}
+
+dropout_layer!(
+    /// `DropoutOp` with its own mode flag and seed node; the identity in evaluation mode.
+    Dropout,
+    DropoutOp
+);
+dropout_layer!(
+    /// `SpatialDropoutOp` for `[batch, channels, length]` convolution feature maps.
+    SpatialDropout,
+    SpatialDropoutOp
+);
@@ -0,0 +1,185 @@
+//! Lookup tables mapping categorical indices to learned dense vectors.
+
+use crate::error::ComputeError;
+use crate::graph::Graph;
+use crate::init::Init;
+use crate::layers::Layer;
+use crate::module::Module;
+use crate::ops::Op;
+use crate::shape::{self, Dim};
+use crate::tensor::Tensor;
+
+/// Gather rows of a `[num_embeddings, dim]` table: inputs `[indices, table]` give
+/// `indices.shape() + [dim]`.
+///
+/// Indices are whole numbers stored as `f32` (see `Vocabulary::encode`). Backward adds
+/// each output gradient into the row it was read from, touching only the looked-up rows;
+/// the row `padding_idx`, if set, reads as zeros and is never updated. Indices receive a
+/// zero gradient.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct EmbeddingOp {
+    pub padding_idx: Option<usize>,
+}
+
+impl EmbeddingOp {
+    /// Row of every index, or `None` for the padding row.
+    fn rows(&self, inputs: &[Tensor]) -> Result<(Vec<Option<usize>>, usize), ComputeError> {
+        let [indices, table] = inputs else {
+            return Err(ComputeError::InputCountError {
+                expected: 2,
+                got: inputs.len(),
+            });
+        };
+        let [num, dim] = table.shape() else {
+            return Err(ComputeError::DimensionError {
+                message: format!(
+                    "embedding table must be [num_embeddings, dim], got {:?}",
+                    table.shape()
+                ),
+            });
+        };
+        let rows = indices
+            .data()
+            .iter()
+            .map(|&v| {
+                if v < 0.0 || v.fract() != 0.0 || v as usize >= *num {
+                    return Err(ComputeError::InvalidOperation {
+                        message: format!("embedding index {v} is not a row of a {num}-row table"),
+                    });
+                }
+                let row = v as usize;
+                Ok((self.padding_idx != Some(row)).then_some(row))
+            })
+            .collect::<Result<_, _>>()?;
+        Ok((rows, *dim))
+    }
+}
+
+impl Op for EmbeddingOp {
+    fn forward(&self, inputs: &[Tensor]) -> Result<Tensor, ComputeError> {
+        let (rows, dim) = self.rows(inputs)?;
+        let table = inputs[1].data();
+        let mut out = Vec::with_capacity(rows.len() * dim);
+        for row in rows {
+            match row {
+                Some(r) => out.extend_from_slice(&table[r * dim..(r + 1) * dim]),
+                None => out.extend(std::iter::repeat_n(0.0, dim)),
+            }
+        }
+        let mut shape = inputs[0].shape().to_vec();
+        shape.push(dim);
+        Tensor::new(out, shape)
+    }
+
+    fn backward(
+        &self,
+        inputs: &[Tensor],
+        grad_output: &Tensor,
+    ) -> Result<Vec<Tensor>, ComputeError> {
+        let (rows, dim) = self.rows(inputs)?;
+        let mut table = Tensor::zeros_like(&inputs[1])?;
+        let grad = table.data_mut();
+        for (row, g) in rows.iter().zip(grad_output.data().chunks(dim.max(1))) {
+            if let Some(r) = row {
+                for (t, g) in grad[r * dim..(r + 1) * dim].iter_mut().zip(g) {
+                    *t += g;
+                }
+            }
+        }
+        Ok(vec![Tensor::zeros_like(&inputs[0])?, table])
+    }
+
+    fn infer_shape(&self, inputs: &[Vec<Dim>]) -> Result<Vec<Dim>, ComputeError> {
+        shape::expect_inputs(inputs, 2)?;
+        let table = &inputs[1];
+        if table.len() != 2 {
+            return Err(ComputeError::DimensionError {
+                message: format!(
+                    "embedding table must be [num_embeddings, dim], got {}",
+                    shape::format_shape(table)
+                ),
+            });
+        }
+        let mut out = inputs[0].clone();
+        out.push(table[1].clone());
+        Ok(out)
+    }
+
+    fn name(&self) -> &str {
+        "EmbeddingOp"
+    }
+
+    fn attributes(&self) -> Option<Vec<(&'static str, String)>> {
+        Some(vec![("padding_idx", format!("{:?}", self.padding_idx))])
+    }
+}
+
+/// A learned `[num_embeddings, dim]` table looked up with `EmbeddingOp`.
+pub struct Embedding {
+    weight_idx: usize,
+    op: EmbeddingOp,
+    pub num_embeddings: usize,
+    pub dim: usize,
+}
+
+impl Embedding {
+    /// Rows drawn from a standard normal distribution.
+    pub fn new(
+        graph: &mut Graph,
+        num_embeddings: usize,
+        dim: usize,
+        seed: u32,
+    ) -> Result<Self, ComputeError> {
+        let init = Init::Normal {
+            mean: 0.0,
+            std: 1.0,
+        };
+        Self::with_init(
+            graph,
+            num_embeddings,
+            dim,
+            EmbeddingOp::default(),
+            &init,
+            seed,
+        )
+    }
+
+    /// Table initialized with `init`; the `op.padding_idx` row starts at zero.
+    pub fn with_init(
+        graph: &mut Graph,
+        num_embeddings: usize,
+        dim: usize,
+        op: EmbeddingOp,
+        init: &Init,
+        seed: u32,
+    ) -> Result<Self, ComputeError> {
+        let mut table = init.tensor(vec![num_embeddings, dim], seed)?;
+        if let Some(pad) = op.padding_idx {
+            let row = table
+                .data_mut()
+                .get_mut(pad * dim..(pad + 1) * dim)
+                .ok_or_else(|| ComputeError::InvalidOperation {
+                    message: format!("padding index {pad} is not below {num_embeddings}"),
+                })?;
+            row.fill(0.0);
+        }
+        Ok(Self {
+            weight_idx: graph.add_parameter(table, true),
+            op,
+            num_embeddings,
+            dim,
+        })
+    }
+}
+
+impl Module for Embedding {
+    fn own_parameters(&self) -> Vec<(String, usize)> {
+        vec![("weight".to_string(), self.weight_idx)]
+    }
+}
+
+impl Layer for Embedding {
+    fn forward(&self, graph: &mut Graph, input_idx: usize) -> Result<usize, ComputeError> {
+        Ok(graph.apply_op(self.op, &[input_idx, self.weight_idx]))
+    }
+}
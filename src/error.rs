@@ -1,4 +1,31 @@
 use core::fmt;
+use std::sync::Arc;
+
+use crate::shape::{self, Dim};
+
+/// Where in a graph an error happened.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NodeContext {
+    pub node: usize,
+    pub op: String,
+    pub input_shapes: Vec<Vec<Dim>>,
+    pub label: Option<String>,
+}
+
+impl fmt::Display for NodeContext {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "node {} ({}", self.node, self.op)?;
+        if let Some(label) = &self.label {
+            write!(f, " '{label}'")?;
+        }
+        let shapes: Vec<String> = self
+            .input_shapes
+            .iter()
+            .map(|s| shape::format_shape(s))
+            .collect();
+        write!(f, ") with input shapes {}", shapes.join(", "))
+    }
+}
 
 #[derive(Debug, Clone)]
 pub enum ComputeError {
@@ -24,6 +51,66 @@ pub enum ComputeError {
     IndexError {
         message: String,
     },
+    /// An operation hit a singular point under `NumericPolicy::Error`, e.g. a zero divisor.
+    SingularPoint {
+        operation: String,
+        index: usize,
+    },
+    /// A NaN or infinity found by `Graph::set_detect_anomalies`.
+    NonFinite {
+        location: String,
+        index: usize,
+        value: f32,
+    },
+    /// An error raised by a graph node's op, annotated with the node it came from.
+    Node {
+        context: NodeContext,
+        source: Box<ComputeError>,
+    },
+    /// An I/O failure, e.g. while reading a replay file.
+    Io {
+        context: String,
+        source: Arc<std::io::Error>,
+    },
+    /// Malformed input data; `context` says where (file, line, field).
+    Parse {
+        context: String,
+        message: String,
+    },
+    /// A state dict entry that does not fit the module loading it. `expected` is `None` for
+    /// keys the module does not have, `got` is `None` for keys missing from the dict, and
+    /// both are `None` when the module's node for `key` is not a parameter.
+    StateDict {
+        key: String,
+        expected: Option<Vec<usize>>,
+        got: Option<Vec<usize>>,
+        source: Option<Box<ComputeError>>,
+    },
+}
+
+impl ComputeError {
+    pub(crate) fn io(context: impl Into<String>, source: std::io::Error) -> Self {
+        ComputeError::Io {
+            context: context.into(),
+            source: Arc::new(source),
+        }
+    }
+
+    /// The innermost error, with all `Node` annotations stripped.
+    pub fn root_cause(&self) -> &ComputeError {
+        match self {
+            ComputeError::Node { source, .. } => source.root_cause(),
+            other => other,
+        }
+    }
+
+    /// Context of the outermost node this error passed through, if any.
+    pub fn node_context(&self) -> Option<&NodeContext> {
+        match self {
+            ComputeError::Node { context, .. } => Some(context),
+            _ => None,
+        }
+    }
 }
 
 impl fmt::Display for ComputeError {
@@ -45,8 +132,55 @@ impl fmt::Display for ComputeError {
             }
             ComputeError::InvalidOperation { message } => write!(f, "invalid operation: {message}"),
             ComputeError::IndexError { message } => write!(f, "index error: {message}"),
+            ComputeError::SingularPoint { operation, index } => {
+                write!(f, "singular point in {operation} at element {index}")
+            }
+            ComputeError::NonFinite {
+                location,
+                index,
+                value,
+            } => write!(
+                f,
+                "non-finite value {value} at element {index} of {location}"
+            ),
+            ComputeError::Node { context, source } => write!(f, "{context}: {source}"),
+            ComputeError::Io { context, source } => write!(f, "i/o error {context}: {source}"),
+            ComputeError::Parse { context, message } => {
+                write!(f, "parse error at {context}: {message}")
+            }
+            ComputeError::StateDict {
+                key,
+                expected,
+                got,
+                source,
+            } => match (expected, got) {
+                (Some(expected), Some(got)) => {
+                    write!(
+                        f,
+                        "cannot load {key}: expected shape {expected:?}, got {got:?}"
+                    )
+                }
+                (Some(_), None) => write!(f, "missing parameter {key} in state dict"),
+                (None, Some(_)) => write!(f, "unexpected parameter {key} in state dict"),
+                (None, None) => match source {
+                    Some(source) => write!(f, "cannot load {key}: {source}"),
+                    None => write!(f, "cannot load {key}"),
+                },
+            },
         }
     }
 }
 
-impl std::error::Error for ComputeError {}
+impl std::error::Error for ComputeError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ComputeError::Node { source, .. } => Some(source.as_ref()),
+            ComputeError::Io { source, .. } => Some(source.as_ref()),
+            ComputeError::StateDict {
+                source: Some(source),
+                ..
+            } => Some(source.as_ref()),
+            _ => None,
+        }
+    }
+}
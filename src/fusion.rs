@@ -0,0 +1,706 @@
+//! Operator fusion.
+//!
+//! `OperatorFusion` rewrites chains of elementwise ops into a single `FusedElementwiseOp`
+//! and `MatMul -> Add -> [Relu]` sequences into a single `FusedLinearOp`. Fused kernels
+//! write one output buffer and recompute what they need in backward instead of keeping
+//! intermediate tensors alive.
+
+use std::borrow::Borrow;
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::error::ComputeError;
+use crate::graph::{Graph, Node};
+use crate::numeric::{self, NumericPolicy};
+use crate::ops::{MatMulOp, Op, OpContext};
+use crate::passes::{compact, validate, GraphPass, NodeRemap};
+use crate::shape::{self, Dim};
+use crate::tensor::{Tensor, MAX_RANK};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BinaryKind {
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+}
+
+impl BinaryKind {
+    /// `None` at a singular point under `NumericPolicy::Error`.
+    fn apply(self, a: f32, b: f32, policy: NumericPolicy) -> Option<f32> {
+        match self {
+            BinaryKind::Add => Some(a + b),
+            BinaryKind::Subtract => Some(a - b),
+            BinaryKind::Multiply => Some(a * b),
+            BinaryKind::Divide => numeric::div(a, b, policy),
+        }
+    }
+
+    /// Gradients with respect to `a` and `b` given upstream gradient `g`.
+    fn grads(self, a: f32, b: f32, g: f32, policy: NumericPolicy) -> Option<(f32, f32)> {
+        match self {
+            BinaryKind::Add => Some((g, g)),
+            BinaryKind::Subtract => Some((g, -g)),
+            BinaryKind::Multiply => Some((g * b, g * a)),
+            BinaryKind::Divide => Some((
+                numeric::div(g, b, policy)?,
+                -numeric::div(g * a, b * b, policy)?,
+            )),
+        }
+    }
+
+    fn operation(self) -> &'static str {
+        match self {
+            BinaryKind::Add => "add",
+            BinaryKind::Subtract => "subtract",
+            BinaryKind::Multiply => "multiply",
+            BinaryKind::Divide => "divide",
+        }
+    }
+
+    fn from_operation(name: &str) -> Option<Self> {
+        match name {
+            "add" => Some(BinaryKind::Add),
+            "subtract" => Some(BinaryKind::Subtract),
+            "multiply" => Some(BinaryKind::Multiply),
+            "divide" => Some(BinaryKind::Divide),
+            _ => None,
+        }
+    }
+
+    fn from_op_name(name: &str) -> Option<Self> {
+        match name {
+            "AddOp" => Some(BinaryKind::Add),
+            "SubtractOp" => Some(BinaryKind::Subtract),
+            "MultiplyOp" => Some(BinaryKind::Multiply),
+            "DivideOp" => Some(BinaryKind::Divide),
+            _ => None,
+        }
+    }
+}
+
+/// One step of a fused elementwise chain.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ElementwiseStep {
+    Relu,
+    Log,
+    /// Binary op between the running value and op input `operand`.
+    /// `chain_is_lhs` is false when the running value is the right-hand side.
+    Binary {
+        kind: BinaryKind,
+        operand: usize,
+        chain_is_lhs: bool,
+    },
+}
+
+impl ElementwiseStep {
+    /// Apply to the running value `v` of output element `flat`.
+    fn apply<T: Borrow<Tensor>>(
+        self,
+        v: f32,
+        inputs: &[T],
+        offsets: &[usize],
+        flat: usize,
+        policy: NumericPolicy,
+    ) -> Result<f32, ComputeError> {
+        let result = match self {
+            ElementwiseStep::Relu => Some(v.max(0.0)),
+            ElementwiseStep::Log => numeric::ln(v, policy),
+            ElementwiseStep::Binary {
+                kind,
+                operand,
+                chain_is_lhs,
+            } => {
+                let o = inputs[operand].borrow().data()[offsets[operand]];
+                if chain_is_lhs {
+                    kind.apply(v, o, policy)
+                } else {
+                    kind.apply(o, v, policy)
+                }
+            }
+        };
+        result.ok_or_else(|| numeric::singular(self.operation(), flat))
+    }
+
+    fn operation(self) -> &'static str {
+        match self {
+            ElementwiseStep::Relu => "relu",
+            ElementwiseStep::Log => "log",
+            ElementwiseStep::Binary { kind, .. } => kind.operation(),
+        }
+    }
+}
+
+/// `relu`, `log`, or `<kind>:<operand>:lhs|rhs` for binary steps, as saved by `serialize`.
+impl fmt::Display for ElementwiseStep {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ElementwiseStep::Binary {
+                kind,
+                operand,
+                chain_is_lhs,
+            } => {
+                let side = if *chain_is_lhs { "lhs" } else { "rhs" };
+                write!(f, "{}:{operand}:{side}", kind.operation())
+            }
+            step => f.write_str(step.operation()),
+        }
+    }
+}
+
+impl FromStr for ElementwiseStep {
+    type Err = ComputeError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let step = match s.split(':').collect::<Vec<_>>().as_slice() {
+            ["relu"] => Some(ElementwiseStep::Relu),
+            ["log"] => Some(ElementwiseStep::Log),
+            [kind, operand, side] => BinaryKind::from_operation(kind).and_then(|kind| {
+                Some(ElementwiseStep::Binary {
+                    kind,
+                    operand: operand.parse().ok()?,
+                    chain_is_lhs: match *side {
+                        "lhs" => true,
+                        "rhs" => false,
+                        _ => return None,
+                    },
+                })
+            }),
+            _ => None,
+        };
+        step.ok_or_else(|| ComputeError::Parse {
+            context: "fused step".to_string(),
+            message: format!("unknown step {s:?}"),
+        })
+    }
+}
+
+/// Maximum number of inputs (chain base plus operands) of a `FusedElementwiseOp`.
+pub const MAX_FUSED_INPUTS: usize = 8;
+
+/// A chain of elementwise steps applied to input 0, with further inputs as operands.
+///
+/// Broadcasting follows `Tensor`'s elementwise rules; input gradients are reduced back to
+/// each input's shape.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FusedElementwiseOp {
+    pub steps: Vec<ElementwiseStep>,
+}
+
+impl FusedElementwiseOp {
+    fn arity(&self) -> usize {
+        self.steps
+            .iter()
+            .filter_map(|s| match s {
+                ElementwiseStep::Binary { operand, .. } => Some(*operand + 1),
+                _ => None,
+            })
+            .max()
+            .unwrap_or(1)
+    }
+
+    /// Broadcast output shape as a fixed array plus its rank.
+    fn output_shape<T: Borrow<Tensor>>(
+        &self,
+        inputs: &[T],
+    ) -> Result<([usize; MAX_RANK], usize), ComputeError> {
+        let arity = self.arity();
+        if inputs.len() != arity || arity > MAX_FUSED_INPUTS {
+            return Err(ComputeError::InputCountError {
+                expected: arity,
+                got: inputs.len(),
+            });
+        }
+        let first = inputs[0].borrow().shape();
+        if first.len() > MAX_RANK {
+            return Err(ComputeError::DimensionError {
+                message: format!(
+                    "rank {} exceeds the supported maximum of {MAX_RANK}",
+                    first.len()
+                ),
+            });
+        }
+        let mut shape = [1usize; MAX_RANK];
+        shape[..first.len()].copy_from_slice(first);
+        let mut rank = first.len();
+        for t in &inputs[1..] {
+            (shape, rank) = Tensor::broadcast_shape_into(&shape[..rank], t.borrow().shape())?;
+        }
+        Ok((shape, rank))
+    }
+
+    /// Flat offset into each input for output element `flat`.
+    fn input_offsets<T: Borrow<Tensor>>(
+        inputs: &[T],
+        out_shape: &[usize],
+        flat: usize,
+        offsets: &mut [usize],
+    ) -> Result<(), ComputeError> {
+        let mut idx = [0usize; MAX_RANK];
+        let mut unraveled = false;
+        for (i, t) in inputs.iter().enumerate() {
+            let t = t.borrow();
+            offsets[i] = if t.shape() == out_shape {
+                flat
+            } else {
+                if !unraveled {
+                    Tensor::unravel_into(flat, out_shape, &mut idx[..out_shape.len()]);
+                    unraveled = true;
+                }
+                t.broadcasted_flat_index(&idx[..out_shape.len()], out_shape)?
+            };
+        }
+        Ok(())
+    }
+}
+
+impl Op for FusedElementwiseOp {
+    fn forward(&self, inputs: &[Tensor]) -> Result<Tensor, ComputeError> {
+        self.forward_with(inputs, &OpContext::default())
+    }
+
+    fn forward_into(&self, inputs: &[&Tensor], out: &mut Tensor) -> Result<(), ComputeError> {
+        self.forward_into_with(inputs, out, &OpContext::default())
+    }
+
+    fn backward(
+        &self,
+        inputs: &[Tensor],
+        grad_output: &Tensor,
+    ) -> Result<Vec<Tensor>, ComputeError> {
+        self.backward_with(inputs, grad_output, &OpContext::default())
+    }
+
+    fn forward_with(&self, inputs: &[Tensor], ctx: &OpContext) -> Result<Tensor, ComputeError> {
+        let refs: Vec<&Tensor> = inputs.iter().collect();
+        let mut out = Tensor::placeholder();
+        self.forward_into_with(&refs, &mut out, ctx)?;
+        Ok(out)
+    }
+
+    fn forward_into_with(
+        &self,
+        inputs: &[&Tensor],
+        out: &mut Tensor,
+        ctx: &OpContext,
+    ) -> Result<(), ComputeError> {
+        let (shape, rank) = self.output_shape(inputs)?;
+        let out_shape = &shape[..rank];
+        out.set_shape(out_shape);
+        let mut offsets = [0usize; MAX_FUSED_INPUTS];
+        let policy = ctx.policy;
+        for (flat, o) in out.data_mut().iter_mut().enumerate() {
+            Self::input_offsets(inputs, out_shape, flat, &mut offsets)?;
+            let mut v = inputs[0].data()[offsets[0]];
+            for step in &self.steps {
+                v = step.apply(v, inputs, &offsets, flat, policy)?;
+            }
+            *o = v;
+        }
+        Ok(())
+    }
+
+    fn backward_with(
+        &self,
+        inputs: &[Tensor],
+        grad_output: &Tensor,
+        ctx: &OpContext,
+    ) -> Result<Vec<Tensor>, ComputeError> {
+        let (shape, rank) = self.output_shape(inputs)?;
+        let out_shape = &shape[..rank];
+        let numel: usize = out_shape.iter().product();
+        if grad_output.data().len() != numel {
+            return Err(ComputeError::ShapeMismatch {
+                expected: numel,
+                got: grad_output.data().len(),
+            });
+        }
+
+        let mut grads = inputs
+            .iter()
+            .map(Tensor::zeros_like)
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut offsets = [0usize; MAX_FUSED_INPUTS];
+        let mut vals = vec![0.0; self.steps.len() + 1];
+        let policy = ctx.policy;
+
+        for flat in 0..numel {
+            Self::input_offsets(inputs, out_shape, flat, &mut offsets)?;
+            // Recompute the chain for this element only.
+            vals[0] = inputs[0].data()[offsets[0]];
+            for (k, step) in self.steps.iter().enumerate() {
+                vals[k + 1] = step.apply(vals[k], inputs, &offsets, flat, policy)?;
+            }
+
+            let mut g = grad_output.data()[flat];
+            for (k, step) in self.steps.iter().enumerate().rev() {
+                let v = vals[k];
+                match *step {
+                    ElementwiseStep::Relu => {
+                        if v <= 0.0 {
+                            g = 0.0;
+                        }
+                    }
+                    ElementwiseStep::Log => {
+                        g = numeric::div(g, v, policy)
+                            .ok_or_else(|| numeric::singular("log", flat))?;
+                    }
+                    ElementwiseStep::Binary {
+                        kind,
+                        operand,
+                        chain_is_lhs,
+                    } => {
+                        let o = inputs[operand].data()[offsets[operand]];
+                        let pair = if chain_is_lhs {
+                            kind.grads(v, o, g, policy)
+                        } else {
+                            kind.grads(o, v, g, policy).map(|(ga, gb)| (gb, ga))
+                        };
+                        let (g_chain, g_operand) =
+                            pair.ok_or_else(|| numeric::singular(kind.operation(), flat))?;
+                        grads[operand].data_mut()[offsets[operand]] += g_operand;
+                        g = g_chain;
+                    }
+                }
+            }
+            grads[0].data_mut()[offsets[0]] += g;
+        }
+
+        Ok(grads)
+    }
+
+    fn infer_shape(&self, inputs: &[Vec<Dim>]) -> Result<Vec<Dim>, ComputeError> {
+        shape::expect_inputs(inputs, self.arity())?;
+        let mut out = inputs[0].clone();
+        for operand in &inputs[1..] {
+            out = shape::broadcast(&out, operand)?;
+        }
+        Ok(out)
+    }
+
+    fn name(&self) -> &str {
+        "FusedElementwiseOp"
+    }
+
+    fn attributes(&self) -> Option<Vec<(&'static str, String)>> {
+        let steps: Vec<String> = self.steps.iter().map(|s| s.to_string()).collect();
+        Some(vec![("steps", steps.join(","))])
+    }
+}
+
+/// Activation applied in a fused epilogue.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Activation {
+    Relu,
+}
+
+impl Activation {
+    fn apply(self, z: f32) -> f32 {
+        match self {
+            Activation::Relu => z.max(0.0),
+        }
+    }
+
+    /// Upstream gradient `g` times the derivative at pre-activation `z`.
+    fn backprop(self, z: f32, g: f32) -> f32 {
+        match self {
+            Activation::Relu => {
+                if z > 0.0 {
+                    g
+                } else {
+                    0.0
+                }
+            }
+        }
+    }
+}
+
+/// `activation(x @ w + b)` in one kernel. Inputs are `[x, w, b]`; `b` broadcasts over rows.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FusedLinearOp {
+    pub activation: Option<Activation>,
+}
+
+impl FusedLinearOp {
+    /// `x @ w + b`, with the bias added in place into the matmul output buffer.
+    fn pre_activation_into<T: Borrow<Tensor>>(
+        inputs: &[T],
+        z: &mut Tensor,
+    ) -> Result<(), ComputeError> {
+        if inputs.len() != 3 {
+            return Err(ComputeError::InputCountError {
+                expected: 3,
+                got: inputs.len(),
+            });
+        }
+        inputs[0].borrow().matmul_into(inputs[1].borrow(), z)?;
+        let shape = [z.shape()[0], z.shape()[1]];
+        let bias = inputs[2].borrow();
+        let (broadcast, rank) = Tensor::broadcast_shape_into(bias.shape(), &shape)?;
+        if broadcast[..rank] != shape {
+            return Err(ComputeError::DimensionError {
+                message: format!(
+                    "bias shape {:?} does not broadcast to {shape:?}",
+                    bias.shape()
+                ),
+            });
+        }
+        let cols = shape[1];
+        for (flat, v) in z.data_mut().iter_mut().enumerate() {
+            let b_flat = bias.broadcasted_flat_index(&[flat / cols, flat % cols], &shape)?;
+            *v += bias.data()[b_flat];
+        }
+        Ok(())
+    }
+}
+
+impl Op for FusedLinearOp {
+    fn forward(&self, inputs: &[Tensor]) -> Result<Tensor, ComputeError> {
+        let refs: Vec<&Tensor> = inputs.iter().collect();
+        let mut out = Tensor::placeholder();
+        self.forward_into(&refs, &mut out)?;
+        Ok(out)
+    }
+
+    fn forward_into(&self, inputs: &[&Tensor], out: &mut Tensor) -> Result<(), ComputeError> {
+        Self::pre_activation_into(inputs, out)?;
+        if let Some(act) = self.activation {
+            for v in out.data_mut().iter_mut() {
+                *v = act.apply(*v);
+            }
+        }
+        Ok(())
+    }
+
+    fn backward(
+        &self,
+        inputs: &[Tensor],
+        grad_output: &Tensor,
+    ) -> Result<Vec<Tensor>, ComputeError> {
+        let mut z = Tensor::placeholder();
+        Self::pre_activation_into(inputs, &mut z)?;
+        if grad_output.shape() != z.shape() {
+            return Err(ComputeError::ShapeMismatch {
+                expected: z.data().len(),
+                got: grad_output.data().len(),
+            });
+        }
+        let mut grad_z = grad_output.clone();
+        if let Some(act) = self.activation {
+            for (g, &zv) in grad_z.data_mut().iter_mut().zip(z.data()) {
+                *g = act.backprop(zv, *g);
+            }
+        }
+        let grad_x = grad_z.matmul(&inputs[1].transpose_2d()?)?;
+        let grad_w = inputs[0].transpose_2d()?.matmul(&grad_z)?;
+        let grad_b = grad_z.sum_to_shape(inputs[2].shape())?;
+        Ok(vec![grad_x, grad_w, grad_b])
+    }
+
+    fn infer_shape(&self, inputs: &[Vec<Dim>]) -> Result<Vec<Dim>, ComputeError> {
+        shape::expect_inputs(inputs, 3)?;
+        let out = MatMulOp.infer_shape(&inputs[..2])?;
+        if shape::broadcast(&inputs[2], &out)? != out {
+            return Err(ComputeError::DimensionError {
+                message: format!(
+                    "bias shape {} does not broadcast to {}",
+                    shape::format_shape(&inputs[2]),
+                    shape::format_shape(&out)
+                ),
+            });
+        }
+        Ok(out)
+    }
+
+    fn name(&self) -> &str {
+        "FusedLinearOp"
+    }
+
+    fn attributes(&self) -> Option<Vec<(&'static str, String)>> {
+        Some(vec![("activation", format!("{:?}", self.activation))])
+    }
+}
+
+/// Fuses matmul epilogues and elementwise chains.
+///
+/// A node is only folded into its consumer when that consumer is its sole user and it is
+/// not one of `outputs` or the graph's buffer update and seed nodes, so no value observable
+/// from outside the fused kernel disappears.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct OperatorFusion;
+
+enum Pending {
+    Unary(ElementwiseStep),
+    /// Binary kind, the operand node and whether the chain is the left-hand side.
+    Binary(BinaryKind, usize, bool),
+}
+
+struct Chain {
+    base: usize,
+    operands: Vec<usize>,
+    steps: Vec<ElementwiseStep>,
+}
+
+impl GraphPass for OperatorFusion {
+    fn name(&self) -> &'static str {
+        "operator_fusion"
+    }
+
+    fn run(&self, graph: &mut Graph, outputs: &[usize]) -> Result<NodeRemap, ComputeError> {
+        validate(graph, outputs)?;
+        let n = graph.nodes.len();
+        let mut uses = vec![0usize; n];
+        for node in &graph.nodes {
+            if let Node::Operation(_, inputs) = node {
+                for &i in inputs {
+                    uses[i] += 1;
+                }
+            }
+        }
+        for out in outputs.iter().copied().chain(graph.state_roots()) {
+            uses[out] += 1;
+        }
+
+        let mut absorbed = vec![false; n];
+        let mut replacements: HashMap<usize, (Box<dyn Op>, Vec<usize>)> = HashMap::new();
+
+        // Matmul epilogues, visited consumer-first so `Relu(Add(MatMul))` wins over `Add(MatMul)`.
+        for idx in (0..n).rev() {
+            if absorbed[idx] {
+                continue;
+            }
+            let (activation, add_idx) = match op_of(graph, idx) {
+                Some(("ReluOp", inputs)) if uses[inputs[0]] == 1 => match op_of(graph, inputs[0]) {
+                    Some(("AddOp", _)) => (Some(Activation::Relu), inputs[0]),
+                    _ => continue,
+                },
+                Some(("AddOp", _)) => (None, idx),
+                _ => continue,
+            };
+            let add_inputs = match op_of(graph, add_idx) {
+                Some((_, inputs)) => inputs,
+                None => continue,
+            };
+            let (mm_idx, bias_idx) = if is_fusible_matmul(graph, add_inputs[0], &uses) {
+                (add_inputs[0], add_inputs[1])
+            } else if is_fusible_matmul(graph, add_inputs[1], &uses) {
+                (add_inputs[1], add_inputs[0])
+            } else {
+                continue;
+            };
+            if !epilogue_keeps_shape(graph, add_idx, mm_idx) {
+                continue;
+            }
+            let mm_inputs = op_of(graph, mm_idx).map(|(_, i)| i).unwrap_or_default();
+            let op = FusedLinearOp { activation };
+            replacements.insert(
+                idx,
+                (Box::new(op), vec![mm_inputs[0], mm_inputs[1], bias_idx]),
+            );
+            absorbed[mm_idx] = true;
+            if add_idx != idx {
+                absorbed[add_idx] = true;
+            }
+        }
+
+        // Elementwise chains, visited producer-first so each chain grows along its consumers.
+        let mut chains: HashMap<usize, Chain> = HashMap::new();
+        for idx in 0..n {
+            if absorbed[idx] || replacements.contains_key(&idx) {
+                continue;
+            }
+            let (name, inputs) = match op_of(graph, idx) {
+                Some(found) => found,
+                None => continue,
+            };
+            // A chain can absorb `i` if nothing else reads it and it has room for one more operand.
+            let fusible = |i: usize| {
+                uses[i] == 1
+                    && chains
+                        .get(&i)
+                        .is_some_and(|c| c.operands.len() + 1 < MAX_FUSED_INPUTS)
+            };
+
+            let (chain_input, pending) = match (name, inputs.len()) {
+                ("ReluOp", 1) => (inputs[0], Pending::Unary(ElementwiseStep::Relu)),
+                ("LogOp", 1) => (inputs[0], Pending::Unary(ElementwiseStep::Log)),
+                (_, 2) => match BinaryKind::from_op_name(name) {
+                    Some(kind) if fusible(inputs[0]) || !fusible(inputs[1]) => {
+                        (inputs[0], Pending::Binary(kind, inputs[1], true))
+                    }
+                    Some(kind) => (inputs[1], Pending::Binary(kind, inputs[0], false)),
+                    None => continue,
+                },
+                _ => continue,
+            };
+
+            let mut chain = if fusible(chain_input) {
+                absorbed[chain_input] = true;
+                chains.remove(&chain_input).expect("fusible chain exists")
+            } else {
+                Chain {
+                    base: chain_input,
+                    operands: Vec::new(),
+                    steps: Vec::new(),
+                }
+            };
+            let step = match pending {
+                Pending::Unary(step) => step,
+                Pending::Binary(kind, node, chain_is_lhs) => {
+                    let pos = match chain.operands.iter().position(|&o| o == node) {
+                        Some(pos) => pos,
+                        None => {
+                            chain.operands.push(node);
+                            chain.operands.len() - 1
+                        }
+                    };
+                    ElementwiseStep::Binary {
+                        kind,
+                        operand: pos + 1,
+                        chain_is_lhs,
+                    }
+                }
+            };
+            chain.steps.push(step);
+            chains.insert(idx, chain);
+        }
+
+        for (idx, chain) in chains {
+            if chain.steps.len() < 2 {
+                continue;
+            }
+            let mut inputs = vec![chain.base];
+            inputs.extend(chain.operands);
+            let op = FusedElementwiseOp { steps: chain.steps };
+            replacements.insert(idx, (Box::new(op), inputs));
+        }
+
+        for (idx, (op, inputs)) in replacements {
+            graph.nodes[idx] = Node::Operation(op, inputs);
+        }
+        let keep: Vec<bool> = absorbed.iter().map(|a| !a).collect();
+        let redirect: Vec<usize> = (0..n).collect();
+        compact(graph, &keep, &redirect)
+    }
+}
+
+fn op_of(graph: &Graph, idx: usize) -> Option<(&str, Vec<usize>)> {
+    match &graph.nodes[idx] {
+        Node::Operation(op, inputs) => Some((op.name(), inputs.clone())),
+        _ => None,
+    }
+}
+
+/// `FusedLinearOp` writes the matmul's shape, so a bias that broadcasts the sum to a
+/// larger shape must stay a separate add.
+fn epilogue_keeps_shape(graph: &Graph, add_idx: usize, mm_idx: usize) -> bool {
+    match graph.infer_shapes(add_idx, &[]) {
+        Ok(shapes) => shapes[&add_idx] == shapes[&mm_idx],
+        Err(_) => false,
+    }
+}
+
+fn is_fusible_matmul(graph: &Graph, idx: usize, uses: &[usize]) -> bool {
+    uses[idx] == 1 && matches!(op_of(graph, idx), Some(("MatMulOp", _)))
+}
@@ -1,13 +1,19 @@
 use std::collections::{HashMap, HashSet};
+use std::ops::Range;
 
-use crate::error::ComputeError;
-use crate::ops::Op;
+use crate::error::{ComputeError, NodeContext};
+use crate::numeric::NumericPolicy;
+use crate::ops::{Op, OpContext};
+use crate::parallel::{self, ThreadPool};
+use crate::prng::XorShift32;
+use crate::shape::{self, Dim};
 use crate::tensor::Tensor;
 
 /// Represents a node in the computational graph.
 pub enum Node {
     Input(Tensor),
-    Parameter(Tensor, bool),            // Tensor + requires_grad
+    Constant(Tensor),        // Fixed value, safe to fold into other constants
+    Parameter(Tensor, bool), // Tensor + requires_grad
     Operation(Box<dyn Op>, Vec<usize>), // Op + input node indices
 }
 
@@ -15,6 +21,60 @@ pub enum Node {
 pub struct Graph {
     pub(crate) nodes: Vec<Node>,
     pub(crate) gradients: HashMap<usize, Tensor>, // Node index -> gradient
+    pub(crate) labels: HashMap<usize, String>,    // Node index -> user label
+    pub(crate) checkpoints: Vec<Range<usize>>,    // Node ranges recomputed in backward
+    pub(crate) buffer_updates: Vec<(usize, usize)>, // (buffer parameter, new value node)
+    pub(crate) seed_nodes: Vec<usize>,            // Parameters redrawn by `advance_seeds`
+    pub(crate) rng: XorShift32,
+    pool: ThreadPool,
+    detect_anomalies: bool,
+    numeric_policy: NumericPolicy,
+    stats: BackwardStats,
+}
+
+/// Memory and recompute counters from the most recent `Graph::backward`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct BackwardStats {
+    /// Largest number of `f32` elements held in cached op outputs at any point.
+    pub peak_cached_elements: usize,
+    /// Number of op evaluations repeated to rematerialize checkpointed values.
+    pub recomputed_ops: usize,
+}
+
+/// Op outputs kept alive while evaluating or differentiating a graph.
+#[derive(Default)]
+struct ValueCache<'a> {
+    values: HashMap<usize, Tensor>,
+    /// Values substituted for `Input` nodes.
+    feeds: HashMap<usize, Tensor>,
+    /// Settings passed to every op evaluated or differentiated with this cache.
+    ctx: OpContext<'a>,
+    elements: usize,
+    stats: BackwardStats,
+}
+
+impl<'a> ValueCache<'a> {
+    fn with_feeds(feeds: &[(usize, Tensor)], ctx: &OpContext<'a>) -> Self {
+        Self {
+            feeds: feeds.iter().cloned().collect(),
+            ctx: *ctx,
+            ..Self::default()
+        }
+    }
+
+    fn insert(&mut self, idx: usize, value: Tensor) {
+        self.elements += value.data().len();
+        self.stats.peak_cached_elements = self.stats.peak_cached_elements.max(self.elements);
+        if let Some(old) = self.values.insert(idx, value) {
+            self.elements -= old.data().len();
+        }
+    }
+
+    fn remove(&mut self, idx: usize) -> Option<Tensor> {
+        let value = self.values.remove(&idx)?;
+        self.elements -= value.data().len();
+        Some(value)
+    }
 }
 
 impl Graph {
@@ -22,6 +82,58 @@ impl Graph {
         Self {
             nodes: Vec::new(),
             gradients: HashMap::new(),
+            labels: HashMap::new(),
+            checkpoints: Vec::new(),
+            buffer_updates: Vec::new(),
+            seed_nodes: Vec::new(),
+            rng: XorShift32::new(0),
+            pool: ThreadPool::new(1),
+            detect_anomalies: false,
+            numeric_policy: NumericPolicy::Ieee,
+            stats: BackwardStats::default(),
+        }
+    }
+
+    /// Number of nodes in the graph.
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    /// Attach a human-readable label to a node; it is reported in errors from that node.
+    pub fn set_label(&mut self, node_idx: usize, label: &str) {
+        self.labels.insert(node_idx, label.to_string());
+    }
+
+    pub fn label(&self, node_idx: usize) -> Option<&str> {
+        self.labels.get(&node_idx).map(|l| l.as_str())
+    }
+
+    /// Wrap an error raised by the op at `node_idx` with the node's context.
+    pub(crate) fn node_error(
+        &self,
+        node_idx: usize,
+        input_shapes: Vec<Vec<Dim>>,
+        source: ComputeError,
+    ) -> ComputeError {
+        let op = match self.nodes.get(node_idx) {
+            Some(Node::Operation(op, _)) => op.name().to_string(),
+            Some(Node::Input(_)) => "Input".to_string(),
+            Some(Node::Constant(_)) => "Constant".to_string(),
+            Some(Node::Parameter(_, _)) => "Parameter".to_string(),
+            None => "?".to_string(),
+        };
+        ComputeError::Node {
+            context: NodeContext {
+                node: node_idx,
+                op,
+                input_shapes,
+                label: self.labels.get(&node_idx).cloned(),
+            },
+            source: Box::new(source),
         }
     }
 
@@ -31,6 +143,27 @@ impl Graph {
         idx
     }
 
+    /// Add a node whose value never changes. Unlike inputs, constants may be folded into
+    /// the ops that read them by `passes::ConstantFolding`.
+    pub fn add_constant(&mut self, tensor: Tensor) -> usize {
+        let idx = self.nodes.len();
+        self.nodes.push(Node::Constant(tensor));
+        idx
+    }
+
+    /// Replace the value of an input node, e.g. one loaded as a placeholder.
+    pub fn set_input(&mut self, node_idx: usize, tensor: Tensor) -> Result<(), ComputeError> {
+        match self.nodes.get_mut(node_idx) {
+            Some(Node::Input(t)) => {
+                *t = tensor;
+                Ok(())
+            }
+            _ => Err(ComputeError::InvalidOperation {
+                message: format!("node {node_idx} is not an input"),
+            }),
+        }
+    }
+
     pub fn add_parameter(&mut self, tensor: Tensor, requires_grad: bool) -> usize {
         let idx = self.nodes.len();
         self.nodes.push(Node::Parameter(tensor, requires_grad));
@@ -45,80 +178,464 @@ impl Graph {
     }
 
     pub fn forward(&self, node_idx: usize) -> Result<Tensor, ComputeError> {
-        let node = self
-            .nodes
-            .get(node_idx)
-            .ok_or_else(|| ComputeError::IndexError {
-                message: format!("node index out of bounds: {node_idx}"),
-            })?;
-
-        match node {
-            Node::Input(t) | Node::Parameter(t, _) => Ok(t.clone()),
-            Node::Operation(op, input_indices) => {
-                let mut inputs = Vec::with_capacity(input_indices.len());
-                for &idx in input_indices {
-                    inputs.push(self.forward(idx)?);
-                }
-                op.forward(&inputs)
+        self.forward_with(node_idx, &[], &self.op_context())
+    }
+
+    /// `forward` with the values of some `Input` nodes replaced by `feeds`, running ops
+    /// with `ctx` instead of this graph's settings.
+    pub(crate) fn forward_with(
+        &self,
+        node_idx: usize,
+        feeds: &[(usize, Tensor)],
+        ctx: &OpContext,
+    ) -> Result<Tensor, ComputeError> {
+        let order = self.topological_sort(node_idx)?;
+        let discard: HashSet<usize> = order.iter().copied().filter(|&i| i != node_idx).collect();
+        let mut cache = ValueCache::with_feeds(feeds, ctx);
+        self.evaluate(&order, &discard, &mut cache)?;
+        Ok(match cache.remove(node_idx) {
+            Some(value) => value,
+            None => self.value(node_idx, &cache).clone(),
+        })
+    }
+
+    /// Mark the op nodes in `nodes` as a checkpointed segment.
+    ///
+    /// `backward` drops the segment's intermediate values once the forward sweep no longer
+    /// needs them and recomputes them from the segment's inputs when the backward sweep
+    /// reaches it. Values read by nodes outside the segment are kept as usual.
+    pub fn checkpoint(&mut self, nodes: Range<usize>) {
+        if !nodes.is_empty() {
+            self.checkpoints.push(nodes);
+        }
+    }
+
+    /// Segments marked with `checkpoint`.
+    pub fn checkpoints(&self) -> &[Range<usize>] {
+        &self.checkpoints
+    }
+
+    /// Have `update_buffers` copy the value of node `value` into the parameter `buffer`,
+    /// e.g. to carry running statistics from one training step to the next.
+    ///
+    /// A buffer holds at most one update: registering another replaces the earlier one, so
+    /// a layer applied again (say, to a new batch) only updates from its latest output.
+    pub fn add_buffer_update(&mut self, buffer: usize, value: usize) {
+        match self.buffer_updates.iter_mut().find(|(b, _)| *b == buffer) {
+            Some(update) => update.1 = value,
+            None => self.buffer_updates.push((buffer, value)),
+        }
+    }
+
+    /// Updates registered with `add_buffer_update`, as `(buffer, value)` pairs.
+    pub fn buffer_updates(&self) -> &[(usize, usize)] {
+        &self.buffer_updates
+    }
+
+    /// Evaluate every registered buffer update, then write all of them at once, so each
+    /// new value is computed from the old buffers. Returns how many buffers were written.
+    pub fn update_buffers(&mut self) -> Result<usize, ComputeError> {
+        let values = self
+            .buffer_updates
+            .iter()
+            .map(|&(buffer, value)| Ok((buffer, self.forward(value)?)))
+            .collect::<Result<Vec<_>, ComputeError>>()?;
+        for (buffer, value) in &values {
+            self.set_parameter(*buffer, value.clone())?;
+        }
+        Ok(values.len())
+    }
+
+    /// Nodes the graph itself reads outside of any output: buffer update pairs and seed
+    /// nodes. Graph passes keep them alive like outputs.
+    pub(crate) fn state_roots(&self) -> Vec<usize> {
+        let mut roots: Vec<usize> = self
+            .buffer_updates
+            .iter()
+            .flat_map(|&(buffer, value)| [buffer, value])
+            .collect();
+        roots.extend(&self.seed_nodes);
+        roots
+    }
+
+    /// Restart the graph's random generator from `seed` and redraw every seed node, so
+    /// stochastic ops such as dropout repeat the same masks on every run with this seed.
+    pub fn set_seed(&mut self, seed: u32) {
+        self.rng = XorShift32::new(seed);
+        self.advance_seeds();
+    }
+
+    /// Add a frozen `[1]` parameter holding a seed drawn from the graph's generator, for
+    /// stochastic ops to derive their random draws from. Its value only changes through
+    /// `set_seed` and `advance_seeds`, so forward and backward passes in between agree.
+    pub fn add_seed_node(&mut self) -> usize {
+        let seed = self.next_seed();
+        let idx = self.add_parameter(seed, false);
+        self.seed_nodes.push(idx);
+        idx
+    }
+
+    /// Draw fresh values for every seed node, e.g. once per training step.
+    pub fn advance_seeds(&mut self) {
+        for i in 0..self.seed_nodes.len() {
+            let seed = self.next_seed();
+            if let Some(Node::Parameter(t, _)) = self.nodes.get_mut(self.seed_nodes[i]) {
+                *t = seed;
             }
         }
     }
 
+    /// A 24-bit seed, which `f32` holds exactly.
+    fn next_seed(&mut self) -> Tensor {
+        let seed = (self.rng.next_u32() >> 8) as f32;
+        Tensor::new(vec![seed], vec![1]).expect("one value for a [1] shape")
+    }
+
+    pub fn backward_stats(&self) -> BackwardStats {
+        self.stats
+    }
+
+    /// Evaluate independent nodes on up to `threads` threads in `forward` and `backward`.
+    /// Results, including gradient accumulation order, do not depend on it.
+    ///
+    /// The graph keeps `threads - 1` worker threads alive until the count changes or the
+    /// graph is dropped.
+    pub fn set_num_threads(&mut self, threads: usize) {
+        if threads.max(1) != self.pool.threads() {
+            self.pool = ThreadPool::new(threads);
+        }
+    }
+
+    pub fn num_threads(&self) -> usize {
+        self.pool.threads()
+    }
+
+    /// Debug mode: fail `forward` and `backward` at the first op whose output or input
+    /// gradient contains NaN or infinity, naming the node and op.
+    pub fn set_detect_anomalies(&mut self, enabled: bool) {
+        self.detect_anomalies = enabled;
+    }
+
+    pub fn detect_anomalies(&self) -> bool {
+        self.detect_anomalies
+    }
+
+    /// How divide, log and sqrt ops of this graph handle their singular points.
+    pub fn set_numeric_policy(&mut self, policy: NumericPolicy) {
+        self.numeric_policy = policy;
+    }
+
+    pub fn numeric_policy(&self) -> NumericPolicy {
+        self.numeric_policy
+    }
+
+    /// The settings this graph passes to its ops.
+    pub(crate) fn op_context(&self) -> OpContext<'_> {
+        OpContext::new(self.numeric_policy, self.detect_anomalies, &self.pool)
+    }
+
     pub fn backward(&mut self, output_idx: usize) -> Result<(), ComputeError> {
-        let output = self.forward(output_idx)?;
-        let grad_output = Tensor::ones_like(&output);
-        self.gradients.insert(output_idx, grad_output);
+        let mut gradients = std::mem::take(&mut self.gradients);
+        let result = self.backprop(output_idx, None, &[], &self.op_context(), &mut gradients);
+        self.gradients = gradients;
+        self.stats = result?;
+        Ok(())
+    }
 
-        let sorted_nodes = self.topological_sort(output_idx)?;
+    /// Gradients of `output_idx` with respect to each fed `Input` node, given `grad_output`.
+    ///
+    /// Leaves the graph untouched; fed nodes without a path to the output get zeros. Ops
+    /// run with `ctx` instead of this graph's settings.
+    pub(crate) fn input_gradients(
+        &self,
+        output_idx: usize,
+        feeds: &[(usize, Tensor)],
+        grad_output: &Tensor,
+        ctx: &OpContext,
+    ) -> Result<Vec<Tensor>, ComputeError> {
+        let mut gradients = HashMap::new();
+        self.backprop(output_idx, Some(grad_output), feeds, ctx, &mut gradients)?;
+        feeds
+            .iter()
+            .map(|(idx, value)| match gradients.remove(idx) {
+                Some(grad) => Ok(grad),
+                None => Tensor::zeros_like(value),
+            })
+            .collect()
+    }
 
-        for &node_idx in sorted_nodes.iter().rev() {
-            let grad = match self.gradients.get(&node_idx).cloned() {
-                Some(g) => g,
-                None => continue,
-            };
+    /// Reverse sweep from `output_idx` seeded with `grad_output` (ones if `None`),
+    /// accumulating into `gradients`. Fed inputs receive gradients like parameters.
+    fn backprop(
+        &self,
+        output_idx: usize,
+        grad_output: Option<&Tensor>,
+        feeds: &[(usize, Tensor)],
+        ctx: &OpContext,
+        gradients: &mut HashMap<usize, Tensor>,
+    ) -> Result<BackwardStats, ComputeError> {
+        let sorted_nodes = self.topological_sort(output_idx)?;
+        let discard = self.checkpointed_intermediates(&sorted_nodes, output_idx);
+        let mut cache = ValueCache::with_feeds(feeds, ctx);
+        self.evaluate(&sorted_nodes, &discard, &mut cache)?;
 
-            let node = self
-                .nodes
-                .get(node_idx)
-                .ok_or_else(|| ComputeError::IndexError {
-                    message: format!("node index out of bounds: {node_idx}"),
-                })?;
+        let seed = match grad_output {
+            Some(grad) => grad.clone(),
+            None => Tensor::ones_like(self.value(output_idx, &cache)),
+        };
+        gradients.insert(output_idx, seed);
 
-            if let Node::Operation(op, input_indices) = node {
-                let mut inputs = Vec::with_capacity(input_indices.len());
-                for &idx in input_indices {
-                    inputs.push(self.forward(idx)?);
+        let mut consumers: HashMap<usize, Vec<usize>> = HashMap::new();
+        for &idx in &sorted_nodes {
+            if let Node::Operation(_, input_indices) = &self.nodes[idx] {
+                for &input in input_indices {
+                    consumers.entry(input).or_default().push(idx);
                 }
+            }
+        }
+        let reversed: Vec<usize> = sorted_nodes.iter().rev().copied().collect();
+        let waves = parallel::waves(&reversed, |idx| {
+            consumers.get(&idx).cloned().unwrap_or_default()
+        });
 
-                let input_grads = op.backward(&inputs, &grad)?;
-                if input_grads.len() != input_indices.len() {
-                    return Err(ComputeError::InvalidOperation {
-                        message: "op.backward returned wrong number of gradients".to_string(),
-                    });
+        // Gradient contributions per node as (consumer, gradient), summed once every
+        // consumer has run.
+        let mut pending: HashMap<usize, Vec<(usize, Tensor)>> = HashMap::new();
+        for wave in waves {
+            let mut jobs = Vec::new();
+            for &node_idx in wave.iter().rev() {
+                // Every consumer of this node has been differentiated, so its value is dead.
+                cache.remove(node_idx);
+                let contributions = pending.remove(&node_idx).unwrap_or_default();
+                let Some(grad) = accumulate(gradients, node_idx, contributions) else {
+                    continue;
+                };
+                if let Node::Operation(_, input_indices) = &self.nodes[node_idx] {
+                    for &idx in input_indices {
+                        self.rematerialize(idx, &mut cache)?;
+                    }
+                    jobs.push((node_idx, grad));
                 }
+            }
 
-                for (&input_idx, input_grad) in input_indices.iter().zip(input_grads.into_iter()) {
+            let results = cache.ctx.map(&jobs, |(node_idx, grad)| {
+                self.backward_node(*node_idx, grad, &cache)
+            });
+            for ((node_idx, _), result) in jobs.iter().zip(results) {
+                let Node::Operation(_, input_indices) = &self.nodes[*node_idx] else {
+                    unreachable!("jobs are operation nodes");
+                };
+                for (&input_idx, input_grad) in input_indices.iter().zip(result?) {
                     // Only accumulate gradients for nodes that should receive gradients.
-                    if !self.node_requires_grad(input_idx) {
-                        continue;
+                    if self.node_requires_grad(input_idx) || cache.feeds.contains_key(&input_idx) {
+                        pending
+                            .entry(input_idx)
+                            .or_default()
+                            .push((*node_idx, input_grad));
                     }
+                }
+            }
+        }
+
+        Ok(cache.stats)
+    }
+
+    /// Run the backward of the op at `node_idx` on cached input values.
+    fn backward_node(
+        &self,
+        node_idx: usize,
+        grad: &Tensor,
+        cache: &ValueCache,
+    ) -> Result<Vec<Tensor>, ComputeError> {
+        let Node::Operation(op, input_indices) = &self.nodes[node_idx] else {
+            return Ok(Vec::new());
+        };
+        let inputs: Vec<Tensor> = input_indices
+            .iter()
+            .map(|&i| self.value(i, cache).clone())
+            .collect();
+        let input_grads = op
+            .backward_with(&inputs, grad, &cache.ctx)
+            .map_err(|e| self.node_error(node_idx, tensor_shapes(&inputs), e))?;
+        if input_grads.len() != input_indices.len() {
+            let err = ComputeError::InvalidOperation {
+                message: "op.backward returned wrong number of gradients".to_string(),
+            };
+            return Err(self.node_error(node_idx, tensor_shapes(&inputs), err));
+        }
+        if cache.ctx.detect_anomalies {
+            for (i, input_grad) in input_grads.iter().enumerate() {
+                check_finite(input_grad, &format!("gradient for input {i}"))
+                    .map_err(|e| self.node_error(node_idx, tensor_shapes(&inputs), e))?;
+            }
+        }
+        Ok(input_grads)
+    }
+
+    /// Evaluate the op nodes of `order` into `cache` wave by wave, dropping each `discard`
+    /// node once the wave holding its last reader has run.
+    fn evaluate(
+        &self,
+        order: &[usize],
+        discard: &HashSet<usize>,
+        cache: &mut ValueCache,
+    ) -> Result<(), ComputeError> {
+        let ops: Vec<usize> = order
+            .iter()
+            .copied()
+            .filter(|&idx| matches!(self.nodes[idx], Node::Operation(_, _)))
+            .collect();
+        let inputs_of = |idx: usize| match &self.nodes[idx] {
+            Node::Operation(_, input_indices) => input_indices.clone(),
+            _ => Vec::new(),
+        };
+        let waves = parallel::waves(&ops, inputs_of);
 
-                    self.gradients
-                        .entry(input_idx)
-                        .and_modify(|existing| {
-                            // Accumulate if node used multiple times.
-                            // Any error here is a programming error: gradients must be compatible.
-                            *existing = existing.add(&input_grad).expect("gradient add");
-                        })
-                        .or_insert(input_grad);
+        let mut last_use: HashMap<usize, usize> = HashMap::new();
+        for (w, wave) in waves.iter().enumerate() {
+            for &idx in wave {
+                for input in inputs_of(idx) {
+                    last_use.insert(input, w);
                 }
             }
         }
 
+        for (w, wave) in waves.iter().enumerate() {
+            let ctx = cache.ctx;
+            let values = ctx.map(wave, |&idx| self.evaluate_node(idx, cache));
+            for (&idx, value) in wave.iter().zip(values) {
+                cache.insert(idx, value?);
+            }
+            for &idx in wave {
+                for input in inputs_of(idx) {
+                    if discard.contains(&input) && last_use.get(&input) == Some(&w) {
+                        cache.remove(input);
+                    }
+                }
+            }
+        }
         Ok(())
     }
 
-    fn topological_sort(&self, start_idx: usize) -> Result<Vec<usize>, ComputeError> {
+    /// Run the op at `idx` on cached input values.
+    fn evaluate_node(&self, idx: usize, cache: &ValueCache) -> Result<Tensor, ComputeError> {
+        let Node::Operation(op, input_indices) = &self.nodes[idx] else {
+            return Ok(self.value(idx, cache).clone());
+        };
+        let inputs: Vec<Tensor> = input_indices
+            .iter()
+            .map(|&i| self.value(i, cache).clone())
+            .collect();
+        let value = op
+            .forward_with(&inputs, &cache.ctx)
+            .map_err(|e| self.node_error(idx, tensor_shapes(&inputs), e))?;
+        if cache.ctx.detect_anomalies {
+            check_finite(&value, "forward output")
+                .map_err(|e| self.node_error(idx, tensor_shapes(&inputs), e))?;
+        }
+        Ok(value)
+    }
+
+    /// Recompute the value of `idx`, and any dropped values it depends on, into `cache`.
+    fn rematerialize(&self, idx: usize, cache: &mut ValueCache) -> Result<(), ComputeError> {
+        let Node::Operation(_, input_indices) = &self.nodes[idx] else {
+            return Ok(());
+        };
+        if cache.values.contains_key(&idx) {
+            return Ok(());
+        }
+        for &input in input_indices {
+            self.rematerialize(input, cache)?;
+        }
+        let value = self.evaluate_node(idx, cache)?;
+        cache.insert(idx, value);
+        cache.stats.recomputed_ops += 1;
+        Ok(())
+    }
+
+    fn value<'a>(&'a self, idx: usize, cache: &'a ValueCache) -> &'a Tensor {
+        match &self.nodes[idx] {
+            Node::Input(t) => cache.feeds.get(&idx).unwrap_or(t),
+            Node::Constant(t) | Node::Parameter(t, _) => t,
+            Node::Operation(_, _) => cache
+                .values
+                .get(&idx)
+                .expect("op value evaluated before use"),
+        }
+    }
+
+    /// Op nodes inside a checkpointed segment whose every reader is in the same segment.
+    fn checkpointed_intermediates(&self, order: &[usize], output_idx: usize) -> HashSet<usize> {
+        let segment_of = |idx: usize| self.checkpoints.iter().position(|r| r.contains(&idx));
+        let mut discard: HashSet<usize> = order
+            .iter()
+            .copied()
+            .filter(|&idx| {
+                idx != output_idx
+                    && matches!(self.nodes[idx], Node::Operation(_, _))
+                    && segment_of(idx).is_some()
+            })
+            .collect();
+        for &idx in order {
+            if let Node::Operation(_, input_indices) = &self.nodes[idx] {
+                for &input in input_indices {
+                    if segment_of(input) != segment_of(idx) {
+                        discard.remove(&input);
+                    }
+                }
+            }
+        }
+        discard
+    }
+
+    /// Statically infer the shape of `output` without evaluating any op.
+    ///
+    /// Fails on the first node whose input shapes are inconsistent, naming the node, its op
+    /// and its input shapes.
+    pub fn check(&self, output_idx: usize) -> Result<Vec<Dim>, ComputeError> {
+        self.check_symbolic(output_idx, &[])
+    }
+
+    /// Like `check`, but with the shapes of some `Input` or `Parameter` nodes overridden,
+    /// e.g. to declare a symbolic batch dimension with `Dim::symbol("batch")`.
+    pub fn check_symbolic(
+        &self,
+        output_idx: usize,
+        overrides: &[(usize, Vec<Dim>)],
+    ) -> Result<Vec<Dim>, ComputeError> {
+        let mut shapes = self.infer_shapes(output_idx, overrides)?;
+        Ok(shapes
+            .remove(&output_idx)
+            .expect("output shape was inferred"))
+    }
+
+    /// Static shapes of every node `output_idx` depends on.
+    pub(crate) fn infer_shapes(
+        &self,
+        output_idx: usize,
+        overrides: &[(usize, Vec<Dim>)],
+    ) -> Result<HashMap<usize, Vec<Dim>>, ComputeError> {
+        let mut shapes: HashMap<usize, Vec<Dim>> = HashMap::new();
+        for idx in self.topological_sort(output_idx)? {
+            let node_shape = match &self.nodes[idx] {
+                Node::Input(t) | Node::Constant(t) | Node::Parameter(t, _) => overrides
+                    .iter()
+                    .find(|(i, _)| *i == idx)
+                    .map(|(_, s)| s.clone())
+                    .unwrap_or_else(|| shape::fixed(t.shape())),
+                Node::Operation(op, input_indices) => {
+                    let input_shapes: Vec<Vec<Dim>> =
+                        input_indices.iter().map(|i| shapes[i].clone()).collect();
+                    op.infer_shape(&input_shapes)
+                        .map_err(|e| self.node_error(idx, input_shapes.clone(), e))?
+                }
+            };
+            shapes.insert(idx, node_shape);
+        }
+        Ok(shapes)
+    }
+
+    pub(crate) fn topological_sort(&self, start_idx: usize) -> Result<Vec<usize>, ComputeError> {
         let mut result = Vec::new();
         let mut visited = HashSet::new();
 
@@ -166,7 +683,7 @@ impl Graph {
         match self.nodes.get(node_idx) {
             Some(Node::Parameter(_, requires_grad)) => *requires_grad,
             Some(Node::Operation(_, _)) => true,
-            Some(Node::Input(_)) => false,
+            Some(Node::Input(_) | Node::Constant(_)) => false,
             None => false,
         }
     }
@@ -183,6 +700,53 @@ impl Graph {
         }
     }
 
+    /// The value of a parameter node, trainable or frozen.
+    pub fn parameter(&self, node_idx: usize) -> Result<&Tensor, ComputeError> {
+        match self.nodes.get(node_idx) {
+            Some(Node::Parameter(t, _)) => Ok(t),
+            _ => Err(ComputeError::InvalidOperation {
+                message: "parameter expected".to_string(),
+            }),
+        }
+    }
+
+    /// Overwrite a parameter node's value; the shape must not change.
+    pub fn set_parameter(&mut self, node_idx: usize, tensor: Tensor) -> Result<(), ComputeError> {
+        match self.nodes.get_mut(node_idx) {
+            Some(Node::Parameter(t, _)) if t.shape() == tensor.shape() => {
+                *t = tensor;
+                Ok(())
+            }
+            Some(Node::Parameter(t, _)) => Err(ComputeError::DimensionError {
+                message: format!(
+                    "parameter {node_idx} has shape {:?}, got {:?}",
+                    t.shape(),
+                    tensor.shape()
+                ),
+            }),
+            _ => Err(ComputeError::InvalidOperation {
+                message: "parameter expected".to_string(),
+            }),
+        }
+    }
+
+    /// Freeze (`false`) or unfreeze (`true`) a parameter node.
+    pub fn set_requires_grad(
+        &mut self,
+        node_idx: usize,
+        requires_grad: bool,
+    ) -> Result<(), ComputeError> {
+        match self.nodes.get_mut(node_idx) {
+            Some(Node::Parameter(_, flag)) => {
+                *flag = requires_grad;
+                Ok(())
+            }
+            _ => Err(ComputeError::InvalidOperation {
+                message: "parameter expected".to_string(),
+            }),
+        }
+    }
+
     pub fn get_tensor(&self, node_idx: usize) -> Result<Tensor, ComputeError> {
         self.forward(node_idx)
     }
@@ -193,3 +757,41 @@ impl Default for Graph {
         Self::new()
     }
 }
+
+fn check_finite(tensor: &Tensor, location: &str) -> Result<(), ComputeError> {
+    match tensor.data().iter().position(|v| !v.is_finite()) {
+        Some(index) => Err(ComputeError::NonFinite {
+            location: location.to_string(),
+            index,
+            value: tensor.data()[index],
+        }),
+        None => Ok(()),
+    }
+}
+
+fn tensor_shapes(tensors: &[Tensor]) -> Vec<Vec<Dim>> {
+    tensors.iter().map(|t| shape::fixed(t.shape())).collect()
+}
+
+/// Sum the gradient contributions for `node_idx` into `gradients`.
+///
+/// Contributions are added in descending consumer order, then argument order, so the
+/// result is the same for any thread count.
+fn accumulate(
+    gradients: &mut HashMap<usize, Tensor>,
+    node_idx: usize,
+    mut contributions: Vec<(usize, Tensor)>,
+) -> Option<Tensor> {
+    contributions.sort_by_key(|c| std::cmp::Reverse(c.0));
+    for (_, grad) in contributions {
+        gradients
+            .entry(node_idx)
+            .and_modify(|existing| {
+                // Accumulate if node used multiple times.
+                // Any error here is a programming error: gradients must be compatible.
+                *existing = existing.add(&grad).expect("gradient add");
+            })
+            .or_insert(grad);
+    }
+    gradients.get(&node_idx).cloned()
+}
@@ -5,3 +5,4 @@ pub mod mtconnect;
 pub mod opcua;
 pub mod replay;
 pub mod schema;
+pub mod vocab;
@@ -7,18 +7,25 @@ use crate::industrial::ingest::IngestSource;
 use crate::industrial::schema::{IndustrialRecord, MachineState, SensorSample, ToolEvent};
 
 pub struct ReplaySource {
+    path: String,
     lines: std::io::Lines<BufReader<File>>,
+    line_no: usize,
 }
 
 impl ReplaySource {
     pub fn from_path(path: &Path) -> Result<Self, ComputeError> {
-        let file = File::open(path).map_err(|e| ComputeError::InvalidOperation {
-            message: format!("failed opening replay file {}: {e}", path.display()),
-        })?;
+        let file = File::open(path)
+            .map_err(|e| ComputeError::io(format!("opening replay file {}", path.display()), e))?;
         Ok(Self {
+            path: path.display().to_string(),
             lines: BufReader::new(file).lines(),
+            line_no: 0,
         })
     }
+
+    fn location(&self) -> String {
+        format!("{}:{}", self.path, self.line_no)
+    }
 }
 
 fn extract_str(line: &str, key: &str) -> Option<String> {
@@ -71,17 +78,17 @@ impl IngestSource for ReplaySource {
         let line = match self.lines.next() {
             Some(Ok(line)) => line,
             Some(Err(e)) => {
-                return Err(ComputeError::InvalidOperation {
-                    message: format!("failed reading replay line: {e}"),
-                });
+                let context = format!("reading {}:{}", self.path, self.line_no + 1);
+                return Err(ComputeError::io(context, e));
             }
             None => return Ok(None),
         };
+        self.line_no += 1;
 
-        let rec_type =
-            extract_str(&line, "type").ok_or_else(|| ComputeError::InvalidOperation {
-                message: "missing type field in replay line".to_string(),
-            })?;
+        let rec_type = extract_str(&line, "type").ok_or_else(|| ComputeError::Parse {
+            context: self.location(),
+            message: "missing type field".to_string(),
+        })?;
 
         let rec = match rec_type.as_str() {
             "machine_state" => IndustrialRecord::MachineState(MachineState {
@@ -101,8 +108,9 @@ impl IngestSource for ReplaySource {
                 event_type: extract_str(&line, "event_type").unwrap_or_default(),
             }),
             other => {
-                return Err(ComputeError::InvalidOperation {
-                    message: format!("unknown record type in replay line: {other}"),
+                return Err(ComputeError::Parse {
+                    context: self.location(),
+                    message: format!("unknown record type {other}"),
                 });
             }
         };
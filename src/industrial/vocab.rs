@@ -0,0 +1,151 @@
+//! Vocabularies mapping categorical identifiers, such as NC program names and tool ids, to
+//! embedding indices.
+
+use std::collections::HashMap;
+
+use crate::error::ComputeError;
+use crate::industrial::schema::IndustrialRecord;
+use crate::tensor::Tensor;
+
+/// A fixed mapping from identifiers to indices `1..len()`, with index `OOV` (0) shared by
+/// identifiers that were not kept and by missing values.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Vocabulary {
+    tokens: Vec<String>,
+    index: HashMap<String, usize>,
+}
+
+impl Vocabulary {
+    /// The out-of-vocabulary bucket.
+    pub const OOV: usize = 0;
+
+    /// A vocabulary whose index `i + 1` is `tokens[i]`, e.g. as saved from `tokens`.
+    pub fn from_tokens(tokens: Vec<String>) -> Result<Self, ComputeError> {
+        let mut index = HashMap::with_capacity(tokens.len());
+        for (i, token) in tokens.iter().enumerate() {
+            if index.insert(token.clone(), i + 1).is_some() {
+                return Err(ComputeError::InvalidOperation {
+                    message: format!("duplicate vocabulary entry {token:?}"),
+                });
+            }
+        }
+        Ok(Self { tokens, index })
+    }
+
+    /// Known identifiers in index order, starting at index 1.
+    pub fn tokens(&self) -> &[String] {
+        &self.tokens
+    }
+
+    /// Number of indices, including the OOV bucket: the `num_embeddings` of a matching
+    /// `Embedding`.
+    pub fn len(&self) -> usize {
+        self.tokens.len() + 1
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tokens.is_empty()
+    }
+
+    /// Index of `token`, or `OOV` if it is unknown or missing.
+    pub fn index(&self, token: Option<&str>) -> usize {
+        token
+            .and_then(|t| self.index.get(t).copied())
+            .unwrap_or(Self::OOV)
+    }
+
+    /// The identifier at `index`, or `None` for the OOV bucket and out-of-range indices.
+    pub fn token(&self, index: usize) -> Option<&str> {
+        index
+            .checked_sub(1)
+            .and_then(|i| self.tokens.get(i))
+            .map(String::as_str)
+    }
+
+    /// Indices of `tokens` as a tensor of `shape`, ready for `EmbeddingOp`.
+    pub fn encode<'a>(
+        &self,
+        tokens: impl IntoIterator<Item = Option<&'a str>>,
+        shape: Vec<usize>,
+    ) -> Result<Tensor, ComputeError> {
+        let data = tokens.into_iter().map(|t| self.index(t) as f32).collect();
+        Tensor::new(data, shape)
+    }
+}
+
+/// Counts identifiers and keeps the frequent ones.
+///
+/// Identifiers are ordered by descending count, ties broken alphabetically, so the
+/// resulting indices do not depend on the order records arrived in.
+#[derive(Clone, Debug)]
+pub struct VocabularyBuilder {
+    counts: HashMap<String, usize>,
+    min_count: usize,
+    max_size: Option<usize>,
+}
+
+impl Default for VocabularyBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl VocabularyBuilder {
+    pub fn new() -> Self {
+        Self {
+            counts: HashMap::new(),
+            min_count: 1,
+            max_size: None,
+        }
+    }
+
+    /// Send identifiers seen fewer than `min_count` times to the OOV bucket.
+    pub fn min_count(mut self, min_count: usize) -> Self {
+        self.min_count = min_count;
+        self
+    }
+
+    /// Keep at most `max_size` identifiers, the most frequent ones.
+    pub fn max_size(mut self, max_size: usize) -> Self {
+        self.max_size = Some(max_size);
+        self
+    }
+
+    /// Count one occurrence; missing values are ignored.
+    pub fn add(&mut self, token: Option<&str>) {
+        if let Some(token) = token {
+            *self.counts.entry(token.to_string()).or_default() += 1;
+        }
+    }
+
+    /// Count the `program` of every `MachineState` record.
+    pub fn add_programs(&mut self, records: &[IndustrialRecord]) {
+        for record in records {
+            if let IndustrialRecord::MachineState(state) = record {
+                self.add(state.program.as_deref());
+            }
+        }
+    }
+
+    /// Count the `tool_id` of every `ToolEvent` record.
+    pub fn add_tool_ids(&mut self, records: &[IndustrialRecord]) {
+        for record in records {
+            if let IndustrialRecord::ToolEvent(event) = record {
+                self.add(event.tool_id.as_deref());
+            }
+        }
+    }
+
+    pub fn build(&self) -> Vocabulary {
+        let mut counted: Vec<(&String, usize)> = self
+            .counts
+            .iter()
+            .filter(|(_, &n)| n >= self.min_count)
+            .map(|(t, &n)| (t, n))
+            .collect();
+        counted.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+        counted.truncate(self.max_size.unwrap_or(usize::MAX));
+        let tokens = counted.into_iter().map(|(t, _)| t.clone()).collect();
+        Vocabulary::from_tokens(tokens).expect("counted identifiers are distinct")
+    }
+}
@@ -0,0 +1,233 @@
+//! Weight initialization schemes.
+//!
+//! Fan sizes follow this crate's layouts: a 2D `[in, out]` weight (as in `Linear`) has
+//! `fan_in = in`, `fan_out = out`; higher-rank `[out_channels, in_channels, kernel...]`
+//! weights multiply the channel counts by the kernel size; 1D tensors use their length for
+//! both. Transposed convolutions store `[in_channels, out_channels, kernel...]` and are
+//! sampled with `FanLayout::ConvTranspose`.
+
+use std::fmt;
+use std::sync::Arc;
+
+use crate::error::ComputeError;
+use crate::prng::XorShift32;
+use crate::tensor::Tensor;
+
+/// The activation following a layer, used to pick the Kaiming gain.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Nonlinearity {
+    Linear,
+    Sigmoid,
+    Tanh,
+    Relu,
+    LeakyRelu(f32),
+}
+
+impl Nonlinearity {
+    /// Recommended gain that keeps activation variance stable through the layer.
+    pub fn gain(self) -> f32 {
+        match self {
+            Nonlinearity::Linear | Nonlinearity::Sigmoid => 1.0,
+            Nonlinearity::Tanh => 5.0 / 3.0,
+            Nonlinearity::Relu => 2f32.sqrt(),
+            Nonlinearity::LeakyRelu(slope) => (2.0 / (1.0 + slope * slope)).sqrt(),
+        }
+    }
+}
+
+/// Which leading axes of a weight are its input and output sides.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum FanLayout {
+    /// `[in, out]` matrices and `[out_channels, in_channels, kernel...]` convolutions.
+    #[default]
+    Standard,
+    /// `[in_channels, out_channels, kernel...]` transposed-convolution weights.
+    ConvTranspose,
+}
+
+type CustomInit = dyn Fn(&[usize], &mut XorShift32) -> Vec<f32> + Send + Sync;
+
+#[derive(Clone)]
+pub enum Init {
+    Constant(f32),
+    Uniform {
+        low: f32,
+        high: f32,
+    },
+    Normal {
+        mean: f32,
+        std: f32,
+    },
+    /// Uniform ±`gain * sqrt(6 / (fan_in + fan_out))`.
+    XavierUniform {
+        gain: f32,
+    },
+    /// Normal with std `gain * sqrt(2 / (fan_in + fan_out))`.
+    XavierNormal {
+        gain: f32,
+    },
+    /// Uniform ±`gain * sqrt(3 / fan_in)`.
+    KaimingUniform(Nonlinearity),
+    /// Normal with std `gain / sqrt(fan_in)`.
+    KaimingNormal(Nonlinearity),
+    /// A (semi-)orthogonal matrix over `[shape[0], rest]`, scaled by `gain`.
+    Orthogonal {
+        gain: f32,
+    },
+    /// `f(shape, rng)` returns the row-major values.
+    Custom(Arc<CustomInit>),
+}
+
+impl Init {
+    pub fn custom<F>(f: F) -> Self
+    where
+        F: Fn(&[usize], &mut XorShift32) -> Vec<f32> + Send + Sync + 'static,
+    {
+        Init::Custom(Arc::new(f))
+    }
+
+    /// A tensor of `shape` drawn with a fresh generator seeded by `seed`.
+    pub fn tensor(&self, shape: Vec<usize>, seed: u32) -> Result<Tensor, ComputeError> {
+        self.sample(shape, &mut XorShift32::new(seed))
+    }
+
+    /// A tensor of `shape` drawn from `rng`, so several tensors can share one stream.
+    pub fn sample(&self, shape: Vec<usize>, rng: &mut XorShift32) -> Result<Tensor, ComputeError> {
+        self.sample_with_layout(shape, FanLayout::Standard, rng)
+    }
+
+    /// `sample` for a weight whose fans follow `layout`.
+    pub fn sample_with_layout(
+        &self,
+        shape: Vec<usize>,
+        layout: FanLayout,
+        rng: &mut XorShift32,
+    ) -> Result<Tensor, ComputeError> {
+        let size: usize = shape.iter().product();
+        let (fan_in, fan_out) = fans_for(&shape, layout);
+        let uniform = |rng: &mut XorShift32, bound: f32| {
+            (0..size)
+                .map(|_| rng.gen_range_f32(-bound, bound))
+                .collect()
+        };
+        let normal = |rng: &mut XorShift32, mean: f32, std: f32| {
+            (0..size)
+                .map(|_| mean + std * rng.next_normal_f32())
+                .collect()
+        };
+        let data: Vec<f32> = match self {
+            Init::Constant(v) => vec![*v; size],
+            Init::Uniform { low, high } => {
+                (0..size).map(|_| rng.gen_range_f32(*low, *high)).collect()
+            }
+            Init::Normal { mean, std } => normal(rng, *mean, *std),
+            Init::XavierUniform { gain } => {
+                uniform(rng, gain * (6.0 / (fan_in + fan_out) as f32).sqrt())
+            }
+            Init::XavierNormal { gain } => {
+                normal(rng, 0.0, gain * (2.0 / (fan_in + fan_out) as f32).sqrt())
+            }
+            Init::KaimingUniform(act) => uniform(rng, act.gain() * (3.0 / fan_in as f32).sqrt()),
+            Init::KaimingNormal(act) => normal(rng, 0.0, act.gain() / (fan_in as f32).sqrt()),
+            Init::Orthogonal { gain } => orthogonal(&shape, *gain, rng)?,
+            Init::Custom(f) => f(&shape, rng),
+        };
+        Tensor::new(data, shape)
+    }
+}
+
+impl fmt::Debug for Init {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Init::Constant(v) => f.debug_tuple("Constant").field(v).finish(),
+            Init::Uniform { low, high } => f
+                .debug_struct("Uniform")
+                .field("low", low)
+                .field("high", high)
+                .finish(),
+            Init::Normal { mean, std } => f
+                .debug_struct("Normal")
+                .field("mean", mean)
+                .field("std", std)
+                .finish(),
+            Init::XavierUniform { gain } => {
+                f.debug_struct("XavierUniform").field("gain", gain).finish()
+            }
+            Init::XavierNormal { gain } => {
+                f.debug_struct("XavierNormal").field("gain", gain).finish()
+            }
+            Init::KaimingUniform(act) => f.debug_tuple("KaimingUniform").field(act).finish(),
+            Init::KaimingNormal(act) => f.debug_tuple("KaimingNormal").field(act).finish(),
+            Init::Orthogonal { gain } => f.debug_struct("Orthogonal").field("gain", gain).finish(),
+            Init::Custom(_) => f.write_str("Custom(..)"),
+        }
+    }
+}
+
+/// `(fan_in, fan_out)` for a weight of `shape` (see the module docs), at least 1 each.
+pub fn fans(shape: &[usize]) -> (usize, usize) {
+    fans_for(shape, FanLayout::Standard)
+}
+
+/// `fans` for a weight stored in `layout`.
+pub fn fans_for(shape: &[usize], layout: FanLayout) -> (usize, usize) {
+    let (fan_in, fan_out) = match shape {
+        [] => (1, 1),
+        [n] => (*n, *n),
+        [rows, cols] => (*rows, *cols),
+        [first, second, kernel @ ..] => {
+            let receptive: usize = kernel.iter().product();
+            let (out, inp) = match layout {
+                FanLayout::Standard => (first, second),
+                FanLayout::ConvTranspose => (second, first),
+            };
+            (inp * receptive, out * receptive)
+        }
+    };
+    (fan_in.max(1), fan_out.max(1))
+}
+
+/// Orthonormalize the smaller side of a Gaussian `[rows, cols]` matrix with modified
+/// Gram-Schmidt.
+fn orthogonal(shape: &[usize], gain: f32, rng: &mut XorShift32) -> Result<Vec<f32>, ComputeError> {
+    if shape.len() < 2 {
+        return Err(ComputeError::DimensionError {
+            message: format!("orthogonal init needs at least 2 dimensions, got {shape:?}"),
+        });
+    }
+    let rows = shape[0];
+    let cols: usize = shape[1..].iter().product();
+    // Work on vectors of length `long`, making `short` of them orthonormal.
+    let (short, long) = (rows.min(cols), rows.max(cols));
+    let mut vectors: Vec<Vec<f32>> = (0..short)
+        .map(|_| (0..long).map(|_| rng.next_normal_f32()).collect())
+        .collect();
+    for i in 0..short {
+        for j in 0..i {
+            let dot: f32 = vectors[i].iter().zip(&vectors[j]).map(|(a, b)| a * b).sum();
+            let (done, rest) = vectors.split_at_mut(i);
+            for (v, q) in rest[0].iter_mut().zip(&done[j]) {
+                *v -= dot * q;
+            }
+        }
+        let norm = vectors[i].iter().map(|v| v * v).sum::<f32>().sqrt();
+        if norm == 0.0 {
+            return Err(ComputeError::InvalidOperation {
+                message: "orthogonal init drew a degenerate matrix".to_string(),
+            });
+        }
+        for v in &mut vectors[i] {
+            *v /= norm;
+        }
+    }
+
+    let mut data = vec![0.0; rows * cols];
+    for (i, vector) in vectors.iter().enumerate() {
+        for (j, &v) in vector.iter().enumerate() {
+            // Orthonormal rows when rows <= cols, orthonormal columns otherwise.
+            let (r, c) = if rows <= cols { (i, j) } else { (j, i) };
+            data[r * cols + c] = gain * v;
+        }
+    }
+    Ok(data)
+}
@@ -1,11 +1,25 @@
+use crate::conv::{Conv1dOp, Conv2dOp, ConvTranspose1dOp, ConvTranspose2dOp};
 use crate::error::ComputeError;
 use crate::graph::Graph;
-use crate::ops::{AddOp, MatMulOp};
+use crate::init::{FanLayout, Init};
+use crate::module::Module;
+use crate::ops::{AddOp, BatchMatMulOp, MatMulOp, ReluOp, SigmoidOp, SoftmaxOp, TanhOp};
+use crate::pool::{
+    AdaptiveAvgPool1dOp, AdaptiveAvgPool2dOp, AvgPool1dOp, AvgPool2dOp, MaxPool1dOp, MaxPool2dOp,
+};
 use crate::prng::XorShift32;
-use crate::tensor::Tensor;
 
-pub trait Layer {
-    fn parameters(&self) -> Vec<usize>;
+/// A building block that appends its computation to a graph.
+///
+/// Parameter names come from `Module`; `parameters` lists the same nodes in that order.
+pub trait Layer: Module {
+    fn parameters(&self) -> Vec<usize> {
+        self.named_parameters()
+            .into_iter()
+            .map(|(_, idx)| idx)
+            .collect()
+    }
+
     fn forward(&self, graph: &mut Graph, input_idx: usize) -> Result<usize, ComputeError>;
 }
 
@@ -17,23 +31,38 @@ pub struct Linear {
 }
 
 impl Linear {
+    /// Weights uniform in ±1/sqrt(`input_size`), zero bias.
     pub fn new(
         graph: &mut Graph,
         input_size: usize,
         output_size: usize,
         seed: u32,
     ) -> Result<Self, ComputeError> {
-        // Xavier/Glorot init: uniform[-k, k], k = 1/sqrt(fan_in)
         let k = 1.0 / (input_size as f32).sqrt();
+        Self::with_init(
+            graph,
+            input_size,
+            output_size,
+            &Init::Uniform { low: -k, high: k },
+            &Init::Constant(0.0),
+            seed,
+        )
+    }
+
+    /// Weights (`[input_size, output_size]`) and bias (`[1, output_size]`) drawn from one
+    /// generator seeded by `seed`.
+    pub fn with_init(
+        graph: &mut Graph,
+        input_size: usize,
+        output_size: usize,
+        weight_init: &Init,
+        bias_init: &Init,
+        seed: u32,
+    ) -> Result<Self, ComputeError> {
         let mut rng = XorShift32::new(seed);
-        let mut weight_data = vec![0.0; input_size * output_size];
-        for v in &mut weight_data {
-            *v = rng.gen_range_f32(-k, k);
-        }
-        let weight = Tensor::new(weight_data, vec![input_size, output_size])?;
+        let weight = weight_init.sample(vec![input_size, output_size], &mut rng)?;
         let weight_idx = graph.add_parameter(weight, true);
-
-        let bias = Tensor::zeros(vec![1, output_size])?;
+        let bias = bias_init.sample(vec![1, output_size], &mut rng)?;
         let bias_idx = graph.add_parameter(bias, true);
 
         Ok(Self {
@@ -43,16 +72,380 @@ impl Linear {
             output_size,
         })
     }
-}
 
-impl Layer for Linear {
-    fn parameters(&self) -> Vec<usize> {
-        vec![self.weight_idx, self.bias_idx]
+    /// Apply to `[..., input_size]` inputs of any rank, e.g. `[batch, time, features]`,
+    /// with `BatchMatMulOp` sharing the weight across the leading axes.
+    pub fn forward_batched(&self, graph: &mut Graph, input_idx: usize) -> usize {
+        let mm = graph.apply_op(BatchMatMulOp, &[input_idx, self.weight_idx]);
+        graph.apply_op(AddOp, &[mm, self.bias_idx])
     }
+}
 
+impl Layer for Linear {
     fn forward(&self, graph: &mut Graph, input_idx: usize) -> Result<usize, ComputeError> {
         let mm = graph.apply_op(MatMulOp, &[input_idx, self.weight_idx]);
         let out = graph.apply_op(AddOp, &[mm, self.bias_idx]);
         Ok(out)
     }
 }
+
+impl Module for Linear {
+    fn own_parameters(&self) -> Vec<(String, usize)> {
+        vec![
+            ("weight".to_string(), self.weight_idx),
+            ("bias".to_string(), self.bias_idx),
+        ]
+    }
+}
+
+/// Check `groups` against a convolution layer's channel counts.
+fn check_conv_groups(
+    groups: usize,
+    in_channels: usize,
+    out_channels: usize,
+) -> Result<(), ComputeError> {
+    if groups == 0 || !in_channels.is_multiple_of(groups) || !out_channels.is_multiple_of(groups) {
+        return Err(ComputeError::InvalidOperation {
+            message: format!(
+                "groups {groups} must divide in channels {in_channels} and out channels {out_channels}"
+            ),
+        });
+    }
+    Ok(())
+}
+
+/// Uniform ±1/sqrt(fan_in) for a convolution weight, where fan_in is the size of one
+/// output channel's slice of `weight_shape`.
+fn default_conv_init(weight_shape: &[usize]) -> Init {
+    let fan_in: usize = weight_shape[1..].iter().product();
+    let k = 1.0 / (fan_in.max(1) as f32).sqrt();
+    Init::Uniform { low: -k, high: k }
+}
+
+/// Add a convolution weight stored in `layout` and its `[bias_len]` bias, drawn from one
+/// generator seeded by `seed`, and return their node indices.
+fn add_conv_parameters(
+    graph: &mut Graph,
+    weight_shape: Vec<usize>,
+    layout: FanLayout,
+    bias_len: usize,
+    weight_init: &Init,
+    bias_init: &Init,
+    seed: u32,
+) -> Result<(usize, usize), ComputeError> {
+    let mut rng = XorShift32::new(seed);
+    let weight = weight_init.sample_with_layout(weight_shape, layout, &mut rng)?;
+    let weight_idx = graph.add_parameter(weight, true);
+    let bias = bias_init.sample(vec![bias_len], &mut rng)?;
+    let bias_idx = graph.add_parameter(bias, true);
+    Ok((weight_idx, bias_idx))
+}
+
+/// Generates a convolution layer around one of the `conv` ops.
+macro_rules! conv_layer {
+    (
+        $(#[$doc:meta])*
+        $name:ident, $op:ty, $kernel:ty, $layout:expr,
+        weight: |$i:ident, $o:ident, $k:ident, $g:ident| $shape:expr
+    ) => {
+        $(#[$doc])*
+        pub struct $name {
+            weight_idx: usize,
+            bias_idx: usize,
+            op: $op,
+            pub in_channels: usize,
+            pub out_channels: usize,
+            pub kernel_size: $kernel,
+        }
+
+        impl $name {
+            fn weight_shape(
+                in_channels: usize,
+                out_channels: usize,
+                kernel_size: $kernel,
+                op: &$op,
+            ) -> Vec<usize> {
+                let ($i, $o, $k, $g) = (in_channels, out_channels, kernel_size, op.groups);
+                $shape
+            }
+
+            /// Weights uniform in ±1/sqrt(fan_in), where fan_in is the size of one output
+            /// channel's slice of the weight, and zero bias.
+            pub fn new(
+                graph: &mut Graph,
+                in_channels: usize,
+                out_channels: usize,
+                kernel_size: $kernel,
+                op: $op,
+                seed: u32,
+            ) -> Result<Self, ComputeError> {
+                check_conv_groups(op.groups, in_channels, out_channels)?;
+                let init =
+                    default_conv_init(&Self::weight_shape(in_channels, out_channels, kernel_size, &op));
+                Self::with_init(
+                    graph,
+                    in_channels,
+                    out_channels,
+                    kernel_size,
+                    op,
+                    &init,
+                    &Init::Constant(0.0),
+                    seed,
+                )
+            }
+
+            /// Weight and bias (`[out_channels]`) drawn from one generator seeded by `seed`.
+            #[allow(clippy::too_many_arguments)]
+            pub fn with_init(
+                graph: &mut Graph,
+                in_channels: usize,
+                out_channels: usize,
+                kernel_size: $kernel,
+                op: $op,
+                weight_init: &Init,
+                bias_init: &Init,
+                seed: u32,
+            ) -> Result<Self, ComputeError> {
+                check_conv_groups(op.groups, in_channels, out_channels)?;
+                let (weight_idx, bias_idx) = add_conv_parameters(
+                    graph,
+                    Self::weight_shape(in_channels, out_channels, kernel_size, &op),
+                    $layout,
+                    out_channels,
+                    weight_init,
+                    bias_init,
+                    seed,
+                )?;
+                Ok(Self {
+                    weight_idx,
+                    bias_idx,
+                    op,
+                    in_channels,
+                    out_channels,
+                    kernel_size,
+                })
+            }
+
+            pub fn op(&self) -> $op {
+                self.op
+            }
+        }
+
+        impl Module for $name {
+            fn own_parameters(&self) -> Vec<(String, usize)> {
+                vec![
+                    ("weight".to_string(), self.weight_idx),
+                    ("bias".to_string(), self.bias_idx),
+                ]
+            }
+        }
+
+        impl Layer for $name {
+            fn forward(&self, graph: &mut Graph, input_idx: usize) -> Result<usize, ComputeError> {
+                Ok(graph.apply_op(self.op, &[input_idx, self.weight_idx, self.bias_idx]))
+            }
+        }
+    };
+}
+
+conv_layer!(
+    /// 1D convolution over `[batch, in_channels, length]` inputs (see `Conv1dOp`), with
+    /// weight `[out_channels, in_channels / groups, kernel_size]`.
+    Conv1d, Conv1dOp, usize, FanLayout::Standard,
+    weight: |i, o, k, g| vec![o, i / g, k]
+);
+conv_layer!(
+    /// 2D convolution over `[batch, in_channels, height, width]` inputs (see `Conv2dOp`),
+    /// with weight `[out_channels, in_channels / groups, kh, kw]`.
+    Conv2d, Conv2dOp, [usize; 2], FanLayout::Standard,
+    weight: |i, o, k, g| vec![o, i / g, k[0], k[1]]
+);
+conv_layer!(
+    /// Transposed 1D convolution (see `ConvTranspose1dOp`), with weight
+    /// `[in_channels, out_channels / groups, kernel_size]`.
+    ConvTranspose1d, ConvTranspose1dOp, usize, FanLayout::ConvTranspose,
+    weight: |i, o, k, g| vec![i, o / g, k]
+);
+conv_layer!(
+    /// Transposed 2D convolution (see `ConvTranspose2dOp`), with weight
+    /// `[in_channels, out_channels / groups, kh, kw]`.
+    ConvTranspose2d, ConvTranspose2dOp, [usize; 2], FanLayout::ConvTranspose,
+    weight: |i, o, k, g| vec![i, o / g, k[0], k[1]]
+);
+
+macro_rules! activation_layer {
+    ($(#[$doc:meta])* $name:ident, $op:expr) => {
+        $(#[$doc])*
+        #[derive(Clone, Copy, Debug, Default)]
+        pub struct $name;
+
+        impl Module for $name {}
+
+        impl Layer for $name {
+            fn forward(&self, graph: &mut Graph, input_idx: usize) -> Result<usize, ComputeError> {
+                Ok(graph.apply_op($op, &[input_idx]))
+            }
+        }
+    };
+}
+
+activation_layer!(
+    /// `ReluOp` as a parameter-free layer.
+    Relu,
+    ReluOp
+);
+activation_layer!(
+    /// `SigmoidOp` as a parameter-free layer.
+    Sigmoid,
+    SigmoidOp
+);
+activation_layer!(
+    /// `TanhOp` as a parameter-free layer.
+    Tanh,
+    TanhOp
+);
+activation_layer!(
+    /// Row-wise `SoftmaxOp` as a parameter-free layer.
+    Softmax,
+    SoftmaxOp
+);
+
+macro_rules! pool_layer {
+    ($(#[$doc:meta])* $name:ident, $op:ty) => {
+        $(#[$doc])*
+        #[derive(Clone, Copy, Debug)]
+        pub struct $name(pub $op);
+
+        impl Module for $name {}
+
+        impl Layer for $name {
+            fn forward(&self, graph: &mut Graph, input_idx: usize) -> Result<usize, ComputeError> {
+                Ok(graph.apply_op(self.0, &[input_idx]))
+            }
+        }
+    };
+}
+
+pool_layer!(
+    /// `MaxPool1dOp` as a parameter-free layer.
+    MaxPool1d,
+    MaxPool1dOp
+);
+pool_layer!(
+    /// `MaxPool2dOp` as a parameter-free layer.
+    MaxPool2d,
+    MaxPool2dOp
+);
+pool_layer!(
+    /// `AvgPool1dOp` as a parameter-free layer.
+    AvgPool1d,
+    AvgPool1dOp
+);
+pool_layer!(
+    /// `AvgPool2dOp` as a parameter-free layer.
+    AvgPool2d,
+    AvgPool2dOp
+);
+pool_layer!(
+    /// `AdaptiveAvgPool1dOp` as a parameter-free layer.
+    AdaptiveAvgPool1d,
+    AdaptiveAvgPool1dOp
+);
+pool_layer!(
+    /// `AdaptiveAvgPool2dOp` as a parameter-free layer.
+    AdaptiveAvgPool2d,
+    AdaptiveAvgPool2dOp
+);
+
+/// Layers applied in order, each feeding the next.
+///
+/// Parameters are named by position (`0.weight`, `2.bias`), matching the layer list.
+///
+/// ```
+/// # use neuroncore::layers::{Layer, Linear, Relu, Sequential};
+/// # use neuroncore::Graph;
+/// let mut graph = Graph::new();
+/// let mlp = Sequential::new()
+///     .with(Linear::new(&mut graph, 4, 8, 1)?)
+///     .with(Relu)
+///     .with(Linear::new(&mut graph, 8, 1, 2)?);
+/// assert_eq!(mlp.parameters().len(), 4);
+/// # Ok::<(), neuroncore::ComputeError>(())
+/// ```
+#[derive(Default)]
+pub struct Sequential {
+    layers: Vec<Box<dyn Layer>>,
+}
+
+impl Sequential {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Append `layer`.
+    pub fn with<L: Layer + 'static>(mut self, layer: L) -> Self {
+        self.layers.push(Box::new(layer));
+        self
+    }
+
+    pub fn push(&mut self, layer: Box<dyn Layer>) {
+        self.layers.push(layer);
+    }
+
+    pub fn len(&self) -> usize {
+        self.layers.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.layers.is_empty()
+    }
+
+    pub fn layers(&self) -> impl Iterator<Item = &dyn Layer> {
+        self.layers.iter().map(|l| l.as_ref())
+    }
+}
+
+impl Module for Sequential {
+    fn children(&self) -> Vec<(String, &dyn Module)> {
+        self.layers
+            .iter()
+            .enumerate()
+            .map(|(i, l)| (i.to_string(), l.as_ref() as &dyn Module))
+            .collect()
+    }
+}
+
+impl Layer for Sequential {
+    fn forward(&self, graph: &mut Graph, input_idx: usize) -> Result<usize, ComputeError> {
+        self.layers
+            .iter()
+            .try_fold(input_idx, |x, layer| layer.forward(graph, x))
+    }
+}
+
+/// Apply `layers` in order, marking each run of `every` consecutive layers as one
+/// checkpointed segment (see `Graph::checkpoint`).
+///
+/// Only the outputs of each group stay cached through `Graph::backward`; the activations
+/// inside a group are recomputed when its gradients are needed.
+pub fn forward_checkpointed(
+    graph: &mut Graph,
+    layers: &[&dyn Layer],
+    input_idx: usize,
+    every: usize,
+) -> Result<usize, ComputeError> {
+    if every == 0 {
+        return Err(ComputeError::InvalidOperation {
+            message: "checkpoint interval must be positive".to_string(),
+        });
+    }
+    let mut x = input_idx;
+    for group in layers.chunks(every) {
+        let start = graph.len();
+        for layer in group {
+            x = layer.forward(graph, x)?;
+        }
+        graph.checkpoint(start..graph.len());
+    }
+    Ok(x)
+}
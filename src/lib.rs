@@ -9,25 +9,44 @@
 //! - No external dependencies: includes a tiny xorshift PRNG for init.
 //! - Correctness-oriented and deliberately unoptimized.
 
+pub mod attention;
+pub mod control;
+pub mod conv;
+pub mod custom;
+pub mod dropout;
+pub mod embedding;
 pub mod error;
+pub mod fusion;
 pub mod graph;
 pub mod health;
 pub mod industrial;
+pub mod init;
 pub mod layers;
 pub mod losses;
+pub mod module;
+pub mod norm;
+pub mod numeric;
 pub mod ops;
 pub mod optim;
+pub mod parallel;
+pub mod passes;
+pub mod plan;
+pub mod pool;
 pub mod prng;
+pub mod rnn;
 pub mod run_manifest;
+pub mod serialize;
+pub mod shape;
+pub mod slicing;
 pub mod tensor;
 pub mod tensor_index;
 pub mod timeseries;
 
 pub use error::ComputeError;
-pub use graph::{Graph, Node};
+pub use graph::{BackwardStats, Graph, Node};
 pub use ops::{
-    AddOp, DivideOp, InvertibleOp, LogOp, MatMulOp, MultiplyOp, Op, ReluOp, SoftmaxOp, SubtractOp,
-    SumOp,
+    AddOp, BatchMatMulOp, DivideOp, InvertibleOp, LogOp, MatMulOp, MeanOp, MultiplyOp, Op, ReluOp,
+    SigmoidOp, SoftmaxOp, SqrtOp, SubtractOp, SumOp, TanhOp,
 };
 pub use tensor::Tensor;
 
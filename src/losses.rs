@@ -1,6 +1,10 @@
+use std::fmt;
+use std::str::FromStr;
+
 use crate::error::ComputeError;
 use crate::graph::Graph;
-use crate::ops::{DivideOp, LogOp, MultiplyOp, SoftmaxOp, SubtractOp, SumOp};
+use crate::ops::{LogOp, MeanOp, MultiplyOp, Op, SoftmaxOp, SubtractOp, SumOp};
+use crate::shape::{self, Dim};
 use crate::tensor::Tensor;
 
 pub struct MSELoss;
@@ -17,17 +21,72 @@ impl MSELoss {
         // squared = diff * diff
         let squared_idx = graph.apply_op(MultiplyOp, &[diff_idx, diff_idx]);
 
-        // sum of squared differences
-        let sum_idx = graph.apply_op(SumOp { dim: None }, &[squared_idx]);
+        // Mean over however many elements the predictions have when the graph runs.
+        Ok(graph.apply_op(MeanOp, &[squared_idx]))
+    }
+}
 
-        // Compute mean: sum / N
-        let pred_tensor = graph.forward(predictions)?;
-        let size = pred_tensor.data().len() as f32;
-        let size_tensor = Tensor::new(vec![size], vec![1])?;
-        let size_idx = graph.add_input(size_tensor);
+/// Mean absolute error through an `L1LossOp`.
+pub struct L1Loss;
 
-        let loss_idx = graph.apply_op(DivideOp, &[sum_idx, size_idx]);
-        Ok(loss_idx)
+impl L1Loss {
+    pub fn compute(
+        graph: &mut Graph,
+        predictions: usize,
+        targets: usize,
+        reduction: Reduction,
+    ) -> Result<usize, ComputeError> {
+        Ok(graph.apply_op(L1LossOp { reduction }, &[predictions, targets]))
+    }
+}
+
+/// Huber loss through a `HuberLossOp`.
+pub struct HuberLoss;
+
+impl HuberLoss {
+    pub fn compute(
+        graph: &mut Graph,
+        predictions: usize,
+        targets: usize,
+        delta: f32,
+        reduction: Reduction,
+    ) -> Result<usize, ComputeError> {
+        let op = HuberLossOp { delta, reduction };
+        Ok(graph.apply_op(op, &[predictions, targets]))
+    }
+}
+
+/// Log-cosh loss through a `LogCoshLossOp`.
+pub struct LogCoshLoss;
+
+impl LogCoshLoss {
+    pub fn compute(
+        graph: &mut Graph,
+        predictions: usize,
+        targets: usize,
+        reduction: Reduction,
+    ) -> Result<usize, ComputeError> {
+        Ok(graph.apply_op(LogCoshLossOp { reduction }, &[predictions, targets]))
+    }
+}
+
+/// Pinball loss through a `QuantileLossOp`.
+pub struct QuantileLoss;
+
+impl QuantileLoss {
+    /// Loss of `predictions` (`[..., quantiles.len()]`) against `targets` (`[...]`); the
+    /// quantile levels are added as a frozen parameter so they are saved with the graph.
+    pub fn compute(
+        graph: &mut Graph,
+        predictions: usize,
+        targets: usize,
+        quantiles: &[f32],
+        reduction: Reduction,
+    ) -> Result<usize, ComputeError> {
+        let levels = Tensor::new(quantiles.to_vec(), vec![quantiles.len()])?;
+        let levels = graph.add_parameter(levels, false);
+        let op = QuantileLossOp { reduction };
+        Ok(graph.apply_op(op, &[predictions, targets, levels]))
     }
 }
 
@@ -46,8 +105,1179 @@ impl CrossEntropyLoss {
         let sum_idx = graph.apply_op(SumOp { dim: None }, &[selected_idx]);
 
         let neg_one = Tensor::new(vec![-1.0], vec![1])?;
-        let neg_one_idx = graph.add_input(neg_one);
+        let neg_one_idx = graph.add_constant(neg_one);
         let loss_idx = graph.apply_op(MultiplyOp, &[sum_idx, neg_one_idx]);
         Ok(loss_idx)
     }
+
+    /// Cross entropy of `logits` (`[..., classes]`) against class indices `targets`
+    /// (`[...]`) through a `CrossEntropyOp`.
+    pub fn with_indices(
+        graph: &mut Graph,
+        logits: usize,
+        targets: usize,
+        options: &CrossEntropyOptions,
+    ) -> Result<usize, ComputeError> {
+        let op = CrossEntropyOp {
+            label_smoothing: options.label_smoothing,
+            ignore_index: options.ignore_index,
+            reduction: options.reduction,
+        };
+        let mut inputs = vec![logits, targets];
+        if let Some(weights) = &options.class_weights {
+            let weights = Tensor::new(weights.clone(), vec![weights.len()])?;
+            inputs.push(graph.add_constant(weights));
+        }
+        Ok(graph.apply_op(op, &inputs))
+    }
+}
+
+/// How per-element losses are combined into the loss value.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Reduction {
+    /// Keep one loss per element.
+    None,
+    /// Average, shape `[1]`.
+    #[default]
+    Mean,
+    /// Total, shape `[1]`.
+    Sum,
+}
+
+impl Reduction {
+    /// Combine `losses`, dividing by `normalizer` for `Mean`. A zero normalizer (nothing
+    /// to average) gives a zero loss rather than NaN.
+    fn apply(
+        self,
+        losses: Vec<f32>,
+        shape: Vec<usize>,
+        normalizer: f32,
+    ) -> Result<Tensor, ComputeError> {
+        let total: f32 = losses.iter().sum();
+        match self {
+            Reduction::None => Tensor::new(losses, shape),
+            Reduction::Sum => Tensor::new(vec![total], vec![1]),
+            Reduction::Mean if normalizer == 0.0 => Tensor::new(vec![0.0], vec![1]),
+            Reduction::Mean => Tensor::new(vec![total / normalizer], vec![1]),
+        }
+    }
+
+    /// The gradient reaching each of `len` per-element losses from `grad_output`.
+    fn element_grads(self, grad_output: &Tensor, len: usize, normalizer: f32) -> Vec<f32> {
+        let g = grad_output.data().first().copied().unwrap_or(0.0);
+        match self {
+            Reduction::None => grad_output.data().to_vec(),
+            Reduction::Sum => vec![g; len],
+            Reduction::Mean if normalizer == 0.0 => vec![0.0; len],
+            Reduction::Mean => vec![g / normalizer; len],
+        }
+    }
+
+    /// Output shape for per-element losses shaped `elements`.
+    fn infer_shape(self, elements: &[Dim]) -> Vec<Dim> {
+        match self {
+            Reduction::None => elements.to_vec(),
+            Reduction::Mean | Reduction::Sum => vec![Dim::Fixed(1)],
+        }
+    }
+}
+
+impl fmt::Display for Reduction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Reduction::None => "none",
+            Reduction::Mean => "mean",
+            Reduction::Sum => "sum",
+        })
+    }
+}
+
+impl FromStr for Reduction {
+    type Err = ComputeError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "none" => Ok(Reduction::None),
+            "mean" => Ok(Reduction::Mean),
+            "sum" => Ok(Reduction::Sum),
+            _ => Err(ComputeError::Parse {
+                context: "reduction".to_string(),
+                message: format!("unknown reduction {s:?}"),
+            }),
+        }
+    }
+}
+
+/// Cross entropy of logits `[..., classes]` against class indices `[...]`, with the
+/// log-softmax fused in so large logits cannot overflow to NaN.
+///
+/// Inputs are `[logits, targets]` or `[logits, targets, class_weights]`. Targets are
+/// whole numbers stored as `f32`. With label smoothing `e` the target distribution is
+/// `1 - e` on the target class plus `e / classes` on every class. Class weights `w` scale
+/// each class's term, and `Mean` divides by the summed weight `w[target]` of the counted
+/// targets, as PyTorch does. Targets equal to `ignore_index` contribute nothing, are left
+/// out of the mean and get a zero loss under `Reduction::None`. Only the logits receive a
+/// gradient.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct CrossEntropyOp {
+    pub label_smoothing: f32,
+    pub ignore_index: Option<usize>,
+    pub reduction: Reduction,
+}
+
+/// Log-softmax rows and targets of one cross-entropy evaluation.
+struct CrossEntropyRows {
+    classes: usize,
+    log_probs: Vec<f32>,
+    /// Target class per row, `None` if ignored.
+    targets: Vec<Option<usize>>,
+}
+
+impl CrossEntropyOp {
+    fn rows(&self, inputs: &[Tensor]) -> Result<CrossEntropyRows, ComputeError> {
+        if inputs.len() != 2 && inputs.len() != 3 {
+            return Err(ComputeError::InputCountError {
+                expected: 3,
+                got: inputs.len(),
+            });
+        }
+        if !(0.0..=1.0).contains(&self.label_smoothing) {
+            return Err(ComputeError::InvalidOperation {
+                message: format!(
+                    "label smoothing must be in [0, 1], got {}",
+                    self.label_smoothing
+                ),
+            });
+        }
+        let (logits, targets) = (&inputs[0], &inputs[1]);
+        let Some((&classes, leading)) = logits.shape().split_last() else {
+            return Err(ComputeError::DimensionError {
+                message: "cross entropy logits need a class axis".to_string(),
+            });
+        };
+        if targets.shape() != leading {
+            return Err(ComputeError::DimensionError {
+                message: format!(
+                    "targets must be {leading:?} for logits {:?}, got {:?}",
+                    logits.shape(),
+                    targets.shape()
+                ),
+            });
+        }
+        if let Some(weights) = inputs.get(2) {
+            if weights.shape() != [classes] {
+                return Err(ComputeError::DimensionError {
+                    message: format!(
+                        "class weights must be [{classes}], got {:?}",
+                        weights.shape()
+                    ),
+                });
+            }
+        }
+        let targets = targets
+            .data()
+            .iter()
+            .map(|&t| {
+                if t >= 0.0 && t.fract() == 0.0 && self.ignore_index == Some(t as usize) {
+                    return Ok(None);
+                }
+                if t < 0.0 || t.fract() != 0.0 || t as usize >= classes {
+                    return Err(ComputeError::InvalidOperation {
+                        message: format!("target {t} is not one of {classes} classes"),
+                    });
+                }
+                Ok(Some(t as usize))
+            })
+            .collect::<Result<_, _>>()?;
+        let mut log_probs = Vec::with_capacity(logits.data().len());
+        for row in logits.data().chunks(classes.max(1)) {
+            let max = row.iter().copied().fold(f32::NEG_INFINITY, f32::max);
+            let log_sum = row.iter().map(|z| (z - max).exp()).sum::<f32>().ln() + max;
+            log_probs.extend(row.iter().map(|z| z - log_sum));
+        }
+        Ok(CrossEntropyRows {
+            classes,
+            log_probs,
+            targets,
+        })
+    }
+
+    /// Per-class coefficients `a` of row loss `-sum_c a_c log p_c` for `target`.
+    fn coefficients(&self, target: usize, classes: usize, weights: Option<&[f32]>) -> Vec<f32> {
+        let weight = |c: usize| weights.map_or(1.0, |w| w[c]);
+        let smooth = self.label_smoothing / classes as f32;
+        (0..classes)
+            .map(|c| {
+                let hit = if c == target {
+                    1.0 - self.label_smoothing
+                } else {
+                    0.0
+                };
+                (hit + smooth) * weight(c)
+            })
+            .collect()
+    }
+
+    /// Summed target weight of the counted rows, the `Mean` denominator.
+    fn normalizer(&self, rows: &CrossEntropyRows, weights: Option<&[f32]>) -> f32 {
+        rows.targets
+            .iter()
+            .flatten()
+            .map(|&t| weights.map_or(1.0, |w| w[t]))
+            .sum()
+    }
+}
+
+impl Op for CrossEntropyOp {
+    fn forward(&self, inputs: &[Tensor]) -> Result<Tensor, ComputeError> {
+        let rows = self.rows(inputs)?;
+        let weights = inputs.get(2).map(Tensor::data);
+        let losses = rows
+            .targets
+            .iter()
+            .zip(rows.log_probs.chunks(rows.classes.max(1)))
+            .map(|(target, log_p)| match target {
+                Some(t) => -self
+                    .coefficients(*t, rows.classes, weights)
+                    .iter()
+                    .zip(log_p)
+                    .map(|(a, l)| a * l)
+                    .sum::<f32>(),
+                None => 0.0,
+            })
+            .collect();
+        let normalizer = self.normalizer(&rows, weights);
+        self.reduction
+            .apply(losses, inputs[1].shape().to_vec(), normalizer)
+    }
+
+    fn backward(
+        &self,
+        inputs: &[Tensor],
+        grad_output: &Tensor,
+    ) -> Result<Vec<Tensor>, ComputeError> {
+        let rows = self.rows(inputs)?;
+        let weights = inputs.get(2).map(Tensor::data);
+        let normalizer = self.normalizer(&rows, weights);
+        let scales = self
+            .reduction
+            .element_grads(grad_output, rows.targets.len(), normalizer);
+        let mut grad = Tensor::zeros_like(&inputs[0])?;
+        let chunks = grad.data_mut().chunks_mut(rows.classes.max(1));
+        for (((g, log_p), target), scale) in chunks
+            .zip(rows.log_probs.chunks(rows.classes.max(1)))
+            .zip(&rows.targets)
+            .zip(scales)
+        {
+            let Some(t) = target else { continue };
+            // d/dz_c of -sum_k a_k log p_k is p_c * sum_k a_k - a_c.
+            let a = self.coefficients(*t, rows.classes, weights);
+            let total: f32 = a.iter().sum();
+            for ((g, l), a) in g.iter_mut().zip(log_p).zip(&a) {
+                *g = scale * (l.exp() * total - a);
+            }
+        }
+        let mut grads = vec![grad];
+        grads.extend(
+            inputs[1..]
+                .iter()
+                .map(Tensor::zeros_like)
+                .collect::<Result<Vec<_>, _>>()?,
+        );
+        Ok(grads)
+    }
+
+    fn infer_shape(&self, inputs: &[Vec<Dim>]) -> Result<Vec<Dim>, ComputeError> {
+        if inputs.len() != 2 && inputs.len() != 3 {
+            return Err(ComputeError::InputCountError {
+                expected: 3,
+                got: inputs.len(),
+            });
+        }
+        let (logits, targets) = (&inputs[0], &inputs[1]);
+        let Some((classes, leading)) = logits.split_last() else {
+            return Err(ComputeError::DimensionError {
+                message: "cross entropy logits need a class axis".to_string(),
+            });
+        };
+        if targets.len() != leading.len() {
+            return Err(ComputeError::DimensionError {
+                message: format!(
+                    "targets {} do not match logits {}",
+                    shape::format_shape(targets),
+                    shape::format_shape(logits)
+                ),
+            });
+        }
+        let elements = leading
+            .iter()
+            .zip(targets)
+            .map(|(a, b)| shape::unify(a, b))
+            .collect::<Result<Vec<_>, _>>()?;
+        if let Some(weights) = inputs.get(2) {
+            match &weights[..] {
+                [w] => {
+                    shape::unify(w, classes)?;
+                }
+                _ => {
+                    return Err(ComputeError::DimensionError {
+                        message: format!(
+                            "class weights must be 1D, got {}",
+                            shape::format_shape(weights)
+                        ),
+                    })
+                }
+            }
+        }
+        Ok(self.reduction.infer_shape(&elements))
+    }
+
+    fn name(&self) -> &str {
+        "CrossEntropyOp"
+    }
+
+    fn attributes(&self) -> Option<Vec<(&'static str, String)>> {
+        Some(vec![
+            ("label_smoothing", self.label_smoothing.to_string()),
+            ("ignore_index", format!("{:?}", self.ignore_index)),
+            ("reduction", self.reduction.to_string()),
+        ])
+    }
+}
+
+/// Options for `CrossEntropyLoss::with_indices`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct CrossEntropyOptions {
+    /// One weight per class, e.g. inverse class frequencies for rare fault classes.
+    pub class_weights: Option<Vec<f32>>,
+    pub label_smoothing: f32,
+    pub ignore_index: Option<usize>,
+    pub reduction: Reduction,
+}
+
+/// Predictions and targets of an element-wise loss, which must have the same shape.
+fn paired<'a>(name: &str, inputs: &'a [Tensor]) -> Result<(&'a Tensor, &'a Tensor), ComputeError> {
+    let [predictions, targets] = inputs else {
+        return Err(ComputeError::InputCountError {
+            expected: 2,
+            got: inputs.len(),
+        });
+    };
+    if predictions.shape() != targets.shape() {
+        return Err(ComputeError::DimensionError {
+            message: format!(
+                "{name} targets must match predictions {:?}, got {:?}",
+                predictions.shape(),
+                targets.shape()
+            ),
+        });
+    }
+    Ok((predictions, targets))
+}
+
+/// Apply `loss` to every residual `prediction - target` and reduce over all elements.
+fn pointwise_forward(
+    name: &str,
+    inputs: &[Tensor],
+    reduction: Reduction,
+    loss: impl Fn(f32) -> f32,
+) -> Result<Tensor, ComputeError> {
+    let (predictions, targets) = paired(name, inputs)?;
+    let losses: Vec<f32> = predictions
+        .data()
+        .iter()
+        .zip(targets.data())
+        .map(|(p, t)| loss(p - t))
+        .collect();
+    let count = losses.len() as f32;
+    reduction.apply(losses, predictions.shape().to_vec(), count)
+}
+
+/// Gradients of `pointwise_forward` given the derivative of `loss` in the residual; the
+/// targets get its negation.
+fn pointwise_backward(
+    name: &str,
+    inputs: &[Tensor],
+    grad_output: &Tensor,
+    reduction: Reduction,
+    derivative: impl Fn(f32) -> f32,
+) -> Result<Vec<Tensor>, ComputeError> {
+    let (predictions, targets) = paired(name, inputs)?;
+    let len = predictions.data().len();
+    let scales = reduction.element_grads(grad_output, len, len as f32);
+    let mut grad = Tensor::zeros_like(predictions)?;
+    for (((g, p), t), scale) in grad
+        .data_mut()
+        .iter_mut()
+        .zip(predictions.data())
+        .zip(targets.data())
+        .zip(scales)
+    {
+        *g = scale * derivative(p - t);
+    }
+    let mut negated = grad.clone();
+    negated.data_mut().iter_mut().for_each(|g| *g = -*g);
+    Ok(vec![grad, negated])
+}
+
+fn pointwise_infer_shape(
+    inputs: &[Vec<Dim>],
+    reduction: Reduction,
+) -> Result<Vec<Dim>, ComputeError> {
+    shape::expect_inputs(inputs, 2)?;
+    let (predictions, targets) = (&inputs[0], &inputs[1]);
+    if predictions.len() != targets.len() {
+        return Err(ComputeError::DimensionError {
+            message: format!(
+                "targets {} do not match predictions {}",
+                shape::format_shape(targets),
+                shape::format_shape(predictions)
+            ),
+        });
+    }
+    let elements = predictions
+        .iter()
+        .zip(targets)
+        .map(|(a, b)| shape::unify(a, b))
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(reduction.infer_shape(&elements))
+}
+
+/// Absolute error `|prediction - target|` over inputs `[predictions, targets]` of one
+/// shape. The gradient at zero error is zero.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct L1LossOp {
+    pub reduction: Reduction,
+}
+
+impl Op for L1LossOp {
+    fn forward(&self, inputs: &[Tensor]) -> Result<Tensor, ComputeError> {
+        pointwise_forward("L1 loss", inputs, self.reduction, f32::abs)
+    }
+
+    fn backward(
+        &self,
+        inputs: &[Tensor],
+        grad_output: &Tensor,
+    ) -> Result<Vec<Tensor>, ComputeError> {
+        pointwise_backward("L1 loss", inputs, grad_output, self.reduction, |d| {
+            if d == 0.0 {
+                0.0
+            } else {
+                d.signum()
+            }
+        })
+    }
+
+    fn infer_shape(&self, inputs: &[Vec<Dim>]) -> Result<Vec<Dim>, ComputeError> {
+        pointwise_infer_shape(inputs, self.reduction)
+    }
+
+    fn name(&self) -> &str {
+        "L1LossOp"
+    }
+
+    fn attributes(&self) -> Option<Vec<(&'static str, String)>> {
+        Some(vec![("reduction", self.reduction.to_string())])
+    }
+}
+
+/// Huber loss: `d^2 / 2` for errors `|d| <= delta` and `delta * (|d| - delta / 2)`
+/// beyond, so outliers pull with a bounded gradient `±delta`. Smooth L1 with parameter
+/// `beta` is this loss divided by `beta`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct HuberLossOp {
+    pub delta: f32,
+    pub reduction: Reduction,
+}
+
+impl Default for HuberLossOp {
+    fn default() -> Self {
+        Self {
+            delta: 1.0,
+            reduction: Reduction::Mean,
+        }
+    }
+}
+
+impl HuberLossOp {
+    fn check_delta(&self) -> Result<(), ComputeError> {
+        if self.delta > 0.0 {
+            Ok(())
+        } else {
+            Err(ComputeError::InvalidOperation {
+                message: format!("huber delta must be positive, got {}", self.delta),
+            })
+        }
+    }
+}
+
+impl Op for HuberLossOp {
+    fn forward(&self, inputs: &[Tensor]) -> Result<Tensor, ComputeError> {
+        self.check_delta()?;
+        let delta = self.delta;
+        pointwise_forward("huber loss", inputs, self.reduction, |d| {
+            if d.abs() <= delta {
+                0.5 * d * d
+            } else {
+                delta * (d.abs() - 0.5 * delta)
+            }
+        })
+    }
+
+    fn backward(
+        &self,
+        inputs: &[Tensor],
+        grad_output: &Tensor,
+    ) -> Result<Vec<Tensor>, ComputeError> {
+        self.check_delta()?;
+        let delta = self.delta;
+        pointwise_backward("huber loss", inputs, grad_output, self.reduction, |d| {
+            d.clamp(-delta, delta)
+        })
+    }
+
+    fn infer_shape(&self, inputs: &[Vec<Dim>]) -> Result<Vec<Dim>, ComputeError> {
+        pointwise_infer_shape(inputs, self.reduction)
+    }
+
+    fn name(&self) -> &str {
+        "HuberLossOp"
+    }
+
+    fn attributes(&self) -> Option<Vec<(&'static str, String)>> {
+        Some(vec![
+            ("delta", self.delta.to_string()),
+            ("reduction", self.reduction.to_string()),
+        ])
+    }
+}
+
+/// `ln(cosh(prediction - target))`: quadratic for small errors, linear for large ones,
+/// and smooth everywhere. Evaluated as `|d| + ln(1 + e^(-2|d|)) - ln 2` so large errors
+/// do not overflow.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct LogCoshLossOp {
+    pub reduction: Reduction,
+}
+
+impl Op for LogCoshLossOp {
+    fn forward(&self, inputs: &[Tensor]) -> Result<Tensor, ComputeError> {
+        pointwise_forward("log-cosh loss", inputs, self.reduction, |d| {
+            d.abs() + (-2.0 * d.abs()).exp().ln_1p() - std::f32::consts::LN_2
+        })
+    }
+
+    fn backward(
+        &self,
+        inputs: &[Tensor],
+        grad_output: &Tensor,
+    ) -> Result<Vec<Tensor>, ComputeError> {
+        pointwise_backward(
+            "log-cosh loss",
+            inputs,
+            grad_output,
+            self.reduction,
+            f32::tanh,
+        )
+    }
+
+    fn infer_shape(&self, inputs: &[Vec<Dim>]) -> Result<Vec<Dim>, ComputeError> {
+        pointwise_infer_shape(inputs, self.reduction)
+    }
+
+    fn name(&self) -> &str {
+        "LogCoshLossOp"
+    }
+
+    fn attributes(&self) -> Option<Vec<(&'static str, String)>> {
+        Some(vec![("reduction", self.reduction.to_string())])
+    }
+}
+
+/// Pinball loss of predictions `[..., Q]` for the quantile levels `[Q]` against targets
+/// `[...]`: with error `e = target - prediction`, level `q` costs `q * e` when the target
+/// lies above the prediction and `(q - 1) * e` below it. Minimizing it fits the `q`-th
+/// quantile, so levels `[0.1, 0.5, 0.9]` give a median with an 80% interval.
+///
+/// Inputs are `[predictions, targets, quantiles]`; levels must lie in `(0, 1)`. `Mean`
+/// averages over all `[..., Q]` terms and `None` keeps that shape. The quantile levels
+/// receive a zero gradient.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct QuantileLossOp {
+    pub reduction: Reduction,
+}
+
+impl QuantileLossOp {
+    /// Validate the inputs and return the number of quantile levels.
+    fn levels(inputs: &[Tensor]) -> Result<usize, ComputeError> {
+        let [predictions, targets, quantiles] = inputs else {
+            return Err(ComputeError::InputCountError {
+                expected: 3,
+                got: inputs.len(),
+            });
+        };
+        let q = quantiles.data().len();
+        if quantiles.shape() != [q]
+            || predictions.shape().split_last() != Some((&q, targets.shape()))
+        {
+            return Err(ComputeError::DimensionError {
+                message: format!(
+                    "quantile loss needs predictions [..., Q], targets [...] and quantiles [Q], got {:?}, {:?} and {:?}",
+                    predictions.shape(),
+                    targets.shape(),
+                    quantiles.shape()
+                ),
+            });
+        }
+        if let Some(level) = quantiles.data().iter().find(|l| !(**l > 0.0 && **l < 1.0)) {
+            return Err(ComputeError::InvalidOperation {
+                message: format!("quantile levels must be in (0, 1), got {level}"),
+            });
+        }
+        Ok(q)
+    }
+
+    /// Per-term errors `target - prediction` paired with their quantile level.
+    fn errors(inputs: &[Tensor]) -> Result<Vec<(f32, f32)>, ComputeError> {
+        let q = Self::levels(inputs)?;
+        let levels = inputs[2].data();
+        Ok(inputs[0]
+            .data()
+            .chunks(q.max(1))
+            .zip(inputs[1].data())
+            .flat_map(|(row, &target)| row.iter().zip(levels).map(move |(p, &l)| (target - p, l)))
+            .collect())
+    }
+}
+
+impl Op for QuantileLossOp {
+    fn forward(&self, inputs: &[Tensor]) -> Result<Tensor, ComputeError> {
+        let losses: Vec<f32> = Self::errors(inputs)?
+            .into_iter()
+            .map(|(e, q)| (q * e).max((q - 1.0) * e))
+            .collect();
+        let count = losses.len() as f32;
+        self.reduction
+            .apply(losses, inputs[0].shape().to_vec(), count)
+    }
+
+    fn backward(
+        &self,
+        inputs: &[Tensor],
+        grad_output: &Tensor,
+    ) -> Result<Vec<Tensor>, ComputeError> {
+        let errors = Self::errors(inputs)?;
+        let q = inputs[2].data().len();
+        let scales = self
+            .reduction
+            .element_grads(grad_output, errors.len(), errors.len() as f32);
+        let mut predictions = Tensor::zeros_like(&inputs[0])?;
+        for ((g, (e, level)), scale) in predictions.data_mut().iter_mut().zip(errors).zip(scales) {
+            // d/dprediction of the pinball loss: -q above the target side, 1 - q below.
+            *g = scale
+                * if e > 0.0 {
+                    -level
+                } else if e < 0.0 {
+                    1.0 - level
+                } else {
+                    0.0
+                };
+        }
+        let mut targets = Tensor::zeros_like(&inputs[1])?;
+        for (t, row) in targets
+            .data_mut()
+            .iter_mut()
+            .zip(predictions.data().chunks(q.max(1)))
+        {
+            *t = -row.iter().sum::<f32>();
+        }
+        Ok(vec![predictions, targets, Tensor::zeros_like(&inputs[2])?])
+    }
+
+    fn infer_shape(&self, inputs: &[Vec<Dim>]) -> Result<Vec<Dim>, ComputeError> {
+        shape::expect_inputs(inputs, 3)?;
+        let (predictions, targets, quantiles) = (&inputs[0], &inputs[1], &inputs[2]);
+        let (Some((levels, leading)), [q]) = (predictions.split_last(), &quantiles[..]) else {
+            return Err(ComputeError::DimensionError {
+                message: format!(
+                    "quantile loss needs predictions [..., Q] and quantiles [Q], got {} and {}",
+                    shape::format_shape(predictions),
+                    shape::format_shape(quantiles)
+                ),
+            });
+        };
+        if leading.len() != targets.len() {
+            return Err(ComputeError::DimensionError {
+                message: format!(
+                    "targets {} do not match predictions {}",
+                    shape::format_shape(targets),
+                    shape::format_shape(predictions)
+                ),
+            });
+        }
+        let mut elements = leading
+            .iter()
+            .zip(targets)
+            .map(|(a, b)| shape::unify(a, b))
+            .collect::<Result<Vec<_>, _>>()?;
+        elements.push(shape::unify(levels, q)?);
+        Ok(self.reduction.infer_shape(&elements))
+    }
+
+    fn name(&self) -> &str {
+        "QuantileLossOp"
+    }
+
+    fn attributes(&self) -> Option<Vec<(&'static str, String)>> {
+        Some(vec![("reduction", self.reduction.to_string())])
+    }
+}
+
+/// Which optional inputs follow `[logits, targets]` in a binary loss, in this order:
+/// `pos_weight` (`[labels]`, scaling the positive term of each label along the last axis),
+/// per-sample `weights`, and a `mask` whose zero entries drop elements from the loss and
+/// from the `Mean` count. Weights and masks take a leading part of the logits shape, such
+/// as `[batch]` or `[batch, time]`, and are broadcast over the remaining axes.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct BinaryInputs {
+    pub pos_weight: bool,
+    pub weights: bool,
+    pub mask: bool,
+}
+
+impl BinaryInputs {
+    fn count(self) -> usize {
+        2 + usize::from(self.pos_weight) + usize::from(self.weights) + usize::from(self.mask)
+    }
+
+    fn attributes(self) -> [(&'static str, String); 3] {
+        [
+            ("pos_weight", self.pos_weight.to_string()),
+            ("weights", self.weights.to_string()),
+            ("mask", self.mask.to_string()),
+        ]
+    }
+}
+
+/// One logit of a binary loss with its target, positive weight and overall scale (the
+/// sample weight, or zero if masked out).
+struct BinaryElement {
+    logit: f32,
+    target: f32,
+    pos_weight: f32,
+    scale: f32,
+}
+
+/// Stable `ln(1 + e^x)`.
+fn softplus(x: f32) -> f32 {
+    x.max(0.0) + (-x.abs()).exp().ln_1p()
+}
+
+fn sigmoid(x: f32) -> f32 {
+    1.0 / (1.0 + (-x).exp())
+}
+
+/// Per-element terms of a binary loss and the number of elements the mask keeps.
+fn binary_elements(
+    inputs: &[Tensor],
+    which: BinaryInputs,
+) -> Result<(Vec<BinaryElement>, f32), ComputeError> {
+    if inputs.len() != which.count() {
+        return Err(ComputeError::InputCountError {
+            expected: which.count(),
+            got: inputs.len(),
+        });
+    }
+    let (logits, targets) = (&inputs[0], &inputs[1]);
+    if logits.shape() != targets.shape() {
+        return Err(ComputeError::DimensionError {
+            message: format!(
+                "targets must match logits {:?}, got {:?}",
+                logits.shape(),
+                targets.shape()
+            ),
+        });
+    }
+    let mut rest = inputs[2..].iter();
+    let labels = logits.shape().last().copied().unwrap_or(1);
+    let pos_weight = which.pos_weight.then(|| rest.next()).flatten();
+    if let Some(pos_weight) = pos_weight {
+        if pos_weight.shape() != [labels] {
+            return Err(ComputeError::DimensionError {
+                message: format!(
+                    "pos_weight must be [{labels}], got {:?}",
+                    pos_weight.shape()
+                ),
+            });
+        }
+    }
+    // Number of logits sharing each entry of a leading-shape weight or mask.
+    let per_sample = |name: &str, t: &Tensor| -> Result<usize, ComputeError> {
+        if !logits.shape().starts_with(t.shape()) {
+            return Err(ComputeError::DimensionError {
+                message: format!(
+                    "{name} must have a leading part of the logits shape {:?}, got {:?}",
+                    logits.shape(),
+                    t.shape()
+                ),
+            });
+        }
+        Ok(logits.data().len() / t.data().len().max(1))
+    };
+    let weights = match which.weights.then(|| rest.next()).flatten() {
+        Some(t) => Some((t.data(), per_sample("weights", t)?)),
+        None => None,
+    };
+    let mask = match which.mask.then(|| rest.next()).flatten() {
+        Some(t) => Some((t.data(), per_sample("mask", t)?)),
+        None => None,
+    };
+    let mut kept = 0.0;
+    let elements = logits
+        .data()
+        .iter()
+        .zip(targets.data())
+        .enumerate()
+        .map(|(i, (&logit, &target))| {
+            let masked = mask.is_some_and(|(m, n)| m[i / n.max(1)] == 0.0);
+            if !masked {
+                kept += 1.0;
+            }
+            let weight = weights.map_or(1.0, |(w, n)| w[i / n.max(1)]);
+            BinaryElement {
+                logit,
+                target,
+                pos_weight: pos_weight.map_or(1.0, |p| p.data()[i % labels.max(1)]),
+                scale: if masked { 0.0 } else { weight },
+            }
+        })
+        .collect();
+    Ok((elements, kept))
+}
+
+fn binary_forward(
+    inputs: &[Tensor],
+    which: BinaryInputs,
+    reduction: Reduction,
+    loss: impl Fn(&BinaryElement) -> f32,
+) -> Result<Tensor, ComputeError> {
+    let (elements, kept) = binary_elements(inputs, which)?;
+    let losses = elements
+        .iter()
+        .map(|e| {
+            if e.scale == 0.0 {
+                0.0
+            } else {
+                e.scale * loss(e)
+            }
+        })
+        .collect();
+    reduction.apply(losses, inputs[0].shape().to_vec(), kept)
+}
+
+/// Logit gradients of `binary_forward` given the derivative of `loss` in the logit; the
+/// other inputs get zeros.
+fn binary_backward(
+    inputs: &[Tensor],
+    grad_output: &Tensor,
+    which: BinaryInputs,
+    reduction: Reduction,
+    derivative: impl Fn(&BinaryElement) -> f32,
+) -> Result<Vec<Tensor>, ComputeError> {
+    let (elements, kept) = binary_elements(inputs, which)?;
+    let scales = reduction.element_grads(grad_output, elements.len(), kept);
+    let mut grad = Tensor::zeros_like(&inputs[0])?;
+    for ((g, e), scale) in grad.data_mut().iter_mut().zip(&elements).zip(scales) {
+        if e.scale != 0.0 {
+            *g = scale * e.scale * derivative(e);
+        }
+    }
+    let mut grads = vec![grad];
+    grads.extend(
+        inputs[1..]
+            .iter()
+            .map(Tensor::zeros_like)
+            .collect::<Result<Vec<_>, _>>()?,
+    );
+    Ok(grads)
+}
+
+fn binary_infer_shape(
+    inputs: &[Vec<Dim>],
+    which: BinaryInputs,
+    reduction: Reduction,
+) -> Result<Vec<Dim>, ComputeError> {
+    shape::expect_inputs(inputs, which.count())?;
+    let (logits, targets) = (&inputs[0], &inputs[1]);
+    if logits.len() != targets.len() {
+        return Err(ComputeError::DimensionError {
+            message: format!(
+                "targets {} do not match logits {}",
+                shape::format_shape(targets),
+                shape::format_shape(logits)
+            ),
+        });
+    }
+    let elements = logits
+        .iter()
+        .zip(targets)
+        .map(|(a, b)| shape::unify(a, b))
+        .collect::<Result<Vec<_>, _>>()?;
+    let mut rest = inputs[2..].iter();
+    if which.pos_weight {
+        let pos_weight = rest.next().map(Vec::as_slice);
+        match (pos_weight, elements.last()) {
+            (Some([p]), Some(labels)) => {
+                shape::unify(p, labels)?;
+            }
+            _ => {
+                return Err(ComputeError::DimensionError {
+                    message: format!(
+                        "pos_weight must be [labels] for logits {}",
+                        shape::format_shape(logits)
+                    ),
+                })
+            }
+        }
+    }
+    for leading in rest {
+        if leading.len() > elements.len() {
+            return Err(ComputeError::DimensionError {
+                message: format!(
+                    "weights and masks must have a leading part of the logits shape {}, got {}",
+                    shape::format_shape(logits),
+                    shape::format_shape(leading)
+                ),
+            });
+        }
+        for (a, b) in leading.iter().zip(&elements) {
+            shape::unify(a, b)?;
+        }
+    }
+    Ok(reduction.infer_shape(&elements))
+}
+
+/// Binary cross entropy of logits against targets in `[0, 1]` with the sigmoid fused in,
+/// one independent label per element, so several alarms can be active at once.
+///
+/// With `p = sigmoid(z)` and positive weight `w+`, each element costs
+/// `-(w+ * y * ln p + (1 - y) * ln(1 - p))`, evaluated through `softplus` so extreme
+/// logits stay finite. `Mean` divides by the number of unmasked elements; sample weights
+/// scale the terms without changing that count. Inputs are `[logits, targets]` followed by
+/// the optional inputs selected in `inputs`. Only the logits receive a gradient.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct BCEWithLogitsOp {
+    pub reduction: Reduction,
+    pub inputs: BinaryInputs,
+}
+
+impl Op for BCEWithLogitsOp {
+    fn forward(&self, inputs: &[Tensor]) -> Result<Tensor, ComputeError> {
+        binary_forward(inputs, self.inputs, self.reduction, |e| {
+            // -ln p = softplus(-z) and -ln(1 - p) = softplus(z).
+            e.pos_weight * e.target * softplus(-e.logit) + (1.0 - e.target) * softplus(e.logit)
+        })
+    }
+
+    fn backward(
+        &self,
+        inputs: &[Tensor],
+        grad_output: &Tensor,
+    ) -> Result<Vec<Tensor>, ComputeError> {
+        binary_backward(inputs, grad_output, self.inputs, self.reduction, |e| {
+            let p = sigmoid(e.logit);
+            (1.0 - e.target) * p - e.pos_weight * e.target * (1.0 - p)
+        })
+    }
+
+    fn infer_shape(&self, inputs: &[Vec<Dim>]) -> Result<Vec<Dim>, ComputeError> {
+        binary_infer_shape(inputs, self.inputs, self.reduction)
+    }
+
+    fn name(&self) -> &str {
+        "BCEWithLogitsOp"
+    }
+
+    fn attributes(&self) -> Option<Vec<(&'static str, String)>> {
+        let mut attrs = vec![("reduction", self.reduction.to_string())];
+        attrs.extend(self.inputs.attributes());
+        Some(attrs)
+    }
+}
+
+/// Sigmoid focal loss: binary cross entropy whose terms are scaled down by `(1 - p_t)^gamma`
+/// as the prediction `p_t` of the true label grows, so the many easy negatives of a rare
+/// fault do not drown out the few hard positives.
+///
+/// Each element costs `-(a * w+ * y * (1 - p)^gamma * ln p + (1 - a) * (1 - y) * p^gamma
+/// * ln(1 - p))`, where `a` is `alpha` for the positive term, or the terms are unweighted
+/// if `alpha` is `None`. `gamma = 0` without `alpha` is `BCEWithLogitsOp`. Inputs,
+/// masking and reductions are as for `BCEWithLogitsOp`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct FocalLossOp {
+    pub gamma: f32,
+    pub alpha: Option<f32>,
+    pub reduction: Reduction,
+    pub inputs: BinaryInputs,
+}
+
+impl Default for FocalLossOp {
+    fn default() -> Self {
+        Self {
+            gamma: 2.0,
+            alpha: Some(0.25),
+            reduction: Reduction::Mean,
+            inputs: BinaryInputs::default(),
+        }
+    }
+}
+
+impl FocalLossOp {
+    fn check(&self) -> Result<(), ComputeError> {
+        if self.gamma.is_nan() || self.gamma < 0.0 {
+            return Err(ComputeError::InvalidOperation {
+                message: format!("focal gamma must be non-negative, got {}", self.gamma),
+            });
+        }
+        if let Some(alpha) = self.alpha.filter(|a| !(0.0..=1.0).contains(a)) {
+            return Err(ComputeError::InvalidOperation {
+                message: format!("focal alpha must be in [0, 1], got {alpha}"),
+            });
+        }
+        Ok(())
+    }
+
+    /// Weights of the positive and negative terms.
+    fn balance(&self, e: &BinaryElement) -> (f32, f32) {
+        let (pos, neg) = self.alpha.map_or((1.0, 1.0), |a| (a, 1.0 - a));
+        (pos * e.pos_weight * e.target, neg * (1.0 - e.target))
+    }
+}
+
+impl Op for FocalLossOp {
+    fn forward(&self, inputs: &[Tensor]) -> Result<Tensor, ComputeError> {
+        self.check()?;
+        binary_forward(inputs, self.inputs, self.reduction, |e| {
+            let p = sigmoid(e.logit);
+            let (pos, neg) = self.balance(e);
+            pos * (1.0 - p).powf(self.gamma) * softplus(-e.logit)
+                + neg * p.powf(self.gamma) * softplus(e.logit)
+        })
+    }
+
+    fn backward(
+        &self,
+        inputs: &[Tensor],
+        grad_output: &Tensor,
+    ) -> Result<Vec<Tensor>, ComputeError> {
+        self.check()?;
+        binary_backward(inputs, grad_output, self.inputs, self.reduction, |e| {
+            // With dp/dz = p(1 - p), d(-ln p)/dz = -(1 - p) and d(-ln(1 - p))/dz = p.
+            let p = sigmoid(e.logit);
+            let q = 1.0 - p;
+            let (pos, neg) = self.balance(e);
+            let gamma = self.gamma;
+            pos * q.powf(gamma) * (-gamma * p * softplus(-e.logit) - q)
+                + neg * p.powf(gamma) * (gamma * q * softplus(e.logit) + p)
+        })
+    }
+
+    fn infer_shape(&self, inputs: &[Vec<Dim>]) -> Result<Vec<Dim>, ComputeError> {
+        binary_infer_shape(inputs, self.inputs, self.reduction)
+    }
+
+    fn name(&self) -> &str {
+        "FocalLossOp"
+    }
+
+    fn attributes(&self) -> Option<Vec<(&'static str, String)>> {
+        let mut attrs = vec![
+            ("gamma", self.gamma.to_string()),
+            ("alpha", format!("{:?}", self.alpha)),
+            ("reduction", self.reduction.to_string()),
+        ];
+        attrs.extend(self.inputs.attributes());
+        Some(attrs)
+    }
+}
+
+/// Options for `BCEWithLogitsLoss` and `FocalLoss`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct BinaryLossOptions {
+    /// One weight per label for its positive term, e.g. negatives / positives of a rare
+    /// alarm.
+    pub pos_weight: Option<Vec<f32>>,
+    /// Node of per-sample weights.
+    pub weights: Option<usize>,
+    /// Node of a per-sample mask, zero for padding or unlabelled samples.
+    pub mask: Option<usize>,
+    pub reduction: Reduction,
+}
+
+impl BinaryLossOptions {
+    /// Op inputs after `[logits, targets]`, adding `pos_weight` as a frozen parameter so it
+    /// is saved with the graph.
+    fn inputs(
+        &self,
+        graph: &mut Graph,
+        logits: usize,
+        targets: usize,
+    ) -> Result<(Vec<usize>, BinaryInputs), ComputeError> {
+        let mut inputs = vec![logits, targets];
+        if let Some(pos_weight) = &self.pos_weight {
+            let pos_weight = Tensor::new(pos_weight.clone(), vec![pos_weight.len()])?;
+            inputs.push(graph.add_parameter(pos_weight, false));
+        }
+        inputs.extend(self.weights);
+        inputs.extend(self.mask);
+        let which = BinaryInputs {
+            pos_weight: self.pos_weight.is_some(),
+            weights: self.weights.is_some(),
+            mask: self.mask.is_some(),
+        };
+        Ok((inputs, which))
+    }
+}
+
+/// Multi-label binary cross entropy through a `BCEWithLogitsOp`.
+pub struct BCEWithLogitsLoss;
+
+impl BCEWithLogitsLoss {
+    pub fn compute(
+        graph: &mut Graph,
+        logits: usize,
+        targets: usize,
+        options: &BinaryLossOptions,
+    ) -> Result<usize, ComputeError> {
+        let (inputs, which) = options.inputs(graph, logits, targets)?;
+        let op = BCEWithLogitsOp {
+            reduction: options.reduction,
+            inputs: which,
+        };
+        Ok(graph.apply_op(op, &inputs))
+    }
+}
+
+/// Sigmoid focal loss through a `FocalLossOp`.
+pub struct FocalLoss;
+
+impl FocalLoss {
+    pub fn compute(
+        graph: &mut Graph,
+        logits: usize,
+        targets: usize,
+        gamma: f32,
+        alpha: Option<f32>,
+        options: &BinaryLossOptions,
+    ) -> Result<usize, ComputeError> {
+        let (inputs, which) = options.inputs(graph, logits, targets)?;
+        let op = FocalLossOp {
+            gamma,
+            alpha,
+            reduction: options.reduction,
+            inputs: which,
+        };
+        Ok(graph.apply_op(op, &inputs))
+    }
 }
@@ -0,0 +1,231 @@
+//! Hierarchically named parameters for models built from layers.
+//!
+//! A `Module` names the parameters it owns (`weight`, `bias`) and its submodules (`0`,
+//! `encoder`), giving dotted paths such as `encoder.0.weight`. Those names, unlike node
+//! indices, survive rebuilding the graph, so they key checkpoints (`state_dict`) and select
+//! parameters to freeze.
+//!
+//! Modules may also own buffers, non-trainable state such as running statistics that is
+//! checkpointed alongside the parameters, and mode flags that switch layers like batch
+//! normalization between training and evaluation behaviour (`train`, `eval`).
+
+use std::collections::BTreeMap;
+
+use crate::error::ComputeError;
+use crate::graph::Graph;
+use crate::tensor::Tensor;
+
+/// Parameter values keyed by dotted name, in name order.
+pub type StateDict = BTreeMap<String, Tensor>;
+
+pub trait Module {
+    /// Parameters owned directly by this module, as `(local name, node index)`.
+    fn own_parameters(&self) -> Vec<(String, usize)> {
+        Vec::new()
+    }
+
+    /// Named submodules, in a stable order.
+    fn children(&self) -> Vec<(String, &dyn Module)> {
+        Vec::new()
+    }
+
+    /// Non-trainable state owned directly by this module, as `(local name, node index)`.
+    fn own_buffers(&self) -> Vec<(String, usize)> {
+        Vec::new()
+    }
+
+    /// Mode flag nodes (see `add_mode_flag`) owned directly by this module.
+    fn own_mode_flags(&self) -> Vec<usize> {
+        Vec::new()
+    }
+
+    /// Every parameter of this module and its submodules, with dotted names.
+    fn named_parameters(&self) -> Vec<(String, usize)> {
+        let mut params = self.own_parameters();
+        for (prefix, child) in self.children() {
+            params.extend(
+                child
+                    .named_parameters()
+                    .into_iter()
+                    .map(|(name, idx)| (format!("{prefix}.{name}"), idx)),
+            );
+        }
+        params
+    }
+
+    /// Every buffer of this module and its submodules, with dotted names.
+    fn named_buffers(&self) -> Vec<(String, usize)> {
+        let mut buffers = self.own_buffers();
+        for (prefix, child) in self.children() {
+            buffers.extend(
+                child
+                    .named_buffers()
+                    .into_iter()
+                    .map(|(name, idx)| (format!("{prefix}.{name}"), idx)),
+            );
+        }
+        buffers
+    }
+
+    /// Switch this module and its submodules to training (`true`) or evaluation mode.
+    fn train(&self, graph: &mut Graph, training: bool) -> Result<(), ComputeError> {
+        for idx in self.own_mode_flags() {
+            graph.set_parameter(idx, Tensor::new(vec![f32::from(training)], vec![1])?)?;
+        }
+        for (_, child) in self.children() {
+            child.train(graph, training)?;
+        }
+        Ok(())
+    }
+
+    fn eval(&self, graph: &mut Graph) -> Result<(), ComputeError> {
+        self.train(graph, false)
+    }
+
+    /// Copy every parameter and buffer value out of `graph`.
+    fn state_dict(&self, graph: &Graph) -> Result<StateDict, ComputeError> {
+        self.named_parameters()
+            .into_iter()
+            .chain(self.named_buffers())
+            .map(|(name, idx)| Ok((name, graph.parameter(idx)?.clone())))
+            .collect()
+    }
+
+    /// Write `state` into `graph`. Every parameter and buffer must be present with its
+    /// current shape, and `state` may not contain names this module doesn't have. Nothing
+    /// is written unless every entry passes these checks.
+    fn load_state_dict(&self, graph: &mut Graph, state: &StateDict) -> Result<(), ComputeError> {
+        let mut params = self.named_parameters();
+        params.extend(self.named_buffers());
+        if let Some(name) = state
+            .keys()
+            .find(|name| !params.iter().any(|(n, _)| n == *name))
+        {
+            return Err(ComputeError::StateDict {
+                key: name.clone(),
+                expected: None,
+                got: state.get(name).map(|t| t.shape().to_vec()),
+                source: None,
+            });
+        }
+        let mut updates = Vec::with_capacity(params.len());
+        for (name, idx) in &params {
+            let expected = graph
+                .parameter(*idx)
+                .map_err(|e| ComputeError::StateDict {
+                    key: name.clone(),
+                    expected: None,
+                    got: None,
+                    source: Some(Box::new(e)),
+                })?
+                .shape();
+            let tensor = state.get(name).ok_or_else(|| ComputeError::StateDict {
+                key: name.clone(),
+                expected: Some(expected.to_vec()),
+                got: None,
+                source: None,
+            })?;
+            if tensor.shape() != expected {
+                return Err(ComputeError::StateDict {
+                    key: name.clone(),
+                    expected: Some(expected.to_vec()),
+                    got: Some(tensor.shape().to_vec()),
+                    source: Some(Box::new(ComputeError::DimensionError {
+                        message: format!(
+                            "parameter {idx} has shape {expected:?}, got {:?}",
+                            tensor.shape()
+                        ),
+                    })),
+                });
+            }
+            updates.push((*idx, tensor));
+        }
+        for (idx, tensor) in updates {
+            graph.set_parameter(idx, tensor.clone())?;
+        }
+        Ok(())
+    }
+
+    /// Set `requires_grad` on every parameter whose name matches `pattern`, where `*`
+    /// matches any run of characters (`encoder.*`, `*.bias`). Returns how many matched.
+    fn set_trainable(
+        &self,
+        graph: &mut Graph,
+        pattern: &str,
+        trainable: bool,
+    ) -> Result<usize, ComputeError> {
+        let mut matched = 0;
+        for (name, idx) in self.named_parameters() {
+            if matches_pattern(pattern, &name) {
+                graph.set_requires_grad(idx, trainable)?;
+                matched += 1;
+            }
+        }
+        Ok(matched)
+    }
+
+    fn freeze(&self, graph: &mut Graph, pattern: &str) -> Result<usize, ComputeError> {
+        self.set_trainable(graph, pattern, false)
+    }
+
+    fn unfreeze(&self, graph: &mut Graph, pattern: &str) -> Result<usize, ComputeError> {
+        self.set_trainable(graph, pattern, true)
+    }
+
+    /// Total number of scalar parameters.
+    fn num_parameters(&self, graph: &Graph) -> Result<usize, ComputeError> {
+        self.named_parameters()
+            .into_iter()
+            .map(|(_, idx)| Ok(graph.parameter(idx)?.data().len()))
+            .sum()
+    }
+
+    /// Number of scalar parameters that are not frozen.
+    fn num_trainable_parameters(&self, graph: &Graph) -> Result<usize, ComputeError> {
+        let mut total = 0;
+        for (_, idx) in self.named_parameters() {
+            let len = graph.parameter(idx)?.data().len();
+            if graph.node_requires_grad(idx) {
+                total += len;
+            }
+        }
+        Ok(total)
+    }
+}
+
+/// Add a mode flag node, a frozen `[1]` parameter that is `1` in training mode and `0` in
+/// evaluation mode. Modules list it in `own_mode_flags` and feed it to mode-dependent ops.
+/// Flags start in training mode.
+pub fn add_mode_flag(graph: &mut Graph) -> Result<usize, ComputeError> {
+    Ok(graph.add_parameter(Tensor::ones(vec![1])?, false))
+}
+
+/// Read a mode flag value inside an op.
+pub(crate) fn is_training(flag: &Tensor) -> Result<bool, ComputeError> {
+    match flag.data() {
+        [value] => Ok(*value != 0.0),
+        _ => Err(ComputeError::DimensionError {
+            message: format!("mode flag must hold one value, got {:?}", flag.shape()),
+        }),
+    }
+}
+
+/// Glob match where `*` stands for any (possibly empty) run of characters.
+pub fn matches_pattern(pattern: &str, name: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or("");
+    let Some(mut rest) = name.strip_prefix(first) else {
+        return false;
+    };
+    let parts: Vec<&str> = parts.collect();
+    let Some((last, middle)) = parts.split_last() else {
+        return rest.is_empty();
+    };
+    for part in middle {
+        match rest.find(part) {
+            Some(pos) => rest = &rest[pos + part.len()..],
+            None => return false,
+        }
+    }
+    rest.len() >= last.len() && rest.ends_with(last)
+}
@@ -0,0 +1,759 @@
+//! Normalization ops and layers.
+//!
+//! Each op standardizes groups of elements to zero mean and unit variance and then applies
+//! a per-channel scale and shift. They differ in how elements are grouped: `LayerNormOp`
+//! over the last axis of each row, `GroupNormOp` over groups of channels of each sample and
+//! `BatchNormOp` over the whole batch for each channel. The variance used for
+//! normalization is the biased (population) one.
+
+use crate::error::ComputeError;
+use crate::graph::Graph;
+use crate::layers::Layer;
+use crate::module::{self, Module};
+use crate::ops::Op;
+use crate::shape::{self, Dim};
+use crate::tensor::Tensor;
+
+/// Which normalization group and which affine channel each element belongs to.
+struct Layout {
+    group: Vec<usize>,
+    groups: usize,
+    channel: Vec<usize>,
+    channels: usize,
+}
+
+impl Layout {
+    /// Rows of the last axis, with the features as channels.
+    fn last_axis(shape: &[usize]) -> Result<Self, ComputeError> {
+        let features = *shape.last().ok_or_else(|| ComputeError::DimensionError {
+            message: "layer norm needs at least one axis".to_string(),
+        })?;
+        let len: usize = shape.iter().product();
+        Ok(Self {
+            group: (0..len).map(|i| i / features).collect(),
+            groups: len / features.max(1),
+            channel: (0..len).map(|i| i % features).collect(),
+            channels: features,
+        })
+    }
+
+    /// `[batch, channels, ...]` input with channels split into `groups` per sample.
+    fn grouped(shape: &[usize], groups: usize) -> Result<Self, ComputeError> {
+        let (batch, channels, spatial) = channel_axis(shape)?;
+        if groups == 0 || !channels.is_multiple_of(groups) {
+            return Err(ComputeError::InvalidOperation {
+                message: format!("{groups} groups must divide {channels} channels"),
+            });
+        }
+        let per_group = channels / groups;
+        let channel: Vec<usize> = (0..batch * channels * spatial)
+            .map(|i| i / spatial % channels)
+            .collect();
+        Ok(Self {
+            group: channel
+                .iter()
+                .enumerate()
+                .map(|(i, c)| i / (channels * spatial) * groups + c / per_group)
+                .collect(),
+            groups: batch * groups,
+            channel,
+            channels,
+        })
+    }
+
+    /// `[batch, channels, ...]` input with one group per channel across the batch.
+    fn per_channel(shape: &[usize]) -> Result<Self, ComputeError> {
+        let (batch, channels, spatial) = channel_axis(shape)?;
+        let channel: Vec<usize> = (0..batch * channels * spatial)
+            .map(|i| i / spatial % channels)
+            .collect();
+        Ok(Self {
+            group: channel.clone(),
+            groups: channels,
+            channel,
+            channels,
+        })
+    }
+
+    fn counts(&self) -> Vec<f32> {
+        let mut counts = vec![0.0; self.groups];
+        for &g in &self.group {
+            counts[g] += 1.0;
+        }
+        counts
+    }
+}
+
+/// Split `[batch, channels, ...]` into batch size, channel count and spatial size.
+fn channel_axis(shape: &[usize]) -> Result<(usize, usize, usize), ComputeError> {
+    match shape {
+        [batch, channels, spatial @ ..] => Ok((*batch, *channels, spatial.iter().product())),
+        _ => Err(ComputeError::DimensionError {
+            message: format!("expected [batch, channels, ...], got {shape:?}"),
+        }),
+    }
+}
+
+/// Per-group mean and biased variance.
+fn moments(x: &[f32], layout: &Layout) -> (Vec<f32>, Vec<f32>) {
+    let counts = layout.counts();
+    let mut mean = vec![0.0; layout.groups];
+    for (v, &g) in x.iter().zip(&layout.group) {
+        mean[g] += v;
+    }
+    for (m, n) in mean.iter_mut().zip(&counts) {
+        *m /= n.max(1.0);
+    }
+    let mut var = vec![0.0; layout.groups];
+    for (v, &g) in x.iter().zip(&layout.group) {
+        var[g] += (v - mean[g]).powi(2);
+    }
+    for (v, n) in var.iter_mut().zip(&counts) {
+        *v /= n.max(1.0);
+    }
+    (mean, var)
+}
+
+/// Statistics shared by the forward and backward passes.
+struct Normalized {
+    /// Standardized input, `(x - mean) * inv_std`.
+    xhat: Vec<f32>,
+    /// Per group.
+    inv_std: Vec<f32>,
+}
+
+fn normalize(x: &[f32], layout: &Layout, eps: f32) -> Normalized {
+    let (mean, var) = moments(x, layout);
+    let inv_std: Vec<f32> = var.iter().map(|v| 1.0 / (v + eps).sqrt()).collect();
+    let xhat = x
+        .iter()
+        .zip(&layout.group)
+        .map(|(v, &g)| (v - mean[g]) * inv_std[g])
+        .collect();
+    Normalized { xhat, inv_std }
+}
+
+/// Gradient with respect to the input of `normalize`, given the gradient `dxhat` with
+/// respect to its output: `inv_std * (dxhat - mean(dxhat) - xhat * mean(dxhat * xhat))`.
+fn normalize_backward(dxhat: &[f32], norm: &Normalized, layout: &Layout) -> Vec<f32> {
+    let counts = layout.counts();
+    let mut mean_d = vec![0.0; layout.groups];
+    let mut mean_dx = vec![0.0; layout.groups];
+    for ((d, x), &g) in dxhat.iter().zip(&norm.xhat).zip(&layout.group) {
+        mean_d[g] += d;
+        mean_dx[g] += d * x;
+    }
+    for g in 0..layout.groups {
+        mean_d[g] /= counts[g].max(1.0);
+        mean_dx[g] /= counts[g].max(1.0);
+    }
+    dxhat
+        .iter()
+        .zip(&norm.xhat)
+        .zip(&layout.group)
+        .map(|((d, x), &g)| norm.inv_std[g] * (d - mean_d[g] - x * mean_dx[g]))
+        .collect()
+}
+
+/// Check optional `[channels]` scale and shift inputs.
+fn check_affine(affine: &[Tensor], channels: usize, what: &str) -> Result<(), ComputeError> {
+    for t in affine {
+        if t.shape() != [channels] {
+            return Err(ComputeError::DimensionError {
+                message: format!(
+                    "{what} scale and shift must be [{channels}], got {:?}",
+                    t.shape()
+                ),
+            });
+        }
+    }
+    Ok(())
+}
+
+/// `xhat * gamma + beta` per channel, or `xhat` unchanged without affine inputs.
+fn affine(mut xhat: Vec<f32>, layout: &Layout, affine: &[Tensor]) -> Vec<f32> {
+    if let [gamma, beta] = affine {
+        for (v, &c) in xhat.iter_mut().zip(&layout.channel) {
+            *v = *v * gamma.data()[c] + beta.data()[c];
+        }
+    }
+    xhat
+}
+
+/// Gradients of `affine`: with respect to `xhat`, then the scale and shift if present.
+fn affine_backward(
+    grad: &[f32],
+    xhat: &[f32],
+    layout: &Layout,
+    affine: &[Tensor],
+) -> Result<(Vec<f32>, Vec<Tensor>), ComputeError> {
+    let [gamma, _] = affine else {
+        return Ok((grad.to_vec(), Vec::new()));
+    };
+    let mut dxhat = Vec::with_capacity(grad.len());
+    let mut ggamma = vec![0.0; layout.channels];
+    let mut gbeta = vec![0.0; layout.channels];
+    for ((g, x), &c) in grad.iter().zip(xhat).zip(&layout.channel) {
+        dxhat.push(g * gamma.data()[c]);
+        ggamma[c] += g * x;
+        gbeta[c] += g;
+    }
+    Ok((
+        dxhat,
+        vec![
+            Tensor::new(ggamma, vec![layout.channels])?,
+            Tensor::new(gbeta, vec![layout.channels])?,
+        ],
+    ))
+}
+
+/// Input count check for ops taking `[x]` or `[x, gamma, beta]`.
+fn expect_affine_inputs(count: usize) -> Result<(), ComputeError> {
+    if count != 1 && count != 3 {
+        return Err(ComputeError::InputCountError {
+            expected: 3,
+            got: count,
+        });
+    }
+    Ok(())
+}
+
+/// Check `inputs[1..]` are 1D and match `channels`.
+fn infer_affine(inputs: &[Vec<Dim>], channels: &Dim, what: &str) -> Result<(), ComputeError> {
+    for affine in inputs {
+        match &affine[..] {
+            [c] => {
+                shape::unify(channels, c)?;
+            }
+            _ => {
+                return Err(ComputeError::DimensionError {
+                    message: format!(
+                        "{what} scale and shift must be 1D, got {}",
+                        shape::format_shape(affine)
+                    ),
+                })
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Normalize each row over the last axis to zero mean and unit variance, then scale and
+/// shift: `y = (x - mean) / sqrt(var + eps) * gamma + beta`.
+///
+/// Inputs are `[x]` or `[x, gamma, beta]` with `gamma` and `beta` shaped `[features]`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LayerNormOp {
+    pub eps: f32,
+}
+
+impl Default for LayerNormOp {
+    fn default() -> Self {
+        Self { eps: 1e-5 }
+    }
+}
+
+impl Op for LayerNormOp {
+    fn forward(&self, inputs: &[Tensor]) -> Result<Tensor, ComputeError> {
+        expect_affine_inputs(inputs.len())?;
+        let x = &inputs[0];
+        let layout = Layout::last_axis(x.shape())?;
+        check_affine(&inputs[1..], layout.channels, "layer norm")?;
+        let norm = normalize(x.data(), &layout, self.eps);
+        Tensor::new(affine(norm.xhat, &layout, &inputs[1..]), x.shape().to_vec())
+    }
+
+    fn backward(
+        &self,
+        inputs: &[Tensor],
+        grad_output: &Tensor,
+    ) -> Result<Vec<Tensor>, ComputeError> {
+        expect_affine_inputs(inputs.len())?;
+        let x = &inputs[0];
+        let layout = Layout::last_axis(x.shape())?;
+        check_affine(&inputs[1..], layout.channels, "layer norm")?;
+        let norm = normalize(x.data(), &layout, self.eps);
+        let (dxhat, affine_grads) =
+            affine_backward(grad_output.data(), &norm.xhat, &layout, &inputs[1..])?;
+        let gx = normalize_backward(&dxhat, &norm, &layout);
+        let mut grads = vec![Tensor::new(gx, x.shape().to_vec())?];
+        grads.extend(affine_grads);
+        Ok(grads)
+    }
+
+    fn infer_shape(&self, inputs: &[Vec<Dim>]) -> Result<Vec<Dim>, ComputeError> {
+        expect_affine_inputs(inputs.len())?;
+        let x = &inputs[0];
+        let Some(last) = x.last() else {
+            return Err(ComputeError::DimensionError {
+                message: "layer norm needs at least one axis".to_string(),
+            });
+        };
+        infer_affine(&inputs[1..], last, "layer norm")?;
+        Ok(x.clone())
+    }
+
+    fn name(&self) -> &str {
+        "LayerNormOp"
+    }
+
+    fn attributes(&self) -> Option<Vec<(&'static str, String)>> {
+        Some(vec![("eps", self.eps.to_string())])
+    }
+}
+
+/// Normalize each sample of a `[batch, channels, ...]` input over groups of
+/// `channels / groups` consecutive channels and all positions, then scale and shift per
+/// channel. Unlike batch normalization it behaves the same for any batch size.
+///
+/// Inputs are `[x]` or `[x, gamma, beta]` with `gamma` and `beta` shaped `[channels]`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct GroupNormOp {
+    pub groups: usize,
+    pub eps: f32,
+}
+
+impl GroupNormOp {
+    pub fn new(groups: usize) -> Self {
+        Self { groups, eps: 1e-5 }
+    }
+
+    fn layout(&self, inputs: &[Tensor]) -> Result<Layout, ComputeError> {
+        expect_affine_inputs(inputs.len())?;
+        let layout = Layout::grouped(inputs[0].shape(), self.groups)?;
+        check_affine(&inputs[1..], layout.channels, "group norm")?;
+        Ok(layout)
+    }
+}
+
+impl Op for GroupNormOp {
+    fn forward(&self, inputs: &[Tensor]) -> Result<Tensor, ComputeError> {
+        let layout = self.layout(inputs)?;
+        let norm = normalize(inputs[0].data(), &layout, self.eps);
+        Tensor::new(
+            affine(norm.xhat, &layout, &inputs[1..]),
+            inputs[0].shape().to_vec(),
+        )
+    }
+
+    fn backward(
+        &self,
+        inputs: &[Tensor],
+        grad_output: &Tensor,
+    ) -> Result<Vec<Tensor>, ComputeError> {
+        let layout = self.layout(inputs)?;
+        let norm = normalize(inputs[0].data(), &layout, self.eps);
+        let (dxhat, affine_grads) =
+            affine_backward(grad_output.data(), &norm.xhat, &layout, &inputs[1..])?;
+        let gx = normalize_backward(&dxhat, &norm, &layout);
+        let mut grads = vec![Tensor::new(gx, inputs[0].shape().to_vec())?];
+        grads.extend(affine_grads);
+        Ok(grads)
+    }
+
+    fn infer_shape(&self, inputs: &[Vec<Dim>]) -> Result<Vec<Dim>, ComputeError> {
+        expect_affine_inputs(inputs.len())?;
+        let x = &inputs[0];
+        if x.len() < 2 {
+            return Err(ComputeError::DimensionError {
+                message: format!(
+                    "group norm expects [batch, channels, ...], got {}",
+                    shape::format_shape(x)
+                ),
+            });
+        }
+        if let Dim::Fixed(channels) = x[1] {
+            if self.groups == 0 || !channels.is_multiple_of(self.groups) {
+                return Err(ComputeError::InvalidOperation {
+                    message: format!("{} groups must divide {channels} channels", self.groups),
+                });
+            }
+        }
+        infer_affine(&inputs[1..], &x[1], "group norm")?;
+        Ok(x.clone())
+    }
+
+    fn name(&self) -> &str {
+        "GroupNormOp"
+    }
+
+    fn attributes(&self) -> Option<Vec<(&'static str, String)>> {
+        Some(vec![
+            ("groups", self.groups.to_string()),
+            ("eps", self.eps.to_string()),
+        ])
+    }
+}
+
+/// Batch normalization of a `[batch, channels, ...]` input per channel.
+///
+/// Inputs are `[x, gamma, beta, running_mean, running_var, mode]`, all but `x` shaped
+/// `[channels]` and `mode` a mode flag (see `module::add_mode_flag`). In training mode
+/// each channel is normalized with the mean and variance of the current batch; in
+/// evaluation mode with the running statistics, which makes the op a fixed per-channel
+/// affine map. The running statistics and the flag receive zero gradients; `RunningStatOp`
+/// computes their updates.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BatchNormOp {
+    pub eps: f32,
+}
+
+impl Default for BatchNormOp {
+    fn default() -> Self {
+        Self { eps: 1e-5 }
+    }
+}
+
+impl BatchNormOp {
+    fn layout(&self, inputs: &[Tensor]) -> Result<Layout, ComputeError> {
+        if inputs.len() != 6 {
+            return Err(ComputeError::InputCountError {
+                expected: 6,
+                got: inputs.len(),
+            });
+        }
+        let layout = Layout::per_channel(inputs[0].shape())?;
+        check_affine(&inputs[1..5], layout.channels, "batch norm")?;
+        Ok(layout)
+    }
+
+    /// Standardize with batch statistics in training mode, running ones otherwise.
+    fn normalize(&self, inputs: &[Tensor], layout: &Layout) -> Result<Normalized, ComputeError> {
+        if module::is_training(&inputs[5])? {
+            return Ok(normalize(inputs[0].data(), layout, self.eps));
+        }
+        let (mean, var) = (inputs[3].data(), inputs[4].data());
+        let inv_std: Vec<f32> = var.iter().map(|v| 1.0 / (v + self.eps).sqrt()).collect();
+        let xhat = inputs[0]
+            .data()
+            .iter()
+            .zip(&layout.channel)
+            .map(|(v, &c)| (v - mean[c]) * inv_std[c])
+            .collect();
+        Ok(Normalized { xhat, inv_std })
+    }
+}
+
+impl Op for BatchNormOp {
+    fn forward(&self, inputs: &[Tensor]) -> Result<Tensor, ComputeError> {
+        let layout = self.layout(inputs)?;
+        let norm = self.normalize(inputs, &layout)?;
+        Tensor::new(
+            affine(norm.xhat, &layout, &inputs[1..3]),
+            inputs[0].shape().to_vec(),
+        )
+    }
+
+    fn backward(
+        &self,
+        inputs: &[Tensor],
+        grad_output: &Tensor,
+    ) -> Result<Vec<Tensor>, ComputeError> {
+        let layout = self.layout(inputs)?;
+        let norm = self.normalize(inputs, &layout)?;
+        let (dxhat, affine_grads) =
+            affine_backward(grad_output.data(), &norm.xhat, &layout, &inputs[1..3])?;
+        let gx = if module::is_training(&inputs[5])? {
+            normalize_backward(&dxhat, &norm, &layout)
+        } else {
+            dxhat
+                .iter()
+                .zip(&layout.channel)
+                .map(|(d, &c)| d * norm.inv_std[c])
+                .collect()
+        };
+        let mut grads = vec![Tensor::new(gx, inputs[0].shape().to_vec())?];
+        grads.extend(affine_grads);
+        for stat in &inputs[3..] {
+            grads.push(Tensor::zeros_like(stat)?);
+        }
+        Ok(grads)
+    }
+
+    fn infer_shape(&self, inputs: &[Vec<Dim>]) -> Result<Vec<Dim>, ComputeError> {
+        shape::expect_inputs(inputs, 6)?;
+        let x = &inputs[0];
+        if x.len() < 2 {
+            return Err(ComputeError::DimensionError {
+                message: format!(
+                    "batch norm expects [batch, channels, ...], got {}",
+                    shape::format_shape(x)
+                ),
+            });
+        }
+        infer_affine(&inputs[1..5], &x[1], "batch norm")?;
+        infer_affine(&inputs[5..], &Dim::Fixed(1), "batch norm mode")?;
+        Ok(x.clone())
+    }
+
+    fn name(&self) -> &str {
+        "BatchNormOp"
+    }
+
+    fn attributes(&self) -> Option<Vec<(&'static str, String)>> {
+        Some(vec![("eps", self.eps.to_string())])
+    }
+}
+
+/// Exponential moving average of a per-channel batch statistic, for updating batch norm
+/// running statistics with `Graph::add_buffer_update`.
+///
+/// Inputs are `[x, running, mode]` with x `[batch, channels, ...]` and running
+/// `[channels]`. In training mode the output is
+/// `(1 - momentum) * running + momentum * stat`, where `stat` is the batch mean or, with
+/// `variance`, the unbiased batch variance; in evaluation mode it is `running` unchanged.
+/// The op is not differentiable and passes back zero gradients.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RunningStatOp {
+    pub momentum: f32,
+    pub variance: bool,
+}
+
+impl Op for RunningStatOp {
+    fn forward(&self, inputs: &[Tensor]) -> Result<Tensor, ComputeError> {
+        let [x, running, mode] = inputs else {
+            return Err(ComputeError::InputCountError {
+                expected: 3,
+                got: inputs.len(),
+            });
+        };
+        let layout = Layout::per_channel(x.shape())?;
+        check_affine(std::slice::from_ref(running), layout.channels, "running")?;
+        if !module::is_training(mode)? {
+            return Ok(running.clone());
+        }
+        let (mean, var) = moments(x.data(), &layout);
+        let stat = if self.variance {
+            let counts = layout.counts();
+            var.iter()
+                .zip(&counts)
+                .map(|(v, n)| v * n / (n - 1.0).max(1.0))
+                .collect()
+        } else {
+            mean
+        };
+        let updated = running
+            .data()
+            .iter()
+            .zip(&stat)
+            .map(|(r, s)| (1.0 - self.momentum) * r + self.momentum * s)
+            .collect();
+        Tensor::new(updated, running.shape().to_vec())
+    }
+
+    fn backward(
+        &self,
+        inputs: &[Tensor],
+        _grad_output: &Tensor,
+    ) -> Result<Vec<Tensor>, ComputeError> {
+        inputs.iter().map(Tensor::zeros_like).collect()
+    }
+
+    fn infer_shape(&self, inputs: &[Vec<Dim>]) -> Result<Vec<Dim>, ComputeError> {
+        shape::expect_inputs(inputs, 3)?;
+        let x = &inputs[0];
+        if x.len() < 2 {
+            return Err(ComputeError::DimensionError {
+                message: format!(
+                    "running statistics expect [batch, channels, ...], got {}",
+                    shape::format_shape(x)
+                ),
+            });
+        }
+        infer_affine(&inputs[1..2], &x[1], "running")?;
+        infer_affine(&inputs[2..], &Dim::Fixed(1), "running mode")?;
+        Ok(inputs[1].clone())
+    }
+
+    fn name(&self) -> &str {
+        "RunningStatOp"
+    }
+
+    fn attributes(&self) -> Option<Vec<(&'static str, String)>> {
+        Some(vec![
+            ("momentum", self.momentum.to_string()),
+            ("variance", self.variance.to_string()),
+        ])
+    }
+}
+
+/// `LayerNormOp` over the last axis with a learned `weight` (ones) and `bias` (zeros).
+pub struct LayerNorm {
+    weight_idx: usize,
+    bias_idx: usize,
+    op: LayerNormOp,
+    pub features: usize,
+}
+
+impl LayerNorm {
+    pub fn new(graph: &mut Graph, features: usize) -> Result<Self, ComputeError> {
+        Self::with_op(graph, features, LayerNormOp::default())
+    }
+
+    pub fn with_op(
+        graph: &mut Graph,
+        features: usize,
+        op: LayerNormOp,
+    ) -> Result<Self, ComputeError> {
+        let weight_idx = graph.add_parameter(Tensor::ones(vec![features])?, true);
+        let bias_idx = graph.add_parameter(Tensor::zeros(vec![features])?, true);
+        Ok(Self {
+            weight_idx,
+            bias_idx,
+            op,
+            features,
+        })
+    }
+}
+
+impl Module for LayerNorm {
+    fn own_parameters(&self) -> Vec<(String, usize)> {
+        vec![
+            ("weight".to_string(), self.weight_idx),
+            ("bias".to_string(), self.bias_idx),
+        ]
+    }
+}
+
+impl Layer for LayerNorm {
+    fn forward(&self, graph: &mut Graph, input_idx: usize) -> Result<usize, ComputeError> {
+        Ok(graph.apply_op(self.op, &[input_idx, self.weight_idx, self.bias_idx]))
+    }
+}
+
+/// `GroupNormOp` with a learned per-channel `weight` (ones) and `bias` (zeros).
+pub struct GroupNorm {
+    weight_idx: usize,
+    bias_idx: usize,
+    op: GroupNormOp,
+    pub channels: usize,
+}
+
+impl GroupNorm {
+    pub fn new(graph: &mut Graph, groups: usize, channels: usize) -> Result<Self, ComputeError> {
+        Self::with_op(graph, channels, GroupNormOp::new(groups))
+    }
+
+    pub fn with_op(
+        graph: &mut Graph,
+        channels: usize,
+        op: GroupNormOp,
+    ) -> Result<Self, ComputeError> {
+        if op.groups == 0 || !channels.is_multiple_of(op.groups) {
+            return Err(ComputeError::InvalidOperation {
+                message: format!("{} groups must divide {channels} channels", op.groups),
+            });
+        }
+        let weight_idx = graph.add_parameter(Tensor::ones(vec![channels])?, true);
+        let bias_idx = graph.add_parameter(Tensor::zeros(vec![channels])?, true);
+        Ok(Self {
+            weight_idx,
+            bias_idx,
+            op,
+            channels,
+        })
+    }
+}
+
+impl Module for GroupNorm {
+    fn own_parameters(&self) -> Vec<(String, usize)> {
+        vec![
+            ("weight".to_string(), self.weight_idx),
+            ("bias".to_string(), self.bias_idx),
+        ]
+    }
+}
+
+impl Layer for GroupNorm {
+    fn forward(&self, graph: &mut Graph, input_idx: usize) -> Result<usize, ComputeError> {
+        Ok(graph.apply_op(self.op, &[input_idx, self.weight_idx, self.bias_idx]))
+    }
+}
+
+/// Batch normalization of `[batch, features]` or `[batch, channels, length]` inputs with a
+/// learned `weight` (ones) and `bias` (zeros) and `running_mean` (zeros) and `running_var`
+/// (ones) buffers.
+///
+/// `forward` registers the running statistic updates with `Graph::add_buffer_update`,
+/// replacing those of any earlier `forward`; call `Graph::update_buffers` once per training
+/// step to apply them. In evaluation mode
+/// (`Module::eval`) the layer normalizes with the running statistics and the updates leave
+/// them unchanged.
+pub struct BatchNorm1d {
+    weight_idx: usize,
+    bias_idx: usize,
+    running_mean_idx: usize,
+    running_var_idx: usize,
+    mode_idx: usize,
+    op: BatchNormOp,
+    pub features: usize,
+    /// Weight of the newest batch in the running statistics.
+    pub momentum: f32,
+}
+
+impl BatchNorm1d {
+    pub fn new(graph: &mut Graph, features: usize) -> Result<Self, ComputeError> {
+        Self::with_op(graph, features, BatchNormOp::default())
+    }
+
+    pub fn with_op(
+        graph: &mut Graph,
+        features: usize,
+        op: BatchNormOp,
+    ) -> Result<Self, ComputeError> {
+        Ok(Self {
+            weight_idx: graph.add_parameter(Tensor::ones(vec![features])?, true),
+            bias_idx: graph.add_parameter(Tensor::zeros(vec![features])?, true),
+            running_mean_idx: graph.add_parameter(Tensor::zeros(vec![features])?, false),
+            running_var_idx: graph.add_parameter(Tensor::ones(vec![features])?, false),
+            mode_idx: module::add_mode_flag(graph)?,
+            op,
+            features,
+            momentum: 0.1,
+        })
+    }
+}
+
+impl Module for BatchNorm1d {
+    fn own_parameters(&self) -> Vec<(String, usize)> {
+        vec![
+            ("weight".to_string(), self.weight_idx),
+            ("bias".to_string(), self.bias_idx),
+        ]
+    }
+
+    fn own_buffers(&self) -> Vec<(String, usize)> {
+        vec![
+            ("running_mean".to_string(), self.running_mean_idx),
+            ("running_var".to_string(), self.running_var_idx),
+        ]
+    }
+
+    fn own_mode_flags(&self) -> Vec<usize> {
+        vec![self.mode_idx]
+    }
+}
+
+impl Layer for BatchNorm1d {
+    fn forward(&self, graph: &mut Graph, input_idx: usize) -> Result<usize, ComputeError> {
+        for (buffer, variance) in [(self.running_mean_idx, false), (self.running_var_idx, true)] {
+            let op = RunningStatOp {
+                momentum: self.momentum,
+                variance,
+            };
+            let updated = graph.apply_op(op, &[input_idx, buffer, self.mode_idx]);
+            graph.add_buffer_update(buffer, updated);
+        }
+        Ok(graph.apply_op(
+            self.op,
+            &[
+                input_idx,
+                self.weight_idx,
+                self.bias_idx,
+                self.running_mean_idx,
+                self.running_var_idx,
+                self.mode_idx,
+            ],
+        ))
+    }
+}
@@ -0,0 +1,94 @@
+//! Numeric policy for operations with singular points: division by zero, `ln` of
+//! non-positive values and `sqrt` of negative values.
+//!
+//! Each `Graph` has its own policy (`Graph::set_numeric_policy`), which it passes to
+//! `DivideOp`, `LogOp`, `SqrtOp` and fused kernels in forward and backward through
+//! `OpContext`. The `*_with` tensor methods and `InvertibleOp::invert_with` take an explicit
+//! policy; `Tensor::divide`, `log`, `sqrt` and plain `Op` calls use the IEEE default.
+
+use std::fmt;
+use std::str::FromStr;
+
+use crate::error::ComputeError;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum NumericPolicy {
+    /// Plain IEEE 754 results: `x / 0 = ±inf`, `ln(0) = -inf`, `sqrt(-1) = NaN`.
+    #[default]
+    Ieee,
+    /// Every singular point yields NaN.
+    Nan,
+    /// Fail with `ComputeError::SingularPoint` naming the first offending element.
+    Error,
+    /// Divisors smaller than `eps` in magnitude become `±eps`; `ln` and `sqrt` arguments
+    /// are clamped to at least `eps`.
+    Epsilon(f32),
+}
+
+/// `ieee`, `nan`, `error` or `epsilon:<eps>`, as saved by `serialize`.
+impl fmt::Display for NumericPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NumericPolicy::Ieee => f.write_str("ieee"),
+            NumericPolicy::Nan => f.write_str("nan"),
+            NumericPolicy::Error => f.write_str("error"),
+            NumericPolicy::Epsilon(eps) => write!(f, "epsilon:{eps}"),
+        }
+    }
+}
+
+impl FromStr for NumericPolicy {
+    type Err = ComputeError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let policy = match s {
+            "ieee" => Some(NumericPolicy::Ieee),
+            "nan" => Some(NumericPolicy::Nan),
+            "error" => Some(NumericPolicy::Error),
+            _ => s
+                .strip_prefix("epsilon:")
+                .and_then(|eps| eps.parse().ok())
+                .map(NumericPolicy::Epsilon),
+        };
+        policy.ok_or_else(|| ComputeError::Parse {
+            context: "numeric policy".to_string(),
+            message: format!("unknown policy {s:?}"),
+        })
+    }
+}
+
+/// `a / b`, or `None` if `b` is zero under `NumericPolicy::Error`.
+pub fn div(a: f32, b: f32, policy: NumericPolicy) -> Option<f32> {
+    match policy {
+        NumericPolicy::Epsilon(eps) if b.abs() < eps => Some(a / eps.copysign(b)),
+        NumericPolicy::Nan if b == 0.0 => Some(f32::NAN),
+        NumericPolicy::Error if b == 0.0 => None,
+        _ => Some(a / b),
+    }
+}
+
+/// Natural log; singular for `x <= 0`.
+pub fn ln(x: f32, policy: NumericPolicy) -> Option<f32> {
+    match policy {
+        NumericPolicy::Epsilon(eps) => Some(x.max(eps).ln()),
+        NumericPolicy::Nan if x <= 0.0 => Some(f32::NAN),
+        NumericPolicy::Error if x <= 0.0 => None,
+        _ => Some(x.ln()),
+    }
+}
+
+/// Square root; singular for `x < 0`.
+pub fn sqrt(x: f32, policy: NumericPolicy) -> Option<f32> {
+    match policy {
+        NumericPolicy::Epsilon(eps) => Some(x.max(eps).sqrt()),
+        NumericPolicy::Error if x < 0.0 => None,
+        _ => Some(x.sqrt()),
+    }
+}
+
+pub(crate) fn singular(operation: &str, index: usize) -> ComputeError {
+    ComputeError::SingularPoint {
+        operation: operation.to_string(),
+        index,
+    }
+}
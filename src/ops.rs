@@ -1,7 +1,50 @@
 use crate::error::ComputeError;
+use crate::numeric::{self, NumericPolicy};
+use crate::parallel::ThreadPool;
+use crate::shape::{self, Dim};
 use crate::tensor::Tensor;
 use crate::tensor_index;
 
+/// Settings of the graph running an op, passed to `Op::forward_with` and friends.
+///
+/// Ops that evaluate subgraphs hand it on, so their bodies run under the outer graph's
+/// numeric policy, anomaly detection and worker threads.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct OpContext<'a> {
+    pub policy: NumericPolicy,
+    pub detect_anomalies: bool,
+    /// Workers of the running graph; `None` evaluates on the calling thread.
+    pool: Option<&'a ThreadPool>,
+}
+
+impl<'a> OpContext<'a> {
+    pub(crate) fn new(policy: NumericPolicy, detect_anomalies: bool, pool: &'a ThreadPool) -> Self {
+        Self {
+            policy,
+            detect_anomalies,
+            pool: Some(pool),
+        }
+    }
+
+    /// Number of threads independent nodes are evaluated on.
+    pub fn threads(&self) -> usize {
+        self.pool.map_or(1, ThreadPool::threads)
+    }
+
+    /// `ThreadPool::map` on the running graph's workers, or inline without a pool.
+    pub(crate) fn map<T, R, F>(&self, items: &[T], f: F) -> Vec<R>
+    where
+        T: Sync,
+        R: Send,
+        F: Fn(&T) -> R + Sync,
+    {
+        match self.pool {
+            Some(pool) => pool.map(items, f),
+            None => items.iter().map(f).collect(),
+        }
+    }
+}
+
 pub trait Op: Send + Sync {
     fn forward(&self, inputs: &[Tensor]) -> Result<Tensor, ComputeError>;
     fn backward(
@@ -9,6 +52,77 @@ pub trait Op: Send + Sync {
         inputs: &[Tensor],
         grad_output: &Tensor,
     ) -> Result<Vec<Tensor>, ComputeError>;
+
+    /// Forward into a preallocated `out` tensor, reshaping it as needed.
+    ///
+    /// The default clones the inputs and calls `forward`; built-in ops override it with
+    /// kernels that do not allocate when `out` already has enough capacity.
+    fn forward_into(&self, inputs: &[&Tensor], out: &mut Tensor) -> Result<(), ComputeError> {
+        let owned: Vec<Tensor> = inputs.iter().map(|&t| t.clone()).collect();
+        out.assign(&self.forward(&owned)?);
+        Ok(())
+    }
+
+    /// `forward` under the settings of the graph running the op.
+    ///
+    /// The default ignores `ctx`. Ops with singular points and ops that evaluate subgraphs
+    /// override it, and the plain `forward` of such ops uses `OpContext::default()`.
+    fn forward_with(&self, inputs: &[Tensor], _ctx: &OpContext) -> Result<Tensor, ComputeError> {
+        self.forward(inputs)
+    }
+
+    /// `backward` under the settings of the graph running the op; the default ignores `ctx`.
+    fn backward_with(
+        &self,
+        inputs: &[Tensor],
+        grad_output: &Tensor,
+        _ctx: &OpContext,
+    ) -> Result<Vec<Tensor>, ComputeError> {
+        self.backward(inputs, grad_output)
+    }
+
+    /// `forward_into` under the settings of the plan running the op; the default ignores
+    /// `ctx`, so ops overriding `forward_with` should override this too.
+    fn forward_into_with(
+        &self,
+        inputs: &[&Tensor],
+        out: &mut Tensor,
+        _ctx: &OpContext,
+    ) -> Result<(), ComputeError> {
+        self.forward_into(inputs, out)
+    }
+
+    /// Static output shape for the given input shapes, which may contain symbolic dims.
+    ///
+    /// The default runs `forward` on zero tensors, which only works for fixed shapes.
+    fn infer_shape(&self, inputs: &[Vec<Dim>]) -> Result<Vec<Dim>, ComputeError> {
+        shape::probe(|ts| self.forward(ts), inputs)
+    }
+
+    /// Op type name used by graph passes and diagnostics.
+    fn name(&self) -> &str {
+        std::any::type_name::<Self>()
+    }
+
+    /// Attributes that distinguish two ops with the same `name`, as `(key, value)` pairs.
+    ///
+    /// `None` marks the op as opaque: passes will never treat two instances as equal.
+    fn attributes(&self) -> Option<Vec<(&'static str, String)>> {
+        None
+    }
+}
+
+/// `forward_into_with` for ops without an allocation-free kernel: clones the inputs and
+/// calls `forward_with`.
+pub(crate) fn forward_into_owned(
+    op: &dyn Op,
+    inputs: &[&Tensor],
+    out: &mut Tensor,
+    ctx: &OpContext,
+) -> Result<(), ComputeError> {
+    let owned: Vec<Tensor> = inputs.iter().map(|&t| t.clone()).collect();
+    out.assign(&op.forward_with(&owned, ctx)?);
+    Ok(())
 }
 
 /// Trait for ops whose forward pass can be algebraically inverted.
@@ -23,6 +137,18 @@ pub trait InvertibleOp: Op {
         known: &[Option<&Tensor>],
         solve_for: usize,
     ) -> Result<Tensor, ComputeError>;
+
+    /// `invert` under `policy`. Only inversions that divide or take roots depend on it;
+    /// their `invert` uses `NumericPolicy::Ieee`.
+    fn invert_with(
+        &self,
+        output: &Tensor,
+        known: &[Option<&Tensor>],
+        solve_for: usize,
+        _policy: NumericPolicy,
+    ) -> Result<Tensor, ComputeError> {
+        self.invert(output, known, solve_for)
+    }
 }
 
 /// Validate that `known` has exactly one `None` at `solve_for` and the rest are `Some`.
@@ -57,6 +183,22 @@ fn validate_invert_args(
     Ok(())
 }
 
+/// Sum each gradient of a broadcasting binary op back down to its input's shape, so a
+/// `[1, n]` bias added to a `[batch, n]` activation gets a `[1, n]` gradient.
+fn reduce_broadcast(inputs: &[Tensor], grads: Vec<Tensor>) -> Result<Vec<Tensor>, ComputeError> {
+    if inputs.len() != grads.len() {
+        return Err(ComputeError::InputCountError {
+            expected: grads.len(),
+            got: inputs.len(),
+        });
+    }
+    grads
+        .into_iter()
+        .zip(inputs)
+        .map(|(g, x)| g.sum_to_shape(x.shape()))
+        .collect()
+}
+
 #[derive(Clone, Copy, Debug)]
 pub struct AddOp;
 
@@ -73,10 +215,33 @@ impl Op for AddOp {
 
     fn backward(
         &self,
-        _inputs: &[Tensor],
+        inputs: &[Tensor],
         grad_output: &Tensor,
     ) -> Result<Vec<Tensor>, ComputeError> {
-        Ok(vec![grad_output.clone(), grad_output.clone()])
+        reduce_broadcast(inputs, vec![grad_output.clone(), grad_output.clone()])
+    }
+
+    fn forward_into(&self, inputs: &[&Tensor], out: &mut Tensor) -> Result<(), ComputeError> {
+        if inputs.len() != 2 {
+            return Err(ComputeError::InputCountError {
+                expected: 2,
+                got: inputs.len(),
+            });
+        }
+        inputs[0].elementwise_into(inputs[1], out, |a, b| a + b)
+    }
+
+    fn infer_shape(&self, inputs: &[Vec<Dim>]) -> Result<Vec<Dim>, ComputeError> {
+        shape::expect_inputs(inputs, 2)?;
+        shape::broadcast(&inputs[0], &inputs[1])
+    }
+
+    fn name(&self) -> &str {
+        "AddOp"
+    }
+
+    fn attributes(&self) -> Option<Vec<(&'static str, String)>> {
+        Some(Vec::new())
     }
 }
 
@@ -110,14 +275,37 @@ impl Op for SubtractOp {
 
     fn backward(
         &self,
-        _inputs: &[Tensor],
+        inputs: &[Tensor],
         grad_output: &Tensor,
     ) -> Result<Vec<Tensor>, ComputeError> {
         let mut neg = grad_output.clone();
         for v in neg.data_mut().iter_mut() {
             *v = -*v;
         }
-        Ok(vec![grad_output.clone(), neg])
+        reduce_broadcast(inputs, vec![grad_output.clone(), neg])
+    }
+
+    fn forward_into(&self, inputs: &[&Tensor], out: &mut Tensor) -> Result<(), ComputeError> {
+        if inputs.len() != 2 {
+            return Err(ComputeError::InputCountError {
+                expected: 2,
+                got: inputs.len(),
+            });
+        }
+        inputs[0].elementwise_into(inputs[1], out, |a, b| a - b)
+    }
+
+    fn infer_shape(&self, inputs: &[Vec<Dim>]) -> Result<Vec<Dim>, ComputeError> {
+        shape::expect_inputs(inputs, 2)?;
+        shape::broadcast(&inputs[0], &inputs[1])
+    }
+
+    fn name(&self) -> &str {
+        "SubtractOp"
+    }
+
+    fn attributes(&self) -> Option<Vec<(&'static str, String)>> {
+        Some(Vec::new())
     }
 }
 
@@ -165,7 +353,30 @@ impl Op for MultiplyOp {
         }
         let grad_a = grad_output.multiply(&inputs[1])?;
         let grad_b = grad_output.multiply(&inputs[0])?;
-        Ok(vec![grad_a, grad_b])
+        reduce_broadcast(inputs, vec![grad_a, grad_b])
+    }
+
+    fn forward_into(&self, inputs: &[&Tensor], out: &mut Tensor) -> Result<(), ComputeError> {
+        if inputs.len() != 2 {
+            return Err(ComputeError::InputCountError {
+                expected: 2,
+                got: inputs.len(),
+            });
+        }
+        inputs[0].elementwise_into(inputs[1], out, |a, b| a * b)
+    }
+
+    fn infer_shape(&self, inputs: &[Vec<Dim>]) -> Result<Vec<Dim>, ComputeError> {
+        shape::expect_inputs(inputs, 2)?;
+        shape::broadcast(&inputs[0], &inputs[1])
+    }
+
+    fn name(&self) -> &str {
+        "MultiplyOp"
+    }
+
+    fn attributes(&self) -> Option<Vec<(&'static str, String)>> {
+        Some(Vec::new())
     }
 }
 
@@ -176,10 +387,20 @@ impl InvertibleOp for MultiplyOp {
         output: &Tensor,
         known: &[Option<&Tensor>],
         solve_for: usize,
+    ) -> Result<Tensor, ComputeError> {
+        self.invert_with(output, known, solve_for, NumericPolicy::Ieee)
+    }
+
+    fn invert_with(
+        &self,
+        output: &Tensor,
+        known: &[Option<&Tensor>],
+        solve_for: usize,
+        policy: NumericPolicy,
     ) -> Result<Tensor, ComputeError> {
         validate_invert_args(known, solve_for, 2)?;
         let other = known[1 - solve_for].unwrap();
-        output.divide(other)
+        output.divide_with(other, policy)
     }
 }
 
@@ -188,19 +409,36 @@ pub struct DivideOp;
 
 impl Op for DivideOp {
     fn forward(&self, inputs: &[Tensor]) -> Result<Tensor, ComputeError> {
+        self.forward_with(inputs, &OpContext::default())
+    }
+
+    fn backward(
+        &self,
+        inputs: &[Tensor],
+        grad_output: &Tensor,
+    ) -> Result<Vec<Tensor>, ComputeError> {
+        self.backward_with(inputs, grad_output, &OpContext::default())
+    }
+
+    fn forward_into(&self, inputs: &[&Tensor], out: &mut Tensor) -> Result<(), ComputeError> {
+        self.forward_into_with(inputs, out, &OpContext::default())
+    }
+
+    fn forward_with(&self, inputs: &[Tensor], ctx: &OpContext) -> Result<Tensor, ComputeError> {
         if inputs.len() != 2 {
             return Err(ComputeError::InputCountError {
                 expected: 2,
                 got: inputs.len(),
             });
         }
-        inputs[0].divide(&inputs[1])
+        inputs[0].divide_with(&inputs[1], ctx.policy)
     }
 
-    fn backward(
+    fn backward_with(
         &self,
         inputs: &[Tensor],
         grad_output: &Tensor,
+        ctx: &OpContext,
     ) -> Result<Vec<Tensor>, ComputeError> {
         if inputs.len() != 2 {
             return Err(ComputeError::InputCountError {
@@ -211,17 +449,46 @@ impl Op for DivideOp {
         let a = &inputs[0];
         let b = &inputs[1];
 
-        let grad_a = grad_output.divide(b)?;
+        let grad_a = grad_output.divide_with(b, ctx.policy)?;
 
         // grad_b = -grad_output * a / (b*b)
         let b2 = b.multiply(b)?;
         let num = grad_output.multiply(a)?;
-        let mut grad_b = num.divide(&b2)?;
+        let mut grad_b = num.divide_with(&b2, ctx.policy)?;
         for v in grad_b.data_mut().iter_mut() {
             *v = -*v;
         }
 
-        Ok(vec![grad_a, grad_b])
+        reduce_broadcast(inputs, vec![grad_a, grad_b])
+    }
+
+    fn forward_into_with(
+        &self,
+        inputs: &[&Tensor],
+        out: &mut Tensor,
+        ctx: &OpContext,
+    ) -> Result<(), ComputeError> {
+        if inputs.len() != 2 {
+            return Err(ComputeError::InputCountError {
+                expected: 2,
+                got: inputs.len(),
+            });
+        }
+        let policy = ctx.policy;
+        inputs[0].try_elementwise_into(inputs[1], out, "divide", |a, b| numeric::div(a, b, policy))
+    }
+
+    fn infer_shape(&self, inputs: &[Vec<Dim>]) -> Result<Vec<Dim>, ComputeError> {
+        shape::expect_inputs(inputs, 2)?;
+        shape::broadcast(&inputs[0], &inputs[1])
+    }
+
+    fn name(&self) -> &str {
+        "DivideOp"
+    }
+
+    fn attributes(&self) -> Option<Vec<(&'static str, String)>> {
+        Some(Vec::new())
     }
 }
 
@@ -232,12 +499,22 @@ impl InvertibleOp for DivideOp {
         output: &Tensor,
         known: &[Option<&Tensor>],
         solve_for: usize,
+    ) -> Result<Tensor, ComputeError> {
+        self.invert_with(output, known, solve_for, NumericPolicy::Ieee)
+    }
+
+    fn invert_with(
+        &self,
+        output: &Tensor,
+        known: &[Option<&Tensor>],
+        solve_for: usize,
+        policy: NumericPolicy,
     ) -> Result<Tensor, ComputeError> {
         validate_invert_args(known, solve_for, 2)?;
         let other = known[1 - solve_for].unwrap();
         match solve_for {
-            0 => output.multiply(other), // a = out * b
-            _ => other.divide(output),   // b = a / out
+            0 => output.multiply(other),            // a = out * b
+            _ => other.divide_with(output, policy), // b = a / out
         }
     }
 }
@@ -275,6 +552,209 @@ impl Op for MatMulOp {
         let grad_b = a_t.matmul(grad_output)?;
         Ok(vec![grad_a, grad_b])
     }
+
+    fn forward_into(&self, inputs: &[&Tensor], out: &mut Tensor) -> Result<(), ComputeError> {
+        if inputs.len() != 2 {
+            return Err(ComputeError::InputCountError {
+                expected: 2,
+                got: inputs.len(),
+            });
+        }
+        inputs[0].matmul_into(inputs[1], out)
+    }
+
+    fn infer_shape(&self, inputs: &[Vec<Dim>]) -> Result<Vec<Dim>, ComputeError> {
+        shape::expect_inputs(inputs, 2)?;
+        let (a, b) = (&inputs[0], &inputs[1]);
+        if a.len() != 2 || b.len() != 2 {
+            return Err(ComputeError::DimensionError {
+                message: "matmul requires 2D tensors".to_string(),
+            });
+        }
+        shape::unify(&a[1], &b[0])?;
+        Ok(vec![a[0].clone(), b[1].clone()])
+    }
+
+    fn name(&self) -> &str {
+        "MatMulOp"
+    }
+
+    fn attributes(&self) -> Option<Vec<(&'static str, String)>> {
+        Some(Vec::new())
+    }
+}
+
+/// Matrix product over the last two axes: `[..., m, k] x [k, n]` with the right side
+/// shared by every batch entry, or `[..., m, k] x [..., k, n]` with equal leading axes.
+///
+/// This is what `Linear::forward_batched` uses for `[batch, time, features]` inputs.
+#[derive(Clone, Copy, Debug)]
+pub struct BatchMatMulOp;
+
+impl BatchMatMulOp {
+    /// `(batch, m, k, n, shared)` for inputs `a` and `b`.
+    fn dims(a: &[usize], b: &[usize]) -> Result<(usize, usize, usize, usize, bool), ComputeError> {
+        let shared = b.len() == 2;
+        let valid = a.len() >= 2
+            && (shared || (b.len() == a.len() && a[..a.len() - 2] == b[..b.len() - 2]));
+        if !valid {
+            return Err(ComputeError::DimensionError {
+                message: format!("cannot batch-multiply shapes {a:?} and {b:?}"),
+            });
+        }
+        let (m, k) = (a[a.len() - 2], a[a.len() - 1]);
+        let (k_other, n) = (b[b.len() - 2], b[b.len() - 1]);
+        if k != k_other {
+            return Err(ComputeError::ShapeMismatch {
+                expected: k,
+                got: k_other,
+            });
+        }
+        let batch = a[..a.len() - 2].iter().product();
+        Ok((batch, m, k, n, shared))
+    }
+}
+
+/// `out[m, n] += a[m, k] * b[k, n]` with optional transposes of the stored operands.
+#[allow(clippy::too_many_arguments)]
+fn matmul_acc(
+    a: &[f32],
+    b: &[f32],
+    out: &mut [f32],
+    m: usize,
+    k: usize,
+    n: usize,
+    a_t: bool,
+    b_t: bool,
+) {
+    for i in 0..m {
+        for p in 0..k {
+            let av = if a_t { a[p * m + i] } else { a[i * k + p] };
+            if av == 0.0 {
+                continue;
+            }
+            for j in 0..n {
+                let bv = if b_t { b[j * k + p] } else { b[p * n + j] };
+                out[i * n + j] += av * bv;
+            }
+        }
+    }
+}
+
+impl Op for BatchMatMulOp {
+    fn forward(&self, inputs: &[Tensor]) -> Result<Tensor, ComputeError> {
+        if inputs.len() != 2 {
+            return Err(ComputeError::InputCountError {
+                expected: 2,
+                got: inputs.len(),
+            });
+        }
+        let (a, b) = (&inputs[0], &inputs[1]);
+        let (batch, m, k, n, shared) = Self::dims(a.shape(), b.shape())?;
+        let mut out = vec![0.0; batch * m * n];
+        for i in 0..batch {
+            let b_block = if shared {
+                b.data()
+            } else {
+                &b.data()[i * k * n..(i + 1) * k * n]
+            };
+            matmul_acc(
+                &a.data()[i * m * k..(i + 1) * m * k],
+                b_block,
+                &mut out[i * m * n..(i + 1) * m * n],
+                m,
+                k,
+                n,
+                false,
+                false,
+            );
+        }
+        let mut shape = a.shape().to_vec();
+        *shape.last_mut().expect("rank checked") = n;
+        Tensor::new(out, shape)
+    }
+
+    fn backward(
+        &self,
+        inputs: &[Tensor],
+        grad_output: &Tensor,
+    ) -> Result<Vec<Tensor>, ComputeError> {
+        if inputs.len() != 2 {
+            return Err(ComputeError::InputCountError {
+                expected: 2,
+                got: inputs.len(),
+            });
+        }
+        let (a, b) = (&inputs[0], &inputs[1]);
+        let (batch, m, k, n, shared) = Self::dims(a.shape(), b.shape())?;
+        let g = grad_output.data();
+        let mut ga = vec![0.0; a.data().len()];
+        let mut gb = vec![0.0; b.data().len()];
+        for i in 0..batch {
+            let (a_range, g_range) = (i * m * k..(i + 1) * m * k, i * m * n..(i + 1) * m * n);
+            let b_range = if shared {
+                0..k * n
+            } else {
+                i * k * n..(i + 1) * k * n
+            };
+            // dA = dY * B^T, dB = A^T * dY
+            matmul_acc(
+                &g[g_range.clone()],
+                &b.data()[b_range.clone()],
+                &mut ga[a_range.clone()],
+                m,
+                n,
+                k,
+                false,
+                true,
+            );
+            matmul_acc(
+                &a.data()[a_range],
+                &g[g_range],
+                &mut gb[b_range],
+                k,
+                m,
+                n,
+                true,
+                false,
+            );
+        }
+        Ok(vec![
+            Tensor::new(ga, a.shape().to_vec())?,
+            Tensor::new(gb, b.shape().to_vec())?,
+        ])
+    }
+
+    fn infer_shape(&self, inputs: &[Vec<Dim>]) -> Result<Vec<Dim>, ComputeError> {
+        shape::expect_inputs(inputs, 2)?;
+        let (a, b) = (&inputs[0], &inputs[1]);
+        if a.len() < 2 || (b.len() != 2 && b.len() != a.len()) {
+            return Err(ComputeError::DimensionError {
+                message: format!(
+                    "cannot batch-multiply {} and {}",
+                    shape::format_shape(a),
+                    shape::format_shape(b)
+                ),
+            });
+        }
+        let mut out = a.clone();
+        if b.len() == a.len() {
+            for (dim, other) in out.iter_mut().zip(b).take(a.len() - 2) {
+                *dim = shape::unify(dim, other)?;
+            }
+        }
+        shape::unify(&a[a.len() - 1], &b[b.len() - 2])?;
+        *out.last_mut().expect("rank checked") = b[b.len() - 1].clone();
+        Ok(out)
+    }
+
+    fn name(&self) -> &str {
+        "BatchMatMulOp"
+    }
+
+    fn attributes(&self) -> Option<Vec<(&'static str, String)>> {
+        Some(Vec::new())
+    }
 }
 
 #[derive(Clone, Copy, Debug)]
@@ -319,6 +799,142 @@ impl Op for ReluOp {
         }
         Ok(vec![grad])
     }
+
+    fn forward_into(&self, inputs: &[&Tensor], out: &mut Tensor) -> Result<(), ComputeError> {
+        if inputs.len() != 1 {
+            return Err(ComputeError::InputCountError {
+                expected: 1,
+                got: inputs.len(),
+            });
+        }
+        inputs[0].map_into(out, |v| v.max(0.0));
+        Ok(())
+    }
+
+    fn infer_shape(&self, inputs: &[Vec<Dim>]) -> Result<Vec<Dim>, ComputeError> {
+        shape::expect_inputs(inputs, 1)?;
+        Ok(inputs[0].clone())
+    }
+
+    fn name(&self) -> &str {
+        "ReluOp"
+    }
+
+    fn attributes(&self) -> Option<Vec<(&'static str, String)>> {
+        Some(Vec::new())
+    }
+}
+
+/// Elementwise logistic `1 / (1 + e^-x)`.
+#[derive(Clone, Copy, Debug)]
+pub struct SigmoidOp;
+
+impl Op for SigmoidOp {
+    fn forward(&self, inputs: &[Tensor]) -> Result<Tensor, ComputeError> {
+        if inputs.len() != 1 {
+            return Err(ComputeError::InputCountError {
+                expected: 1,
+                got: inputs.len(),
+            });
+        }
+        inputs[0].sigmoid()
+    }
+
+    fn backward(
+        &self,
+        inputs: &[Tensor],
+        grad_output: &Tensor,
+    ) -> Result<Vec<Tensor>, ComputeError> {
+        let y = self.forward(inputs)?;
+        let mut grad = grad_output.multiply(&y)?;
+        for (g, &s) in grad.data_mut().iter_mut().zip(y.data()) {
+            *g *= 1.0 - s;
+        }
+        Ok(vec![grad])
+    }
+
+    fn forward_into(&self, inputs: &[&Tensor], out: &mut Tensor) -> Result<(), ComputeError> {
+        if inputs.len() != 1 {
+            return Err(ComputeError::InputCountError {
+                expected: 1,
+                got: inputs.len(),
+            });
+        }
+        inputs[0].map_into(out, crate::tensor::sigmoid);
+        Ok(())
+    }
+
+    fn infer_shape(&self, inputs: &[Vec<Dim>]) -> Result<Vec<Dim>, ComputeError> {
+        shape::expect_inputs(inputs, 1)?;
+        Ok(inputs[0].clone())
+    }
+
+    fn name(&self) -> &str {
+        "SigmoidOp"
+    }
+
+    fn attributes(&self) -> Option<Vec<(&'static str, String)>> {
+        Some(Vec::new())
+    }
+}
+
+/// Elementwise hyperbolic tangent.
+#[derive(Clone, Copy, Debug)]
+pub struct TanhOp;
+
+impl Op for TanhOp {
+    fn forward(&self, inputs: &[Tensor]) -> Result<Tensor, ComputeError> {
+        if inputs.len() != 1 {
+            return Err(ComputeError::InputCountError {
+                expected: 1,
+                got: inputs.len(),
+            });
+        }
+        inputs[0].tanh()
+    }
+
+    fn backward(
+        &self,
+        inputs: &[Tensor],
+        grad_output: &Tensor,
+    ) -> Result<Vec<Tensor>, ComputeError> {
+        let y = self.forward(inputs)?;
+        let mut grad = grad_output.clone();
+        if grad.data().len() != y.data().len() {
+            return Err(ComputeError::ShapeMismatch {
+                expected: y.data().len(),
+                got: grad.data().len(),
+            });
+        }
+        for (g, &t) in grad.data_mut().iter_mut().zip(y.data()) {
+            *g *= 1.0 - t * t;
+        }
+        Ok(vec![grad])
+    }
+
+    fn forward_into(&self, inputs: &[&Tensor], out: &mut Tensor) -> Result<(), ComputeError> {
+        if inputs.len() != 1 {
+            return Err(ComputeError::InputCountError {
+                expected: 1,
+                got: inputs.len(),
+            });
+        }
+        inputs[0].map_into(out, f32::tanh);
+        Ok(())
+    }
+
+    fn infer_shape(&self, inputs: &[Vec<Dim>]) -> Result<Vec<Dim>, ComputeError> {
+        shape::expect_inputs(inputs, 1)?;
+        Ok(inputs[0].clone())
+    }
+
+    fn name(&self) -> &str {
+        "TanhOp"
+    }
+
+    fn attributes(&self) -> Option<Vec<(&'static str, String)>> {
+        Some(Vec::new())
+    }
 }
 
 #[derive(Clone, Copy, Debug)]
@@ -380,6 +996,88 @@ impl Op for SumOp {
 
         Ok(vec![grad_input])
     }
+
+    fn forward_into(&self, inputs: &[&Tensor], out: &mut Tensor) -> Result<(), ComputeError> {
+        if inputs.len() != 1 {
+            return Err(ComputeError::InputCountError {
+                expected: 1,
+                got: inputs.len(),
+            });
+        }
+        inputs[0].sum_into(self.dim, out)
+    }
+
+    fn infer_shape(&self, inputs: &[Vec<Dim>]) -> Result<Vec<Dim>, ComputeError> {
+        shape::expect_inputs(inputs, 1)?;
+        match self.dim {
+            None => Ok(vec![Dim::Fixed(1)]),
+            Some(axis) if axis < inputs[0].len() => {
+                let mut out = inputs[0].clone();
+                out[axis] = Dim::Fixed(1);
+                Ok(out)
+            }
+            Some(axis) => Err(ComputeError::DimensionError {
+                message: format!("invalid axis {axis} for rank {}", inputs[0].len()),
+            }),
+        }
+    }
+
+    fn name(&self) -> &str {
+        "SumOp"
+    }
+
+    fn attributes(&self) -> Option<Vec<(&'static str, String)>> {
+        Some(vec![("dim", format!("{:?}", self.dim))])
+    }
+}
+
+/// Mean of all elements, shaped `[1]`. The element count is read from the input when the
+/// op runs, so the same graph serves any batch size.
+#[derive(Clone, Copy, Debug)]
+pub struct MeanOp;
+
+impl Op for MeanOp {
+    fn forward(&self, inputs: &[Tensor]) -> Result<Tensor, ComputeError> {
+        let [input] = inputs else {
+            return Err(ComputeError::InputCountError {
+                expected: 1,
+                got: inputs.len(),
+            });
+        };
+        let count = input.data().len().max(1) as f32;
+        Tensor::new(vec![input.data().iter().sum::<f32>() / count], vec![1])
+    }
+
+    fn backward(
+        &self,
+        inputs: &[Tensor],
+        grad_output: &Tensor,
+    ) -> Result<Vec<Tensor>, ComputeError> {
+        let [input] = inputs else {
+            return Err(ComputeError::InputCountError {
+                expected: 1,
+                got: inputs.len(),
+            });
+        };
+        let g = grad_output.data().first().copied().unwrap_or(0.0);
+        let mut grad = Tensor::zeros_like(input)?;
+        let share = g / input.data().len().max(1) as f32;
+        grad.data_mut().fill(share);
+        Ok(vec![grad])
+    }
+
+    fn infer_shape(&self, inputs: &[Vec<Dim>]) -> Result<Vec<Dim>, ComputeError> {
+        shape::expect_inputs(inputs, 1)?;
+        Ok(vec![Dim::Fixed(1)])
+    }
+
+    fn name(&self) -> &str {
+        "MeanOp"
+    }
+
+    fn attributes(&self) -> Option<Vec<(&'static str, String)>> {
+        Some(Vec::new())
+    }
 }
 
 #[derive(Clone, Copy, Debug)]
@@ -387,21 +1085,36 @@ pub struct LogOp;
 
 impl Op for LogOp {
     fn forward(&self, inputs: &[Tensor]) -> Result<Tensor, ComputeError> {
+        self.forward_with(inputs, &OpContext::default())
+    }
+
+    fn backward(
+        &self,
+        inputs: &[Tensor],
+        grad_output: &Tensor,
+    ) -> Result<Vec<Tensor>, ComputeError> {
+        self.backward_with(inputs, grad_output, &OpContext::default())
+    }
+
+    fn forward_into(&self, inputs: &[&Tensor], out: &mut Tensor) -> Result<(), ComputeError> {
+        self.forward_into_with(inputs, out, &OpContext::default())
+    }
+
+    fn forward_with(&self, inputs: &[Tensor], ctx: &OpContext) -> Result<Tensor, ComputeError> {
         if inputs.len() != 1 {
             return Err(ComputeError::InputCountError {
                 expected: 1,
                 got: inputs.len(),
             });
         }
-        let x = &inputs[0];
-        let out: Vec<f32> = x.data().iter().map(|&v| v.ln()).collect();
-        Tensor::new(out, x.shape().to_vec())
+        inputs[0].log_with(ctx.policy)
     }
 
-    fn backward(
+    fn backward_with(
         &self,
         inputs: &[Tensor],
         grad_output: &Tensor,
+        ctx: &OpContext,
     ) -> Result<Vec<Tensor>, ComputeError> {
         if inputs.len() != 1 {
             return Err(ComputeError::InputCountError {
@@ -416,11 +1129,36 @@ impl Op for LogOp {
                 got: grad_output.data().len(),
             });
         }
-        let mut grad = Tensor::zeros_like(x)?;
-        for i in 0..x.data().len() {
-            grad.data_mut()[i] = grad_output.data()[i] / x.data()[i];
+        Ok(vec![grad_output.divide_with(x, ctx.policy)?])
+    }
+
+    fn forward_into_with(
+        &self,
+        inputs: &[&Tensor],
+        out: &mut Tensor,
+        ctx: &OpContext,
+    ) -> Result<(), ComputeError> {
+        if inputs.len() != 1 {
+            return Err(ComputeError::InputCountError {
+                expected: 1,
+                got: inputs.len(),
+            });
         }
-        Ok(vec![grad])
+        let policy = ctx.policy;
+        inputs[0].try_map_into(out, "log", |v| numeric::ln(v, policy))
+    }
+
+    fn infer_shape(&self, inputs: &[Vec<Dim>]) -> Result<Vec<Dim>, ComputeError> {
+        shape::expect_inputs(inputs, 1)?;
+        Ok(inputs[0].clone())
+    }
+
+    fn name(&self) -> &str {
+        "LogOp"
+    }
+
+    fn attributes(&self) -> Option<Vec<(&'static str, String)>> {
+        Some(Vec::new())
     }
 }
 
@@ -438,6 +1176,117 @@ impl InvertibleOp for LogOp {
     }
 }
 
+#[derive(Clone, Copy, Debug)]
+pub struct SqrtOp;
+
+impl Op for SqrtOp {
+    fn forward(&self, inputs: &[Tensor]) -> Result<Tensor, ComputeError> {
+        self.forward_with(inputs, &OpContext::default())
+    }
+
+    fn backward(
+        &self,
+        inputs: &[Tensor],
+        grad_output: &Tensor,
+    ) -> Result<Vec<Tensor>, ComputeError> {
+        self.backward_with(inputs, grad_output, &OpContext::default())
+    }
+
+    fn forward_into(&self, inputs: &[&Tensor], out: &mut Tensor) -> Result<(), ComputeError> {
+        self.forward_into_with(inputs, out, &OpContext::default())
+    }
+
+    fn forward_with(&self, inputs: &[Tensor], ctx: &OpContext) -> Result<Tensor, ComputeError> {
+        if inputs.len() != 1 {
+            return Err(ComputeError::InputCountError {
+                expected: 1,
+                got: inputs.len(),
+            });
+        }
+        inputs[0].sqrt_with(ctx.policy)
+    }
+
+    fn backward_with(
+        &self,
+        inputs: &[Tensor],
+        grad_output: &Tensor,
+        ctx: &OpContext,
+    ) -> Result<Vec<Tensor>, ComputeError> {
+        if inputs.len() != 1 {
+            return Err(ComputeError::InputCountError {
+                expected: 1,
+                got: inputs.len(),
+            });
+        }
+        // d/dx sqrt(x) = 1 / (2 sqrt(x))
+        let root = inputs[0].sqrt_with(ctx.policy)?;
+        let twice = root.add(&root)?;
+        Ok(vec![grad_output.divide_with(&twice, ctx.policy)?])
+    }
+
+    fn forward_into_with(
+        &self,
+        inputs: &[&Tensor],
+        out: &mut Tensor,
+        ctx: &OpContext,
+    ) -> Result<(), ComputeError> {
+        if inputs.len() != 1 {
+            return Err(ComputeError::InputCountError {
+                expected: 1,
+                got: inputs.len(),
+            });
+        }
+        let policy = ctx.policy;
+        inputs[0].try_map_into(out, "sqrt", |v| numeric::sqrt(v, policy))
+    }
+
+    fn infer_shape(&self, inputs: &[Vec<Dim>]) -> Result<Vec<Dim>, ComputeError> {
+        shape::expect_inputs(inputs, 1)?;
+        Ok(inputs[0].clone())
+    }
+
+    fn name(&self) -> &str {
+        "SqrtOp"
+    }
+
+    fn attributes(&self) -> Option<Vec<(&'static str, String)>> {
+        Some(Vec::new())
+    }
+}
+
+/// out = sqrt(x) → x = out², singular for negative `out` under `NumericPolicy::Error`.
+impl InvertibleOp for SqrtOp {
+    fn invert(
+        &self,
+        output: &Tensor,
+        known: &[Option<&Tensor>],
+        solve_for: usize,
+    ) -> Result<Tensor, ComputeError> {
+        self.invert_with(output, known, solve_for, NumericPolicy::Ieee)
+    }
+
+    fn invert_with(
+        &self,
+        output: &Tensor,
+        known: &[Option<&Tensor>],
+        solve_for: usize,
+        policy: NumericPolicy,
+    ) -> Result<Tensor, ComputeError> {
+        validate_invert_args(known, solve_for, 1)?;
+        let mut x = output.multiply(output)?;
+        for (i, (v, &o)) in x.data_mut().iter_mut().zip(output.data()).enumerate() {
+            if o < 0.0 {
+                match policy {
+                    NumericPolicy::Error => return Err(numeric::singular("sqrt inverse", i)),
+                    NumericPolicy::Nan => *v = f32::NAN,
+                    _ => {}
+                }
+            }
+        }
+        Ok(x)
+    }
+}
+
 #[derive(Clone, Copy, Debug)]
 pub struct SoftmaxOp;
 
@@ -514,6 +1363,55 @@ impl Op for SoftmaxOp {
             Ok(vec![Tensor::new(grad, vec![rows, cols])?])
         }
     }
+
+    fn forward_into(&self, inputs: &[&Tensor], out: &mut Tensor) -> Result<(), ComputeError> {
+        if inputs.len() != 1 {
+            return Err(ComputeError::InputCountError {
+                expected: 1,
+                got: inputs.len(),
+            });
+        }
+        let x = inputs[0];
+        let cols = match x.shape().len() {
+            1 | 2 => x.shape()[x.shape().len() - 1],
+            _ => {
+                return Err(ComputeError::DimensionError {
+                    message: "softmax supports 1D or 2D tensors".to_string(),
+                })
+            }
+        };
+        x.map_into(out, |v| v);
+        for row in out.data_mut().chunks_mut(cols.max(1)) {
+            let max = row.iter().cloned().fold(f32::NEG_INFINITY, f32::max);
+            let mut sum = 0.0;
+            for v in row.iter_mut() {
+                *v = (*v - max).exp();
+                sum += *v;
+            }
+            for v in row.iter_mut() {
+                *v /= sum;
+            }
+        }
+        Ok(())
+    }
+
+    fn infer_shape(&self, inputs: &[Vec<Dim>]) -> Result<Vec<Dim>, ComputeError> {
+        shape::expect_inputs(inputs, 1)?;
+        match inputs[0].len() {
+            1 | 2 => Ok(inputs[0].clone()),
+            _ => Err(ComputeError::DimensionError {
+                message: "softmax supports 1D or 2D tensors".to_string(),
+            }),
+        }
+    }
+
+    fn name(&self) -> &str {
+        "SoftmaxOp"
+    }
+
+    fn attributes(&self) -> Option<Vec<(&'static str, String)>> {
+        Some(Vec::new())
+    }
 }
 
 fn softmax_1d(x: &Tensor) -> Result<Tensor, ComputeError> {
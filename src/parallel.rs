@@ -0,0 +1,162 @@
+//! Wavefront scheduling of graph nodes onto a persistent pool of `std::thread` workers.
+//!
+//! Nodes are grouped into waves whose members do not depend on each other, so a wave can
+//! be evaluated concurrently once the previous one has finished. Each graph owns a
+//! `ThreadPool`, so workers are spawned once per `Graph::set_num_threads` rather than per
+//! wave.
+
+use std::any::Any;
+use std::cell::Cell;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::mpsc::{self, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+
+/// Group `order` into waves by dependency depth.
+///
+/// `deps(idx)` lists the nodes `idx` must wait for; nodes outside `order` are treated as
+/// already available. Each wave is sorted by node index.
+pub(crate) fn waves<F, I>(order: &[usize], deps: F) -> Vec<Vec<usize>>
+where
+    F: Fn(usize) -> I,
+    I: IntoIterator<Item = usize>,
+{
+    let mut level: std::collections::HashMap<usize, usize> =
+        std::collections::HashMap::with_capacity(order.len());
+    let mut waves: Vec<Vec<usize>> = Vec::new();
+    for &idx in order {
+        let depth = deps(idx)
+            .into_iter()
+            .filter_map(|d| level.get(&d).map(|l| l + 1))
+            .max()
+            .unwrap_or(0);
+        level.insert(idx, depth);
+        if waves.len() <= depth {
+            waves.resize_with(depth + 1, Vec::new);
+        }
+        waves[depth].push(idx);
+    }
+    for wave in &mut waves {
+        wave.sort_unstable();
+    }
+    waves
+}
+
+type Job = Box<dyn FnOnce() + Send + 'static>;
+
+thread_local! {
+    /// Set on pool workers, so a `map` issued from inside a job runs inline.
+    static IN_WORKER: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Worker threads fed from a shared job queue. The thread calling `map` runs one share of
+/// the work itself, so a pool for `threads` threads spawns `threads - 1` workers.
+#[derive(Debug)]
+pub(crate) struct ThreadPool {
+    sender: Option<Sender<Job>>,
+    workers: Vec<JoinHandle<()>>,
+}
+
+impl ThreadPool {
+    pub(crate) fn new(threads: usize) -> Self {
+        let (sender, receiver) = mpsc::channel::<Job>();
+        let queue = Arc::new(Mutex::new(receiver));
+        let workers = (1..threads.max(1))
+            .map(|_| {
+                let queue = Arc::clone(&queue);
+                std::thread::spawn(move || {
+                    IN_WORKER.with(|w| w.set(true));
+                    loop {
+                        // Jobs catch their own panics, so the lock is never poisoned.
+                        let job = queue.lock().expect("job queue").recv();
+                        match job {
+                            Ok(job) => job(),
+                            Err(_) => break,
+                        }
+                    }
+                })
+            })
+            .collect();
+        Self {
+            sender: Some(sender),
+            workers,
+        }
+    }
+
+    /// Number of threads `map` spreads work over, including the caller.
+    pub(crate) fn threads(&self) -> usize {
+        self.workers.len() + 1
+    }
+
+    /// Apply `f` to every item, returning results in item order. Runs inline when there is
+    /// at most one item, no worker, or when called from a worker: a job that blocked on
+    /// jobs queued behind it could otherwise deadlock the pool.
+    pub(crate) fn map<T, R, F>(&self, items: &[T], f: F) -> Vec<R>
+    where
+        T: Sync,
+        R: Send,
+        F: Fn(&T) -> R + Sync,
+    {
+        if self.workers.is_empty() || items.len() <= 1 || IN_WORKER.with(Cell::get) {
+            return items.iter().map(f).collect();
+        }
+        let chunk = items.len().div_ceil(self.threads().min(items.len()));
+        let mut parts = items.chunks(chunk);
+        let first = parts.next().expect("at least two items");
+        let (done, results) = mpsc::channel();
+        let mut pending = 0;
+        for (i, part) in parts.enumerate() {
+            let done = done.clone();
+            let f = &f;
+            let job: Box<dyn FnOnce() + Send + '_> = Box::new(move || {
+                let result = panic::catch_unwind(AssertUnwindSafe(|| {
+                    part.iter().map(f).collect::<Vec<R>>()
+                }));
+                let _ = done.send((i + 1, result));
+            });
+            // SAFETY: the job borrows `items` and `f`, which outlive this call: `map` does
+            // not return, or unwind, until every job it queued has sent its result.
+            let job: Job = unsafe { std::mem::transmute(job) };
+            self.sender
+                .as_ref()
+                .expect("pool is running")
+                .send(job)
+                .expect("workers outlive the pool's sender");
+            pending += 1;
+        }
+
+        let mut chunks: Vec<Option<std::thread::Result<Vec<R>>>> =
+            (0..=pending).map(|_| None).collect();
+        chunks[0] = Some(panic::catch_unwind(AssertUnwindSafe(|| {
+            first.iter().map(&f).collect::<Vec<R>>()
+        })));
+        for (i, result) in results.iter().take(pending) {
+            chunks[i] = Some(result);
+        }
+
+        let mut out = Vec::with_capacity(items.len());
+        let mut panicked: Option<Box<dyn Any + Send>> = None;
+        for chunk in chunks {
+            match chunk.expect("every chunk reported") {
+                Ok(results) => out.extend(results),
+                Err(payload) => {
+                    panicked.get_or_insert(payload);
+                }
+            }
+        }
+        if let Some(payload) = panicked {
+            panic::resume_unwind(payload);
+        }
+        out
+    }
+}
+
+impl Drop for ThreadPool {
+    fn drop(&mut self) {
+        // Closing the queue ends each worker's loop.
+        self.sender = None;
+        for worker in self.workers.drain(..) {
+            let _ = worker.join();
+        }
+    }
+}
@@ -0,0 +1,339 @@
+//! Graph optimization passes.
+//!
+//! Passes rewrite a `Graph` in place and return a `NodeRemap` from the old node indices to
+//! the new ones. Any node index held outside the graph (layer parameters, optimizer
+//! `param_indices`, loss outputs) must be translated through the remap afterwards.
+//! Accumulated gradients are cleared by every pass that renumbers nodes.
+//!
+//! Buffer update nodes and seed nodes (see `Graph::add_buffer_update` and
+//! `Graph::add_seed_node`) are treated as extra outputs, so passes run from a loss never
+//! remove the running statistics or seeds it does not depend on.
+
+use std::collections::HashMap;
+
+use crate::error::ComputeError;
+use crate::graph::{Graph, Node};
+
+/// Mapping from node indices before a pass to node indices after it.
+///
+/// `None` means the node was removed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct NodeRemap {
+    map: Vec<Option<usize>>,
+}
+
+impl NodeRemap {
+    pub fn identity(len: usize) -> Self {
+        Self {
+            map: (0..len).map(Some).collect(),
+        }
+    }
+
+    pub fn get(&self, old_idx: usize) -> Option<usize> {
+        self.map.get(old_idx).copied().flatten()
+    }
+
+    /// Number of nodes the remap was built for (the pre-pass node count).
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    /// Translate a list of indices, failing if any of them was removed.
+    pub fn apply(&self, indices: &[usize]) -> Result<Vec<usize>, ComputeError> {
+        indices
+            .iter()
+            .map(|&idx| {
+                self.get(idx).ok_or_else(|| ComputeError::IndexError {
+                    message: format!("node {idx} was removed by a graph pass"),
+                })
+            })
+            .collect()
+    }
+
+    /// Compose `self` (old -> mid) with `next` (mid -> new) into old -> new.
+    pub fn then(&self, next: &NodeRemap) -> NodeRemap {
+        NodeRemap {
+            map: self
+                .map
+                .iter()
+                .map(|m| m.and_then(|mid| next.get(mid)))
+                .collect(),
+        }
+    }
+}
+
+/// A rewrite over a graph that must preserve the values of `outputs`.
+pub trait GraphPass {
+    fn name(&self) -> &'static str;
+    fn run(&self, graph: &mut Graph, outputs: &[usize]) -> Result<NodeRemap, ComputeError>;
+}
+
+/// Removes every node that `outputs` do not depend on, along with its label.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct DeadCodeElimination;
+
+impl GraphPass for DeadCodeElimination {
+    fn name(&self) -> &'static str {
+        "dead_code_elimination"
+    }
+
+    fn run(&self, graph: &mut Graph, outputs: &[usize]) -> Result<NodeRemap, ComputeError> {
+        validate(graph, outputs)?;
+        let mut live = vec![false; graph.nodes.len()];
+        let mut stack: Vec<usize> = outputs.to_vec();
+        stack.extend(graph.state_roots());
+        while let Some(idx) = stack.pop() {
+            if live[idx] {
+                continue;
+            }
+            live[idx] = true;
+            if let Node::Operation(_, inputs) = &graph.nodes[idx] {
+                stack.extend(inputs.iter().copied());
+            }
+        }
+        let redirect: Vec<usize> = (0..graph.nodes.len()).collect();
+        compact(graph, &live, &redirect)
+    }
+}
+
+/// Evaluates ops whose inputs are all `Constant` nodes and replaces them with a constant
+/// holding the result.
+///
+/// Only nodes added with `Graph::add_constant` are folded: `Input` values can change
+/// through `Graph::set_input` and parameters through training. Node indices are
+/// unchanged; run `DeadCodeElimination` afterwards to drop the constants that are no
+/// longer referenced.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ConstantFolding;
+
+impl GraphPass for ConstantFolding {
+    fn name(&self) -> &'static str {
+        "constant_folding"
+    }
+
+    fn run(&self, graph: &mut Graph, outputs: &[usize]) -> Result<NodeRemap, ComputeError> {
+        validate(graph, outputs)?;
+        for idx in 0..graph.nodes.len() {
+            let folded = match &graph.nodes[idx] {
+                Node::Operation(op, inputs) => {
+                    let mut values = Vec::with_capacity(inputs.len());
+                    for &input_idx in inputs {
+                        match &graph.nodes[input_idx] {
+                            Node::Constant(t) => values.push(t.clone()),
+                            _ => break,
+                        }
+                    }
+                    if values.len() == inputs.len() {
+                        Some(op.forward_with(&values, &graph.op_context())?)
+                    } else {
+                        None
+                    }
+                }
+                _ => None,
+            };
+            if let Some(value) = folded {
+                graph.nodes[idx] = Node::Constant(value);
+            }
+        }
+        graph.gradients.clear();
+        Ok(NodeRemap::identity(graph.nodes.len()))
+    }
+}
+
+/// Merges ops with the same name, attributes and inputs, and identical `Constant` nodes.
+///
+/// Inputs and parameters are never merged, since their values can change later. A merged
+/// node's label moves to the node it merges into, unless that node has its own.
+///
+/// Ops whose `attributes()` is `None` are opaque and never merged.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct CommonSubexpressionElimination;
+
+#[derive(Hash, PartialEq, Eq)]
+enum NodeKey {
+    Constant(Vec<usize>, Vec<u32>),
+    Operation(String, Vec<(&'static str, String)>, Vec<usize>),
+}
+
+impl GraphPass for CommonSubexpressionElimination {
+    fn name(&self) -> &'static str {
+        "common_subexpression_elimination"
+    }
+
+    fn run(&self, graph: &mut Graph, outputs: &[usize]) -> Result<NodeRemap, ComputeError> {
+        validate(graph, outputs)?;
+        let mut seen: HashMap<NodeKey, usize> = HashMap::new();
+        let mut redirect: Vec<usize> = Vec::with_capacity(graph.nodes.len());
+
+        for idx in 0..graph.nodes.len() {
+            let key = match &mut graph.nodes[idx] {
+                Node::Constant(t) => Some(NodeKey::Constant(
+                    t.shape().to_vec(),
+                    t.data().iter().map(|v| v.to_bits()).collect(),
+                )),
+                Node::Input(_) | Node::Parameter(_, _) => None,
+                Node::Operation(op, inputs) => {
+                    for input_idx in inputs.iter_mut() {
+                        *input_idx = redirect[*input_idx];
+                    }
+                    op.attributes().map(|attrs| {
+                        NodeKey::Operation(op.name().to_string(), attrs, inputs.clone())
+                    })
+                }
+            };
+            let canonical = match key {
+                Some(key) => *seen.entry(key).or_insert(idx),
+                None => idx,
+            };
+            redirect.push(canonical);
+        }
+
+        let keep: Vec<bool> = redirect.iter().enumerate().map(|(i, &c)| i == c).collect();
+        compact(graph, &keep, &redirect)
+    }
+}
+
+/// An ordered list of passes run one after another.
+pub struct PassPipeline {
+    passes: Vec<Box<dyn GraphPass>>,
+}
+
+impl PassPipeline {
+    pub fn new() -> Self {
+        Self { passes: Vec::new() }
+    }
+
+    pub fn with<P: GraphPass + 'static>(mut self, pass: P) -> Self {
+        self.passes.push(Box::new(pass));
+        self
+    }
+
+    pub fn pass_names(&self) -> Vec<&'static str> {
+        self.passes.iter().map(|p| p.name()).collect()
+    }
+
+    /// Run every pass, threading `outputs` through each intermediate remap.
+    pub fn run(&self, graph: &mut Graph, outputs: &[usize]) -> Result<NodeRemap, ComputeError> {
+        let mut total = NodeRemap::identity(graph.nodes.len());
+        let mut current = outputs.to_vec();
+        for pass in &self.passes {
+            let remap = pass.run(graph, &current)?;
+            current = remap.apply(&current)?;
+            total = total.then(&remap);
+        }
+        Ok(total)
+    }
+}
+
+impl Default for PassPipeline {
+    /// Fold constants, merge duplicates, then drop everything the outputs don't use.
+    fn default() -> Self {
+        Self::new()
+            .with(ConstantFolding)
+            .with(CommonSubexpressionElimination)
+            .with(DeadCodeElimination)
+    }
+}
+
+/// Run the default pipeline over `graph`.
+pub fn optimize(graph: &mut Graph, outputs: &[usize]) -> Result<NodeRemap, ComputeError> {
+    PassPipeline::default().run(graph, outputs)
+}
+
+/// Check that outputs and state roots exist and every op only references earlier nodes.
+pub(crate) fn validate(graph: &Graph, outputs: &[usize]) -> Result<(), ComputeError> {
+    for &out in outputs.iter().chain(&graph.state_roots()) {
+        if out >= graph.nodes.len() {
+            return Err(ComputeError::IndexError {
+                message: format!("node index out of bounds: {out}"),
+            });
+        }
+    }
+    for (idx, node) in graph.nodes.iter().enumerate() {
+        if let Node::Operation(_, inputs) = node {
+            if let Some(&bad) = inputs.iter().find(|&&i| i >= idx) {
+                return Err(ComputeError::IndexError {
+                    message: format!("node {idx} references node {bad}, which is not earlier"),
+                });
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Rebuild the node list keeping only `keep[i]` nodes; dropped nodes resolve through
+/// `redirect[i]` (a kept node with a smaller or equal index).
+///
+/// The label of a node redirected to another one moves there unless that node has its own
+/// label; when several merge into one node, the lowest index wins. Labels of nodes that
+/// resolve to nothing are dropped with them.
+///
+/// Fails if a buffer update or seed node would be lost, rather than silently dropping it.
+pub(crate) fn compact(
+    graph: &mut Graph,
+    keep: &[bool],
+    redirect: &[usize],
+) -> Result<NodeRemap, ComputeError> {
+    let resolves = |idx: usize| redirect.get(idx).is_some_and(|&r| keep[r]);
+    if let Some(lost) = graph.state_roots().into_iter().find(|&idx| !resolves(idx)) {
+        return Err(ComputeError::IndexError {
+            message: format!(
+                "node {lost} is a buffer update or seed node and would be removed by a graph pass"
+            ),
+        });
+    }
+    let old_nodes = std::mem::take(&mut graph.nodes);
+    let mut new_index: Vec<Option<usize>> = vec![None; old_nodes.len()];
+    for (idx, node) in old_nodes.into_iter().enumerate() {
+        if !keep[idx] {
+            continue;
+        }
+        let node = match node {
+            Node::Operation(op, inputs) => {
+                let inputs = inputs
+                    .iter()
+                    .map(|&i| new_index[redirect[i]].expect("inputs of kept nodes are kept"))
+                    .collect();
+                Node::Operation(op, inputs)
+            }
+            other => other,
+        };
+        new_index[idx] = Some(graph.nodes.len());
+        graph.nodes.push(node);
+    }
+    graph.gradients.clear();
+    let mut labels: Vec<(usize, String)> = std::mem::take(&mut graph.labels).into_iter().collect();
+    labels.sort_by_key(|&(idx, _)| (!keep[idx], idx));
+    for (idx, label) in labels {
+        if let Some(n) = new_index[redirect[idx]] {
+            graph.labels.entry(n).or_insert(label);
+        }
+    }
+    // Order is preserved, so the surviving nodes of a segment stay contiguous.
+    graph.checkpoints = std::mem::take(&mut graph.checkpoints)
+        .into_iter()
+        .filter_map(|range| {
+            let mut kept = range.filter_map(|idx| new_index.get(idx).copied().flatten());
+            let first = kept.next()?;
+            let last = kept.next_back().unwrap_or(first);
+            Some(first..last + 1)
+        })
+        .collect();
+    let resolve = |idx: usize| new_index[redirect[idx]].expect("state roots are kept");
+    graph.buffer_updates = std::mem::take(&mut graph.buffer_updates)
+        .into_iter()
+        .map(|(buffer, value)| (resolve(buffer), resolve(value)))
+        .collect();
+    graph.seed_nodes = std::mem::take(&mut graph.seed_nodes)
+        .into_iter()
+        .map(resolve)
+        .collect();
+
+    Ok(NodeRemap {
+        map: redirect.iter().map(|&r| new_index[r]).collect(),
+    })
+}
@@ -0,0 +1,321 @@
+//! Static execution plans for bounded-memory inference.
+//!
+//! `ExecutionPlan::compile` flattens the ops one output depends on into a schedule, infers
+//! the shape of every node with the same rules as `Graph::check` and assigns each
+//! intermediate tensor to one of a small set of arena buffers, reusing a buffer as soon as
+//! the value it held is no longer needed.
+//! `ExecutionPlan::run` then evaluates the schedule with `Op::forward_into_with`, under the
+//! graph's current settings, so built-in ops do not allocate once the arenas are sized.
+
+use std::collections::HashMap;
+
+use crate::error::ComputeError;
+use crate::graph::{Graph, Node};
+use crate::ops::Op;
+use crate::shape;
+use crate::tensor::Tensor;
+
+/// Where a step reads one of its inputs from.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Value {
+    /// An `Input` or `Parameter` tensor read from the graph at run time.
+    Node(usize),
+    /// A caller-provided tensor replacing an `Input` node.
+    Feed(usize),
+    /// An arena buffer written by an earlier step.
+    Buffer(usize),
+}
+
+struct Step {
+    node: usize,
+    inputs: Vec<Value>,
+    buffer: usize,
+}
+
+pub struct ExecutionPlan {
+    graph_len: usize,
+    /// Fed nodes with the shape each was compiled for.
+    feeds: Vec<(usize, Vec<usize>)>,
+    /// Input, constant and parameter nodes read from the graph, with their compiled shapes.
+    leaves: Vec<(usize, Vec<usize>)>,
+    steps: Vec<Step>,
+    output: Value,
+    shapes: HashMap<usize, Vec<usize>>,
+    arenas: Vec<Tensor>,
+}
+
+impl ExecutionPlan {
+    /// Compile the subgraph that `output` depends on.
+    ///
+    /// `feeds` lists `Input` nodes whose value is supplied on each `run`; their current
+    /// tensors fix the shapes the plan is compiled for. Feeds the output does not depend on
+    /// are accepted and ignored. Parameters are read from the graph on every run, so a plan
+    /// stays valid across optimizer steps, as long as their shapes do not change.
+    pub fn compile(graph: &Graph, output: usize, feeds: &[usize]) -> Result<Self, ComputeError> {
+        for &feed in feeds {
+            if !matches!(graph.nodes.get(feed), Some(Node::Input(_))) {
+                return Err(ComputeError::InvalidOperation {
+                    message: format!("feed node {feed} is not an input"),
+                });
+            }
+        }
+
+        let order = graph.topological_sort(output)?;
+        let mut shapes = HashMap::new();
+        for (idx, static_shape) in graph.infer_shapes(output, &[])? {
+            let dims = shape::concrete(&static_shape).expect("graph tensors have fixed shapes");
+            shapes.insert(idx, dims);
+        }
+        let leaf_shape = |idx: usize| match &graph.nodes[idx] {
+            Node::Input(t) | Node::Constant(t) | Node::Parameter(t, _) => t.shape().to_vec(),
+            Node::Operation(_, _) => unreachable!("leaves are not operations"),
+        };
+        let leaves = order
+            .iter()
+            .copied()
+            .filter(|idx| !feeds.contains(idx))
+            .filter(|&idx| !matches!(graph.nodes[idx], Node::Operation(_, _)))
+            .map(|idx| (idx, leaf_shape(idx)))
+            .collect();
+
+        let step_nodes: Vec<usize> = order
+            .iter()
+            .copied()
+            .filter(|&idx| matches!(graph.nodes[idx], Node::Operation(_, _)))
+            .collect();
+
+        // Last step reading each op node; the output stays live to the end.
+        let mut last_use: HashMap<usize, usize> = HashMap::new();
+        for (pos, &idx) in step_nodes.iter().enumerate() {
+            if let Node::Operation(_, inputs) = &graph.nodes[idx] {
+                for &input in inputs {
+                    last_use.insert(input, pos);
+                }
+            }
+        }
+        last_use.insert(output, usize::MAX);
+
+        let mut capacities: Vec<usize> = Vec::new();
+        let mut free: Vec<usize> = Vec::new();
+        let mut buffer_of: HashMap<usize, usize> = HashMap::new();
+        let mut steps = Vec::with_capacity(step_nodes.len());
+
+        for (pos, &idx) in step_nodes.iter().enumerate() {
+            let numel: usize = shapes[&idx].iter().product();
+            let buffer = Self::take_buffer(&mut free, &mut capacities, numel);
+            buffer_of.insert(idx, buffer);
+
+            let input_nodes = match &graph.nodes[idx] {
+                Node::Operation(_, inputs) => inputs.clone(),
+                _ => unreachable!("steps are operation nodes"),
+            };
+            let inputs = input_nodes
+                .iter()
+                .map(|&i| Self::value_of(graph, i, feeds, &buffer_of))
+                .collect();
+
+            // Release buffers whose last reader is this step. The output buffer was taken
+            // first, so a step never writes into a buffer it reads.
+            for &input in &input_nodes {
+                if last_use.get(&input) == Some(&pos) {
+                    if let Some(b) = buffer_of.get(&input) {
+                        if !free.contains(b) {
+                            free.push(*b);
+                        }
+                    }
+                }
+            }
+
+            steps.push(Step {
+                node: idx,
+                inputs,
+                buffer,
+            });
+        }
+
+        Ok(Self {
+            graph_len: graph.nodes.len(),
+            feeds: feeds.iter().map(|&idx| (idx, leaf_shape(idx))).collect(),
+            leaves,
+            steps,
+            output: Self::value_of(graph, output, feeds, &buffer_of),
+            shapes,
+            arenas: capacities.into_iter().map(Tensor::buffer).collect(),
+        })
+    }
+
+    /// Evaluate the plan. Every compiled feed must be supplied with its compiled shape, and
+    /// the graph's other inputs and parameters must still have theirs.
+    pub fn run<'a>(
+        &'a mut self,
+        graph: &'a Graph,
+        feeds: &[(usize, &'a Tensor)],
+    ) -> Result<&'a Tensor, ComputeError> {
+        if graph.nodes.len() != self.graph_len {
+            return Err(ComputeError::InvalidOperation {
+                message: "graph changed since the execution plan was compiled".to_string(),
+            });
+        }
+        for (node, compiled) in &self.feeds {
+            let tensor = Self::feed(feeds, *node)?;
+            if tensor.shape() != compiled.as_slice() {
+                return Err(ComputeError::DimensionError {
+                    message: format!(
+                        "feed for node {node} has shape {:?}, plan was compiled for {compiled:?}",
+                        tensor.shape(),
+                    ),
+                });
+            }
+        }
+        for (node, compiled) in &self.leaves {
+            let tensor = Self::resolve(Value::Node(*node), graph, feeds, &self.arenas)?;
+            if tensor.shape() != compiled.as_slice() {
+                return Err(ComputeError::DimensionError {
+                    message: format!(
+                        "node {node} now has shape {:?}, plan was compiled for {compiled:?}",
+                        tensor.shape(),
+                    ),
+                });
+            }
+        }
+
+        for step in &self.steps {
+            let op = match &graph.nodes[step.node] {
+                Node::Operation(op, _) => op,
+                _ => {
+                    return Err(ComputeError::InvalidOperation {
+                        message: format!("node {} is no longer an operation", step.node),
+                    })
+                }
+            };
+
+            let mut out = std::mem::replace(&mut self.arenas[step.buffer], Tensor::placeholder());
+            let result = Self::execute(
+                op.as_ref(),
+                &step.inputs,
+                graph,
+                feeds,
+                &self.arenas,
+                &mut out,
+            );
+            let shape_ok = out.shape() == self.shapes[&step.node].as_slice();
+            self.arenas[step.buffer] = out;
+            result?;
+            if !shape_ok {
+                return Err(ComputeError::DimensionError {
+                    message: format!(
+                        "node {} produced a shape other than the compiled {:?}",
+                        step.node, self.shapes[&step.node]
+                    ),
+                });
+            }
+        }
+
+        Self::resolve(self.output, graph, feeds, &self.arenas)
+    }
+
+    /// Node indices of the scheduled ops, in execution order.
+    pub fn schedule(&self) -> Vec<usize> {
+        self.steps.iter().map(|s| s.node).collect()
+    }
+
+    /// Compiled shape of any node the output depends on.
+    pub fn shape(&self, node_idx: usize) -> Option<&[usize]> {
+        self.shapes.get(&node_idx).map(|s| s.as_slice())
+    }
+
+    pub fn num_buffers(&self) -> usize {
+        self.arenas.len()
+    }
+
+    /// Total number of `f32` elements preallocated across all arena buffers.
+    pub fn arena_elements(&self) -> usize {
+        self.arenas.iter().map(|a| a.capacity()).sum()
+    }
+
+    /// Pick the smallest free buffer that fits, else grow the largest free one, else add one.
+    fn take_buffer(free: &mut Vec<usize>, capacities: &mut Vec<usize>, numel: usize) -> usize {
+        let fitting = free
+            .iter()
+            .enumerate()
+            .filter(|(_, &b)| capacities[b] >= numel)
+            .min_by_key(|(_, &b)| capacities[b]);
+        let chosen = fitting
+            .or_else(|| free.iter().enumerate().max_by_key(|(_, &b)| capacities[b]))
+            .map(|(pos, _)| pos);
+        match chosen {
+            Some(pos) => {
+                let b = free.swap_remove(pos);
+                capacities[b] = capacities[b].max(numel);
+                b
+            }
+            None => {
+                capacities.push(numel);
+                capacities.len() - 1
+            }
+        }
+    }
+
+    /// Run one step into `out`. Inputs are passed as stack arrays for the common arities.
+    fn execute(
+        op: &dyn Op,
+        inputs: &[Value],
+        graph: &Graph,
+        feeds: &[(usize, &Tensor)],
+        arenas: &[Tensor],
+        out: &mut Tensor,
+    ) -> Result<(), ComputeError> {
+        let get = |v: &Value| Self::resolve(*v, graph, feeds, arenas);
+        let ctx = graph.op_context();
+        match inputs {
+            [a] => op.forward_into_with(&[get(a)?], out, &ctx),
+            [a, b] => op.forward_into_with(&[get(a)?, get(b)?], out, &ctx),
+            [a, b, c] => op.forward_into_with(&[get(a)?, get(b)?, get(c)?], out, &ctx),
+            many => {
+                let refs = many.iter().map(get).collect::<Result<Vec<_>, _>>()?;
+                op.forward_into_with(&refs, out, &ctx)
+            }
+        }
+    }
+
+    fn value_of(
+        graph: &Graph,
+        idx: usize,
+        feeds: &[usize],
+        buffer_of: &HashMap<usize, usize>,
+    ) -> Value {
+        match graph.nodes[idx] {
+            Node::Operation(_, _) => Value::Buffer(buffer_of[&idx]),
+            Node::Input(_) if feeds.contains(&idx) => Value::Feed(idx),
+            _ => Value::Node(idx),
+        }
+    }
+
+    fn resolve<'a>(
+        value: Value,
+        graph: &'a Graph,
+        feeds: &[(usize, &'a Tensor)],
+        arenas: &'a [Tensor],
+    ) -> Result<&'a Tensor, ComputeError> {
+        match value {
+            Value::Buffer(b) => Ok(&arenas[b]),
+            Value::Feed(node) => Self::feed(feeds, node),
+            Value::Node(node) => match &graph.nodes[node] {
+                Node::Input(t) | Node::Constant(t) | Node::Parameter(t, _) => Ok(t),
+                Node::Operation(_, _) => Err(ComputeError::InvalidOperation {
+                    message: format!("node {node} is no longer an input or parameter"),
+                }),
+            },
+        }
+    }
+
+    fn feed<'a>(feeds: &[(usize, &'a Tensor)], node: usize) -> Result<&'a Tensor, ComputeError> {
+        feeds
+            .iter()
+            .find(|(idx, _)| *idx == node)
+            .map(|(_, t)| *t)
+            .ok_or_else(|| ComputeError::InvalidOperation {
+                message: format!("missing feed for input node {node}"),
+            })
+    }
+}
@@ -0,0 +1,474 @@
+//! Pooling ops over `[batch, channels, ...spatial]` tensors.
+//!
+//! Like the convolutions, 1D pools run as 2D pools of height 1. Padding never contributes
+//! values: max pooling skips padded positions and average pooling divides by the number of
+//! real elements in each window. `layers` wraps each op as a
+//! parameter-free layer.
+
+use crate::conv::{format_pair, output_size};
+use crate::error::ComputeError;
+use crate::ops::Op;
+use crate::shape::{self, Dim};
+use crate::tensor::Tensor;
+
+#[derive(Clone, Copy, Debug)]
+enum Window {
+    Sliding {
+        kernel: [usize; 2],
+        stride: [usize; 2],
+        padding: [usize; 2],
+    },
+    Adaptive {
+        output: [usize; 2],
+    },
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Reduce {
+    Max,
+    Mean,
+}
+
+/// One pooling problem, lifted to two spatial axes.
+#[derive(Clone, Copy, Debug)]
+struct Pool {
+    spatial: usize,
+    window: Window,
+    reduce: Reduce,
+}
+
+impl Pool {
+    fn sliding(
+        spatial: usize,
+        kernel: [usize; 2],
+        stride: [usize; 2],
+        padding: [usize; 2],
+        reduce: Reduce,
+    ) -> Self {
+        Self {
+            spatial,
+            window: Window::Sliding {
+                kernel,
+                stride,
+                padding,
+            },
+            reduce,
+        }
+    }
+
+    fn adaptive(spatial: usize, output: [usize; 2]) -> Self {
+        Self {
+            spatial,
+            window: Window::Adaptive { output },
+            reduce: Reduce::Mean,
+        }
+    }
+
+    /// Input ranges `start..end` pooled into each output position along one axis.
+    fn ranges(&self, axis: usize, input: usize) -> Result<Vec<(usize, usize)>, ComputeError> {
+        match self.window {
+            Window::Sliding {
+                kernel,
+                stride,
+                padding,
+            } => {
+                let (k, s, p) = (kernel[axis], stride[axis], padding[axis]);
+                if 2 * p > k {
+                    return Err(ComputeError::InvalidOperation {
+                        message: format!("pool padding {p} must be at most half the kernel {k}"),
+                    });
+                }
+                let out = output_size(input, k, s, p, 1)?;
+                Ok((0..out)
+                    .map(|o| {
+                        let start = (o * s).saturating_sub(p);
+                        let end = (o * s + k - p).min(input);
+                        (start, end)
+                    })
+                    .collect())
+            }
+            Window::Adaptive { output } => {
+                let out = output[axis];
+                if out == 0 || input == 0 {
+                    return Err(ComputeError::InvalidOperation {
+                        message: "adaptive pool sizes must be positive".to_string(),
+                    });
+                }
+                Ok((0..out)
+                    .map(|o| (o * input / out, ((o + 1) * input).div_ceil(out)))
+                    .collect())
+            }
+        }
+    }
+
+    /// Per-axis ranges for a `[height, width]` input plane; a 1D height stays a single row.
+    fn plane_ranges(&self, plane: [usize; 2]) -> Result<[Vec<(usize, usize)>; 2], ComputeError> {
+        let rows = if self.spatial == 1 {
+            vec![(0, 1)]
+        } else {
+            self.ranges(0, plane[0])?
+        };
+        Ok([rows, self.ranges(1, plane[1])?])
+    }
+
+    fn check_rank(&self, shape: &[usize]) -> Result<(), ComputeError> {
+        if shape.len() != self.spatial + 2 {
+            return Err(ComputeError::DimensionError {
+                message: format!(
+                    "pool{}d input must have rank {}, got shape {shape:?}",
+                    self.spatial,
+                    self.spatial + 2
+                ),
+            });
+        }
+        Ok(())
+    }
+
+    /// Call `f(output, window)` with the flat input indices of every output element, and
+    /// return the output shape.
+    fn for_each_window(
+        &self,
+        shape: &[usize],
+        mut f: impl FnMut(usize, &[usize]),
+    ) -> Result<Vec<usize>, ComputeError> {
+        self.check_rank(shape)?;
+        let plane = match shape[2..] {
+            [w] => [1, w],
+            [h, w] => [h, w],
+            _ => unreachable!("rank checked above"),
+        };
+        let [rows, cols] = self.plane_ranges(plane)?;
+        let planes = shape[0] * shape[1];
+        let mut window = Vec::new();
+        let mut o = 0;
+        for p in 0..planes {
+            for &(y0, y1) in &rows {
+                for &(x0, x1) in &cols {
+                    window.clear();
+                    for y in y0..y1 {
+                        window.extend((x0..x1).map(|x| (p * plane[0] + y) * plane[1] + x));
+                    }
+                    f(o, &window);
+                    o += 1;
+                }
+            }
+        }
+        let mut out = shape[..2].to_vec();
+        if self.spatial == 2 {
+            out.push(rows.len());
+        }
+        out.push(cols.len());
+        Ok(out)
+    }
+
+    /// Index of the first maximum in `window`.
+    fn argmax(data: &[f32], window: &[usize]) -> usize {
+        window
+            .iter()
+            .copied()
+            .reduce(|best, i| if data[i] > data[best] { i } else { best })
+            .expect("pool windows are never empty")
+    }
+
+    fn forward(&self, inputs: &[Tensor]) -> Result<Tensor, ComputeError> {
+        let x = expect_one(inputs)?;
+        let data = x.data();
+        let mut y = Vec::new();
+        let shape = self.for_each_window(x.shape(), |_, window| {
+            y.push(match self.reduce {
+                Reduce::Max => data[Self::argmax(data, window)],
+                Reduce::Mean => window.iter().map(|&i| data[i]).sum::<f32>() / window.len() as f32,
+            });
+        })?;
+        Tensor::new(y, shape)
+    }
+
+    fn backward(&self, inputs: &[Tensor], grad: &Tensor) -> Result<Vec<Tensor>, ComputeError> {
+        let x = expect_one(inputs)?;
+        let (data, gd) = (x.data(), grad.data());
+        let mut gx = vec![0.0; data.len()];
+        let mut outputs = 0;
+        self.for_each_window(x.shape(), |o, window| {
+            outputs += 1;
+            let Some(&g) = gd.get(o) else {
+                return;
+            };
+            match self.reduce {
+                // The whole gradient goes to the element that was selected.
+                Reduce::Max => gx[Self::argmax(data, window)] += g,
+                Reduce::Mean => {
+                    let share = g / window.len() as f32;
+                    for &i in window {
+                        gx[i] += share;
+                    }
+                }
+            }
+        })?;
+        if gd.len() != outputs {
+            return Err(ComputeError::ShapeMismatch {
+                expected: outputs,
+                got: gd.len(),
+            });
+        }
+        Ok(vec![Tensor::new(gx, x.shape().to_vec())?])
+    }
+
+    /// Static shape; spatial sizes must be fixed, batch and channels may be symbolic.
+    fn infer_shape(&self, inputs: &[Vec<Dim>]) -> Result<Vec<Dim>, ComputeError> {
+        shape::expect_inputs(inputs, 1)?;
+        let x = &inputs[0];
+        if x.len() != self.spatial + 2 {
+            return Err(ComputeError::DimensionError {
+                message: format!(
+                    "pool{}d input must have rank {}, got {}",
+                    self.spatial,
+                    self.spatial + 2,
+                    shape::format_shape(x)
+                ),
+            });
+        }
+        let sizes = shape::concrete(&x[2..]).ok_or_else(|| ComputeError::DimensionError {
+            message: format!(
+                "pooling needs fixed spatial sizes, got {}",
+                shape::format_shape(x)
+            ),
+        })?;
+        let plane = match sizes[..] {
+            [w] => [1, w],
+            [h, w] => [h, w],
+            _ => unreachable!("rank checked above"),
+        };
+        let [rows, cols] = self.plane_ranges(plane)?;
+        let mut out = x[..2].to_vec();
+        if self.spatial == 2 {
+            out.push(Dim::Fixed(rows.len()));
+        }
+        out.push(Dim::Fixed(cols.len()));
+        Ok(out)
+    }
+}
+
+fn expect_one(inputs: &[Tensor]) -> Result<&Tensor, ComputeError> {
+    match inputs {
+        [x] => Ok(x),
+        _ => Err(ComputeError::InputCountError {
+            expected: 1,
+            got: inputs.len(),
+        }),
+    }
+}
+
+/// Implements `Op` for a pool op with `pool()` and `attribute_list()` methods.
+macro_rules! pool_op {
+    ($name:ident) => {
+        impl Op for $name {
+            fn forward(&self, inputs: &[Tensor]) -> Result<Tensor, ComputeError> {
+                self.pool().forward(inputs)
+            }
+
+            fn backward(
+                &self,
+                inputs: &[Tensor],
+                grad_output: &Tensor,
+            ) -> Result<Vec<Tensor>, ComputeError> {
+                self.pool().backward(inputs, grad_output)
+            }
+
+            fn infer_shape(&self, inputs: &[Vec<Dim>]) -> Result<Vec<Dim>, ComputeError> {
+                self.pool().infer_shape(inputs)
+            }
+
+            fn name(&self) -> &str {
+                stringify!($name)
+            }
+
+            fn attributes(&self) -> Option<Vec<(&'static str, String)>> {
+                Some(self.attribute_list())
+            }
+        }
+    };
+}
+
+/// Max over 1D windows of `[batch, channels, length]` inputs; the gradient flows only to
+/// the first maximum of each window.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MaxPool1dOp {
+    pub kernel: usize,
+    pub stride: usize,
+    pub padding: usize,
+}
+
+impl MaxPool1dOp {
+    /// Non-overlapping windows of `kernel` elements.
+    pub fn new(kernel: usize) -> Self {
+        Self {
+            kernel,
+            stride: kernel,
+            padding: 0,
+        }
+    }
+
+    fn pool(&self) -> Pool {
+        Pool::sliding(
+            1,
+            [1, self.kernel],
+            [1, self.stride],
+            [0, self.padding],
+            Reduce::Max,
+        )
+    }
+
+    fn attribute_list(&self) -> Vec<(&'static str, String)> {
+        vec![
+            ("kernel", self.kernel.to_string()),
+            ("stride", self.stride.to_string()),
+            ("padding", self.padding.to_string()),
+        ]
+    }
+}
+
+pool_op!(MaxPool1dOp);
+
+/// Max over 2D windows of `[batch, channels, height, width]` inputs.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MaxPool2dOp {
+    pub kernel: [usize; 2],
+    pub stride: [usize; 2],
+    pub padding: [usize; 2],
+}
+
+impl MaxPool2dOp {
+    /// Non-overlapping `kernel` windows.
+    pub fn new(kernel: [usize; 2]) -> Self {
+        Self {
+            kernel,
+            stride: kernel,
+            padding: [0, 0],
+        }
+    }
+
+    fn pool(&self) -> Pool {
+        Pool::sliding(2, self.kernel, self.stride, self.padding, Reduce::Max)
+    }
+
+    fn attribute_list(&self) -> Vec<(&'static str, String)> {
+        vec![
+            ("kernel", format_pair(self.kernel)),
+            ("stride", format_pair(self.stride)),
+            ("padding", format_pair(self.padding)),
+        ]
+    }
+}
+
+pool_op!(MaxPool2dOp);
+
+/// Mean over 1D windows of `[batch, channels, length]` inputs, excluding padding.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AvgPool1dOp {
+    pub kernel: usize,
+    pub stride: usize,
+    pub padding: usize,
+}
+
+impl AvgPool1dOp {
+    /// Non-overlapping windows of `kernel` elements.
+    pub fn new(kernel: usize) -> Self {
+        Self {
+            kernel,
+            stride: kernel,
+            padding: 0,
+        }
+    }
+
+    fn pool(&self) -> Pool {
+        Pool::sliding(
+            1,
+            [1, self.kernel],
+            [1, self.stride],
+            [0, self.padding],
+            Reduce::Mean,
+        )
+    }
+
+    fn attribute_list(&self) -> Vec<(&'static str, String)> {
+        vec![
+            ("kernel", self.kernel.to_string()),
+            ("stride", self.stride.to_string()),
+            ("padding", self.padding.to_string()),
+        ]
+    }
+}
+
+pool_op!(AvgPool1dOp);
+
+/// Mean over 2D windows of `[batch, channels, height, width]` inputs, excluding padding.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AvgPool2dOp {
+    pub kernel: [usize; 2],
+    pub stride: [usize; 2],
+    pub padding: [usize; 2],
+}
+
+impl AvgPool2dOp {
+    /// Non-overlapping `kernel` windows.
+    pub fn new(kernel: [usize; 2]) -> Self {
+        Self {
+            kernel,
+            stride: kernel,
+            padding: [0, 0],
+        }
+    }
+
+    fn pool(&self) -> Pool {
+        Pool::sliding(2, self.kernel, self.stride, self.padding, Reduce::Mean)
+    }
+
+    fn attribute_list(&self) -> Vec<(&'static str, String)> {
+        vec![
+            ("kernel", format_pair(self.kernel)),
+            ("stride", format_pair(self.stride)),
+            ("padding", format_pair(self.padding)),
+        ]
+    }
+}
+
+pool_op!(AvgPool2dOp);
+
+/// Mean pooling to a fixed output length, whatever the input length.
+///
+/// Output position `o` averages inputs `floor(o * length / output)` up to
+/// `ceil((o + 1) * length / output)`; windows may overlap when the sizes do not divide.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AdaptiveAvgPool1dOp {
+    pub output: usize,
+}
+
+impl AdaptiveAvgPool1dOp {
+    fn pool(&self) -> Pool {
+        Pool::adaptive(1, [1, self.output])
+    }
+
+    fn attribute_list(&self) -> Vec<(&'static str, String)> {
+        vec![("output", self.output.to_string())]
+    }
+}
+
+pool_op!(AdaptiveAvgPool1dOp);
+
+/// Mean pooling to a fixed `[height, width]`; `[1, 1]` is global average pooling.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AdaptiveAvgPool2dOp {
+    pub output: [usize; 2],
+}
+
+impl AdaptiveAvgPool2dOp {
+    fn pool(&self) -> Pool {
+        Pool::adaptive(2, self.output)
+    }
+
+    fn attribute_list(&self) -> Vec<(&'static str, String)> {
+        vec![("output", format_pair(self.output))]
+    }
+}
+
+pool_op!(AdaptiveAvgPool2dOp);
@@ -12,6 +12,11 @@ impl XorShift32 {
         Self { state: seed }
     }
 
+    /// The current state; `XorShift32::new(state)` continues the same sequence.
+    pub fn state(&self) -> u32 {
+        self.state
+    }
+
     pub fn next_u32(&mut self) -> u32 {
         let mut x = self.state;
         x ^= x << 13;
@@ -32,4 +37,12 @@ impl XorShift32 {
     pub fn gen_range_f32(&mut self, low: f32, high: f32) -> f32 {
         low + (high - low) * self.next_f32()
     }
+
+    /// Standard normal sample (Box-Muller, one of the pair).
+    pub fn next_normal_f32(&mut self) -> f32 {
+        // 1 - u keeps the log argument in (0, 1].
+        let u1 = 1.0 - self.next_f32();
+        let u2 = self.next_f32();
+        (-2.0 * u1.ln()).sqrt() * (std::f32::consts::TAU * u2).cos()
+    }
 }
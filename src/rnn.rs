@@ -0,0 +1,459 @@
+//! Recurrent layers over `[batch, time, features]` sequences.
+//!
+//! Each layer and direction runs its cell with a `ScanOp` along the time axis, so gradients
+//! are computed by backpropagation through time. Weights follow `Linear`'s `[in, out]`
+//! layout with the gates side by side: LSTM gates are ordered input, forget, cell, output
+//! and GRU gates reset, update, new.
+//!
+//! The recurrent state is one `[num_layers * directions, batch, state_size]` tensor, with
+//! rows ordered layer by layer and forward before reverse. A GRU state row is `h`; an LSTM
+//! state row is `h` followed by the cell `c`, so its `state_size` is `2 * hidden_size`.
+//! Feeding the final state of one window as the initial state of the next continues the
+//! sequence, which is how stateful streaming inference works.
+
+use crate::control::ScanOp;
+use crate::custom::MacroOp;
+use crate::error::ComputeError;
+use crate::graph::Graph;
+use crate::init::Init;
+use crate::layers::Layer;
+use crate::module::Module;
+use crate::ops::{AddOp, MatMulOp, MultiplyOp, Op, SigmoidOp, SubtractOp, TanhOp};
+use crate::prng::XorShift32;
+use crate::shape::{self, Dim};
+use crate::slicing::{ConcatOp, FlipOp, NarrowOp, SelectOp, StackOp};
+use crate::tensor::Tensor;
+
+/// Sizes of a recurrent layer stack.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RnnConfig {
+    pub input_size: usize,
+    pub hidden_size: usize,
+    pub num_layers: usize,
+    /// Also run every layer backwards in time and concatenate both outputs.
+    pub bidirectional: bool,
+}
+
+impl RnnConfig {
+    /// One unidirectional layer.
+    pub fn new(input_size: usize, hidden_size: usize) -> Self {
+        Self {
+            input_size,
+            hidden_size,
+            num_layers: 1,
+            bidirectional: false,
+        }
+    }
+
+    pub fn directions(&self) -> usize {
+        if self.bidirectional {
+            2
+        } else {
+            1
+        }
+    }
+
+    /// Features per time step of the output sequence.
+    pub fn output_size(&self) -> usize {
+        self.hidden_size * self.directions()
+    }
+}
+
+/// Node indices produced by a recurrent forward pass.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RnnOutput {
+    /// `[batch, time, hidden_size * directions]` hidden states of the last layer.
+    pub output: usize,
+    /// `[num_layers * directions, batch, state_size]` state after the last step.
+    pub state: usize,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Cell {
+    Lstm,
+    Gru,
+}
+
+impl Cell {
+    fn gates(self) -> usize {
+        match self {
+            Cell::Lstm => 4,
+            Cell::Gru => 3,
+        }
+    }
+
+    fn state_size(self, hidden: usize) -> usize {
+        match self {
+            Cell::Lstm => 2 * hidden,
+            Cell::Gru => hidden,
+        }
+    }
+
+    /// One step `(x_t, state, w_ih, w_hh, b_ih, b_hh) -> state`.
+    fn build(self, hidden: usize) -> Result<MacroOp, ComputeError> {
+        let gate = move |graph: &mut Graph, from: usize, index: usize| {
+            graph.apply_op(
+                NarrowOp {
+                    axis: 1,
+                    start: index * hidden,
+                    len: hidden,
+                },
+                &[from],
+            )
+        };
+        match self {
+            Cell::Lstm => MacroOp::new("LstmCell", 6, |g, p| {
+                let h = gate(g, p[1], 0);
+                let c = gate(g, p[1], 1);
+                let xw = g.apply_op(MatMulOp, &[p[0], p[2]]);
+                let xw = g.apply_op(AddOp, &[xw, p[4]]);
+                let hw = g.apply_op(MatMulOp, &[h, p[3]]);
+                let hw = g.apply_op(AddOp, &[hw, p[5]]);
+                let gates = g.apply_op(AddOp, &[xw, hw]);
+                let i = gate(g, gates, 0);
+                let i = g.apply_op(SigmoidOp, &[i]);
+                let f = gate(g, gates, 1);
+                let f = g.apply_op(SigmoidOp, &[f]);
+                let cell = gate(g, gates, 2);
+                let cell = g.apply_op(TanhOp, &[cell]);
+                let o = gate(g, gates, 3);
+                let o = g.apply_op(SigmoidOp, &[o]);
+                let kept = g.apply_op(MultiplyOp, &[f, c]);
+                let written = g.apply_op(MultiplyOp, &[i, cell]);
+                let c = g.apply_op(AddOp, &[kept, written]);
+                let squashed = g.apply_op(TanhOp, &[c]);
+                let h = g.apply_op(MultiplyOp, &[o, squashed]);
+                Ok(g.apply_op(ConcatOp { axis: 1 }, &[h, c]))
+            }),
+            Cell::Gru => MacroOp::new("GruCell", 6, |g, p| {
+                let h = p[1];
+                let xw = g.apply_op(MatMulOp, &[p[0], p[2]]);
+                let xw = g.apply_op(AddOp, &[xw, p[4]]);
+                let hw = g.apply_op(MatMulOp, &[h, p[3]]);
+                let hw = g.apply_op(AddOp, &[hw, p[5]]);
+                let mut sigmoid_gate = |index| {
+                    let a = gate(g, xw, index);
+                    let b = gate(g, hw, index);
+                    let sum = g.apply_op(AddOp, &[a, b]);
+                    g.apply_op(SigmoidOp, &[sum])
+                };
+                let r = sigmoid_gate(0);
+                let z = sigmoid_gate(1);
+                let xn = gate(g, xw, 2);
+                let hn = gate(g, hw, 2);
+                let reset = g.apply_op(MultiplyOp, &[r, hn]);
+                let n = g.apply_op(AddOp, &[xn, reset]);
+                let n = g.apply_op(TanhOp, &[n]);
+                // h' = (1 - z) * n + z * h = n + z * (h - n)
+                let delta = g.apply_op(SubtractOp, &[h, n]);
+                let delta = g.apply_op(MultiplyOp, &[z, delta]);
+                Ok(g.apply_op(AddOp, &[n, delta]))
+            }),
+        }
+    }
+}
+
+/// A zero `[rows, batch, size]` state for a `[batch, ...]` input, used when no initial
+/// state is given.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct ZeroStateOp {
+    pub(crate) rows: usize,
+    pub(crate) size: usize,
+}
+
+impl Op for ZeroStateOp {
+    fn forward(&self, inputs: &[Tensor]) -> Result<Tensor, ComputeError> {
+        let batch = inputs
+            .first()
+            .and_then(|x| x.shape().first())
+            .copied()
+            .ok_or(ComputeError::InputCountError {
+                expected: 1,
+                got: inputs.len(),
+            })?;
+        Tensor::zeros(vec![self.rows, batch, self.size])
+    }
+
+    fn backward(
+        &self,
+        inputs: &[Tensor],
+        _grad_output: &Tensor,
+    ) -> Result<Vec<Tensor>, ComputeError> {
+        inputs.iter().map(Tensor::zeros_like).collect()
+    }
+
+    fn infer_shape(&self, inputs: &[Vec<Dim>]) -> Result<Vec<Dim>, ComputeError> {
+        shape::expect_inputs(inputs, 1)?;
+        let batch = inputs[0]
+            .first()
+            .cloned()
+            .ok_or_else(|| ComputeError::DimensionError {
+                message: "recurrent input must have a batch dimension".to_string(),
+            })?;
+        Ok(vec![Dim::Fixed(self.rows), batch, Dim::Fixed(self.size)])
+    }
+
+    fn name(&self) -> &str {
+        "ZeroStateOp"
+    }
+
+    fn attributes(&self) -> Option<Vec<(&'static str, String)>> {
+        Some(vec![
+            ("rows", self.rows.to_string()),
+            ("size", self.size.to_string()),
+        ])
+    }
+}
+
+/// Weights of one layer in one direction.
+#[derive(Clone, Copy, Debug)]
+struct CellWeights {
+    weight_ih: usize,
+    weight_hh: usize,
+    bias_ih: usize,
+    bias_hh: usize,
+}
+
+/// The shared implementation of `Lstm` and `Gru`.
+struct Recurrent {
+    cell: Cell,
+    config: RnnConfig,
+    /// `num_layers * directions` entries, in state row order.
+    weights: Vec<CellWeights>,
+}
+
+impl Recurrent {
+    fn new(
+        graph: &mut Graph,
+        cell: Cell,
+        config: RnnConfig,
+        weight_init: &Init,
+        bias_init: &Init,
+        seed: u32,
+    ) -> Result<Self, ComputeError> {
+        if config.input_size == 0 || config.hidden_size == 0 || config.num_layers == 0 {
+            return Err(ComputeError::InvalidOperation {
+                message: format!("recurrent sizes must be positive, got {config:?}"),
+            });
+        }
+        let width = cell.gates() * config.hidden_size;
+        let mut rng = XorShift32::new(seed);
+        let mut weights = Vec::with_capacity(config.num_layers * config.directions());
+        for layer in 0..config.num_layers {
+            let input = if layer == 0 {
+                config.input_size
+            } else {
+                config.output_size()
+            };
+            for _ in 0..config.directions() {
+                let mut add = |shape: Vec<usize>, init: &Init| -> Result<usize, ComputeError> {
+                    Ok(graph.add_parameter(init.sample(shape, &mut rng)?, true))
+                };
+                weights.push(CellWeights {
+                    weight_ih: add(vec![input, width], weight_init)?,
+                    weight_hh: add(vec![config.hidden_size, width], weight_init)?,
+                    bias_ih: add(vec![1, width], bias_init)?,
+                    bias_hh: add(vec![1, width], bias_init)?,
+                });
+            }
+        }
+        Ok(Self {
+            cell,
+            config,
+            weights,
+        })
+    }
+
+    /// PyTorch's default: everything uniform in ±1/sqrt(hidden_size).
+    fn default_init(config: &RnnConfig) -> Init {
+        let k = 1.0 / (config.hidden_size.max(1) as f32).sqrt();
+        Init::Uniform { low: -k, high: k }
+    }
+
+    fn state_size(&self) -> usize {
+        self.cell.state_size(self.config.hidden_size)
+    }
+
+    fn initial_state(&self, batch: usize) -> Result<Tensor, ComputeError> {
+        Tensor::zeros(vec![self.weights.len(), batch, self.state_size()])
+    }
+
+    fn forward_with_state(
+        &self,
+        graph: &mut Graph,
+        input_idx: usize,
+        state: Option<usize>,
+    ) -> Result<RnnOutput, ComputeError> {
+        let hidden = self.config.hidden_size;
+        let state = match state {
+            Some(idx) => idx,
+            None => graph.apply_op(
+                ZeroStateOp {
+                    rows: self.weights.len(),
+                    size: self.state_size(),
+                },
+                &[input_idx],
+            ),
+        };
+        let directions = self.config.directions();
+        let mut sequence = input_idx;
+        let mut finals = Vec::with_capacity(self.weights.len());
+        for layer in 0..self.config.num_layers {
+            let mut outputs = Vec::with_capacity(directions);
+            for direction in 0..directions {
+                let row = layer * directions + direction;
+                let w = self.weights[row];
+                let reverse = direction == 1;
+                let initial = graph.apply_op(
+                    SelectOp {
+                        axis: 0,
+                        index: row as isize,
+                    },
+                    &[state],
+                );
+                let steps = if reverse {
+                    graph.apply_op(FlipOp { axis: 1 }, &[sequence])
+                } else {
+                    sequence
+                };
+                let scan = ScanOp::new(self.cell.build(hidden)?)?.with_axis(1);
+                let states = graph.apply_op(
+                    scan,
+                    &[
+                        steps,
+                        initial,
+                        w.weight_ih,
+                        w.weight_hh,
+                        w.bias_ih,
+                        w.bias_hh,
+                    ],
+                );
+                finals.push(graph.apply_op(SelectOp { axis: 1, index: -1 }, &[states]));
+                let mut hs = match self.cell {
+                    Cell::Lstm => graph.apply_op(
+                        NarrowOp {
+                            axis: 2,
+                            start: 0,
+                            len: hidden,
+                        },
+                        &[states],
+                    ),
+                    Cell::Gru => states,
+                };
+                if reverse {
+                    hs = graph.apply_op(FlipOp { axis: 1 }, &[hs]);
+                }
+                outputs.push(hs);
+            }
+            sequence = match outputs[..] {
+                [only] => only,
+                _ => graph.apply_op(ConcatOp { axis: 2 }, &outputs),
+            };
+        }
+        Ok(RnnOutput {
+            output: sequence,
+            state: graph.apply_op(StackOp { axis: 0 }, &finals),
+        })
+    }
+
+    fn own_parameters(&self) -> Vec<(String, usize)> {
+        let directions = self.config.directions();
+        self.weights
+            .iter()
+            .enumerate()
+            .flat_map(|(row, w)| {
+                let suffix = format!(
+                    "l{}{}",
+                    row / directions,
+                    if row % directions == 1 {
+                        "_reverse"
+                    } else {
+                        ""
+                    }
+                );
+                [
+                    (format!("weight_ih_{suffix}"), w.weight_ih),
+                    (format!("weight_hh_{suffix}"), w.weight_hh),
+                    (format!("bias_ih_{suffix}"), w.bias_ih),
+                    (format!("bias_hh_{suffix}"), w.bias_hh),
+                ]
+            })
+            .collect()
+    }
+}
+
+macro_rules! recurrent_layer {
+    ($(#[$doc:meta])* $name:ident, $cell:expr) => {
+        $(#[$doc])*
+        pub struct $name(Recurrent);
+
+        impl $name {
+            /// Weights and biases uniform in ±1/sqrt(hidden_size).
+            pub fn new(graph: &mut Graph, config: RnnConfig, seed: u32) -> Result<Self, ComputeError> {
+                let init = Recurrent::default_init(&config);
+                Self::with_init(graph, config, &init, &init, seed)
+            }
+
+            /// Weights (`[in, gates * hidden]` and `[hidden, gates * hidden]`) and biases
+            /// (`[1, gates * hidden]`) drawn from one generator seeded by `seed`.
+            pub fn with_init(
+                graph: &mut Graph,
+                config: RnnConfig,
+                weight_init: &Init,
+                bias_init: &Init,
+                seed: u32,
+            ) -> Result<Self, ComputeError> {
+                Recurrent::new(graph, $cell, config, weight_init, bias_init, seed).map(Self)
+            }
+
+            pub fn config(&self) -> RnnConfig {
+                self.0.config
+            }
+
+            /// Width of one state row.
+            pub fn state_size(&self) -> usize {
+                self.0.state_size()
+            }
+
+            /// A zero state for `batch` sequences, to feed as the first initial state.
+            pub fn initial_state(&self, batch: usize) -> Result<Tensor, ComputeError> {
+                self.0.initial_state(batch)
+            }
+
+            /// Run over a `[batch, time, input_size]` sequence from `state` (zeros if
+            /// `None`), returning the output sequence and the final state.
+            pub fn forward_with_state(
+                &self,
+                graph: &mut Graph,
+                input_idx: usize,
+                state: Option<usize>,
+            ) -> Result<RnnOutput, ComputeError> {
+                self.0.forward_with_state(graph, input_idx, state)
+            }
+        }
+
+        impl Module for $name {
+            fn own_parameters(&self) -> Vec<(String, usize)> {
+                self.0.own_parameters()
+            }
+        }
+
+        impl Layer for $name {
+            /// The output sequence, starting from a zero state.
+            fn forward(&self, graph: &mut Graph, input_idx: usize) -> Result<usize, ComputeError> {
+                Ok(self.forward_with_state(graph, input_idx, None)?.output)
+            }
+        }
+    };
+}
+
+recurrent_layer!(
+    /// Long short-term memory layers; state rows are `[h, c]`.
+    Lstm,
+    Cell::Lstm
+);
+recurrent_layer!(
+    /// Gated recurrent unit layers; state rows are `h`.
+    Gru,
+    Cell::Gru
+);
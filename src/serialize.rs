@@ -0,0 +1,769 @@
+//! Versioned text format for saving and loading graphs.
+//!
+//! ```text
+//! neuroncore-graph 2
+//! nodes 5
+//! policy error
+//! detect_anomalies
+//! input [1,3]
+//! const [1] 2
+//! param trainable [3,2] 0.5 -0.25 0.125 1 0 -1
+//! op MatMulOp 0,2
+//! op SumOp 3 dim=Some(1)
+//! label 4 head
+//! checkpoint 3 5
+//! ```
+//!
+//! Nodes are written in index order, one per line. `Input` nodes are placeholders: only
+//! their shape is stored and they load as zeros. Constants and parameters keep their
+//! values exactly (`f32` is written in its shortest round-trip form). Ops are stored by
+//! `Op::name` plus `Op::attributes` and rebuilt on load through an `OpRegistry`; opaque ops
+//! (attributes `None`) cannot be saved. These are the closure and subgraph ops: `FnOp`,
+//! `MacroOp`, `CondOp`, `WhileOp` and `ScanOp`, and with them the recurrent layers of
+//! `rnn`, whose cells run in a `ScanOp`.
+//!
+//! The node count is followed by the graph's settings: a `policy <policy>` record when
+//! the numeric policy is not IEEE and a `detect_anomalies` record when anomaly detection
+//! is on. After the nodes come the graph's labels, checkpointed segments
+//! (`checkpoint <start> <end>`), buffer updates (`buffer <buffer> <value>`), seed nodes
+//! (`seed <node>`) and, if there are seed nodes, the state of the seed generator
+//! (`rng <state>`). Version 1 files, which have only labels, still load.
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+
+use crate::attention::{AddPositionsOp, ScaledDotProductAttentionOp, SinusoidalEncodingOp};
+use crate::conv::{Conv1dOp, Conv2dOp, ConvTranspose1dOp, ConvTranspose2dOp};
+use crate::dropout::{DropoutOp, SpatialDropoutOp};
+use crate::embedding::EmbeddingOp;
+use crate::error::ComputeError;
+use crate::fusion::{Activation, FusedElementwiseOp, FusedLinearOp};
+use crate::graph::{Graph, Node};
+use crate::losses::{
+    BCEWithLogitsOp, BinaryInputs, CrossEntropyOp, FocalLossOp, HuberLossOp, L1LossOp,
+    LogCoshLossOp, QuantileLossOp,
+};
+use crate::norm::{BatchNormOp, GroupNormOp, LayerNormOp, RunningStatOp};
+use crate::numeric::NumericPolicy;
+use crate::ops::{
+    AddOp, BatchMatMulOp, DivideOp, LogOp, MatMulOp, MeanOp, MultiplyOp, Op, ReluOp, SigmoidOp,
+    SoftmaxOp, SqrtOp, SubtractOp, SumOp, TanhOp,
+};
+use crate::pool::{
+    AdaptiveAvgPool1dOp, AdaptiveAvgPool2dOp, AvgPool1dOp, AvgPool2dOp, MaxPool1dOp, MaxPool2dOp,
+};
+use crate::prng::XorShift32;
+use crate::rnn::ZeroStateOp;
+use crate::slicing::{ConcatOp, FlipOp, NarrowOp, SelectOp, StackOp};
+use crate::tensor::Tensor;
+
+pub const FORMAT_VERSION: u32 = 2;
+const MAGIC: &str = "neuroncore-graph";
+
+/// Attributes of a serialized op, as written by `Op::attributes`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Attributes(Vec<(String, String)>);
+
+impl Attributes {
+    pub fn get(&self, key: &str) -> Result<&str, ComputeError> {
+        self.0
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.as_str())
+            .ok_or_else(|| ComputeError::Parse {
+                context: format!("attribute {key}"),
+                message: "missing".to_string(),
+            })
+    }
+
+    /// `get(key)` parsed with `FromStr`.
+    pub fn parse<T: std::str::FromStr>(&self, key: &str) -> Result<T, ComputeError> {
+        let value = self.get(key)?;
+        value.parse().map_err(|_| ComputeError::Parse {
+            context: format!("attribute {key}"),
+            message: format!("cannot parse {value}"),
+        })
+    }
+
+    /// A `[height, width]` setting written as `h,w`.
+    pub fn pair(&self, key: &str) -> Result<[usize; 2], ComputeError> {
+        let value = self.get(key)?;
+        value
+            .split_once(',')
+            .and_then(|(h, w)| Some([h.parse().ok()?, w.parse().ok()?]))
+            .ok_or_else(|| ComputeError::Parse {
+                context: format!("attribute {key}"),
+                message: format!("expected h,w, got {value}"),
+            })
+    }
+}
+
+type Constructor = dyn Fn(&Attributes) -> Result<Box<dyn Op>, ComputeError> + Send + Sync;
+
+/// Maps op names to constructors used when loading a graph.
+pub struct OpRegistry {
+    constructors: HashMap<String, Box<Constructor>>,
+}
+
+impl OpRegistry {
+    /// A registry with no ops.
+    pub fn empty() -> Self {
+        Self {
+            constructors: HashMap::new(),
+        }
+    }
+
+    /// A registry with every built-in op that has serializable attributes.
+    pub fn new() -> Self {
+        let mut registry = Self::empty();
+        registry.register_unit("AddOp", AddOp);
+        registry.register_unit("SubtractOp", SubtractOp);
+        registry.register_unit("MultiplyOp", MultiplyOp);
+        registry.register_unit("DivideOp", DivideOp);
+        registry.register_unit("MatMulOp", MatMulOp);
+        registry.register_unit("BatchMatMulOp", BatchMatMulOp);
+        registry.register_unit("ReluOp", ReluOp);
+        registry.register_unit("LogOp", LogOp);
+        registry.register_unit("SqrtOp", SqrtOp);
+        registry.register_unit("SigmoidOp", SigmoidOp);
+        registry.register_unit("TanhOp", TanhOp);
+        registry.register_unit("SoftmaxOp", SoftmaxOp);
+        registry.register_unit("MeanOp", MeanOp);
+        registry.register("SumOp", |attrs| {
+            let dim = parse_option(attrs.get("dim")?, |v| v.parse::<usize>().ok())?;
+            Ok(Box::new(SumOp { dim }))
+        });
+        registry.register("NarrowOp", |attrs| {
+            Ok(Box::new(NarrowOp {
+                axis: attrs.parse("axis")?,
+                start: attrs.parse("start")?,
+                len: attrs.parse("len")?,
+            }))
+        });
+        registry.register("ConcatOp", |attrs| {
+            Ok(Box::new(ConcatOp {
+                axis: attrs.parse("axis")?,
+            }))
+        });
+        registry.register("SelectOp", |attrs| {
+            Ok(Box::new(SelectOp {
+                axis: attrs.parse("axis")?,
+                index: attrs.parse("index")?,
+            }))
+        });
+        registry.register("StackOp", |attrs| {
+            Ok(Box::new(StackOp {
+                axis: attrs.parse("axis")?,
+            }))
+        });
+        registry.register("FlipOp", |attrs| {
+            Ok(Box::new(FlipOp {
+                axis: attrs.parse("axis")?,
+            }))
+        });
+        registry.register("Conv1dOp", |attrs| {
+            Ok(Box::new(Conv1dOp {
+                stride: attrs.parse("stride")?,
+                padding: attrs.parse("padding")?,
+                dilation: attrs.parse("dilation")?,
+                groups: attrs.parse("groups")?,
+            }))
+        });
+        registry.register("Conv2dOp", |attrs| {
+            Ok(Box::new(Conv2dOp {
+                stride: attrs.pair("stride")?,
+                padding: attrs.pair("padding")?,
+                dilation: attrs.pair("dilation")?,
+                groups: attrs.parse("groups")?,
+            }))
+        });
+        registry.register("ConvTranspose1dOp", |attrs| {
+            Ok(Box::new(ConvTranspose1dOp {
+                stride: attrs.parse("stride")?,
+                padding: attrs.parse("padding")?,
+                output_padding: attrs.parse("output_padding")?,
+                dilation: attrs.parse("dilation")?,
+                groups: attrs.parse("groups")?,
+            }))
+        });
+        registry.register("ConvTranspose2dOp", |attrs| {
+            Ok(Box::new(ConvTranspose2dOp {
+                stride: attrs.pair("stride")?,
+                padding: attrs.pair("padding")?,
+                output_padding: attrs.pair("output_padding")?,
+                dilation: attrs.pair("dilation")?,
+                groups: attrs.parse("groups")?,
+            }))
+        });
+        registry.register("MaxPool1dOp", |attrs| {
+            Ok(Box::new(MaxPool1dOp {
+                kernel: attrs.parse("kernel")?,
+                stride: attrs.parse("stride")?,
+                padding: attrs.parse("padding")?,
+            }))
+        });
+        registry.register("MaxPool2dOp", |attrs| {
+            Ok(Box::new(MaxPool2dOp {
+                kernel: attrs.pair("kernel")?,
+                stride: attrs.pair("stride")?,
+                padding: attrs.pair("padding")?,
+            }))
+        });
+        registry.register("AvgPool1dOp", |attrs| {
+            Ok(Box::new(AvgPool1dOp {
+                kernel: attrs.parse("kernel")?,
+                stride: attrs.parse("stride")?,
+                padding: attrs.parse("padding")?,
+            }))
+        });
+        registry.register("AvgPool2dOp", |attrs| {
+            Ok(Box::new(AvgPool2dOp {
+                kernel: attrs.pair("kernel")?,
+                stride: attrs.pair("stride")?,
+                padding: attrs.pair("padding")?,
+            }))
+        });
+        registry.register("AdaptiveAvgPool1dOp", |attrs| {
+            Ok(Box::new(AdaptiveAvgPool1dOp {
+                output: attrs.parse("output")?,
+            }))
+        });
+        registry.register("AdaptiveAvgPool2dOp", |attrs| {
+            Ok(Box::new(AdaptiveAvgPool2dOp {
+                output: attrs.pair("output")?,
+            }))
+        });
+        registry.register("LayerNormOp", |attrs| {
+            Ok(Box::new(LayerNormOp {
+                eps: attrs.parse("eps")?,
+            }))
+        });
+        registry.register("GroupNormOp", |attrs| {
+            Ok(Box::new(GroupNormOp {
+                groups: attrs.parse("groups")?,
+                eps: attrs.parse("eps")?,
+            }))
+        });
+        registry.register("BatchNormOp", |attrs| {
+            Ok(Box::new(BatchNormOp {
+                eps: attrs.parse("eps")?,
+            }))
+        });
+        registry.register("RunningStatOp", |attrs| {
+            Ok(Box::new(RunningStatOp {
+                momentum: attrs.parse("momentum")?,
+                variance: attrs.parse("variance")?,
+            }))
+        });
+        registry.register("DropoutOp", |attrs| {
+            Ok(Box::new(DropoutOp {
+                p: attrs.parse("p")?,
+            }))
+        });
+        registry.register("SpatialDropoutOp", |attrs| {
+            Ok(Box::new(SpatialDropoutOp {
+                p: attrs.parse("p")?,
+            }))
+        });
+        registry.register("EmbeddingOp", |attrs| {
+            let padding_idx = parse_option(attrs.get("padding_idx")?, |v| v.parse::<usize>().ok())?;
+            Ok(Box::new(EmbeddingOp { padding_idx }))
+        });
+        registry.register("CrossEntropyOp", |attrs| {
+            let ignore_index =
+                parse_option(attrs.get("ignore_index")?, |v| v.parse::<usize>().ok())?;
+            Ok(Box::new(CrossEntropyOp {
+                label_smoothing: attrs.parse("label_smoothing")?,
+                ignore_index,
+                reduction: attrs.parse("reduction")?,
+            }))
+        });
+        registry.register("L1LossOp", |attrs| {
+            Ok(Box::new(L1LossOp {
+                reduction: attrs.parse("reduction")?,
+            }))
+        });
+        registry.register("HuberLossOp", |attrs| {
+            Ok(Box::new(HuberLossOp {
+                delta: attrs.parse("delta")?,
+                reduction: attrs.parse("reduction")?,
+            }))
+        });
+        registry.register("LogCoshLossOp", |attrs| {
+            Ok(Box::new(LogCoshLossOp {
+                reduction: attrs.parse("reduction")?,
+            }))
+        });
+        registry.register("QuantileLossOp", |attrs| {
+            Ok(Box::new(QuantileLossOp {
+                reduction: attrs.parse("reduction")?,
+            }))
+        });
+        registry.register("BCEWithLogitsOp", |attrs| {
+            Ok(Box::new(BCEWithLogitsOp {
+                reduction: attrs.parse("reduction")?,
+                inputs: binary_inputs(attrs)?,
+            }))
+        });
+        registry.register("FocalLossOp", |attrs| {
+            let alpha = parse_option(attrs.get("alpha")?, |v| v.parse::<f32>().ok())?;
+            Ok(Box::new(FocalLossOp {
+                gamma: attrs.parse("gamma")?,
+                alpha,
+                reduction: attrs.parse("reduction")?,
+                inputs: binary_inputs(attrs)?,
+            }))
+        });
+        registry.register("ScaledDotProductAttentionOp", |attrs| {
+            Ok(Box::new(ScaledDotProductAttentionOp {
+                causal: attrs.parse("causal")?,
+            }))
+        });
+        registry.register("SinusoidalEncodingOp", |attrs| {
+            Ok(Box::new(SinusoidalEncodingOp {
+                dim: attrs.parse("dim")?,
+                max_period: attrs.parse("max_period")?,
+            }))
+        });
+        registry.register_unit("AddPositionsOp", AddPositionsOp);
+        registry.register("FusedLinearOp", |attrs| {
+            let activation = parse_option(attrs.get("activation")?, |v| match v {
+                "Relu" => Some(Activation::Relu),
+                _ => None,
+            })?;
+            Ok(Box::new(FusedLinearOp { activation }))
+        });
+        registry.register("FusedElementwiseOp", |attrs| {
+            let steps = attrs
+                .get("steps")?
+                .split(',')
+                .map(str::parse)
+                .collect::<Result<_, _>>()?;
+            Ok(Box::new(FusedElementwiseOp { steps }))
+        });
+        registry.register("ZeroStateOp", |attrs| {
+            Ok(Box::new(ZeroStateOp {
+                rows: attrs.parse("rows")?,
+                size: attrs.parse("size")?,
+            }))
+        });
+        registry
+    }
+
+    /// Register a constructor for ops saved under `name`, replacing any previous one.
+    pub fn register<F>(&mut self, name: &str, constructor: F)
+    where
+        F: Fn(&Attributes) -> Result<Box<dyn Op>, ComputeError> + Send + Sync + 'static,
+    {
+        self.constructors
+            .insert(name.to_string(), Box::new(constructor));
+    }
+
+    /// Register an attribute-less op that can be copied.
+    pub fn register_unit<O: Op + Copy + 'static>(&mut self, name: &str, op: O) {
+        self.register(name, move |_| Ok(Box::new(op)));
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.constructors.contains_key(name)
+    }
+
+    pub fn construct(&self, name: &str, attrs: &Attributes) -> Result<Box<dyn Op>, ComputeError> {
+        let constructor =
+            self.constructors
+                .get(name)
+                .ok_or_else(|| ComputeError::InvalidOperation {
+                    message: format!("no constructor registered for op {name}"),
+                })?;
+        constructor(attrs)
+    }
+}
+
+impl Default for OpRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The optional-input flags written by `BinaryInputs`.
+fn binary_inputs(attrs: &Attributes) -> Result<BinaryInputs, ComputeError> {
+    Ok(BinaryInputs {
+        pos_weight: attrs.parse("pos_weight")?,
+        weights: attrs.parse("weights")?,
+        mask: attrs.parse("mask")?,
+    })
+}
+
+/// Parse `None` / `Some(x)` as written by `{:?}`.
+fn parse_option<T>(
+    value: &str,
+    parse: impl Fn(&str) -> Option<T>,
+) -> Result<Option<T>, ComputeError> {
+    if value == "None" {
+        return Ok(None);
+    }
+    value
+        .strip_prefix("Some(")
+        .and_then(|v| v.strip_suffix(')'))
+        .and_then(parse)
+        .map(Some)
+        .ok_or_else(|| ComputeError::Parse {
+            context: "attribute value".to_string(),
+            message: format!("cannot parse {value}"),
+        })
+}
+
+/// Escape whitespace and `%` so a value fits in one space-separated field.
+fn escape(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '%' => out.push_str("%25"),
+            ' ' => out.push_str("%20"),
+            '\t' => out.push_str("%09"),
+            '\n' => out.push_str("%0A"),
+            '\r' => out.push_str("%0D"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+fn unescape(value: &str) -> Option<String> {
+    let mut bytes = Vec::with_capacity(value.len());
+    let mut rest = value.as_bytes();
+    while let Some((&b, tail)) = rest.split_first() {
+        if b == b'%' {
+            let hex = std::str::from_utf8(tail.get(..2)?).ok()?;
+            bytes.push(u8::from_str_radix(hex, 16).ok()?);
+            rest = &tail[2..];
+        } else {
+            bytes.push(b);
+            rest = tail;
+        }
+    }
+    String::from_utf8(bytes).ok()
+}
+
+fn format_list(values: &[usize]) -> String {
+    let parts: Vec<String> = values.iter().map(|v| v.to_string()).collect();
+    parts.join(",")
+}
+
+/// Write `graph` in the text format.
+pub fn save<W: Write>(graph: &Graph, writer: W) -> Result<(), ComputeError> {
+    let mut out = BufWriter::new(writer);
+    let io = |e| ComputeError::io("writing graph", e);
+    writeln!(out, "{MAGIC} {FORMAT_VERSION}").map_err(io)?;
+    writeln!(out, "nodes {}", graph.len()).map_err(io)?;
+    if graph.numeric_policy() != NumericPolicy::default() {
+        writeln!(out, "policy {}", graph.numeric_policy()).map_err(io)?;
+    }
+    if graph.detect_anomalies() {
+        writeln!(out, "detect_anomalies").map_err(io)?;
+    }
+    for (idx, node) in graph.nodes.iter().enumerate() {
+        let line = match node {
+            Node::Input(t) => format!("input [{}]", format_list(t.shape())),
+            Node::Constant(t) => {
+                let mut line = format!("const [{}]", format_list(t.shape()));
+                for v in t.data() {
+                    line.push(' ');
+                    line.push_str(&v.to_string());
+                }
+                line
+            }
+            Node::Parameter(t, requires_grad) => {
+                let mut line = format!(
+                    "param {} [{}]",
+                    if *requires_grad {
+                        "trainable"
+                    } else {
+                        "frozen"
+                    },
+                    format_list(t.shape())
+                );
+                for v in t.data() {
+                    line.push(' ');
+                    line.push_str(&v.to_string());
+                }
+                line
+            }
+            Node::Operation(op, inputs) => {
+                let attrs = op
+                    .attributes()
+                    .ok_or_else(|| ComputeError::InvalidOperation {
+                        message: format!(
+                            "node {idx} ({}) is opaque and cannot be saved",
+                            op.name()
+                        ),
+                    })?;
+                let inputs = if inputs.is_empty() {
+                    "-".to_string()
+                } else {
+                    format_list(inputs)
+                };
+                let mut line = format!("op {} {inputs}", escape(op.name()));
+                for (key, value) in attrs {
+                    line.push_str(&format!(" {key}={}", escape(&value)));
+                }
+                line
+            }
+        };
+        writeln!(out, "{line}").map_err(io)?;
+    }
+    let mut labels: Vec<(&usize, &String)> = graph.labels.iter().collect();
+    labels.sort();
+    for (idx, label) in labels {
+        writeln!(out, "label {idx} {}", escape(label)).map_err(io)?;
+    }
+    for range in &graph.checkpoints {
+        writeln!(out, "checkpoint {} {}", range.start, range.end).map_err(io)?;
+    }
+    for (buffer, value) in &graph.buffer_updates {
+        writeln!(out, "buffer {buffer} {value}").map_err(io)?;
+    }
+    for idx in &graph.seed_nodes {
+        writeln!(out, "seed {idx}").map_err(io)?;
+    }
+    if !graph.seed_nodes.is_empty() {
+        writeln!(out, "rng {}", graph.rng.state()).map_err(io)?;
+    }
+    out.flush().map_err(io)
+}
+
+pub fn save_to_string(graph: &Graph) -> Result<String, ComputeError> {
+    let mut buffer = Vec::new();
+    save(graph, &mut buffer)?;
+    Ok(String::from_utf8(buffer).expect("the format is ASCII apart from escaped text"))
+}
+
+pub fn save_to_path(graph: &Graph, path: &Path) -> Result<(), ComputeError> {
+    let file = File::create(path)
+        .map_err(|e| ComputeError::io(format!("creating graph file {}", path.display()), e))?;
+    save(graph, file)
+}
+
+/// Read a graph written by `save`, rebuilding ops through `registry`.
+pub fn load<R: BufRead>(reader: R, registry: &OpRegistry) -> Result<Graph, ComputeError> {
+    let mut lines = reader.lines().enumerate();
+    let mut next_line = || -> Result<Option<(usize, String)>, ComputeError> {
+        match lines.next() {
+            Some((n, Ok(line))) => Ok(Some((n + 1, line))),
+            Some((n, Err(e))) => Err(ComputeError::io(format!("reading graph line {}", n + 1), e)),
+            None => Ok(None),
+        }
+    };
+    let parse_err = |line: usize, message: String| ComputeError::Parse {
+        context: format!("graph line {line}"),
+        message,
+    };
+
+    let (n, header) = next_line()?.ok_or_else(|| parse_err(1, "empty input".to_string()))?;
+    let version = header
+        .strip_prefix(MAGIC)
+        .map(str::trim)
+        .ok_or_else(|| parse_err(n, format!("expected '{MAGIC} <version>' header")))?;
+    if !version
+        .parse::<u32>()
+        .is_ok_and(|v| (1..=FORMAT_VERSION).contains(&v))
+    {
+        return Err(parse_err(
+            n,
+            format!("unsupported format version {version}"),
+        ));
+    }
+    let (n, count_line) =
+        next_line()?.ok_or_else(|| parse_err(2, "missing node count".to_string()))?;
+    let count: usize = count_line
+        .strip_prefix("nodes ")
+        .and_then(|c| c.trim().parse().ok())
+        .ok_or_else(|| parse_err(n, "expected 'nodes <count>'".to_string()))?;
+
+    let mut graph = Graph::new();
+    while let Some((n, line)) = next_line()? {
+        let mut fields = line.split_whitespace();
+        let kind = match fields.next() {
+            Some(kind) => kind,
+            None => continue,
+        };
+        let fields: Vec<&str> = fields.collect();
+        match kind {
+            "policy" => {
+                let policy = fields
+                    .first()
+                    .ok_or_else(|| parse_err(n, "missing numeric policy".to_string()))?
+                    .parse()
+                    .map_err(|e: ComputeError| parse_err(n, e.to_string()))?;
+                graph.set_numeric_policy(policy);
+            }
+            "detect_anomalies" => graph.set_detect_anomalies(true),
+            "input" => {
+                let shape = parse_shape(fields.first().copied()).map_err(|m| parse_err(n, m))?;
+                let tensor = if shape.is_empty() {
+                    Tensor::placeholder()
+                } else {
+                    Tensor::zeros(shape).map_err(|e| parse_err(n, e.to_string()))?
+                };
+                graph.add_input(tensor);
+            }
+            "const" => {
+                let shape = parse_shape(fields.first().copied()).map_err(|m| parse_err(n, m))?;
+                let data = fields[1..]
+                    .iter()
+                    .map(|v| v.parse::<f32>())
+                    .collect::<Result<Vec<_>, _>>()
+                    .map_err(|e| parse_err(n, format!("bad constant value: {e}")))?;
+                let tensor = Tensor::new(data, shape).map_err(|e| parse_err(n, e.to_string()))?;
+                graph.add_constant(tensor);
+            }
+            "param" => {
+                let requires_grad = match fields.first().copied() {
+                    Some("trainable") => true,
+                    Some("frozen") => false,
+                    other => return Err(parse_err(n, format!("bad parameter mode {other:?}"))),
+                };
+                let shape = parse_shape(fields.get(1).copied()).map_err(|m| parse_err(n, m))?;
+                let data = fields[2..]
+                    .iter()
+                    .map(|v| v.parse::<f32>())
+                    .collect::<Result<Vec<_>, _>>()
+                    .map_err(|e| parse_err(n, format!("bad parameter value: {e}")))?;
+                let tensor = Tensor::new(data, shape).map_err(|e| parse_err(n, e.to_string()))?;
+                graph.add_parameter(tensor, requires_grad);
+            }
+            "op" => {
+                let name = fields
+                    .first()
+                    .and_then(|f| unescape(f))
+                    .ok_or_else(|| parse_err(n, "missing op name".to_string()))?;
+                let inputs = match fields.get(1).copied() {
+                    Some("-") => Vec::new(),
+                    Some(list) => list
+                        .split(',')
+                        .map(|i| i.parse::<usize>())
+                        .collect::<Result<Vec<_>, _>>()
+                        .map_err(|e| parse_err(n, format!("bad input index: {e}")))?,
+                    None => return Err(parse_err(n, "missing op inputs".to_string())),
+                };
+                if let Some(&bad) = inputs.iter().find(|&&i| i >= graph.len()) {
+                    return Err(parse_err(n, format!("input {bad} is not an earlier node")));
+                }
+                let mut attrs = Vec::new();
+                for field in &fields[2..] {
+                    let (key, value) = field
+                        .split_once('=')
+                        .and_then(|(k, v)| Some((k.to_string(), unescape(v)?)))
+                        .ok_or_else(|| parse_err(n, format!("bad attribute {field}")))?;
+                    attrs.push((key, value));
+                }
+                let op = registry
+                    .construct(&name, &Attributes(attrs))
+                    .map_err(|e| parse_err(n, e.to_string()))?;
+                let idx = graph.len();
+                graph.nodes.push(Node::Operation(op, inputs));
+                debug_assert_eq!(idx + 1, graph.len());
+            }
+            "label" => {
+                let idx = node_ref(&graph, fields.first(), "label").map_err(|m| parse_err(n, m))?;
+                let label = fields
+                    .get(1)
+                    .and_then(|l| unescape(l))
+                    .ok_or_else(|| parse_err(n, "bad label text".to_string()))?;
+                graph.set_label(idx, &label);
+            }
+            "checkpoint" => {
+                let start: usize = fields
+                    .first()
+                    .and_then(|i| i.parse().ok())
+                    .ok_or_else(|| parse_err(n, "bad checkpoint start".to_string()))?;
+                let end: usize = fields
+                    .get(1)
+                    .and_then(|i| i.parse().ok())
+                    .filter(|&end| start < end && end <= graph.len())
+                    .ok_or_else(|| parse_err(n, "bad checkpoint end".to_string()))?;
+                graph.checkpoint(start..end);
+            }
+            "buffer" => {
+                let buffer =
+                    parameter_ref(&graph, fields.first(), "buffer").map_err(|m| parse_err(n, m))?;
+                let value =
+                    node_ref(&graph, fields.get(1), "buffer value").map_err(|m| parse_err(n, m))?;
+                graph.add_buffer_update(buffer, value);
+            }
+            "seed" => {
+                let idx =
+                    parameter_ref(&graph, fields.first(), "seed").map_err(|m| parse_err(n, m))?;
+                graph.seed_nodes.push(idx);
+            }
+            "rng" => {
+                let state: u32 = fields
+                    .first()
+                    .and_then(|s| s.parse().ok())
+                    .ok_or_else(|| parse_err(n, "bad generator state".to_string()))?;
+                graph.rng = XorShift32::new(state);
+            }
+            other => return Err(parse_err(n, format!("unknown record {other}"))),
+        }
+    }
+
+    if graph.len() != count {
+        return Err(ComputeError::Parse {
+            context: "graph".to_string(),
+            message: format!("expected {count} nodes, found {}", graph.len()),
+        });
+    }
+    Ok(graph)
+}
+
+pub fn load_from_str(text: &str, registry: &OpRegistry) -> Result<Graph, ComputeError> {
+    load(text.as_bytes(), registry)
+}
+
+pub fn load_from_path(path: &Path, registry: &OpRegistry) -> Result<Graph, ComputeError> {
+    let file = File::open(path)
+        .map_err(|e| ComputeError::io(format!("opening graph file {}", path.display()), e))?;
+    load(BufReader::new(file), registry)
+}
+
+/// A node index that refers to an already loaded node.
+fn node_ref(graph: &Graph, field: Option<&&str>, what: &str) -> Result<usize, String> {
+    let idx: usize = field
+        .and_then(|i| i.parse().ok())
+        .ok_or_else(|| format!("bad {what} node {field:?}"))?;
+    if idx >= graph.len() {
+        return Err(format!(
+            "{what} node {idx} is out of range for {} nodes",
+            graph.len()
+        ));
+    }
+    Ok(idx)
+}
+
+/// Like `node_ref`, but the node must be a parameter.
+fn parameter_ref(graph: &Graph, field: Option<&&str>, what: &str) -> Result<usize, String> {
+    let idx = node_ref(graph, field, what)?;
+    match graph.nodes[idx] {
+        Node::Parameter(_, _) => Ok(idx),
+        _ => Err(format!("{what} node {idx} is not a parameter")),
+    }
+}
+
+fn parse_shape(field: Option<&str>) -> Result<Vec<usize>, String> {
+    let inner = field
+        .and_then(|f| f.strip_prefix('['))
+        .and_then(|f| f.strip_suffix(']'))
+        .ok_or_else(|| format!("expected a shape like [2,3], got {field:?}"))?;
+    if inner.is_empty() {
+        return Ok(Vec::new());
+    }
+    inner
+        .split(',')
+        .map(|d| {
+            d.parse::<usize>()
+                .map_err(|e| format!("bad dimension {d}: {e}"))
+        })
+        .collect()
+}
@@ -0,0 +1,135 @@
+//! Static shapes with optional symbolic dimensions, used by `Op::infer_shape` and
+//! `Graph::check`.
+
+use core::fmt;
+
+use crate::error::ComputeError;
+use crate::tensor::Tensor;
+
+/// One dimension of a statically inferred shape.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Dim {
+    Fixed(usize),
+    /// A named size only known at run time, e.g. `"batch"`.
+    Symbol(String),
+}
+
+impl Dim {
+    pub fn symbol(name: &str) -> Self {
+        Dim::Symbol(name.to_string())
+    }
+
+    pub fn fixed(&self) -> Option<usize> {
+        match self {
+            Dim::Fixed(n) => Some(*n),
+            Dim::Symbol(_) => None,
+        }
+    }
+}
+
+impl fmt::Display for Dim {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Dim::Fixed(n) => write!(f, "{n}"),
+            Dim::Symbol(name) => write!(f, "{name}"),
+        }
+    }
+}
+
+/// Lift a concrete shape into a static one.
+pub fn fixed(shape: &[usize]) -> Vec<Dim> {
+    shape.iter().map(|&n| Dim::Fixed(n)).collect()
+}
+
+/// The concrete shape, if no dimension is symbolic.
+pub fn concrete(shape: &[Dim]) -> Option<Vec<usize>> {
+    shape.iter().map(Dim::fixed).collect()
+}
+
+/// Render as `[batch, 3]`.
+pub fn format_shape(shape: &[Dim]) -> String {
+    let dims: Vec<String> = shape.iter().map(|d| d.to_string()).collect();
+    format!("[{}]", dims.join(", "))
+}
+
+/// NumPy-style broadcast of two static shapes.
+///
+/// A symbol broadcasts against `1`, itself, or a fixed size (which it must then equal at run
+/// time). Two different symbols are rejected since their relation is unknown.
+pub fn broadcast(a: &[Dim], b: &[Dim]) -> Result<Vec<Dim>, ComputeError> {
+    let rank = a.len().max(b.len());
+    let one = Dim::Fixed(1);
+    let mut out = Vec::with_capacity(rank);
+    for i in 0..rank {
+        let a_i = if i >= rank - a.len() {
+            &a[i - (rank - a.len())]
+        } else {
+            &one
+        };
+        let b_i = if i >= rank - b.len() {
+            &b[i - (rank - b.len())]
+        } else {
+            &one
+        };
+        let dim = match (a_i, b_i) {
+            (x, y) if x == y => x.clone(),
+            (Dim::Fixed(1), other) | (other, Dim::Fixed(1)) => other.clone(),
+            (Dim::Fixed(n), Dim::Symbol(_)) | (Dim::Symbol(_), Dim::Fixed(n)) => Dim::Fixed(*n),
+            (Dim::Fixed(x), Dim::Fixed(y)) => {
+                return Err(ComputeError::BroadcastError {
+                    dim: i,
+                    shape1: *x,
+                    shape2: *y,
+                })
+            }
+            (x, y) => {
+                return Err(ComputeError::DimensionError {
+                    message: format!("cannot broadcast symbolic dims {x} and {y}"),
+                })
+            }
+        };
+        out.push(dim);
+    }
+    Ok(out)
+}
+
+/// Check that two dims can be equal, returning the more specific one.
+pub fn unify(a: &Dim, b: &Dim) -> Result<Dim, ComputeError> {
+    match (a, b) {
+        (x, y) if x == y => Ok(x.clone()),
+        (Dim::Fixed(n), Dim::Symbol(_)) | (Dim::Symbol(_), Dim::Fixed(n)) => Ok(Dim::Fixed(*n)),
+        (x, y) => Err(ComputeError::DimensionError {
+            message: format!("dimension mismatch: {x} vs {y}"),
+        }),
+    }
+}
+
+pub(crate) fn expect_inputs(inputs: &[Vec<Dim>], expected: usize) -> Result<(), ComputeError> {
+    if inputs.len() != expected {
+        return Err(ComputeError::InputCountError {
+            expected,
+            got: inputs.len(),
+        });
+    }
+    Ok(())
+}
+
+/// Infer an output shape by running `forward` on zero tensors.
+///
+/// This is the fallback for ops without a static rule; it only works for fixed shapes.
+pub(crate) fn probe<F>(forward: F, inputs: &[Vec<Dim>]) -> Result<Vec<Dim>, ComputeError>
+where
+    F: Fn(&[Tensor]) -> Result<Tensor, ComputeError>,
+{
+    let mut tensors = Vec::with_capacity(inputs.len());
+    for shape in inputs {
+        let dims = concrete(shape).ok_or_else(|| ComputeError::DimensionError {
+            message: format!(
+                "op has no static shape rule and input {} is symbolic",
+                format_shape(shape)
+            ),
+        })?;
+        tensors.push(Tensor::zeros(dims)?);
+    }
+    Ok(fixed(forward(&tensors)?.shape()))
+}
@@ -0,0 +1,432 @@
+//! Ops that slice, join and reorder tensors along one axis without arithmetic.
+//!
+//! Each views its input as `[outer, axis, inner]` blocks; the backward passes route
+//! gradients back to the elements they came from.
+
+use crate::error::ComputeError;
+use crate::ops::Op;
+use crate::shape::{self, Dim};
+use crate::tensor::Tensor;
+
+/// `(outer, size, inner)` for `axis` of `shape`.
+fn split_axis(shape: &[usize], axis: usize) -> Result<(usize, usize, usize), ComputeError> {
+    if axis >= shape.len() {
+        return Err(ComputeError::DimensionError {
+            message: format!("invalid axis {axis} for rank {}", shape.len()),
+        });
+    }
+    Ok((
+        shape[..axis].iter().product(),
+        shape[axis],
+        shape[axis + 1..].iter().product(),
+    ))
+}
+
+fn expect_one(inputs: &[Tensor]) -> Result<&Tensor, ComputeError> {
+    match inputs {
+        [x] => Ok(x),
+        _ => Err(ComputeError::InputCountError {
+            expected: 1,
+            got: inputs.len(),
+        }),
+    }
+}
+
+fn expect_static_axis(shape: &[Dim], axis: usize) -> Result<(), ComputeError> {
+    if axis >= shape.len() {
+        return Err(ComputeError::DimensionError {
+            message: format!("invalid axis {axis} for {}", shape::format_shape(shape)),
+        });
+    }
+    Ok(())
+}
+
+/// `len` consecutive slices of `axis` starting at `start`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct NarrowOp {
+    pub axis: usize,
+    pub start: usize,
+    pub len: usize,
+}
+
+impl NarrowOp {
+    fn check(&self, size: usize) -> Result<(), ComputeError> {
+        if self.start + self.len > size || self.len == 0 {
+            return Err(ComputeError::IndexError {
+                message: format!(
+                    "narrow {}..{} out of bounds for axis {} of size {size}",
+                    self.start,
+                    self.start + self.len,
+                    self.axis
+                ),
+            });
+        }
+        Ok(())
+    }
+}
+
+impl Op for NarrowOp {
+    fn forward(&self, inputs: &[Tensor]) -> Result<Tensor, ComputeError> {
+        let x = expect_one(inputs)?;
+        let (outer, size, inner) = split_axis(x.shape(), self.axis)?;
+        self.check(size)?;
+        let mut data = Vec::with_capacity(outer * self.len * inner);
+        for o in 0..outer {
+            let from = (o * size + self.start) * inner;
+            data.extend_from_slice(&x.data()[from..from + self.len * inner]);
+        }
+        let mut shape = x.shape().to_vec();
+        shape[self.axis] = self.len;
+        Tensor::new(data, shape)
+    }
+
+    fn backward(
+        &self,
+        inputs: &[Tensor],
+        grad_output: &Tensor,
+    ) -> Result<Vec<Tensor>, ComputeError> {
+        let x = expect_one(inputs)?;
+        let (outer, size, inner) = split_axis(x.shape(), self.axis)?;
+        self.check(size)?;
+        let mut grad = Tensor::zeros_like(x)?;
+        let block = self.len * inner;
+        for o in 0..outer {
+            let to = (o * size + self.start) * inner;
+            grad.data_mut()[to..to + block]
+                .copy_from_slice(&grad_output.data()[o * block..(o + 1) * block]);
+        }
+        Ok(vec![grad])
+    }
+
+    fn infer_shape(&self, inputs: &[Vec<Dim>]) -> Result<Vec<Dim>, ComputeError> {
+        shape::expect_inputs(inputs, 1)?;
+        expect_static_axis(&inputs[0], self.axis)?;
+        if let Dim::Fixed(size) = inputs[0][self.axis] {
+            self.check(size)?;
+        }
+        let mut out = inputs[0].clone();
+        out[self.axis] = Dim::Fixed(self.len);
+        Ok(out)
+    }
+
+    fn name(&self) -> &str {
+        "NarrowOp"
+    }
+
+    fn attributes(&self) -> Option<Vec<(&'static str, String)>> {
+        Some(vec![
+            ("axis", self.axis.to_string()),
+            ("start", self.start.to_string()),
+            ("len", self.len.to_string()),
+        ])
+    }
+}
+
+/// Join inputs along an existing `axis`; all other dimensions must match.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ConcatOp {
+    pub axis: usize,
+}
+
+impl ConcatOp {
+    /// `(outer, inner, sizes)` after checking that the inputs agree off `axis`.
+    fn layout(&self, inputs: &[Tensor]) -> Result<(usize, usize, Vec<usize>), ComputeError> {
+        let first = inputs.first().ok_or(ComputeError::InputCountError {
+            expected: 1,
+            got: 0,
+        })?;
+        let (outer, _, inner) = split_axis(first.shape(), self.axis)?;
+        let mut sizes = Vec::with_capacity(inputs.len());
+        for t in inputs {
+            let mut a = t.shape().to_vec();
+            let mut b = first.shape().to_vec();
+            if a.len() != b.len() || self.axis >= a.len() {
+                return Err(ComputeError::DimensionError {
+                    message: format!(
+                        "cannot concatenate shapes {:?} and {:?}",
+                        first.shape(),
+                        t.shape()
+                    ),
+                });
+            }
+            sizes.push(a[self.axis]);
+            a[self.axis] = 0;
+            b[self.axis] = 0;
+            if a != b {
+                return Err(ComputeError::DimensionError {
+                    message: format!(
+                        "cannot concatenate shapes {:?} and {:?} along axis {}",
+                        first.shape(),
+                        t.shape(),
+                        self.axis
+                    ),
+                });
+            }
+        }
+        Ok((outer, inner, sizes))
+    }
+}
+
+impl Op for ConcatOp {
+    fn forward(&self, inputs: &[Tensor]) -> Result<Tensor, ComputeError> {
+        let (outer, inner, sizes) = self.layout(inputs)?;
+        let total: usize = sizes.iter().sum();
+        let mut data = Vec::with_capacity(outer * total * inner);
+        for o in 0..outer {
+            for (t, size) in inputs.iter().zip(&sizes) {
+                let block = size * inner;
+                data.extend_from_slice(&t.data()[o * block..(o + 1) * block]);
+            }
+        }
+        let mut shape = inputs[0].shape().to_vec();
+        shape[self.axis] = total;
+        Tensor::new(data, shape)
+    }
+
+    fn backward(
+        &self,
+        inputs: &[Tensor],
+        grad_output: &Tensor,
+    ) -> Result<Vec<Tensor>, ComputeError> {
+        let (outer, inner, sizes) = self.layout(inputs)?;
+        let total: usize = sizes.iter().sum();
+        let mut grads = inputs
+            .iter()
+            .map(Tensor::zeros_like)
+            .collect::<Result<Vec<_>, _>>()?;
+        for o in 0..outer {
+            let mut from = o * total * inner;
+            for (grad, size) in grads.iter_mut().zip(&sizes) {
+                let block = size * inner;
+                grad.data_mut()[o * block..(o + 1) * block]
+                    .copy_from_slice(&grad_output.data()[from..from + block]);
+                from += block;
+            }
+        }
+        Ok(grads)
+    }
+
+    fn infer_shape(&self, inputs: &[Vec<Dim>]) -> Result<Vec<Dim>, ComputeError> {
+        let first = inputs.first().ok_or(ComputeError::InputCountError {
+            expected: 1,
+            got: 0,
+        })?;
+        expect_static_axis(first, self.axis)?;
+        let mut out = first.clone();
+        let mut total = 0;
+        for shape in inputs {
+            if shape.len() != first.len() {
+                return Err(ComputeError::DimensionError {
+                    message: format!(
+                        "cannot concatenate {} and {}",
+                        shape::format_shape(first),
+                        shape::format_shape(shape)
+                    ),
+                });
+            }
+            for (axis, dim) in shape.iter().enumerate() {
+                if axis == self.axis {
+                    total += dim.fixed().ok_or_else(|| ComputeError::DimensionError {
+                        message: format!(
+                            "concatenation axis must be fixed, got {}",
+                            shape::format_shape(shape)
+                        ),
+                    })?;
+                } else {
+                    out[axis] = shape::unify(&out[axis], dim)?;
+                }
+            }
+        }
+        out[self.axis] = Dim::Fixed(total);
+        Ok(out)
+    }
+
+    fn name(&self) -> &str {
+        "ConcatOp"
+    }
+
+    fn attributes(&self) -> Option<Vec<(&'static str, String)>> {
+        Some(vec![("axis", self.axis.to_string())])
+    }
+}
+
+/// The slice at `index` along `axis`, with that axis removed (see `Tensor::select`).
+///
+/// A negative `index` counts from the end, so `-1` is the last slice; this lets graphs pick
+/// the final step of sequences whose length is only known at run time.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SelectOp {
+    pub axis: usize,
+    pub index: isize,
+}
+
+impl SelectOp {
+    fn resolve(&self, size: usize) -> Result<usize, ComputeError> {
+        let index = if self.index < 0 {
+            size.checked_sub(self.index.unsigned_abs())
+        } else {
+            Some(self.index as usize).filter(|&i| i < size)
+        };
+        index.ok_or_else(|| ComputeError::IndexError {
+            message: format!(
+                "index {} out of bounds for axis {} of size {size}",
+                self.index, self.axis
+            ),
+        })
+    }
+}
+
+impl Op for SelectOp {
+    fn forward(&self, inputs: &[Tensor]) -> Result<Tensor, ComputeError> {
+        let x = expect_one(inputs)?;
+        let (_, size, _) = split_axis(x.shape(), self.axis)?;
+        x.select(self.axis, self.resolve(size)?)
+    }
+
+    fn backward(
+        &self,
+        inputs: &[Tensor],
+        grad_output: &Tensor,
+    ) -> Result<Vec<Tensor>, ComputeError> {
+        let x = expect_one(inputs)?;
+        let (outer, size, inner) = split_axis(x.shape(), self.axis)?;
+        let index = self.resolve(size)?;
+        let mut grad = Tensor::zeros_like(x)?;
+        for o in 0..outer {
+            let to = (o * size + index) * inner;
+            grad.data_mut()[to..to + inner]
+                .copy_from_slice(&grad_output.data()[o * inner..(o + 1) * inner]);
+        }
+        Ok(vec![grad])
+    }
+
+    fn infer_shape(&self, inputs: &[Vec<Dim>]) -> Result<Vec<Dim>, ComputeError> {
+        shape::expect_inputs(inputs, 1)?;
+        expect_static_axis(&inputs[0], self.axis)?;
+        if let Dim::Fixed(size) = inputs[0][self.axis] {
+            self.resolve(size)?;
+        }
+        let mut out = inputs[0].clone();
+        out.remove(self.axis);
+        Ok(out)
+    }
+
+    fn name(&self) -> &str {
+        "SelectOp"
+    }
+
+    fn attributes(&self) -> Option<Vec<(&'static str, String)>> {
+        Some(vec![
+            ("axis", self.axis.to_string()),
+            ("index", self.index.to_string()),
+        ])
+    }
+}
+
+/// Stack equally shaped inputs along a new `axis` (see `Tensor::stack`).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct StackOp {
+    pub axis: usize,
+}
+
+impl Op for StackOp {
+    fn forward(&self, inputs: &[Tensor]) -> Result<Tensor, ComputeError> {
+        Tensor::stack(inputs, self.axis)
+    }
+
+    fn backward(
+        &self,
+        inputs: &[Tensor],
+        grad_output: &Tensor,
+    ) -> Result<Vec<Tensor>, ComputeError> {
+        (0..inputs.len())
+            .map(|i| grad_output.select(self.axis, i))
+            .collect()
+    }
+
+    fn infer_shape(&self, inputs: &[Vec<Dim>]) -> Result<Vec<Dim>, ComputeError> {
+        let first = inputs.first().ok_or(ComputeError::InputCountError {
+            expected: 1,
+            got: 0,
+        })?;
+        let mut out = first.clone();
+        for shape in &inputs[1..] {
+            if shape.len() != out.len() {
+                return Err(ComputeError::DimensionError {
+                    message: format!(
+                        "cannot stack {} and {}",
+                        shape::format_shape(first),
+                        shape::format_shape(shape)
+                    ),
+                });
+            }
+            for (dim, other) in out.iter_mut().zip(shape) {
+                *dim = shape::unify(dim, other)?;
+            }
+        }
+        if self.axis > out.len() {
+            return Err(ComputeError::DimensionError {
+                message: format!("invalid stack axis {} for rank {}", self.axis, out.len()),
+            });
+        }
+        out.insert(self.axis, Dim::Fixed(inputs.len()));
+        Ok(out)
+    }
+
+    fn name(&self) -> &str {
+        "StackOp"
+    }
+
+    fn attributes(&self) -> Option<Vec<(&'static str, String)>> {
+        Some(vec![("axis", self.axis.to_string())])
+    }
+}
+
+/// Reverse the order of the slices along `axis`, e.g. time for a backward RNN pass.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FlipOp {
+    pub axis: usize,
+}
+
+impl FlipOp {
+    fn flip(&self, x: &Tensor) -> Result<Tensor, ComputeError> {
+        let (outer, size, inner) = split_axis(x.shape(), self.axis)?;
+        let mut data = Vec::with_capacity(x.data().len());
+        for o in 0..outer {
+            for i in (0..size).rev() {
+                let from = (o * size + i) * inner;
+                data.extend_from_slice(&x.data()[from..from + inner]);
+            }
+        }
+        Tensor::new(data, x.shape().to_vec())
+    }
+}
+
+impl Op for FlipOp {
+    fn forward(&self, inputs: &[Tensor]) -> Result<Tensor, ComputeError> {
+        self.flip(expect_one(inputs)?)
+    }
+
+    fn backward(
+        &self,
+        inputs: &[Tensor],
+        grad_output: &Tensor,
+    ) -> Result<Vec<Tensor>, ComputeError> {
+        expect_one(inputs)?;
+        Ok(vec![self.flip(grad_output)?])
+    }
+
+    fn infer_shape(&self, inputs: &[Vec<Dim>]) -> Result<Vec<Dim>, ComputeError> {
+        shape::expect_inputs(inputs, 1)?;
+        expect_static_axis(&inputs[0], self.axis)?;
+        Ok(inputs[0].clone())
+    }
+
+    fn name(&self) -> &str {
+        "FlipOp"
+    }
+
+    fn attributes(&self) -> Option<Vec<(&'static str, String)>> {
+        Some(vec![("axis", self.axis.to_string())])
+    }
+}
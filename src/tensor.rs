@@ -1,4 +1,6 @@
 use crate::error::ComputeError;
+use crate::init::Init;
+use crate::numeric::{self, NumericPolicy};
 use crate::prng::XorShift32;
 
 #[derive(Clone, Debug, PartialEq)]
@@ -69,6 +71,11 @@ impl Tensor {
         Self::new(data, shape)
     }
 
+    /// A tensor of `shape` drawn from `init` with a generator seeded by `seed`.
+    pub fn from_init(shape: Vec<usize>, init: &Init, seed: u32) -> Result<Self, ComputeError> {
+        init.tensor(shape, seed)
+    }
+
     pub fn shape(&self) -> &[usize] {
         &self.shape
     }
@@ -93,8 +100,35 @@ impl Tensor {
         self.elementwise_op(other, |a, b| a * b)
     }
 
+    /// Elementwise division under `NumericPolicy::Ieee`.
     pub fn divide(&self, other: &Tensor) -> Result<Tensor, ComputeError> {
-        self.elementwise_op(other, |a, b| if b != 0.0 { a / b } else { f32::NAN })
+        self.divide_with(other, NumericPolicy::Ieee)
+    }
+
+    pub fn divide_with(
+        &self,
+        other: &Tensor,
+        policy: NumericPolicy,
+    ) -> Result<Tensor, ComputeError> {
+        self.try_elementwise_op(other, "divide", |a, b| numeric::div(a, b, policy))
+    }
+
+    /// Elementwise natural log under `NumericPolicy::Ieee`.
+    pub fn log(&self) -> Result<Tensor, ComputeError> {
+        self.log_with(NumericPolicy::Ieee)
+    }
+
+    pub fn log_with(&self, policy: NumericPolicy) -> Result<Tensor, ComputeError> {
+        self.try_map("log", |v| numeric::ln(v, policy))
+    }
+
+    /// Elementwise square root under `NumericPolicy::Ieee`.
+    pub fn sqrt(&self) -> Result<Tensor, ComputeError> {
+        self.sqrt_with(NumericPolicy::Ieee)
+    }
+
+    pub fn sqrt_with(&self, policy: NumericPolicy) -> Result<Tensor, ComputeError> {
+        self.try_map("sqrt", |v| numeric::sqrt(v, policy))
     }
 
     pub fn matmul(&self, other: &Tensor) -> Result<Tensor, ComputeError> {
@@ -153,6 +187,16 @@ impl Tensor {
         Tensor::new(data, self.shape.clone())
     }
 
+    pub fn sigmoid(&self) -> Result<Tensor, ComputeError> {
+        let data: Vec<f32> = self.data.iter().map(|&v| sigmoid(v)).collect();
+        Tensor::new(data, self.shape.clone())
+    }
+
+    pub fn tanh(&self) -> Result<Tensor, ComputeError> {
+        let data: Vec<f32> = self.data.iter().map(|&v| v.tanh()).collect();
+        Tensor::new(data, self.shape.clone())
+    }
+
     pub fn sum(&self, dim: Option<usize>) -> Result<Tensor, ComputeError> {
         match dim {
             None => {
@@ -184,6 +228,19 @@ impl Tensor {
     fn elementwise_op<F>(&self, other: &Tensor, op: F) -> Result<Tensor, ComputeError>
     where
         F: Fn(f32, f32) -> f32,
+    {
+        self.try_elementwise_op(other, "", |a, b| Some(op(a, b)))
+    }
+
+    /// Like `elementwise_op`, failing with a singular point error where `op` returns `None`.
+    fn try_elementwise_op<F>(
+        &self,
+        other: &Tensor,
+        operation: &str,
+        op: F,
+    ) -> Result<Tensor, ComputeError>
+    where
+        F: Fn(f32, f32) -> Option<f32>,
     {
         let out_shape = Self::broadcast_shapes(&self.shape, &other.shape)?;
         let mut out = Tensor::zeros(out_shape.clone())?;
@@ -192,13 +249,102 @@ impl Tensor {
             let out_idx = Self::unravel_index_static(out_flat, &out_shape);
             let a_flat = self.broadcasted_flat_index(&out_idx, &out_shape)?;
             let b_flat = other.broadcasted_flat_index(&out_idx, &out_shape)?;
-            out.data[out_flat] = op(self.data[a_flat], other.data[b_flat]);
+            out.data[out_flat] = op(self.data[a_flat], other.data[b_flat])
+                .ok_or_else(|| numeric::singular(operation, out_flat))?;
         }
 
         Ok(out)
     }
 
-    fn broadcast_shapes(a: &[usize], b: &[usize]) -> Result<Vec<usize>, ComputeError> {
+    fn try_map<F>(&self, operation: &str, f: F) -> Result<Tensor, ComputeError>
+    where
+        F: Fn(f32) -> Option<f32>,
+    {
+        let mut out = Tensor::placeholder();
+        self.try_map_into(&mut out, operation, f)?;
+        Ok(out)
+    }
+
+    /// Sum this tensor down to `shape`, undoing NumPy-style broadcasting.
+    ///
+    /// Used to turn gradients of a broadcast result into gradients of the smaller operand.
+    pub fn sum_to_shape(&self, shape: &[usize]) -> Result<Tensor, ComputeError> {
+        if self.shape == shape {
+            return Ok(self.clone());
+        }
+        let full = Self::broadcast_shapes(&self.shape, shape)?;
+        if full != self.shape {
+            return Err(ComputeError::DimensionError {
+                message: format!("cannot reduce shape {:?} to {:?}", self.shape, shape),
+            });
+        }
+        let mut out = Tensor::zeros(shape.to_vec())?;
+        for flat in 0..self.data.len() {
+            let idx = Self::unravel_index_static(flat, &self.shape);
+            let t_flat = out.broadcasted_flat_index(&idx, &self.shape)?;
+            out.data[t_flat] += self.data[flat];
+        }
+        Ok(out)
+    }
+
+    /// The slice at `index` along `axis`, with that axis removed.
+    pub fn select(&self, axis: usize, index: usize) -> Result<Tensor, ComputeError> {
+        if axis >= self.shape.len() {
+            return Err(ComputeError::DimensionError {
+                message: format!("invalid axis {axis} for rank {}", self.shape.len()),
+            });
+        }
+        if index >= self.shape[axis] {
+            return Err(ComputeError::IndexError {
+                message: format!(
+                    "index {index} out of bounds for axis {axis} of size {}",
+                    self.shape[axis]
+                ),
+            });
+        }
+        let outer: usize = self.shape[..axis].iter().product();
+        let inner: usize = self.shape[axis + 1..].iter().product();
+        let mut data = Vec::with_capacity(outer * inner);
+        for o in 0..outer {
+            let start = (o * self.shape[axis] + index) * inner;
+            data.extend_from_slice(&self.data[start..start + inner]);
+        }
+        let mut shape = self.shape.clone();
+        shape.remove(axis);
+        Tensor::new(data, shape)
+    }
+
+    /// Stack equally shaped tensors along a new `axis`; the inverse of `select`.
+    pub fn stack(tensors: &[Tensor], axis: usize) -> Result<Tensor, ComputeError> {
+        let first = tensors
+            .first()
+            .ok_or_else(|| ComputeError::InvalidOperation {
+                message: "cannot stack an empty list of tensors".to_string(),
+            })?;
+        if axis > first.shape.len() {
+            return Err(ComputeError::DimensionError {
+                message: format!("invalid stack axis {axis} for rank {}", first.shape.len()),
+            });
+        }
+        if let Some(t) = tensors.iter().find(|t| t.shape != first.shape) {
+            return Err(ComputeError::DimensionError {
+                message: format!("cannot stack shapes {:?} and {:?}", first.shape, t.shape),
+            });
+        }
+        let outer: usize = first.shape[..axis].iter().product();
+        let inner: usize = first.shape[axis..].iter().product();
+        let mut data = Vec::with_capacity(outer * inner * tensors.len());
+        for o in 0..outer {
+            for t in tensors {
+                data.extend_from_slice(&t.data[o * inner..(o + 1) * inner]);
+            }
+        }
+        let mut shape = first.shape.clone();
+        shape.insert(axis, tensors.len());
+        Tensor::new(data, shape)
+    }
+
+    pub(crate) fn broadcast_shapes(a: &[usize], b: &[usize]) -> Result<Vec<usize>, ComputeError> {
         let max_dims = a.len().max(b.len());
         let mut out = vec![1; max_dims];
 
@@ -233,7 +379,7 @@ impl Tensor {
         Ok(out)
     }
 
-    fn broadcasted_flat_index(
+    pub(crate) fn broadcasted_flat_index(
         &self,
         out_indices: &[usize],
         out_shape: &[usize],
@@ -271,7 +417,7 @@ impl Tensor {
         Ok(flat)
     }
 
-    fn unravel_index_static(mut flat: usize, shape: &[usize]) -> Vec<usize> {
+    pub(crate) fn unravel_index_static(mut flat: usize, shape: &[usize]) -> Vec<usize> {
         let strides = Self::compute_strides(shape);
         let mut out = vec![0; shape.len()];
         for i in 0..shape.len() {
@@ -290,3 +436,259 @@ impl Tensor {
             .sum()
     }
 }
+
+/// Maximum rank supported by the allocation-free `*_into` kernels.
+pub(crate) const MAX_RANK: usize = 8;
+
+/// Allocation-free kernels used by `plan::ExecutionPlan`.
+///
+/// Each writes into a preallocated `out` tensor, reshaping it in place. As long as `out`
+/// has enough capacity for the result, no heap allocation happens.
+impl Tensor {
+    /// A buffer with room for `capacity` elements and `MAX_RANK` dimensions.
+    pub(crate) fn buffer(capacity: usize) -> Tensor {
+        let mut data = Vec::with_capacity(capacity.max(1));
+        data.push(0.0);
+        let mut shape = Vec::with_capacity(MAX_RANK);
+        shape.push(1);
+        let mut strides = Vec::with_capacity(MAX_RANK);
+        strides.push(1);
+        Tensor {
+            data,
+            shape,
+            strides,
+        }
+    }
+
+    /// An empty placeholder used while a buffer is temporarily moved out. Does not allocate.
+    pub(crate) fn placeholder() -> Tensor {
+        Tensor {
+            data: Vec::new(),
+            shape: Vec::new(),
+            strides: Vec::new(),
+        }
+    }
+
+    pub(crate) fn capacity(&self) -> usize {
+        self.data.capacity()
+    }
+
+    /// Reshape in place, zero-filling any new elements.
+    pub(crate) fn set_shape(&mut self, shape: &[usize]) {
+        self.data.resize(shape.iter().product(), 0.0);
+        self.shape.clear();
+        self.shape.extend_from_slice(shape);
+        self.strides.clear();
+        self.strides.resize(shape.len(), 1);
+        for i in (0..shape.len().saturating_sub(1)).rev() {
+            self.strides[i] = self.strides[i + 1] * shape[i + 1];
+        }
+    }
+
+    /// Copy `src` into `self`, reusing the existing allocation where possible.
+    pub(crate) fn assign(&mut self, src: &Tensor) {
+        self.set_shape(&src.shape);
+        self.data.copy_from_slice(&src.data);
+    }
+
+    pub(crate) fn broadcast_shape_into(
+        a: &[usize],
+        b: &[usize],
+    ) -> Result<([usize; MAX_RANK], usize), ComputeError> {
+        let rank = a.len().max(b.len());
+        if rank > MAX_RANK {
+            return Err(ComputeError::DimensionError {
+                message: format!("rank {rank} exceeds the supported maximum of {MAX_RANK}"),
+            });
+        }
+        let mut out = [1usize; MAX_RANK];
+        for (i, o) in out.iter_mut().enumerate().take(rank) {
+            let a_i = if i >= rank - a.len() {
+                a[i - (rank - a.len())]
+            } else {
+                1
+            };
+            let b_i = if i >= rank - b.len() {
+                b[i - (rank - b.len())]
+            } else {
+                1
+            };
+            *o = if a_i == b_i || b_i == 1 {
+                a_i
+            } else if a_i == 1 {
+                b_i
+            } else {
+                return Err(ComputeError::BroadcastError {
+                    dim: i,
+                    shape1: a_i,
+                    shape2: b_i,
+                });
+            };
+        }
+        Ok((out, rank))
+    }
+
+    pub(crate) fn unravel_into(mut flat: usize, shape: &[usize], idx: &mut [usize]) {
+        for i in (0..shape.len()).rev() {
+            idx[i] = flat % shape[i];
+            flat /= shape[i];
+        }
+    }
+
+    pub(crate) fn elementwise_into<F>(
+        &self,
+        other: &Tensor,
+        out: &mut Tensor,
+        op: F,
+    ) -> Result<(), ComputeError>
+    where
+        F: Fn(f32, f32) -> f32,
+    {
+        self.try_elementwise_into(other, out, "", |a, b| Some(op(a, b)))
+    }
+
+    /// Like `elementwise_into`, failing with a singular point error where `op` returns `None`.
+    pub(crate) fn try_elementwise_into<F>(
+        &self,
+        other: &Tensor,
+        out: &mut Tensor,
+        operation: &str,
+        op: F,
+    ) -> Result<(), ComputeError>
+    where
+        F: Fn(f32, f32) -> Option<f32>,
+    {
+        let (shape, rank) = Self::broadcast_shape_into(&self.shape, &other.shape)?;
+        let shape = &shape[..rank];
+        out.set_shape(shape);
+        if self.shape == shape && other.shape == shape {
+            for (flat, ((o, &a), &b)) in out
+                .data
+                .iter_mut()
+                .zip(&self.data)
+                .zip(&other.data)
+                .enumerate()
+            {
+                *o = op(a, b).ok_or_else(|| numeric::singular(operation, flat))?;
+            }
+            return Ok(());
+        }
+        let mut idx = [0usize; MAX_RANK];
+        for flat in 0..out.data.len() {
+            Self::unravel_into(flat, shape, &mut idx[..rank]);
+            let a_flat = self.broadcasted_flat_index(&idx[..rank], shape)?;
+            let b_flat = other.broadcasted_flat_index(&idx[..rank], shape)?;
+            out.data[flat] = op(self.data[a_flat], other.data[b_flat])
+                .ok_or_else(|| numeric::singular(operation, flat))?;
+        }
+        Ok(())
+    }
+
+    pub(crate) fn map_into<F>(&self, out: &mut Tensor, f: F)
+    where
+        F: Fn(f32) -> f32,
+    {
+        out.set_shape(&self.shape);
+        for (o, &v) in out.data.iter_mut().zip(&self.data) {
+            *o = f(v);
+        }
+    }
+
+    /// Like `map_into`, failing with a singular point error where `f` returns `None`.
+    pub(crate) fn try_map_into<F>(
+        &self,
+        out: &mut Tensor,
+        operation: &str,
+        f: F,
+    ) -> Result<(), ComputeError>
+    where
+        F: Fn(f32) -> Option<f32>,
+    {
+        out.set_shape(&self.shape);
+        for (flat, (o, &v)) in out.data.iter_mut().zip(&self.data).enumerate() {
+            *o = f(v).ok_or_else(|| numeric::singular(operation, flat))?;
+        }
+        Ok(())
+    }
+
+    pub(crate) fn matmul_into(&self, other: &Tensor, out: &mut Tensor) -> Result<(), ComputeError> {
+        if self.shape.len() != 2 || other.shape.len() != 2 {
+            return Err(ComputeError::DimensionError {
+                message: "matmul requires 2D tensors".to_string(),
+            });
+        }
+        let (m, k, n) = (self.shape[0], self.shape[1], other.shape[1]);
+        if other.shape[0] != k {
+            return Err(ComputeError::InvalidOperation {
+                message: format!(
+                    "matmul dimension mismatch: left is {m}x{k}, right is {}x{n}",
+                    other.shape[0]
+                ),
+            });
+        }
+        out.set_shape(&[m, n]);
+        for i in 0..m {
+            for j in 0..n {
+                let mut sum = 0.0;
+                for p in 0..k {
+                    sum += self.data[i * k + p] * other.data[p * n + j];
+                }
+                out.data[i * n + j] = sum;
+            }
+        }
+        Ok(())
+    }
+
+    pub(crate) fn sum_into(
+        &self,
+        dim: Option<usize>,
+        out: &mut Tensor,
+    ) -> Result<(), ComputeError> {
+        match dim {
+            None => {
+                out.set_shape(&[1]);
+                out.data[0] = self.data.iter().sum();
+            }
+            Some(axis) => {
+                if axis >= self.shape.len() {
+                    return Err(ComputeError::DimensionError {
+                        message: format!("invalid axis {axis} for rank {}", self.shape.len()),
+                    });
+                }
+                let rank = self.shape.len();
+                if rank > MAX_RANK {
+                    return Err(ComputeError::DimensionError {
+                        message: format!("rank {rank} exceeds the supported maximum of {MAX_RANK}"),
+                    });
+                }
+                let len = self.shape[axis];
+                let inner = self.strides[axis];
+                let outer = self.data.len() / (len * inner).max(1);
+                let mut out_shape = [0usize; MAX_RANK];
+                out_shape[..rank].copy_from_slice(&self.shape);
+                out_shape[axis] = 1;
+                out.set_shape(&out_shape[..rank]);
+                for o in 0..outer {
+                    for i in 0..inner {
+                        let mut acc = 0.0;
+                        for a in 0..len {
+                            acc += self.data[(o * len + a) * inner + i];
+                        }
+                        out.data[o * inner + i] = acc;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Logistic function, computed without overflow for large `|x|`.
+pub(crate) fn sigmoid(x: f32) -> f32 {
+    if x >= 0.0 {
+        1.0 / (1.0 + (-x).exp())
+    } else {
+        let e = x.exp();
+        e / (1.0 + e)
+    }
+}
@@ -1,4 +1,5 @@
 use crate::error::ComputeError;
+use crate::tensor::Tensor;
 
 pub fn windows_1d(x: &[f32], window: usize, stride: usize) -> Result<Vec<Vec<f32>>, ComputeError> {
     if window == 0 || stride == 0 {
@@ -41,3 +42,20 @@ pub fn windows_2d(
     }
     Ok(out)
 }
+
+/// Stack windows from `windows_2d` into a `[batch, time, features]` tensor, the layout the
+/// recurrent layers consume.
+pub fn windows_to_tensor(windows: &[Vec<Vec<f32>>]) -> Result<Tensor, ComputeError> {
+    let time = windows.first().map_or(0, Vec::len);
+    let features = windows.first().and_then(|w| w.first()).map_or(0, Vec::len);
+    let mut data = Vec::with_capacity(windows.len() * time * features);
+    for window in windows {
+        if window.len() != time || window.iter().any(|row| row.len() != features) {
+            return Err(ComputeError::DimensionError {
+                message: format!("every window must be {time} steps of {features} features"),
+            });
+        }
+        data.extend(window.iter().flatten());
+    }
+    Tensor::new(data, vec![windows.len(), time, features])
+}
@@ -0,0 +1,53 @@
+use neuroncore::ops::{DivideOp, LogOp, SumOp};
+use neuroncore::{ComputeError, Graph, Tensor};
+
+#[test]
+fn forward_reports_first_non_finite_node() {
+    let mut g = Graph::new();
+    let a = g.add_input(Tensor::new(vec![1.0, 2.0], vec![2]).unwrap());
+    let b = g.add_input(Tensor::new(vec![1.0, 0.0], vec![2]).unwrap());
+    let ratio = g.apply_op(DivideOp, &[a, b]);
+    g.set_label(ratio, "rate");
+    let out = g.apply_op(SumOp { dim: None }, &[ratio]);
+
+    // Off by default: the infinity propagates silently.
+    assert!(!g.forward(out).unwrap().data()[0].is_finite());
+
+    g.set_detect_anomalies(true);
+    let err = g.forward(out).unwrap_err();
+    let context = err.node_context().unwrap();
+    assert_eq!((context.node, context.op.as_str()), (ratio, "DivideOp"));
+    assert!(matches!(
+        err.root_cause(),
+        ComputeError::NonFinite { index: 1, .. }
+    ));
+    assert!(err.to_string().contains("'rate'"), "{err}");
+}
+
+#[test]
+fn forward_flags_log_of_zero() {
+    let mut g = Graph::new();
+    g.set_detect_anomalies(true);
+    let x = g.add_input(Tensor::new(vec![0.5, 0.0], vec![2]).unwrap());
+    let y = g.apply_op(LogOp, &[x]);
+    let err = g.forward(y).unwrap_err();
+    assert_eq!(err.node_context().unwrap().op, "LogOp");
+    match err.root_cause() {
+        ComputeError::NonFinite { value, .. } => assert_eq!(*value, f32::NEG_INFINITY),
+        other => panic!("unexpected error {other}"),
+    }
+}
+
+#[test]
+fn backward_reports_non_finite_gradients() {
+    let mut g = Graph::new();
+    g.set_detect_anomalies(true);
+    let a = g.add_parameter(Tensor::new(vec![1.0], vec![1]).unwrap(), true);
+    let b = g.add_parameter(Tensor::new(vec![1e-30], vec![1]).unwrap(), true);
+    let ratio = g.apply_op(DivideOp, &[a, b]);
+
+    assert!(g.forward(ratio).unwrap().data()[0].is_finite());
+    let err = g.backward(ratio).unwrap_err();
+    assert_eq!(err.node_context().unwrap().node, ratio);
+    assert!(err.to_string().contains("gradient for input 1"), "{err}");
+}
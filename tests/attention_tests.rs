@@ -0,0 +1,154 @@
+use neuroncore::attention::{
+    positions, AttentionMask, LearnedPositionalEncoding, MultiHeadAttention,
+    ScaledDotProductAttentionOp, SinusoidalEncodingOp, TransformerEncoderLayer,
+};
+use neuroncore::layers::Layer;
+use neuroncore::module::Module;
+use neuroncore::norm::LayerNormOp;
+use neuroncore::ops::SumOp;
+use neuroncore::{BatchMatMulOp, Graph, Op, Tensor};
+
+mod common;
+use common::{batched, check_gradients, round_trip, ALL_INPUTS};
+
+#[test]
+fn attention_norm_and_encoding_ops_match_finite_differences() {
+    let q = Tensor::random(vec![2, 3, 4], 1).unwrap();
+    let k = Tensor::random(vec![2, 3, 4], 2).unwrap();
+    let v = Tensor::random(vec![2, 3, 2], 3).unwrap();
+    // The second sequence's last step is padding.
+    let mask = Tensor::new(vec![1.0, 1.0, 1.0, 1.0, 1.0, 0.0], vec![2, 3]).unwrap();
+    let causal = ScaledDotProductAttentionOp { causal: true };
+    check_gradients(
+        &causal,
+        &[q.clone(), k.clone(), v.clone(), mask],
+        ALL_INPUTS,
+    );
+    check_gradients(
+        &ScaledDotProductAttentionOp::default(),
+        &[q.clone(), k, v],
+        ALL_INPUTS,
+    );
+
+    let gamma = Tensor::random(vec![4], 4).unwrap();
+    let beta = Tensor::random(vec![4], 5).unwrap();
+    check_gradients(
+        &LayerNormOp::default(),
+        &[q.clone(), gamma, beta],
+        ALL_INPUTS,
+    );
+    check_gradients(
+        &LayerNormOp::default(),
+        std::slice::from_ref(&q),
+        ALL_INPUTS,
+    );
+
+    let w = Tensor::random(vec![4, 3], 6).unwrap();
+    check_gradients(&BatchMatMulOp, &[q.clone(), w], ALL_INPUTS);
+    let per_batch = Tensor::random(vec![2, 4, 3], 7).unwrap();
+    check_gradients(&BatchMatMulOp, &[q, per_batch], ALL_INPUTS);
+
+    // Irregular timestamps.
+    let t = Tensor::new(vec![0.0, 0.3, 1.7, 0.0, 2.2, 2.5], vec![2, 3]).unwrap();
+    let encoding = SinusoidalEncodingOp {
+        dim: 6,
+        max_period: 100.0,
+    };
+    check_gradients(&encoding, &[t], ALL_INPUTS);
+}
+
+#[test]
+fn causal_and_padding_masks_hide_keys() {
+    let mut graph = Graph::new();
+    let x = graph.add_input(Tensor::random(vec![1, 4, 4], 11).unwrap());
+    let mha = MultiHeadAttention::new(&mut graph, 4, 2, 5).unwrap();
+    let mask = AttentionMask {
+        causal: true,
+        key_padding: None,
+    };
+    let out = mha.attend(&mut graph, x, x, x, &mask).unwrap();
+    let before = graph.forward(out).unwrap().clone();
+
+    // Changing the last step must leave every earlier output untouched.
+    let mut changed = Tensor::random(vec![1, 4, 4], 11).unwrap();
+    for v in &mut changed.data_mut()[12..] {
+        *v += 3.0;
+    }
+    graph.set_input(x, changed).unwrap();
+    let after = graph.forward(out).unwrap().clone();
+    assert_eq!(before.data()[..12], after.data()[..12]);
+    assert_ne!(before.data()[12..], after.data()[12..]);
+
+    // A padded key gets zero weight: its values do not matter.
+    let q = Tensor::random(vec![1, 2, 2], 1).unwrap();
+    let k = Tensor::random(vec![1, 3, 2], 2).unwrap();
+    let mut v = Tensor::random(vec![1, 3, 2], 3).unwrap();
+    let mask = Tensor::new(vec![1.0, 1.0, 0.0], vec![1, 3]).unwrap();
+    let op = ScaledDotProductAttentionOp::default();
+    let y = op
+        .forward(&[q.clone(), k.clone(), v.clone(), mask.clone()])
+        .unwrap();
+    v.data_mut()[4] = 100.0;
+    assert_eq!(y, op.forward(&[q, k, v, mask]).unwrap());
+
+    // Regular positions: pe(0) alternates sin 0 = 0 and cos 0 = 1.
+    let encoding = SinusoidalEncodingOp {
+        dim: 4,
+        max_period: 10_000.0,
+    };
+    let pe = encoding.forward(&[positions(1, 2).unwrap()]).unwrap();
+    assert_eq!(pe.shape(), &[1, 2, 4]);
+    assert_eq!(pe.data()[..4], [0.0, 1.0, 0.0, 1.0]);
+    assert!((pe.data()[4] - 1f32.sin()).abs() < 1e-6);
+}
+
+#[test]
+fn encoder_layer_checks_symbolic_batch_and_trains_every_parameter() {
+    let mut graph = Graph::new();
+    let x = graph.add_input(Tensor::random(vec![3, 5, 8], 9).unwrap());
+    let padding = graph.add_input(Tensor::ones(vec![3, 5]).unwrap());
+    let positional = LearnedPositionalEncoding::new(&mut graph, 16, 8, 2).unwrap();
+    let mut encoder = TransformerEncoderLayer::new(&mut graph, 8, 2, 16, 3).unwrap();
+    encoder.norm_first = true;
+
+    let h = positional.forward(&mut graph, x).unwrap();
+    let mask = AttentionMask {
+        causal: true,
+        key_padding: Some(padding),
+    };
+    let out = encoder.forward_masked(&mut graph, h, &mask).unwrap();
+    assert_eq!(graph.forward(out).unwrap().shape(), &[3, 5, 8]);
+    let symbolic = graph
+        .check_symbolic(out, &[(x, batched(&[5, 8])), (padding, batched(&[5]))])
+        .unwrap();
+    assert_eq!(symbolic, batched(&[5, 8]));
+
+    let names: Vec<String> = encoder
+        .named_parameters()
+        .into_iter()
+        .map(|(n, _)| n)
+        .collect();
+    assert_eq!(names[0], "self_attn.q_proj.weight");
+    assert_eq!(names.last().unwrap(), "norm2.bias");
+    assert_eq!(
+        encoder.num_parameters(&graph).unwrap(),
+        4 * (64 + 8) + 8 * 16 + 16 + 16 * 8 + 8 + 4 * 8
+    );
+
+    let loss = graph.apply_op(SumOp { dim: None }, &[out]);
+    graph.zero_grad();
+    graph.backward(loss).unwrap();
+    for (name, idx) in encoder
+        .named_parameters()
+        .into_iter()
+        .chain(positional.named_parameters())
+    {
+        let grad = graph.get_gradient(idx).unwrap();
+        assert!(grad.data().iter().all(|g| g.is_finite()), "{name}");
+    }
+
+    // Every op in the block round-trips through the text format.
+    let (_, loaded) = round_trip(&graph, &[x, padding]);
+    assert_eq!(loaded.len(), graph.len());
+    assert_eq!(loaded.forward(out).unwrap(), graph.forward(out).unwrap());
+}
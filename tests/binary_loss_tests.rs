@@ -0,0 +1,182 @@
+use neuroncore::losses::{
+    BCEWithLogitsLoss, BCEWithLogitsOp, BinaryInputs, BinaryLossOptions, FocalLoss, FocalLossOp,
+    Reduction,
+};
+use neuroncore::shape::Dim;
+use neuroncore::{Graph, Op, Tensor};
+
+mod common;
+use common::{batched, check_gradients, round_trip};
+
+#[test]
+fn binary_losses_match_finite_differences() {
+    // [batch, time, alarms] with per-step weights and a mask dropping step (1, 2).
+    let logits = Tensor::random(vec![2, 3, 2], 7).unwrap();
+    let targets = Tensor::new(
+        vec![1.0, 0.0, 0.0, 0.0, 1.0, 1.0, 0.0, 1.0, 0.3, 0.0, 1.0, 0.0],
+        vec![2, 3, 2],
+    )
+    .unwrap();
+    let pos_weight = Tensor::new(vec![3.0, 0.5], vec![2]).unwrap();
+    let weights = Tensor::new(vec![1.0, 2.0, 0.5, 1.0, 1.5, 1.0], vec![2, 3]).unwrap();
+    let mask = Tensor::new(vec![1.0, 1.0, 1.0, 1.0, 1.0, 0.0], vec![2, 3]).unwrap();
+    let all = BinaryInputs {
+        pos_weight: true,
+        weights: true,
+        mask: true,
+    };
+    let inputs = [logits, targets, pos_weight, weights, mask];
+    for reduction in [Reduction::Mean, Reduction::Sum, Reduction::None] {
+        let bce = BCEWithLogitsOp {
+            reduction,
+            inputs: all,
+        };
+        check_gradients(&bce, &inputs, 1);
+        for alpha in [None, Some(0.25)] {
+            let focal = FocalLossOp {
+                gamma: 2.0,
+                alpha,
+                reduction,
+                inputs: all,
+            };
+            check_gradients(&focal, &inputs, 1);
+        }
+        if reduction == Reduction::None {
+            let losses = bce.forward(&inputs).unwrap();
+            assert_eq!(losses.shape(), &[2, 3, 2]);
+            assert_eq!(losses.data()[10..], [0.0, 0.0]);
+        }
+    }
+
+    // Mean counts the 10 unmasked logits, whatever their weights.
+    let op = |reduction| BCEWithLogitsOp {
+        reduction,
+        inputs: all,
+    };
+    let sum = op(Reduction::Sum).forward(&inputs).unwrap().data()[0];
+    let mean = op(Reduction::Mean).forward(&inputs).unwrap().data()[0];
+    assert!((mean - sum / 10.0).abs() < 1e-5);
+}
+
+#[test]
+fn binary_losses_stay_finite_and_reduce_to_known_values() {
+    let logits = Tensor::new(vec![100.0, -100.0, 0.0, 2.0], vec![4]).unwrap();
+    let targets = Tensor::new(vec![0.0, 1.0, 1.0, 1.0], vec![4]).unwrap();
+    let inputs = [logits.clone(), targets.clone()];
+    let none = BCEWithLogitsOp {
+        reduction: Reduction::None,
+        ..BCEWithLogitsOp::default()
+    };
+    let losses = none.forward(&inputs).unwrap();
+    assert_eq!(losses.data()[..2], [100.0, 100.0]);
+    assert!((losses.data()[2] - 2f32.ln()).abs() < 1e-6);
+    let grads = none
+        .backward(&inputs, &Tensor::ones(vec![4]).unwrap())
+        .unwrap();
+    assert_eq!(grads[0].data()[..3], [1.0, -1.0, -0.5]);
+    assert_eq!(grads[1].data(), &[0.0; 4]);
+
+    // Focal loss without focusing or balancing is plain BCE.
+    let plain = FocalLossOp {
+        gamma: 0.0,
+        alpha: None,
+        reduction: Reduction::None,
+        inputs: BinaryInputs::default(),
+    };
+    let focal = plain.forward(&inputs).unwrap();
+    for (a, b) in focal.data().iter().zip(losses.data()) {
+        assert!((a - b).abs() < 1e-5);
+    }
+    // Focusing shrinks the well-classified positive far more than the uncertain one.
+    let focused = FocalLossOp {
+        gamma: 2.0,
+        ..plain
+    };
+    let focused = focused.forward(&inputs).unwrap();
+    assert!((focused.data()[2] - 0.25 * 2f32.ln()).abs() < 1e-6);
+    assert!(focused.data()[3] / losses.data()[3] < 0.02);
+
+    // pos_weight scales only the positive term.
+    let weighted = BCEWithLogitsOp {
+        reduction: Reduction::None,
+        inputs: BinaryInputs {
+            pos_weight: true,
+            ..BinaryInputs::default()
+        },
+    };
+    let pos_weight = Tensor::new(vec![4.0], vec![1]).unwrap();
+    let column = |t: &Tensor| Tensor::new(t.data().to_vec(), vec![4, 1]).unwrap();
+    let scaled = weighted
+        .forward(&[column(&logits), column(&targets), pos_weight])
+        .unwrap();
+    assert_eq!(scaled.data()[0], 100.0);
+    assert!((scaled.data()[2] - 4.0 * 2f32.ln()).abs() < 1e-5);
+
+    assert!(FocalLossOp {
+        alpha: Some(1.5),
+        ..FocalLossOp::default()
+    }
+    .forward(&inputs)
+    .is_err());
+    let short = [logits, Tensor::zeros(vec![3]).unwrap()];
+    assert!(BCEWithLogitsOp::default().forward(&short).is_err());
+}
+
+#[test]
+fn multi_label_alarm_losses_build_check_and_round_trip() {
+    let mut graph = Graph::new();
+    let logits = graph.add_parameter(Tensor::random(vec![3, 4], 1).unwrap(), true);
+    let alarms = graph.add_input(
+        Tensor::new(
+            vec![1.0, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 1.0, 1.0, 1.0],
+            vec![3, 4],
+        )
+        .unwrap(),
+    );
+    let weights = graph.add_input(Tensor::new(vec![1.0, 0.5, 2.0], vec![3]).unwrap());
+    let mask = graph.add_input(Tensor::new(vec![1.0, 1.0, 0.0], vec![3]).unwrap());
+    let options = BinaryLossOptions {
+        pos_weight: Some(vec![1.0, 5.0, 5.0, 2.0]),
+        weights: Some(weights),
+        mask: Some(mask),
+        reduction: Reduction::Mean,
+    };
+    let bce = BCEWithLogitsLoss::compute(&mut graph, logits, alarms, &options).unwrap();
+    let focal = FocalLoss::compute(
+        &mut graph,
+        logits,
+        alarms,
+        2.0,
+        Some(0.25),
+        &BinaryLossOptions {
+            reduction: Reduction::None,
+            ..options.clone()
+        },
+    )
+    .unwrap();
+
+    let shapes = [
+        (logits, batched(&[4])),
+        (alarms, batched(&[4])),
+        (weights, batched(&[])),
+        (mask, batched(&[])),
+    ];
+    assert_eq!(
+        graph.check_symbolic(bce, &shapes).unwrap(),
+        vec![Dim::Fixed(1)]
+    );
+    assert_eq!(graph.check_symbolic(focal, &shapes).unwrap(), batched(&[4]));
+
+    let per_alarm = graph.forward(focal).unwrap();
+    assert_eq!(per_alarm.data()[8..], [0.0; 4]);
+    graph.backward(bce).unwrap();
+    let grad = graph.get_gradient(logits).unwrap();
+    assert_eq!(grad.data()[8..], [0.0; 4]);
+    assert!(grad.data()[..8].iter().all(|g| *g != 0.0));
+
+    let expected = graph.forward(bce).unwrap();
+    // Parameters, including pos_weight, are kept.
+    let (text, loaded) = round_trip(&graph, &[alarms, weights, mask]);
+    assert!(text.contains("alpha=Some(0.25)"));
+    assert_eq!(loaded.forward(bce).unwrap(), expected);
+}
@@ -0,0 +1,63 @@
+use neuroncore::layers::{forward_checkpointed, Layer, Linear};
+use neuroncore::ops::SumOp;
+use neuroncore::{Graph, Tensor};
+
+/// Eight 16-wide linear layers; returns the loss node and all parameter nodes.
+fn build(g: &mut Graph, checkpoint_every: Option<usize>) -> (usize, Vec<usize>) {
+    let data = (0..16).map(|i| (i as f32 * 0.37).sin()).collect();
+    let x = g.add_input(Tensor::new(data, vec![1, 16]).unwrap());
+    let layers: Vec<Linear> = (0..8)
+        .map(|i| Linear::new(g, 16, 16, 10 + i).unwrap())
+        .collect();
+    let refs: Vec<&dyn Layer> = layers.iter().map(|l| l as &dyn Layer).collect();
+    let h = match checkpoint_every {
+        Some(n) => forward_checkpointed(g, &refs, x, n).unwrap(),
+        None => refs.iter().fold(x, |h, l| l.forward(g, h).unwrap()),
+    };
+    let loss = g.apply_op(SumOp { dim: None }, &[h]);
+    (loss, layers.iter().flat_map(|l| l.parameters()).collect())
+}
+
+#[test]
+fn checkpointed_gradients_match_plain_backward() {
+    let mut plain = Graph::new();
+    let (loss, params) = build(&mut plain, None);
+    plain.backward(loss).unwrap();
+
+    let mut checkpointed = Graph::new();
+    let (ck_loss, ck_params) = build(&mut checkpointed, Some(2));
+    assert_eq!(checkpointed.checkpoints().len(), 4);
+    checkpointed.backward(ck_loss).unwrap();
+
+    for (&p, &q) in params.iter().zip(&ck_params) {
+        assert_eq!(plain.get_gradient(p), checkpointed.get_gradient(q));
+    }
+}
+
+#[test]
+fn checkpointing_lowers_peak_memory_by_recomputing() {
+    let mut plain = Graph::new();
+    let (loss, _) = build(&mut plain, None);
+    plain.backward(loss).unwrap();
+    let plain_stats = plain.backward_stats();
+    assert_eq!(plain_stats.recomputed_ops, 0);
+
+    let mut checkpointed = Graph::new();
+    let (ck_loss, _) = build(&mut checkpointed, Some(4));
+    checkpointed.backward(ck_loss).unwrap();
+    let stats = checkpointed.backward_stats();
+
+    assert!(stats.recomputed_ops > 0);
+    assert!(
+        stats.peak_cached_elements < plain_stats.peak_cached_elements,
+        "{stats:?} vs {plain_stats:?}"
+    );
+}
+
+#[test]
+fn checkpoint_interval_must_be_positive() {
+    let mut g = Graph::new();
+    let x = g.add_input(Tensor::zeros(vec![1, 2]).unwrap());
+    let layer = Linear::new(&mut g, 2, 2, 1).unwrap();
+    assert!(forward_checkpointed(&mut g, &[&layer], x, 0).is_err());
+}
@@ -0,0 +1,108 @@
+//! Helpers shared by the integration tests: finite-difference gradient checks, symbolic
+//! batch shapes and save/load round trips.
+
+// Each test crate uses a different subset.
+#![allow(dead_code)]
+
+use neuroncore::layers::Layer;
+use neuroncore::ops::{MultiplyOp, SumOp};
+use neuroncore::serialize::{self, OpRegistry};
+use neuroncore::shape::Dim;
+use neuroncore::{Graph, Op, Tensor};
+
+const EPS: f32 = 1e-2;
+
+/// `differentiable` count for ops whose every input gets a gradient check.
+pub const ALL_INPUTS: usize = usize::MAX;
+
+/// Compare `op.backward` for the first `differentiable` inputs against central differences
+/// of `sum(op(inputs) * weights)`.
+pub fn check_gradients(op: &dyn Op, inputs: &[Tensor], differentiable: usize) {
+    check_gradients_within(op, inputs, differentiable, 1e-2);
+}
+
+/// `check_gradients` with an explicit per-element `tolerance`.
+pub fn check_gradients_within(
+    op: &dyn Op,
+    inputs: &[Tensor],
+    differentiable: usize,
+    tolerance: f32,
+) {
+    let out = op.forward(inputs).unwrap();
+    let weights = Tensor::random(out.shape().to_vec(), 17).unwrap();
+    let grads = op.backward(inputs, &weights).unwrap();
+    let objective = |inputs: &[Tensor]| -> f32 {
+        let y = op.forward(inputs).unwrap();
+        y.data()
+            .iter()
+            .zip(weights.data())
+            .map(|(a, b)| a * b)
+            .sum()
+    };
+    for (which, input) in inputs.iter().enumerate().take(differentiable) {
+        assert_eq!(grads[which].shape(), input.shape());
+        for i in 0..input.data().len() {
+            let mut hi = inputs.to_vec();
+            hi[which].data_mut()[i] += EPS;
+            let mut lo = inputs.to_vec();
+            lo[which].data_mut()[i] -= EPS;
+            let numeric = (objective(&hi) - objective(&lo)) / (2.0 * EPS);
+            let analytic = grads[which].data()[i];
+            assert!(
+                (numeric - analytic).abs() < tolerance,
+                "{} input {which}[{i}]: numeric {numeric} vs analytic {analytic}",
+                op.name()
+            );
+        }
+    }
+}
+
+/// Compare the gradient of `sum(output * weights)` with central differences for every
+/// parameter element of `layer`, restoring each parameter afterwards.
+pub fn check_parameter_gradients(graph: &mut Graph, layer: &dyn Layer, output: usize) {
+    let shape = graph.forward(output).unwrap().shape().to_vec();
+    let weights = graph.add_input(Tensor::random(shape, 3).unwrap());
+    let weighted = graph.apply_op(MultiplyOp, &[output, weights]);
+    let loss = graph.apply_op(SumOp { dim: None }, &[weighted]);
+    graph.zero_grad();
+    graph.backward(loss).unwrap();
+    for (name, idx) in layer.named_parameters() {
+        let analytic = graph.get_gradient(idx).unwrap().clone();
+        let original = graph.parameter(idx).unwrap().clone();
+        for i in 0..original.data().len() {
+            let mut probe = |delta: f32| {
+                let mut t = original.clone();
+                t.data_mut()[i] += delta;
+                graph.set_parameter(idx, t).unwrap();
+                graph.forward(loss).unwrap().data()[0]
+            };
+            let numeric = (probe(EPS) - probe(-EPS)) / (2.0 * EPS);
+            assert!(
+                (numeric - analytic.data()[i]).abs() < 1e-2,
+                "{name}[{i}]: numeric {numeric} vs analytic {}",
+                analytic.data()[i]
+            );
+        }
+        graph.set_parameter(idx, original).unwrap();
+    }
+}
+
+/// A symbolic `batch` dimension followed by fixed `dims`.
+pub fn batched(dims: &[usize]) -> Vec<Dim> {
+    let mut shape = vec![Dim::symbol("batch")];
+    shape.extend(dims.iter().map(|&d| Dim::Fixed(d)));
+    shape
+}
+
+/// Save `graph` to text and load it back with the built-in ops. Inputs load as zeros, so
+/// the values of `inputs` are copied over from `graph`. Returns the text and the copy.
+pub fn round_trip(graph: &Graph, inputs: &[usize]) -> (String, Graph) {
+    let text = serialize::save_to_string(graph).unwrap();
+    let mut loaded = serialize::load_from_str(&text, &OpRegistry::new()).unwrap();
+    for &idx in inputs {
+        loaded
+            .set_input(idx, graph.get_tensor(idx).unwrap())
+            .unwrap();
+    }
+    (text, loaded)
+}
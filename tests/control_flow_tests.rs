@@ -0,0 +1,186 @@
+use neuroncore::control::{CondOp, ScanOp, WhileOp};
+use neuroncore::custom::{FnOp, MacroOp};
+use neuroncore::ops::{AddOp, MatMulOp, MultiplyOp, ReluOp, SumOp};
+use neuroncore::shape::Dim;
+use neuroncore::timeseries::windows_2d;
+use neuroncore::{Graph, Tensor};
+
+fn unary(name: &str, build: impl FnOnce(&mut Graph, usize) -> usize) -> MacroOp {
+    MacroOp::new(name, 1, |body, p| Ok(build(body, p[0]))).unwrap()
+}
+
+#[test]
+fn cond_runs_and_differentiates_only_the_taken_branch() {
+    for (flag, value, grad) in [(1.0, 6.0, 2.0), (0.0, 9.0, 6.0)] {
+        let cond = CondOp::new(
+            unary("Double", |b, x| b.apply_op(AddOp, &[x, x])),
+            unary("Square", |b, x| b.apply_op(MultiplyOp, &[x, x])),
+        )
+        .unwrap();
+        let mut g = Graph::new();
+        let p = g.add_input(Tensor::new(vec![flag], vec![1]).unwrap());
+        let x = g.add_parameter(Tensor::new(vec![3.0], vec![1]).unwrap(), true);
+        let y = g.apply_op(cond, &[p, x]);
+        assert_eq!(g.forward(y).unwrap().data(), &[value]);
+        g.backward(y).unwrap();
+        assert_eq!(g.get_gradient(x).unwrap().data(), &[grad]);
+    }
+
+    let identity = unary("Identity", |_, x| x);
+    let pair = MacroOp::new("First", 2, |_, p| Ok(p[0])).unwrap();
+    assert!(CondOp::new(identity, pair).is_err());
+}
+
+#[test]
+fn while_loop_backpropagates_through_every_iteration() {
+    // Keep doubling x until its sum reaches 20.
+    let below = MacroOp::new("Below20", 1, |b, p| {
+        let limit = b.add_input(Tensor::new(vec![20.0], vec![1]).unwrap());
+        let total = b.apply_op(SumOp { dim: None }, &[p[0]]);
+        Ok(b.apply_op(
+            FnOp::new(
+                "Less",
+                2,
+                |i| {
+                    Tensor::new(
+                        vec![(i[0].data()[0] < i[1].data()[0]) as u8 as f32],
+                        vec![1],
+                    )
+                },
+                |i, _| Ok(vec![Tensor::zeros_like(&i[0])?, Tensor::zeros_like(&i[1])?]),
+            ),
+            &[total, limit],
+        ))
+    })
+    .unwrap();
+    let double = unary("Double", |b, x| b.apply_op(AddOp, &[x, x]));
+
+    let mut g = Graph::new();
+    let x = g.add_parameter(Tensor::new(vec![1.0, 2.0], vec![2]).unwrap(), true);
+    let y = g.apply_op(WhileOp::new(below, double, 10).unwrap(), &[x]);
+    let loss = g.apply_op(SumOp { dim: None }, &[y]);
+
+    // 3 -> 6 -> 12 -> 24: three doublings.
+    assert_eq!(g.forward(y).unwrap().data(), &[8.0, 16.0]);
+    g.backward(loss).unwrap();
+    assert_eq!(g.get_gradient(x).unwrap().data(), &[8.0, 8.0]);
+}
+
+fn rnn_cell() -> MacroOp {
+    // h' = relu(x_t Wx + h Wh)
+    MacroOp::new("RnnCell", 4, |b, p| {
+        let xw = b.apply_op(MatMulOp, &[p[0], p[2]]);
+        let hw = b.apply_op(MatMulOp, &[p[1], p[3]]);
+        let z = b.apply_op(AddOp, &[xw, hw]);
+        Ok(b.apply_op(ReluOp, &[z]))
+    })
+    .unwrap()
+}
+
+#[test]
+fn scan_matches_manually_unrolled_rnn() {
+    let series: Vec<Vec<f32>> = (0..6)
+        .map(|t| vec![(t as f32 * 0.7).sin(), (t as f32 * 0.3).cos()])
+        .collect();
+    let window = &windows_2d(&series, 4, 2).unwrap()[0];
+    let steps: Vec<Tensor> = window
+        .iter()
+        .map(|row| Tensor::new(row.clone(), vec![1, 2]).unwrap())
+        .collect();
+    let wx_value = Tensor::new(vec![0.5, -0.3, 0.8, 0.2, 0.1, 0.4], vec![2, 3]).unwrap();
+    let wh_value = Tensor::random(vec![3, 3], 9).unwrap();
+    let h0_value = Tensor::new(vec![0.1, 0.2, 0.3], vec![1, 3]).unwrap();
+
+    let mut unrolled = Graph::new();
+    let wx = unrolled.add_parameter(wx_value.clone(), true);
+    let wh = unrolled.add_parameter(wh_value.clone(), true);
+    let h0 = unrolled.add_parameter(h0_value.clone(), true);
+    let mut h = h0;
+    let mut total = None;
+    for step in &steps {
+        let x = unrolled.add_input(step.clone());
+        let xw = unrolled.apply_op(MatMulOp, &[x, wx]);
+        let hw = unrolled.apply_op(MatMulOp, &[h, wh]);
+        let z = unrolled.apply_op(AddOp, &[xw, hw]);
+        h = unrolled.apply_op(ReluOp, &[z]);
+        let s = unrolled.apply_op(SumOp { dim: None }, &[h]);
+        total = Some(match total {
+            Some(acc) => unrolled.apply_op(AddOp, &[acc, s]),
+            None => s,
+        });
+    }
+    unrolled.backward(total.unwrap()).unwrap();
+
+    let mut scanned = Graph::new();
+    let xs = scanned.add_input(Tensor::stack(&steps, 0).unwrap());
+    let swx = scanned.add_parameter(wx_value, true);
+    let swh = scanned.add_parameter(wh_value, true);
+    let sh0 = scanned.add_parameter(h0_value, true);
+    let hs = scanned.apply_op(ScanOp::new(rnn_cell()).unwrap(), &[xs, sh0, swx, swh]);
+    let loss = scanned.apply_op(SumOp { dim: None }, &[hs]);
+    assert_eq!(scanned.forward(hs).unwrap().shape(), &[4, 1, 3]);
+    scanned.backward(loss).unwrap();
+
+    let close = |a: &Tensor, b: &Tensor| {
+        a.data()
+            .iter()
+            .zip(b.data())
+            .all(|(x, y)| (x - y).abs() < 1e-5)
+    };
+    for (a, b) in [(wx, swx), (wh, swh), (h0, sh0)] {
+        let (ga, gb) = (
+            unrolled.get_gradient(a).unwrap(),
+            scanned.get_gradient(b).unwrap(),
+        );
+        assert_eq!(ga.shape(), gb.shape());
+        assert!(close(ga, gb), "{:?} vs {:?}", ga.data(), gb.data());
+    }
+}
+
+#[test]
+fn scan_infers_symbolic_sequence_shapes() {
+    let mut g = Graph::new();
+    let xs = g.add_input(Tensor::zeros(vec![1, 5, 2]).unwrap());
+    let h0 = g.add_input(Tensor::zeros(vec![1, 3]).unwrap());
+    let wx = g.add_parameter(Tensor::zeros(vec![2, 3]).unwrap(), true);
+    let wh = g.add_parameter(Tensor::zeros(vec![3, 3]).unwrap(), true);
+    let scan = ScanOp::new(rnn_cell()).unwrap().with_axis(1);
+    let hs = g.apply_op(scan, &[xs, h0, wx, wh]);
+
+    let batch = Dim::symbol("batch");
+    let overrides = [
+        (xs, vec![batch.clone(), Dim::symbol("time"), Dim::Fixed(2)]),
+        (h0, vec![batch.clone(), Dim::Fixed(3)]),
+    ];
+    let shape = g.check_symbolic(hs, &overrides).unwrap();
+    assert_eq!(shape, vec![batch, Dim::symbol("time"), Dim::Fixed(3)]);
+    assert_eq!(g.forward(hs).unwrap().shape(), &[1, 5, 3]);
+}
+
+#[test]
+fn scan_over_a_plain_vector_steps_with_single_element_tensors() {
+    // h' = h * w + x_t
+    let cell = MacroOp::new("Decay", 3, |b, p| {
+        let hw = b.apply_op(MultiplyOp, &[p[1], p[2]]);
+        Ok(b.apply_op(AddOp, &[hw, p[0]]))
+    })
+    .unwrap();
+
+    let mut g = Graph::new();
+    let xs = g.add_parameter(Tensor::new(vec![1.0, 2.0, 3.0], vec![3]).unwrap(), true);
+    let h0 = g.add_input(Tensor::zeros(vec![1]).unwrap());
+    let w = g.add_parameter(Tensor::new(vec![0.5], vec![1]).unwrap(), true);
+    let hs = g.apply_op(ScanOp::new(cell).unwrap(), &[xs, h0, w]);
+    let loss = g.apply_op(SumOp { dim: None }, &[hs]);
+
+    let time = vec![Dim::symbol("time")];
+    let shape = g.check_symbolic(hs, &[(xs, time)]).unwrap();
+    assert_eq!(shape, vec![Dim::symbol("time"), Dim::Fixed(1)]);
+    let out = g.forward(hs).unwrap();
+    assert_eq!(out.shape(), &[3, 1]);
+    assert_eq!(out.data(), &[1.0, 2.5, 4.25]);
+
+    g.backward(loss).unwrap();
+    assert_eq!(g.get_gradient(xs).unwrap().data(), &[1.75, 1.5, 1.0]);
+    assert_eq!(g.get_gradient(xs).unwrap().shape(), &[3]);
+}
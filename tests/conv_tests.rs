@@ -0,0 +1,173 @@
+use neuroncore::conv::{Conv1dOp, Conv2dOp, ConvTranspose1dOp, ConvTranspose2dOp};
+use neuroncore::layers::{Conv1d, Layer, Relu, Sequential};
+use neuroncore::module::Module;
+use neuroncore::ops::SumOp;
+use neuroncore::{Graph, Op, Tensor};
+
+mod common;
+use common::{batched, check_gradients, round_trip, ALL_INPUTS};
+
+#[test]
+fn conv1d_forward_matches_hand_computation() {
+    // One channel, kernel [1, 0, -1]: a central difference.
+    let x = Tensor::new(vec![1.0, 2.0, 4.0, 8.0, 16.0], vec![1, 1, 5]).unwrap();
+    let w = Tensor::new(vec![1.0, 0.0, -1.0], vec![1, 1, 3]).unwrap();
+    let b = Tensor::new(vec![0.5], vec![1]).unwrap();
+
+    let y = Conv1dOp::default()
+        .forward(&[x.clone(), w.clone(), b])
+        .unwrap();
+    assert_eq!(y.shape(), &[1, 1, 3]);
+    assert_eq!(y.data(), &[-2.5, -5.5, -11.5]);
+
+    let padded = Conv1dOp {
+        stride: 2,
+        padding: 1,
+        ..Default::default()
+    };
+    let y = padded.forward(&[x, w]).unwrap();
+    assert_eq!(y.data(), &[-2.0, -6.0, 8.0]);
+}
+
+#[test]
+fn conv1d_backward_matches_finite_differences() {
+    let op = Conv1dOp {
+        stride: 2,
+        padding: 2,
+        dilation: 2,
+        groups: 2,
+    };
+    let x = Tensor::random(vec![2, 4, 7], 1).unwrap();
+    let w = Tensor::random(vec![6, 2, 3], 2).unwrap();
+    let b = Tensor::random(vec![6], 3).unwrap();
+    check_gradients(&op, &[x.clone(), w.clone(), b], ALL_INPUTS);
+    check_gradients(
+        &Conv1dOp::default(),
+        &[x, Tensor::random(vec![3, 4, 2], 4).unwrap()],
+        ALL_INPUTS,
+    );
+
+    let err = op
+        .forward(&[
+            Tensor::zeros(vec![1, 3, 7]).unwrap(),
+            Tensor::zeros(vec![6, 1, 3]).unwrap(),
+        ])
+        .unwrap_err();
+    assert!(err.to_string().contains("groups 2"));
+}
+
+#[test]
+fn conv1d_layer_trains_and_serializes() {
+    let mut graph = Graph::new();
+    let x = graph.add_input(Tensor::random(vec![4, 2, 16], 5).unwrap());
+    let net = Sequential::new()
+        .with(
+            Conv1d::new(
+                &mut graph,
+                2,
+                4,
+                5,
+                Conv1dOp {
+                    padding: 2,
+                    ..Default::default()
+                },
+                1,
+            )
+            .unwrap(),
+        )
+        .with(Relu)
+        .with(
+            Conv1d::new(
+                &mut graph,
+                4,
+                1,
+                3,
+                Conv1dOp {
+                    stride: 2,
+                    ..Default::default()
+                },
+                2,
+            )
+            .unwrap(),
+        );
+    assert_eq!(
+        net.num_parameters(&graph).unwrap(),
+        4 * 2 * 5 + 4 + 4 * 3 + 1
+    );
+
+    let out = net.forward(&mut graph, x).unwrap();
+    let symbolic = graph
+        .check_symbolic(out, &[(x, batched(&[2, 16]))])
+        .unwrap();
+    assert_eq!(symbolic, batched(&[1, 7]));
+
+    let total = graph.apply_op(SumOp { dim: None }, &[out]);
+    graph.backward(total).unwrap();
+    for (name, idx) in net.named_parameters() {
+        let grad = graph.get_gradient(idx).unwrap();
+        assert_eq!(
+            grad.shape(),
+            graph.parameter(idx).unwrap().shape(),
+            "{name}"
+        );
+    }
+
+    let (text, loaded) = round_trip(&graph, &[x]);
+    assert!(text.contains("op Conv1dOp 0,1,2 stride=1 padding=2 dilation=1 groups=1"));
+    assert_eq!(
+        loaded.forward(total).unwrap(),
+        graph.forward(total).unwrap()
+    );
+}
+
+#[test]
+fn conv2d_and_transposed_backward_match_finite_differences() {
+    let conv = Conv2dOp {
+        stride: [2, 1],
+        padding: [1, 2],
+        dilation: [1, 2],
+        groups: 2,
+    };
+    let x = Tensor::random(vec![2, 4, 5, 6], 6).unwrap();
+    let w = Tensor::random(vec![2, 2, 3, 2], 7).unwrap();
+    let b = Tensor::random(vec![2], 8).unwrap();
+    check_gradients(&conv, &[x, w, b], ALL_INPUTS);
+
+    let up = ConvTranspose2dOp {
+        stride: [2, 3],
+        padding: [1, 0],
+        output_padding: [1, 2],
+        groups: 2,
+        ..Default::default()
+    };
+    let x = Tensor::random(vec![1, 4, 3, 2], 9).unwrap();
+    let w = Tensor::random(vec![4, 3, 3, 2], 10).unwrap();
+    let b = Tensor::random(vec![6], 11).unwrap();
+    let y = up.forward(&[x.clone(), w.clone(), b.clone()]).unwrap();
+    // (3 - 1) * 2 - 2 + 3 + 1 rows, (2 - 1) * 3 + 2 + 2 columns.
+    assert_eq!(y.shape(), &[1, 6, 6, 7]);
+    check_gradients(&up, &[x, w, b], ALL_INPUTS);
+
+    // A stride-2 transposed convolution undoes the shape change of the matching conv.
+    let down = Conv1dOp {
+        stride: 2,
+        padding: 1,
+        ..Default::default()
+    };
+    let up = ConvTranspose1dOp {
+        stride: 2,
+        padding: 1,
+        output_padding: 1,
+        ..Default::default()
+    };
+    let x = Tensor::random(vec![1, 2, 8], 12).unwrap();
+    let h = down
+        .forward(&[x, Tensor::random(vec![3, 2, 3], 13).unwrap()])
+        .unwrap();
+    let w = Tensor::random(vec![3, 2, 3], 14).unwrap();
+    assert_eq!(
+        up.forward(&[h.clone(), w.clone()]).unwrap().shape(),
+        &[1, 2, 8]
+    );
+    check_gradients(&up, &[h, w], ALL_INPUTS);
+}
@@ -0,0 +1,129 @@
+use neuroncore::custom::{FnOp, MacroOp};
+use neuroncore::ops::{AddOp, LogOp, MatMulOp, ReluOp, SumOp};
+use neuroncore::shape::Dim;
+use neuroncore::{ComputeError, Graph, Op, Tensor};
+
+fn square() -> FnOp {
+    FnOp::new(
+        "Square",
+        1,
+        |inputs| inputs[0].multiply(&inputs[0]),
+        |inputs, grad| {
+            let two_x = inputs[0].add(&inputs[0])?;
+            Ok(vec![grad.multiply(&two_x)?])
+        },
+    )
+}
+
+#[test]
+fn fn_op_runs_closures_and_checks_arity() {
+    let mut g = Graph::new();
+    let x = g.add_parameter(Tensor::new(vec![1.0, -2.0, 3.0], vec![3]).unwrap(), true);
+    let y = g.apply_op(square(), &[x]);
+    let loss = g.apply_op(SumOp { dim: None }, &[y]);
+
+    assert_eq!(g.forward(y).unwrap().data(), &[1.0, 4.0, 9.0]);
+    g.backward(loss).unwrap();
+    assert_eq!(g.get_gradient(x).unwrap().data(), &[2.0, -4.0, 6.0]);
+
+    let bad = g.apply_op(square(), &[x, x]);
+    let err = g.forward(bad).unwrap_err();
+    assert!(matches!(
+        err.root_cause(),
+        ComputeError::InputCountError {
+            expected: 1,
+            got: 2
+        }
+    ));
+    assert_eq!(err.node_context().unwrap().op, "Square");
+}
+
+#[test]
+fn fn_op_rejects_wrong_gradient_count() {
+    let op = FnOp::new(
+        "Broken",
+        2,
+        |inputs| inputs[0].add(&inputs[1]),
+        |_, grad| Ok(vec![grad.clone()]),
+    );
+    let a = Tensor::new(vec![1.0], vec![1]).unwrap();
+    let grad = Tensor::new(vec![1.0], vec![1]).unwrap();
+    assert!(op.backward(&[a.clone(), a], &grad).is_err());
+}
+
+fn dense_relu() -> MacroOp {
+    MacroOp::new("DenseRelu", 3, |body, p| {
+        let mm = body.apply_op(MatMulOp, &[p[0], p[1]]);
+        let z = body.apply_op(AddOp, &[mm, p[2]]);
+        Ok(body.apply_op(ReluOp, &[z]))
+    })
+    .unwrap()
+}
+
+#[test]
+fn macro_op_backward_matches_expanded_subgraph() {
+    let x_value = Tensor::new(vec![0.5, -1.0, 2.0, 0.25], vec![2, 2]).unwrap();
+    let w_value = Tensor::new(vec![1.0, -0.5, 0.3, 0.8], vec![2, 2]).unwrap();
+    let b_value = Tensor::new(vec![0.1, -0.2], vec![1, 2]).unwrap();
+
+    let mut expanded = Graph::new();
+    let x = expanded.add_input(x_value.clone());
+    let w = expanded.add_parameter(w_value.clone(), true);
+    let b = expanded.add_parameter(b_value.clone(), true);
+    let mm = expanded.apply_op(MatMulOp, &[x, w]);
+    let z = expanded.apply_op(AddOp, &[mm, b]);
+    let h = expanded.apply_op(ReluOp, &[z]);
+    let loss = expanded.apply_op(SumOp { dim: None }, &[h]);
+    expanded.backward(loss).unwrap();
+
+    let mut packed = Graph::new();
+    let px = packed.add_input(x_value);
+    let pw = packed.add_parameter(w_value, true);
+    let pb = packed.add_parameter(b_value, true);
+    let ph = packed.apply_op(dense_relu(), &[px, pw, pb]);
+    let ploss = packed.apply_op(SumOp { dim: None }, &[ph]);
+    packed.backward(ploss).unwrap();
+
+    assert_eq!(packed.forward(ph).unwrap(), expanded.forward(h).unwrap());
+    assert_eq!(packed.get_gradient(pw), expanded.get_gradient(w));
+    assert_eq!(packed.get_gradient(pb), expanded.get_gradient(b));
+    assert!(packed.get_gradient(px).is_none());
+}
+
+#[test]
+fn macro_op_infers_shapes_from_its_body() {
+    let mut g = Graph::new();
+    let x = g.add_input(Tensor::zeros(vec![1, 3]).unwrap());
+    let w = g.add_parameter(Tensor::zeros(vec![3, 5]).unwrap(), true);
+    let b = g.add_parameter(Tensor::zeros(vec![1, 5]).unwrap(), true);
+    let h = g.apply_op(dense_relu(), &[x, w, b]);
+
+    let batch = vec![Dim::symbol("batch"), Dim::Fixed(3)];
+    let shape = g.check_symbolic(h, &[(x, batch)]).unwrap();
+    assert_eq!(shape, vec![Dim::symbol("batch"), Dim::Fixed(5)]);
+}
+
+#[test]
+fn macro_op_body_uses_shaped_placeholders_and_outer_settings() {
+    let log_of_sum = MacroOp::with_input_shapes("LogOfSum", &[vec![2], vec![2]], |body, p| {
+        let sum = body.apply_op(AddOp, &[p[0], p[1]]);
+        let out = body.apply_op(LogOp, &[sum]);
+        assert_eq!(body.forward(sum)?.shape(), &[2]);
+        Ok(out)
+    })
+    .unwrap();
+
+    let mut g = Graph::new();
+    let a = g.add_input(Tensor::new(vec![1.0, 0.0], vec![2]).unwrap());
+    let b = g.add_input(Tensor::new(vec![1.0, 0.0], vec![2]).unwrap());
+    let y = g.apply_op(log_of_sum, &[a, b]);
+    assert_eq!(g.forward(y).unwrap().data()[1], f32::NEG_INFINITY);
+
+    g.set_detect_anomalies(true);
+    let err = g.forward(y).unwrap_err();
+    assert_eq!(err.node_context().unwrap().op, "LogOfSum");
+    let ComputeError::Node { source, .. } = &err else {
+        panic!("expected node context, got {err}");
+    };
+    assert_eq!(source.node_context().unwrap().op, "LogOp");
+}
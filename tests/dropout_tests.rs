@@ -0,0 +1,127 @@
+use neuroncore::dropout::{Dropout, DropoutOp, SpatialDropout, SpatialDropoutOp};
+use neuroncore::layers::Layer;
+use neuroncore::module::Module;
+use neuroncore::ops::SumOp;
+use neuroncore::serialize::{self, OpRegistry};
+use neuroncore::shape::Dim;
+use neuroncore::{ComputeError, Graph, MultiplyOp, Op, Tensor};
+
+#[test]
+fn dropout_scales_survivors_and_reuses_its_mask_in_backward() {
+    let mut graph = Graph::new();
+    graph.set_seed(7);
+    let x = graph.add_parameter(Tensor::ones(vec![20, 50]).unwrap(), true);
+    let dropout = Dropout::new(&mut graph, 0.25).unwrap();
+    let y = dropout.forward(&mut graph, x).unwrap();
+
+    let out = graph.forward(y).unwrap();
+    let dropped = out.data().iter().filter(|&&v| v == 0.0).count();
+    assert!((200..300).contains(&dropped), "{dropped} of 1000 dropped");
+    assert!(out
+        .data()
+        .iter()
+        .all(|&v| v == 0.0 || (v - 4.0 / 3.0).abs() < 1e-6));
+
+    // d sum(y) / dx is the mask itself, identical to the forward one.
+    let loss = graph.apply_op(SumOp { dim: None }, &[y]);
+    graph.backward(loss).unwrap();
+    assert_eq!(graph.get_gradient(x).unwrap(), &out);
+
+    dropout.eval(&mut graph).unwrap();
+    assert_eq!(
+        graph.forward(y).unwrap(),
+        Tensor::ones(vec![20, 50]).unwrap()
+    );
+    graph.zero_grad();
+    graph.backward(loss).unwrap();
+    assert_eq!(
+        graph.get_gradient(x).unwrap(),
+        &Tensor::ones(vec![20, 50]).unwrap()
+    );
+    assert!(Dropout::new(&mut graph, 1.0).is_err());
+}
+
+#[test]
+fn masks_are_reproducible_from_the_graph_seed() {
+    let build = |seed: u32| {
+        let mut graph = Graph::new();
+        graph.set_seed(seed);
+        let x = graph.add_input(Tensor::ones(vec![4, 8]).unwrap());
+        let first = Dropout::new(&mut graph, 0.5).unwrap();
+        let second = Dropout::new(&mut graph, 0.5).unwrap();
+        let h = first.forward(&mut graph, x).unwrap();
+        let y = second.forward(&mut graph, h).unwrap();
+        (graph, y)
+    };
+    let (mut a, ya) = build(42);
+    let (mut b, yb) = build(42);
+    let (c, yc) = build(43);
+    let step0 = a.forward(ya).unwrap();
+    assert_eq!(step0, b.forward(yb).unwrap());
+    assert_ne!(step0, c.forward(yc).unwrap());
+
+    // Each step draws new masks, in the same sequence for the same seed.
+    a.advance_seeds();
+    b.advance_seeds();
+    let step1 = a.forward(ya).unwrap();
+    assert_ne!(step1, step0);
+    assert_eq!(step1, b.forward(yb).unwrap());
+
+    // Reseeding restarts the sequence.
+    a.set_seed(42);
+    assert_eq!(a.forward(ya).unwrap(), step0);
+}
+
+#[test]
+fn spatial_dropout_drops_whole_channels() {
+    let mut graph = Graph::new();
+    graph.set_seed(3);
+    let x = graph.add_input(Tensor::random(vec![4, 6, 10], 1).unwrap());
+    let dropout = SpatialDropout::new(&mut graph, 0.5).unwrap();
+    let y = dropout.forward(&mut graph, x).unwrap();
+    let squared = graph.apply_op(MultiplyOp, &[y, y]);
+
+    let out = graph.forward(y).unwrap();
+    let mut dropped_channels = 0;
+    for channel in out.data().chunks(10) {
+        let zeros = channel.iter().filter(|&&v| v == 0.0).count();
+        assert!(zeros == 0 || zeros == 10);
+        dropped_channels += usize::from(zeros == 10);
+    }
+    assert!((1..24).contains(&dropped_channels));
+
+    let symbolic = graph
+        .check_symbolic(
+            squared,
+            &[(x, vec![Dim::symbol("batch"), Dim::Fixed(6), Dim::Fixed(10)])],
+        )
+        .unwrap();
+    assert_eq!(
+        symbolic,
+        vec![Dim::symbol("batch"), Dim::Fixed(6), Dim::Fixed(10)]
+    );
+
+    // The seed is a parameter, so a saved graph replays the same mask.
+    let text = serialize::save_to_string(&graph).unwrap();
+    let mut loaded = serialize::load_from_str(&text, &OpRegistry::new()).unwrap();
+    loaded
+        .set_input(x, Tensor::random(vec![4, 6, 10], 1).unwrap())
+        .unwrap();
+    assert_eq!(loaded.forward(y).unwrap(), out);
+}
+
+#[test]
+fn dropout_ops_report_missing_inputs() {
+    for op in [
+        &DropoutOp { p: 0.5 } as &dyn Op,
+        &SpatialDropoutOp { p: 0.5 },
+    ] {
+        assert!(matches!(
+            op.forward(&[]),
+            Err(ComputeError::InputCountError {
+                expected: 3,
+                got: 0
+            })
+        ));
+    }
+}
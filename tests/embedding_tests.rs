@@ -0,0 +1,150 @@
+use neuroncore::embedding::{Embedding, EmbeddingOp};
+use neuroncore::industrial::schema::{IndustrialRecord, MachineState, ToolEvent};
+use neuroncore::industrial::vocab::{Vocabulary, VocabularyBuilder};
+use neuroncore::init::Init;
+use neuroncore::layers::Layer;
+use neuroncore::module::Module;
+use neuroncore::ops::SumOp;
+use neuroncore::serialize::{self, OpRegistry};
+use neuroncore::shape::Dim;
+use neuroncore::slicing::ConcatOp;
+use neuroncore::{Graph, Op, Tensor};
+
+fn state(ts: i64, program: Option<&str>) -> IndustrialRecord {
+    IndustrialRecord::MachineState(MachineState {
+        ts,
+        spindle_rpm: Some(1200.0),
+        feed_rate: None,
+        program: program.map(str::to_string),
+        alarms: None,
+    })
+}
+
+fn tool(ts: i64, tool_id: &str) -> IndustrialRecord {
+    IndustrialRecord::ToolEvent(ToolEvent {
+        ts,
+        tool_id: Some(tool_id.to_string()),
+        event_type: "change".to_string(),
+    })
+}
+
+#[test]
+fn vocabulary_orders_by_frequency_and_buckets_the_rest() {
+    let records = vec![
+        state(0, Some("O1002")),
+        tool(1, "T7"),
+        state(2, Some("O1001")),
+        state(3, Some("O1002")),
+        state(4, None),
+        state(5, Some("O2000")),
+        tool(6, "T3"),
+        state(7, Some("O1001")),
+    ];
+    let mut builder = VocabularyBuilder::new().min_count(2);
+    builder.add_programs(&records);
+    let programs = builder.build();
+    // Ties are broken alphabetically; O2000 is too rare.
+    assert_eq!(programs.tokens(), ["O1001", "O1002"]);
+    assert_eq!(programs.len(), 3);
+    assert_eq!(programs.index(Some("O1002")), 2);
+    assert_eq!(programs.index(Some("O2000")), Vocabulary::OOV);
+    assert_eq!(programs.index(None), Vocabulary::OOV);
+    assert_eq!(programs.token(1), Some("O1001"));
+    assert_eq!(programs.token(Vocabulary::OOV), None);
+
+    let encoded = programs
+        .encode(
+            [Some("O1001"), None, Some("O1002"), Some("new")],
+            vec![2, 2],
+        )
+        .unwrap();
+    assert_eq!(encoded.data(), &[1.0, 0.0, 2.0, 0.0]);
+
+    let mut builder = VocabularyBuilder::new().max_size(1);
+    builder.add_tool_ids(&records);
+    builder.add(Some("T3"));
+    assert_eq!(builder.build().tokens(), ["T3"]);
+
+    let restored = Vocabulary::from_tokens(programs.tokens().to_vec()).unwrap();
+    assert_eq!(restored, programs);
+    assert!(Vocabulary::from_tokens(vec!["a".into(), "a".into()]).is_err());
+}
+
+#[test]
+fn embedding_gradients_accumulate_into_looked_up_rows() {
+    let table = Tensor::new((0..8).map(|v| v as f32).collect(), vec![4, 2]).unwrap();
+    let indices = Tensor::new(vec![2.0, 0.0, 2.0], vec![3]).unwrap();
+    let op = EmbeddingOp {
+        padding_idx: Some(0),
+    };
+    let out = op.forward(&[indices.clone(), table.clone()]).unwrap();
+    assert_eq!(out.shape(), &[3, 2]);
+    assert_eq!(out.data(), &[4.0, 5.0, 0.0, 0.0, 4.0, 5.0]);
+
+    let grad = Tensor::new(vec![1.0, 2.0, 10.0, 20.0, 3.0, 4.0], vec![3, 2]).unwrap();
+    let grads = op.backward(&[indices, table.clone()], &grad).unwrap();
+    // Row 2 was read twice; the padding row and unused rows get nothing.
+    assert_eq!(grads[1].data(), &[0.0, 0.0, 0.0, 0.0, 4.0, 6.0, 0.0, 0.0]);
+    assert_eq!(grads[0].data(), &[0.0; 3]);
+
+    for bad in [4.0, -1.0, 1.5] {
+        let indices = Tensor::new(vec![bad], vec![1]).unwrap();
+        assert!(op.forward(&[indices, table.clone()]).is_err());
+    }
+}
+
+#[test]
+fn program_embedding_conditions_a_sensor_sequence() {
+    let mut vocab = VocabularyBuilder::new();
+    for program in ["O1001", "O1002", "O1003"] {
+        vocab.add(Some(program));
+    }
+    let vocab = vocab.build();
+
+    let mut graph = Graph::new();
+    let programs = [Some("O1001"), Some("O1003"), None, Some("O9999")];
+    let ids = graph.add_input(vocab.encode(programs, vec![2, 2]).unwrap());
+    let sensors = graph.add_input(Tensor::random(vec![2, 2, 3], 4).unwrap());
+    let init = Init::Constant(0.5);
+    let op = EmbeddingOp {
+        padding_idx: Some(Vocabulary::OOV),
+    };
+    let embedding = Embedding::with_init(&mut graph, vocab.len(), 4, op, &init, 1).unwrap();
+    let embedded = embedding.forward(&mut graph, ids).unwrap();
+    let features = graph.apply_op(ConcatOp { axis: 2 }, &[sensors, embedded]);
+
+    let out = graph.forward(features).unwrap();
+    assert_eq!(out.shape(), &[2, 2, 7]);
+    // Unknown and missing programs share the zero padding row.
+    assert_eq!(out.data()[17..21], [0.0; 4]);
+    assert_eq!(out.data()[3..7], [0.5; 4]);
+
+    let symbolic = graph
+        .check_symbolic(
+            features,
+            &[
+                (ids, vec![Dim::symbol("batch"), Dim::symbol("time")]),
+                (
+                    sensors,
+                    vec![Dim::symbol("batch"), Dim::symbol("time"), Dim::Fixed(3)],
+                ),
+            ],
+        )
+        .unwrap();
+    assert_eq!(
+        symbolic,
+        vec![Dim::symbol("batch"), Dim::symbol("time"), Dim::Fixed(7)]
+    );
+
+    let loss = graph.apply_op(SumOp { dim: None }, &[features]);
+    graph.backward(loss).unwrap();
+    let weight = embedding.named_parameters()[0].1;
+    let grad = graph.get_gradient(weight).unwrap();
+    assert_eq!(grad.data()[..4], [0.0; 4]);
+    assert_eq!(grad.data()[4..8], [1.0; 4]);
+    assert_eq!(grad.data()[8..12], [0.0; 4]);
+
+    let text = serialize::save_to_string(&graph).unwrap();
+    let loaded = serialize::load_from_str(&text, &OpRegistry::new()).unwrap();
+    assert_eq!(loaded.len(), graph.len());
+}
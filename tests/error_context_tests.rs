@@ -0,0 +1,88 @@
+use std::error::Error;
+use std::path::Path;
+
+use neuroncore::industrial::ingest::IngestSource;
+use neuroncore::industrial::replay::ReplaySource;
+use neuroncore::ops::{MatMulOp, ReluOp};
+use neuroncore::{ComputeError, Graph, Tensor};
+
+#[test]
+fn forward_error_names_node_op_shapes_and_label() {
+    let mut g = Graph::new();
+    let a = g.add_input(Tensor::zeros(vec![2, 3]).unwrap());
+    let b = g.add_input(Tensor::zeros(vec![4, 1]).unwrap());
+    let bad = g.apply_op(MatMulOp, &[a, b]);
+    g.set_label(bad, "encoder.proj");
+    let out = g.apply_op(ReluOp, &[bad]);
+
+    let err = g.forward(out).unwrap_err();
+    let context = err.node_context().expect("node context");
+    assert_eq!(context.node, bad);
+    assert_eq!(context.op, "MatMulOp");
+    assert_eq!(context.label.as_deref(), Some("encoder.proj"));
+
+    let message = err.to_string();
+    assert!(message.contains("'encoder.proj'"), "{message}");
+    assert!(message.contains("[2, 3], [4, 1]"), "{message}");
+    let source = err.source().and_then(|s| s.downcast_ref::<ComputeError>());
+    assert_eq!(
+        source.map(|s| s.to_string()),
+        Some(err.root_cause().to_string())
+    );
+    assert!(err.root_cause().node_context().is_none());
+}
+
+#[test]
+fn replay_missing_file_is_io_error_with_source() {
+    let err = ReplaySource::from_path(Path::new("tests/fixtures/does_not_exist.ndjson"))
+        .err()
+        .expect("missing file");
+    assert!(matches!(err, ComputeError::Io { .. }));
+    let source = err.source().expect("io source");
+    assert!(source.downcast_ref::<std::io::Error>().is_some());
+}
+
+#[test]
+fn replay_bad_line_is_parse_error_with_line_number() {
+    let path =
+        std::env::temp_dir().join(format!("neuroncore_replay_{}.ndjson", std::process::id()));
+    std::fs::write(
+        &path,
+        "{\"type\":\"sensor_sample\",\"ts\":1,\"channels\":[1.0]}\n{\"type\":\"bogus\"}\n",
+    )
+    .unwrap();
+
+    let mut src = ReplaySource::from_path(&path).unwrap();
+    assert!(src.next().unwrap().is_some());
+    let err = src.next().unwrap_err();
+    std::fs::remove_file(&path).unwrap();
+
+    match err {
+        ComputeError::Parse { context, message } => {
+            assert!(context.ends_with(":2"), "{context}");
+            assert!(message.contains("bogus"), "{message}");
+        }
+        other => panic!("expected parse error, got {other}"),
+    }
+}
+
+#[test]
+fn replay_read_error_names_the_line_being_read() {
+    let path = std::env::temp_dir().join(format!(
+        "neuroncore_replay_utf8_{}.ndjson",
+        std::process::id()
+    ));
+    let mut bytes = b"{\"type\":\"sensor_sample\",\"ts\":1,\"channels\":[1.0]}\n".to_vec();
+    bytes.extend_from_slice(&[0xff, 0xfe, b'\n']);
+    std::fs::write(&path, bytes).unwrap();
+
+    let mut src = ReplaySource::from_path(&path).unwrap();
+    assert!(src.next().unwrap().is_some());
+    let err = src.next().unwrap_err();
+    std::fs::remove_file(&path).unwrap();
+
+    match err {
+        ComputeError::Io { context, .. } => assert!(context.ends_with(":2"), "{context}"),
+        other => panic!("expected i/o error, got {other}"),
+    }
+}
@@ -0,0 +1,132 @@
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+
+use neuroncore::fusion::OperatorFusion;
+use neuroncore::layers::{Layer, Linear};
+use neuroncore::ops::{AddOp, MatMulOp, ReluOp, SoftmaxOp, SumOp};
+use neuroncore::passes::GraphPass;
+use neuroncore::plan::ExecutionPlan;
+use neuroncore::{Graph, Tensor};
+
+/// Counts allocations made by the current thread, so parallel tests don't interfere.
+struct CountingAlloc;
+
+thread_local! {
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.with(|c| c.set(c.get() + 1));
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAlloc = CountingAlloc;
+
+fn allocations() -> usize {
+    ALLOCATIONS.with(|c| c.get())
+}
+
+/// 4 -> 8 -> 8 -> 3 MLP with a softmax head.
+fn build_classifier(g: &mut Graph, x_value: Tensor) -> (usize, usize) {
+    let x = g.add_input(x_value);
+    let l1 = Linear::new(g, 4, 8, 1).unwrap();
+    let l2 = Linear::new(g, 8, 8, 2).unwrap();
+    let l3 = Linear::new(g, 8, 3, 3).unwrap();
+    let h = l1.forward(g, x).unwrap();
+    let h = g.apply_op(ReluOp, &[h]);
+    let h = l2.forward(g, h).unwrap();
+    let h = g.apply_op(ReluOp, &[h]);
+    let h = l3.forward(g, h).unwrap();
+    let out = g.apply_op(SoftmaxOp, &[h]);
+    (x, out)
+}
+
+#[test]
+fn plan_matches_graph_forward_and_reuses_buffers() {
+    let mut g = Graph::new();
+    let (x, out) = build_classifier(&mut g, Tensor::zeros(vec![2, 4]).unwrap());
+    let mut plan = ExecutionPlan::compile(&g, out, &[x]).unwrap();
+
+    assert_eq!(plan.schedule().len(), 9);
+    assert_eq!(plan.shape(out), Some(&[2, 3][..]));
+    assert!(plan.num_buffers() < plan.schedule().len());
+
+    let sample = Tensor::new((0..8).map(|v| v as f32 * 0.1).collect(), vec![2, 4]).unwrap();
+    let expected = {
+        let mut reference = Graph::new();
+        let (_, ref_out) = build_classifier(&mut reference, sample.clone());
+        reference.forward(ref_out).unwrap()
+    };
+
+    let y = plan.run(&g, &[(x, &sample)]).unwrap();
+    assert_eq!(y, &expected);
+}
+
+#[test]
+fn plan_run_does_not_allocate_after_compile() {
+    let mut g = Graph::new();
+    let (x, out) = build_classifier(&mut g, Tensor::zeros(vec![2, 4]).unwrap());
+    let remap = OperatorFusion.run(&mut g, &[out]).unwrap();
+    let (x, out) = (remap.get(x).unwrap(), remap.get(out).unwrap());
+    let mut plan = ExecutionPlan::compile(&g, out, &[x]).unwrap();
+
+    let samples: Vec<Tensor> = (0..4)
+        .map(|i| Tensor::new(vec![i as f32; 8], vec![2, 4]).unwrap())
+        .collect();
+
+    let before = allocations();
+    for sample in &samples {
+        let y = plan.run(&g, &[(x, sample)]).unwrap();
+        assert_eq!(y.shape(), &[2, 3]);
+    }
+    assert_eq!(allocations(), before);
+}
+
+#[test]
+fn plan_rejects_wrong_feed_shape() {
+    let mut g = Graph::new();
+    let (x, out) = build_classifier(&mut g, Tensor::zeros(vec![2, 4]).unwrap());
+    let mut plan = ExecutionPlan::compile(&g, out, &[x]).unwrap();
+    let bad = Tensor::zeros(vec![3, 4]).unwrap();
+    assert!(plan.run(&g, &[(x, &bad)]).is_err());
+    assert!(plan.run(&g, &[]).is_err());
+
+    // A feed the output does not depend on is accepted.
+    let unused = g.add_input(Tensor::zeros(vec![5]).unwrap());
+    let mut plan = ExecutionPlan::compile(&g, out, &[x, unused]).unwrap();
+    let sample = Tensor::zeros(vec![2, 4]).unwrap();
+    let other = Tensor::zeros(vec![5]).unwrap();
+    assert!(plan.run(&g, &[(x, &sample), (unused, &other)]).is_ok());
+
+    // Reshaping an input the plan reads from the graph makes the plan stale.
+    let mut plan = ExecutionPlan::compile(&g, out, &[]).unwrap();
+    assert!(plan.run(&g, &[]).is_ok());
+    g.set_input(x, Tensor::zeros(vec![3, 4]).unwrap()).unwrap();
+    let err = plan.run(&g, &[]).unwrap_err();
+    assert!(err.to_string().contains("compiled for [2, 4]"), "{err}");
+}
+
+#[test]
+fn plan_reuses_a_freed_buffer_at_a_lower_rank() {
+    let mut g = Graph::new();
+    let x = g.add_input(Tensor::random(vec![2, 3, 4], 5).unwrap());
+    let w = g.add_parameter(Tensor::random(vec![3, 4], 6).unwrap(), true);
+    let v = g.add_parameter(Tensor::random(vec![4, 1], 7).unwrap(), true);
+    let doubled = g.apply_op(AddOp, &[x, x]);
+    let rows = g.apply_op(SumOp { dim: Some(2) }, &[doubled]);
+    // The [3, 1] product lands in the buffer `doubled` held as [2, 3, 4].
+    let mm = g.apply_op(MatMulOp, &[w, v]);
+    let out = g.apply_op(AddOp, &[rows, mm]);
+
+    let mut plan = ExecutionPlan::compile(&g, out, &[]).unwrap();
+    assert!(plan.num_buffers() < plan.schedule().len());
+    let expected = g.forward(out).unwrap();
+    assert_eq!(plan.run(&g, &[]).unwrap(), &expected);
+}
@@ -0,0 +1,134 @@
+mod common;
+
+use common::round_trip;
+use neuroncore::fusion::{Activation, FusedLinearOp, OperatorFusion};
+use neuroncore::layers::{Layer, Linear};
+use neuroncore::ops::{
+    AddOp, DivideOp, LogOp, MatMulOp, MultiplyOp, Op, ReluOp, SubtractOp, SumOp,
+};
+use neuroncore::passes::GraphPass;
+use neuroncore::{Graph, Tensor};
+
+const TOL: f32 = 1e-5;
+
+fn assert_close(a: &[f32], b: &[f32]) {
+    assert_eq!(a.len(), b.len());
+    for (x, y) in a.iter().zip(b) {
+        assert!((x - y).abs() < TOL, "{x} vs {y}");
+    }
+}
+
+/// relu(x @ w + b) * s - t, summed; returns (graph, loss, [w, b, s]).
+fn build_mlp() -> (Graph, usize, Vec<usize>) {
+    let mut g = Graph::new();
+    let x = g.add_input(Tensor::new(vec![0.5, -1.0, 2.0], vec![1, 3]).unwrap());
+    let layer = Linear::new(&mut g, 3, 4, 7).unwrap();
+    let s = g.add_parameter(
+        Tensor::new(vec![1.5, -2.0, 0.5, 3.0], vec![1, 4]).unwrap(),
+        true,
+    );
+    let t = g.add_input(Tensor::new(vec![0.25], vec![1]).unwrap());
+    let h = layer.forward(&mut g, x).unwrap();
+    let h = g.apply_op(ReluOp, &[h]);
+    let scaled = g.apply_op(MultiplyOp, &[h, s]);
+    let shifted = g.apply_op(SubtractOp, &[scaled, t]);
+    let loss = g.apply_op(SumOp { dim: None }, &[shifted]);
+    let mut params = layer.parameters();
+    params.push(s);
+    (g, loss, params)
+}
+
+#[test]
+fn fusion_preserves_forward_and_gradients() {
+    let (mut reference, ref_loss, ref_params) = build_mlp();
+    reference.backward(ref_loss).unwrap();
+
+    let (mut g, loss, params) = build_mlp();
+    let before = g.len();
+    let remap = OperatorFusion.run(&mut g, &[loss]).unwrap();
+    assert_eq!(g.len(), before - 3);
+
+    let loss = remap.get(loss).unwrap();
+    assert_close(
+        g.forward(loss).unwrap().data(),
+        reference.forward(ref_loss).unwrap().data(),
+    );
+    g.backward(loss).unwrap();
+    for (&p, &rp) in remap.apply(&params).unwrap().iter().zip(&ref_params) {
+        assert_close(
+            g.get_gradient(p).unwrap().data(),
+            reference.get_gradient(rp).unwrap().data(),
+        );
+    }
+}
+
+#[test]
+fn fusion_keeps_nodes_with_other_users() {
+    let mut g = Graph::new();
+    let a = g.add_parameter(Tensor::new(vec![1.0, 2.0], vec![2]).unwrap(), true);
+    let b = g.add_parameter(Tensor::new(vec![4.0, 8.0], vec![2]).unwrap(), true);
+    let shared = g.apply_op(DivideOp, &[a, b]);
+    let left = g.apply_op(ReluOp, &[shared]);
+    let out = g.apply_op(AddOp, &[left, shared]);
+
+    let remap = OperatorFusion.run(&mut g, &[out]).unwrap();
+    assert!(remap.get(shared).is_some());
+    let out = remap.get(out).unwrap();
+    assert_close(g.forward(out).unwrap().data(), &[0.5, 0.5]);
+
+    // A bias that broadcasts the matmul output to a larger shape is not an epilogue.
+    let mut g = Graph::new();
+    let x = g.add_input(Tensor::new(vec![1.0, 2.0], vec![1, 2]).unwrap());
+    let w = g.add_parameter(
+        Tensor::new(vec![1.0, 0.0, 0.0, 1.0], vec![2, 2]).unwrap(),
+        true,
+    );
+    let bias = g.add_parameter(
+        Tensor::new(vec![0.0, 0.0, 10.0, 10.0], vec![2, 2]).unwrap(),
+        true,
+    );
+    let mm = g.apply_op(MatMulOp, &[x, w]);
+    let out = g.apply_op(AddOp, &[mm, bias]);
+    let expected = g.forward(out).unwrap();
+    assert_eq!(expected.shape(), &[2, 2]);
+    let remap = OperatorFusion.run(&mut g, &[out]).unwrap();
+    assert!(remap.get(mm).is_some());
+    assert_eq!(g.forward(remap.get(out).unwrap()).unwrap(), expected);
+}
+
+#[test]
+fn fused_linear_reduces_bias_gradient_over_batch() {
+    let op = FusedLinearOp {
+        activation: Some(Activation::Relu),
+    };
+    let x = Tensor::new(vec![1.0, 2.0, -1.0, 0.5], vec![2, 2]).unwrap();
+    let w = Tensor::new(vec![1.0, -1.0, 0.5, 2.0], vec![2, 2]).unwrap();
+    let b = Tensor::new(vec![0.0, 0.1], vec![1, 2]).unwrap();
+    let inputs = [x, w, b];
+
+    // z = [[2, 3.1], [-0.75, 2.1]] -> relu masks one element.
+    let y = op.forward(&inputs).unwrap();
+    assert_close(y.data(), &[2.0, 3.1, 0.0, 2.1]);
+
+    let grads = op.backward(&inputs, &Tensor::ones_like(&y)).unwrap();
+    assert_eq!(grads[2].shape(), &[1, 2]);
+    assert_close(grads[2].data(), &[1.0, 2.0]);
+}
+
+#[test]
+fn fused_graphs_save_and_load() {
+    let mut g = Graph::new();
+    let a = g.add_parameter(Tensor::new(vec![1.0, 2.0, 4.0], vec![3]).unwrap(), true);
+    let b = g.add_parameter(Tensor::new(vec![0.5, 3.0, -1.0], vec![3]).unwrap(), true);
+    let log_a = g.apply_op(LogOp, &[a]);
+    let diff = g.apply_op(SubtractOp, &[b, log_a]);
+    let h = g.apply_op(ReluOp, &[diff]);
+    let out = g.apply_op(DivideOp, &[h, b]);
+    let expected = g.forward(out).unwrap();
+
+    let remap = OperatorFusion.run(&mut g, &[out]).unwrap();
+    let out = remap.get(out).unwrap();
+    let (text, loaded) = round_trip(&g, &[]);
+    assert!(text.contains("FusedElementwiseOp"), "{text}");
+    assert_close(loaded.forward(out).unwrap().data(), expected.data());
+}
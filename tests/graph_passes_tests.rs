@@ -0,0 +1,152 @@
+use neuroncore::losses::MSELoss;
+use neuroncore::ops::{AddOp, MatMulOp, MultiplyOp, ReluOp, SumOp};
+use neuroncore::passes::{
+    optimize, CommonSubexpressionElimination, ConstantFolding, DeadCodeElimination, GraphPass,
+    PassPipeline,
+};
+use neuroncore::{Graph, Tensor};
+
+#[test]
+fn dce_removes_unreachable_nodes_and_remaps() {
+    let mut g = Graph::new();
+    let a = g.add_parameter(Tensor::new(vec![1.0, 2.0], vec![2]).unwrap(), true);
+    let dead = g.add_input(Tensor::new(vec![5.0], vec![1]).unwrap());
+    let _dead_op = g.apply_op(ReluOp, &[dead]);
+    let out = g.apply_op(ReluOp, &[a]);
+
+    let remap = DeadCodeElimination.run(&mut g, &[out]).unwrap();
+    assert_eq!(remap.get(dead), None);
+    assert_eq!(remap.get(a), Some(0));
+    let out = remap.get(out).unwrap();
+    assert_eq!(out, 1);
+    assert_eq!(g.forward(out).unwrap().data(), &[1.0, 2.0]);
+}
+
+#[test]
+fn constant_folding_replaces_constant_subgraph() {
+    let mut g = Graph::new();
+    let c1 = g.add_constant(Tensor::new(vec![2.0], vec![1]).unwrap());
+    let c2 = g.add_constant(Tensor::new(vec![3.0], vec![1]).unwrap());
+    let c = g.apply_op(MultiplyOp, &[c1, c2]);
+    let p = g.add_parameter(Tensor::new(vec![1.0, 1.0], vec![2]).unwrap(), true);
+    let out = g.apply_op(MultiplyOp, &[p, c]);
+
+    let remap = PassPipeline::new()
+        .with(ConstantFolding)
+        .with(DeadCodeElimination)
+        .run(&mut g, &[out])
+        .unwrap();
+    assert_eq!(remap.get(c1), None);
+    let out = remap.get(out).unwrap();
+    assert_eq!(g.forward(out).unwrap().data(), &[6.0, 6.0]);
+
+    let p = remap.get(p).unwrap();
+    g.backward(out).unwrap();
+    assert_eq!(g.get_gradient(p).unwrap().data(), &[6.0, 6.0]);
+}
+
+#[test]
+fn cse_merges_identical_ops() {
+    let mut g = Graph::new();
+    let x = g.add_input(Tensor::new(vec![1.0, -1.0], vec![1, 2]).unwrap());
+    let w = g.add_parameter(Tensor::new(vec![1.0, 2.0], vec![2, 1]).unwrap(), true);
+    let m1 = g.apply_op(MatMulOp, &[x, w]);
+    let m2 = g.apply_op(MatMulOp, &[x, w]);
+    let out = g.apply_op(AddOp, &[m1, m2]);
+
+    let remap = CommonSubexpressionElimination.run(&mut g, &[out]).unwrap();
+    assert_eq!(remap.get(m1), remap.get(m2));
+    assert_eq!(g.forward(remap.get(out).unwrap()).unwrap().data(), &[-2.0]);
+}
+
+#[test]
+fn cse_respects_op_attributes() {
+    let mut g = Graph::new();
+    let p = g.add_parameter(
+        Tensor::new(vec![1.0, 2.0, 3.0, 4.0], vec![2, 2]).unwrap(),
+        true,
+    );
+    let s0 = g.apply_op(SumOp { dim: Some(0) }, &[p]);
+    let s1 = g.apply_op(SumOp { dim: Some(1) }, &[p]);
+
+    let remap = CommonSubexpressionElimination
+        .run(&mut g, &[s0, s1])
+        .unwrap();
+    assert_ne!(remap.get(s0), remap.get(s1));
+}
+
+#[test]
+fn default_pipeline_collapses_repeated_epochs() {
+    let mut g = Graph::new();
+    let x = g.add_input(Tensor::new(vec![0.5, -0.5], vec![1, 2]).unwrap());
+    let y = g.add_input(Tensor::new(vec![0.75], vec![1, 1]).unwrap());
+    let w = g.add_parameter(Tensor::new(vec![0.1, 0.2], vec![2, 1]).unwrap(), true);
+
+    let mut loss = 0;
+    for _ in 0..3 {
+        let pred = g.apply_op(MatMulOp, &[x, w]);
+        loss = MSELoss::compute(&mut g, pred, y).unwrap();
+    }
+    let expected = g.forward(loss).unwrap();
+    let before = g.len();
+
+    let remap = optimize(&mut g, &[loss]).unwrap();
+    let loss = remap.get(loss).unwrap();
+    // Only the three leaves and a single epoch's nodes survive.
+    let per_epoch = (before - 3) / 3;
+    assert_eq!(g.len(), 3 + per_epoch);
+    assert_eq!(g.forward(loss).unwrap(), expected);
+}
+
+#[test]
+fn inputs_stay_feedable_after_optimize() {
+    let mut g = Graph::new();
+    // Two zero-initialized data inputs must not be merged or folded.
+    let x = g.add_input(Tensor::zeros(vec![2]).unwrap());
+    let y = g.add_input(Tensor::zeros(vec![2]).unwrap());
+    let scale = g.add_constant(Tensor::new(vec![2.0], vec![1]).unwrap());
+    let sx = g.apply_op(MultiplyOp, &[x, scale]);
+    let out = g.apply_op(AddOp, &[sx, y]);
+
+    let remap = optimize(&mut g, &[out]).unwrap();
+    let (x, y, out) = (
+        remap.get(x).unwrap(),
+        remap.get(y).unwrap(),
+        remap.get(out).unwrap(),
+    );
+    assert_ne!(x, y);
+    g.set_input(x, Tensor::new(vec![1.0, 2.0], vec![2]).unwrap())
+        .unwrap();
+    g.set_input(y, Tensor::new(vec![10.0, 20.0], vec![2]).unwrap())
+        .unwrap();
+    assert_eq!(g.forward(out).unwrap().data(), &[12.0, 24.0]);
+    assert!(g
+        .set_input(remap.get(scale).unwrap(), Tensor::zeros(vec![1]).unwrap())
+        .is_err());
+}
+
+#[test]
+fn passes_move_labels_of_merged_nodes_and_drop_removed_ones() {
+    let mut g = Graph::new();
+    let x = g.add_input(Tensor::new(vec![1.0, -1.0], vec![2]).unwrap());
+    let r1 = g.apply_op(ReluOp, &[x]);
+    let r2 = g.apply_op(ReluOp, &[x]);
+    let s1 = g.apply_op(SumOp { dim: None }, &[r1]);
+    let s2 = g.apply_op(SumOp { dim: None }, &[r2]);
+    let out = g.apply_op(AddOp, &[s1, s2]);
+    let dead = g.apply_op(ReluOp, &[out]);
+    g.set_label(r2, "merged");
+    g.set_label(s1, "first");
+    g.set_label(s2, "second");
+    g.set_label(dead, "unused");
+
+    let remap = CommonSubexpressionElimination.run(&mut g, &[out]).unwrap();
+    assert_eq!(g.label(remap.get(r2).unwrap()), Some("merged"));
+    // The surviving node keeps its own label.
+    assert_eq!(g.label(remap.get(s2).unwrap()), Some("first"));
+
+    let (out, dead) = (remap.get(out).unwrap(), remap.get(dead).unwrap());
+    let remap = DeadCodeElimination.run(&mut g, &[out]).unwrap();
+    assert_eq!(remap.get(dead), None);
+    assert!((0..g.len()).all(|idx| g.label(idx) != Some("unused")));
+}
@@ -0,0 +1,129 @@
+use neuroncore::attention::{MultiHeadAttention, TransformerEncoderLayer};
+use neuroncore::conv::ConvTranspose1dOp;
+use neuroncore::init::{fans, fans_for, FanLayout, Init, Nonlinearity};
+use neuroncore::layers::{ConvTranspose1d, Layer, Linear};
+use neuroncore::module::Module;
+use neuroncore::{Graph, Tensor};
+
+fn std_dev(t: &Tensor) -> f32 {
+    let n = t.data().len() as f32;
+    let mean = t.data().iter().sum::<f32>() / n;
+    (t.data().iter().map(|v| (v - mean).powi(2)).sum::<f32>() / n).sqrt()
+}
+
+#[test]
+fn fan_scaled_schemes_respect_bounds_and_variance() {
+    assert_eq!(fans(&[64, 32]), (64, 32));
+    assert_eq!(fans(&[16, 8, 3, 3]), (72, 144));
+    // Transposed convolutions store [in, out, kernel...].
+    assert_eq!(
+        fans_for(&[16, 8, 3, 3], FanLayout::ConvTranspose),
+        (144, 72)
+    );
+    assert_eq!(fans_for(&[64, 32], FanLayout::ConvTranspose), (64, 32));
+
+    // Kaiming bound sqrt(2) * sqrt(3 / (2 * 3)) = 1 from the 2 input channels, not the 50
+    // output channels.
+    let mut graph = Graph::new();
+    let kaiming = Init::KaimingUniform(Nonlinearity::Relu);
+    let op = ConvTranspose1dOp::default();
+    let up =
+        ConvTranspose1d::with_init(&mut graph, 2, 50, 3, op, &kaiming, &Init::Constant(0.0), 4)
+            .unwrap();
+    let w = graph.parameter(up.parameters()[0]).unwrap();
+    assert_eq!(w.shape(), &[2, 50, 3]);
+    let largest = w.data().iter().fold(0.0f32, |m, v| m.max(v.abs()));
+    assert!(largest <= 1.0 + 1e-6 && largest > 0.5, "{largest}");
+
+    let xavier = Tensor::from_init(vec![64, 32], &Init::XavierUniform { gain: 1.0 }, 1).unwrap();
+    let bound = (6.0f32 / 96.0).sqrt();
+    assert!(xavier.data().iter().all(|v| v.abs() <= bound));
+    assert!((std_dev(&xavier) - bound / 3f32.sqrt()).abs() < 0.02);
+
+    let relu = Nonlinearity::Relu;
+    let kaiming = Tensor::from_init(vec![200, 100], &Init::KaimingNormal(relu), 2).unwrap();
+    assert!((std_dev(&kaiming) - relu.gain() / 200f32.sqrt()).abs() < 0.005);
+
+    let normal = Init::Normal {
+        mean: 3.0,
+        std: 0.5,
+    }
+    .tensor(vec![5000], 3)
+    .unwrap();
+    let mean = normal.data().iter().sum::<f32>() / 5000.0;
+    assert!((mean - 3.0).abs() < 0.05);
+    assert!((std_dev(&normal) - 0.5).abs() < 0.05);
+}
+
+#[test]
+fn orthogonal_init_has_orthonormal_rows_or_columns() {
+    for shape in [vec![3, 5], vec![5, 3]] {
+        let w = Tensor::from_init(shape.clone(), &Init::Orthogonal { gain: 2.0 }, 7).unwrap();
+        let gram = if shape[0] <= shape[1] {
+            w.matmul(&w.transpose_2d().unwrap()).unwrap()
+        } else {
+            w.transpose_2d().unwrap().matmul(&w).unwrap()
+        };
+        let n = gram.shape()[0];
+        for i in 0..n {
+            for j in 0..n {
+                let expected = if i == j { 4.0 } else { 0.0 };
+                assert!((gram.data()[i * n + j] - expected).abs() < 1e-4);
+            }
+        }
+    }
+    assert!(Init::Orthogonal { gain: 1.0 }.tensor(vec![4], 1).is_err());
+}
+
+#[test]
+fn layers_accept_any_scheme_deterministically() {
+    let counting = Init::custom(|shape, _| {
+        (0..shape.iter().product::<usize>())
+            .map(|i| i as f32)
+            .collect()
+    });
+    let mut graph = Graph::new();
+    let layer = Linear::with_init(&mut graph, 2, 3, &counting, &Init::Constant(0.1), 5).unwrap();
+    let [w, b] = layer.parameters()[..] else {
+        panic!("two parameters");
+    };
+    assert_eq!(
+        graph.parameter(w).unwrap().data(),
+        &[0.0, 1.0, 2.0, 3.0, 4.0, 5.0]
+    );
+    assert_eq!(graph.parameter(b).unwrap().data(), &[0.1, 0.1, 0.1]);
+
+    let kaiming = Init::KaimingUniform(Nonlinearity::Relu);
+    let a = Linear::with_init(&mut graph, 4, 4, &kaiming, &Init::Constant(0.0), 9).unwrap();
+    let c = Linear::with_init(&mut graph, 4, 4, &kaiming, &Init::Constant(0.0), 9).unwrap();
+    assert_eq!(
+        graph.parameter(a.parameters()[0]).unwrap(),
+        graph.parameter(c.parameters()[0]).unwrap()
+    );
+    assert_eq!(format!("{counting:?}"), "Custom(..)");
+
+    let half = Init::Constant(0.5);
+    let zero = Init::Constant(0.0);
+    let attn = MultiHeadAttention::with_init(&mut graph, 4, 2, &half, &zero, 1).unwrap();
+    let block = TransformerEncoderLayer::with_init(&mut graph, 4, 2, 8, &half, &zero, 1).unwrap();
+    for (name, idx) in attn
+        .named_parameters()
+        .into_iter()
+        .chain(block.named_parameters())
+    {
+        let value = graph.parameter(idx).unwrap().data()[0];
+        let expected = match name.as_str() {
+            n if n.starts_with("norm") && n.ends_with("weight") => 1.0,
+            n if n.ends_with("weight") => 0.5,
+            _ => 0.0,
+        };
+        assert_eq!(value, expected, "{name}");
+    }
+    // `new` keeps its per-layer default scheme.
+    let reference = MultiHeadAttention::new(&mut graph, 4, 2, 1).unwrap();
+    let default = Linear::new(&mut graph, 4, 4, 1).unwrap();
+    assert_eq!(
+        graph.parameter(reference.parameters()[0]).unwrap(),
+        graph.parameter(default.parameters()[0]).unwrap()
+    );
+}
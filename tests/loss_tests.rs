@@ -0,0 +1,124 @@
+use neuroncore::losses::{CrossEntropyLoss, CrossEntropyOp, CrossEntropyOptions, Reduction};
+use neuroncore::shape::Dim;
+use neuroncore::{Graph, Op, Tensor};
+
+mod common;
+use common::{batched, check_gradients, round_trip};
+
+#[test]
+fn cross_entropy_with_indices_matches_one_hot_and_stays_finite() {
+    let logits = Tensor::new(vec![2.0, 0.5, -1.0, 0.1, 0.2, 3.0], vec![2, 3]).unwrap();
+    let mut graph = Graph::new();
+    let z = graph.add_input(logits.clone());
+    let one_hot =
+        graph.add_input(Tensor::new(vec![1.0, 0.0, 0.0, 0.0, 0.0, 1.0], vec![2, 3]).unwrap());
+    let classes = graph.add_input(Tensor::new(vec![0.0, 2.0], vec![2]).unwrap());
+    let reference = CrossEntropyLoss::compute(&mut graph, z, one_hot).unwrap();
+    let options = |reduction| CrossEntropyOptions {
+        reduction,
+        ..CrossEntropyOptions::default()
+    };
+    let sum =
+        CrossEntropyLoss::with_indices(&mut graph, z, classes, &options(Reduction::Sum)).unwrap();
+    let mean =
+        CrossEntropyLoss::with_indices(&mut graph, z, classes, &options(Reduction::Mean)).unwrap();
+    let none =
+        CrossEntropyLoss::with_indices(&mut graph, z, classes, &options(Reduction::None)).unwrap();
+
+    let expected = graph.forward(reference).unwrap().data()[0];
+    assert!((graph.forward(sum).unwrap().data()[0] - expected).abs() < 1e-5);
+    assert!((graph.forward(mean).unwrap().data()[0] - expected / 2.0).abs() < 1e-5);
+    let per_sample = graph.forward(none).unwrap();
+    assert_eq!(per_sample.shape(), &[2]);
+    assert!((per_sample.data().iter().sum::<f32>() - expected).abs() < 1e-5);
+
+    // Logits that overflow exp() in an unfused softmax.
+    let huge = Tensor::new(vec![1000.0, 0.0, -1000.0], vec![1, 3]).unwrap();
+    let target = Tensor::new(vec![1.0], vec![1]).unwrap();
+    let op = CrossEntropyOp::default();
+    let loss = op.forward(&[huge.clone(), target.clone()]).unwrap();
+    assert!((loss.data()[0] - 1000.0).abs() < 1e-3);
+    let grad = op
+        .backward(&[huge, target], &Tensor::ones(vec![1]).unwrap())
+        .unwrap();
+    assert_eq!(grad[0].data(), &[1.0, -1.0, 0.0]);
+}
+
+#[test]
+fn weighted_smoothed_cross_entropy_matches_finite_differences() {
+    // [batch, time, classes] logits with per-step targets; step (1, 0) is ignored.
+    let logits = Tensor::random(vec![2, 3, 4], 5).unwrap();
+    let targets = Tensor::new(vec![0.0, 3.0, 1.0, 255.0, 2.0, 3.0], vec![2, 3]).unwrap();
+    let weights = Tensor::new(vec![0.5, 1.0, 2.0, 4.0], vec![4]).unwrap();
+    for reduction in [Reduction::Mean, Reduction::Sum, Reduction::None] {
+        let op = CrossEntropyOp {
+            label_smoothing: 0.1,
+            ignore_index: Some(255),
+            reduction,
+        };
+        let inputs = [logits.clone(), targets.clone(), weights.clone()];
+        check_gradients(&op, &inputs, 1);
+        if reduction == Reduction::None {
+            assert_eq!(op.forward(&inputs).unwrap().data()[3], 0.0);
+        }
+    }
+
+    // Mean divides by the weights of the counted targets: 0.5 + 4 + 1 + 2 + 4.
+    let op = |reduction| CrossEntropyOp {
+        ignore_index: Some(255),
+        reduction,
+        ..CrossEntropyOp::default()
+    };
+    let inputs = [logits, targets, weights];
+    let sum = op(Reduction::Sum).forward(&inputs).unwrap().data()[0];
+    let mean = op(Reduction::Mean).forward(&inputs).unwrap().data()[0];
+    assert!((mean - sum / 11.5).abs() < 1e-5);
+}
+
+#[test]
+fn cross_entropy_edge_cases_shapes_and_serialization() {
+    // Uniform logits: every target distribution costs ln(classes).
+    let uniform = Tensor::zeros(vec![1, 4]).unwrap();
+    let target = Tensor::new(vec![2.0], vec![1]).unwrap();
+    let smoothed = CrossEntropyOp {
+        label_smoothing: 0.3,
+        ..CrossEntropyOp::default()
+    };
+    let loss = smoothed
+        .forward(&[uniform.clone(), target.clone()])
+        .unwrap();
+    assert!((loss.data()[0] - 4f32.ln()).abs() < 1e-6);
+
+    // Nothing to average is a zero loss, not NaN.
+    let ignored = CrossEntropyOp {
+        ignore_index: Some(2),
+        ..CrossEntropyOp::default()
+    };
+    let loss = ignored.forward(&[uniform.clone(), target.clone()]).unwrap();
+    assert_eq!(loss.data(), &[0.0]);
+    let bad = Tensor::new(vec![4.0], vec![1]).unwrap();
+    assert!(CrossEntropyOp::default().forward(&[uniform, bad]).is_err());
+    assert_eq!("sum".parse::<Reduction>().unwrap(), Reduction::Sum);
+    assert!("average".parse::<Reduction>().is_err());
+
+    let mut graph = Graph::new();
+    let logits = graph.add_input(Tensor::random(vec![3, 5], 1).unwrap());
+    let classes = graph.add_input(Tensor::new(vec![0.0, 4.0, 1.0], vec![3]).unwrap());
+    let options = CrossEntropyOptions {
+        class_weights: Some(vec![1.0, 2.0, 1.0, 1.0, 3.0]),
+        label_smoothing: 0.05,
+        ignore_index: Some(1),
+        reduction: Reduction::None,
+    };
+    let loss = CrossEntropyLoss::with_indices(&mut graph, logits, classes, &options).unwrap();
+    let symbolic = graph
+        .check_symbolic(loss, &[(logits, batched(&[5])), (classes, batched(&[]))])
+        .unwrap();
+    assert_eq!(symbolic, batched(&[]));
+
+    assert_eq!(graph.forward(loss).unwrap().shape(), &[3]);
+    let (text, loaded) = round_trip(&graph, &[logits, classes]);
+    assert!(text.contains("reduction=none"));
+    assert_eq!(loaded.check(loss).unwrap(), vec![Dim::Fixed(3)]);
+    assert_eq!(loaded.forward(loss).unwrap(), graph.forward(loss).unwrap());
+}
@@ -0,0 +1,149 @@
+use neuroncore::layers::{Layer, Linear};
+use neuroncore::module::{matches_pattern, Module};
+use neuroncore::{ComputeError, Graph, Tensor};
+
+struct Stack(Vec<Linear>);
+
+impl Module for Stack {
+    fn own_parameters(&self) -> Vec<(String, usize)> {
+        Vec::new()
+    }
+
+    fn children(&self) -> Vec<(String, &dyn Module)> {
+        self.0
+            .iter()
+            .enumerate()
+            .map(|(i, l)| (i.to_string(), l as &dyn Module))
+            .collect()
+    }
+}
+
+struct Model {
+    encoder: Stack,
+    head: Linear,
+}
+
+impl Model {
+    fn new(graph: &mut Graph, seed: u32) -> Self {
+        Self {
+            encoder: Stack(vec![
+                Linear::new(graph, 3, 4, seed).unwrap(),
+                Linear::new(graph, 4, 4, seed + 1).unwrap(),
+            ]),
+            head: Linear::new(graph, 4, 1, seed + 2).unwrap(),
+        }
+    }
+}
+
+impl Module for Model {
+    fn own_parameters(&self) -> Vec<(String, usize)> {
+        Vec::new()
+    }
+
+    fn children(&self) -> Vec<(String, &dyn Module)> {
+        vec![
+            ("encoder".to_string(), &self.encoder as &dyn Module),
+            ("head".to_string(), &self.head),
+        ]
+    }
+}
+
+#[test]
+fn named_parameters_are_hierarchical_and_counted() {
+    let mut graph = Graph::new();
+    let model = Model::new(&mut graph, 3);
+    let names: Vec<String> = model
+        .named_parameters()
+        .into_iter()
+        .map(|(n, _)| n)
+        .collect();
+    assert_eq!(
+        names,
+        [
+            "encoder.0.weight",
+            "encoder.0.bias",
+            "encoder.1.weight",
+            "encoder.1.bias",
+            "head.weight",
+            "head.bias"
+        ]
+    );
+    assert_eq!(
+        model.num_parameters(&graph).unwrap(),
+        12 + 4 + 16 + 4 + 4 + 1
+    );
+}
+
+#[test]
+fn state_dict_moves_weights_between_graphs() {
+    let mut source = Graph::new();
+    let trained = Model::new(&mut source, 3);
+    let state = trained.state_dict(&source).unwrap();
+
+    // A different graph layout: an extra input shifts every node index.
+    let mut target = Graph::new();
+    let x = target.add_input(Tensor::new(vec![1.0, -2.0, 0.5], vec![1, 3]).unwrap());
+    let fresh = Model::new(&mut target, 99);
+    assert_ne!(fresh.state_dict(&target).unwrap(), state);
+    fresh.load_state_dict(&mut target, &state).unwrap();
+    assert_eq!(fresh.state_dict(&target).unwrap(), state);
+
+    let h = fresh.encoder.0[0].forward(&mut target, x).unwrap();
+    assert_eq!(target.forward(h).unwrap().shape(), &[1, 4]);
+
+    let mut partial = state.clone();
+    partial.remove("head.bias");
+    let err = fresh.load_state_dict(&mut target, &partial).unwrap_err();
+    assert!(err.to_string().contains("missing parameter head.bias"));
+
+    let mut wrong = state;
+    wrong.insert("head.bias".to_string(), Tensor::zeros(vec![1, 2]).unwrap());
+    let err = fresh.load_state_dict(&mut target, &wrong).unwrap_err();
+    let ComputeError::StateDict {
+        key, expected, got, ..
+    } = &err
+    else {
+        panic!("expected a state dict error, got {err}");
+    };
+    assert_eq!(key, "head.bias");
+    assert_eq!(expected.as_deref(), Some(&[1, 1][..]));
+    assert_eq!(got.as_deref(), Some(&[1, 2][..]));
+    assert!(std::error::Error::source(&err).is_some());
+}
+
+#[test]
+fn freezing_by_pattern_excludes_parameters_from_training() {
+    let mut graph = Graph::new();
+    let model = Model::new(&mut graph, 5);
+    assert_eq!(model.freeze(&mut graph, "encoder.*").unwrap(), 4);
+    assert_eq!(model.num_trainable_parameters(&graph).unwrap(), 5);
+    assert_eq!(model.unfreeze(&mut graph, "*.1.bias").unwrap(), 1);
+    assert_eq!(model.num_trainable_parameters(&graph).unwrap(), 9);
+
+    assert!(matches_pattern("*.bias", "head.bias"));
+    assert!(matches_pattern("encoder.*.weight", "encoder.10.weight"));
+    assert!(!matches_pattern("encoder.*.weight", "encoder.1.bias"));
+    assert!(!matches_pattern("head.weight", "head.weight2"));
+}
+
+#[test]
+fn failed_state_dict_load_leaves_parameters_unchanged() {
+    let mut source = Graph::new();
+    let trained = Model::new(&mut source, 3);
+    let state = trained.state_dict(&source).unwrap();
+
+    let mut target = Graph::new();
+    let fresh = Model::new(&mut target, 99);
+    let before = fresh.state_dict(&target).unwrap();
+
+    // `head` is loaded last, after every encoder parameter passed its checks.
+    let mut wrong = state.clone();
+    wrong.insert("head.bias".to_string(), Tensor::zeros(vec![1, 2]).unwrap());
+    assert!(fresh.load_state_dict(&mut target, &wrong).is_err());
+    assert_eq!(fresh.state_dict(&target).unwrap(), before);
+
+    let mut partial = state;
+    partial.remove("head.weight");
+    assert!(fresh.load_state_dict(&mut target, &partial).is_err());
+    assert_eq!(fresh.state_dict(&target).unwrap(), before);
+}
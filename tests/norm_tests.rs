@@ -0,0 +1,154 @@
+use neuroncore::layers::{Layer, Linear, Sequential};
+use neuroncore::module::Module;
+use neuroncore::norm::{BatchNorm1d, BatchNormOp, GroupNorm, GroupNormOp, LayerNormOp};
+use neuroncore::ops::SumOp;
+use neuroncore::passes::{optimize, DeadCodeElimination, GraphPass};
+use neuroncore::{Graph, Op, Tensor};
+
+mod common;
+use common::{batched, check_gradients_within};
+
+#[test]
+fn group_and_batch_norm_match_finite_differences() {
+    let x = Tensor::random(vec![2, 4, 3], 1).unwrap();
+    let gamma = Tensor::random(vec![4], 2).unwrap();
+    let beta = Tensor::random(vec![4], 3).unwrap();
+    check_gradients_within(
+        &GroupNormOp::new(2),
+        &[x.clone(), gamma.clone(), beta.clone()],
+        3,
+        2e-2,
+    );
+    check_gradients_within(&GroupNormOp::new(4), std::slice::from_ref(&x), 1, 2e-2);
+
+    let mean = Tensor::random(vec![4], 4).unwrap();
+    let var = Tensor::ones(vec![4]).unwrap();
+    for mode in [1.0, 0.0] {
+        let flag = Tensor::new(vec![mode], vec![1]).unwrap();
+        let inputs = [
+            x.clone(),
+            gamma.clone(),
+            beta.clone(),
+            mean.clone(),
+            var.clone(),
+            flag,
+        ];
+        // Running statistics are buffers, not trained.
+        check_gradients_within(&BatchNormOp::default(), &inputs, 3, 2e-2);
+    }
+
+    // One group over every channel of a [batch, channels, 1] input is a layer norm.
+    let rows = Tensor::random(vec![3, 5], 5).unwrap();
+    let as_channels = Tensor::new(rows.data().to_vec(), vec![3, 5, 1]).unwrap();
+    let group = GroupNormOp::new(1).forward(&[as_channels]).unwrap();
+    let layer = LayerNormOp::default().forward(&[rows]).unwrap();
+    for (a, b) in group.data().iter().zip(layer.data()) {
+        assert!((a - b).abs() < 1e-5);
+    }
+}
+
+#[test]
+fn batch_norm_tracks_running_statistics_and_switches_mode() {
+    let mut graph = Graph::new();
+    // Two channels on very different scales.
+    let batch = Tensor::new(
+        vec![1.0, 1000.0, 3.0, 3000.0, 5.0, 2000.0, 7.0, 6000.0],
+        vec![4, 2],
+    )
+    .unwrap();
+    let x = graph.add_input(batch);
+    let bn = BatchNorm1d::new(&mut graph, 2).unwrap();
+    let y = bn.forward(&mut graph, x).unwrap();
+
+    // Training mode: each channel of the batch is standardized.
+    let out = graph.forward(y).unwrap();
+    for c in 0..2 {
+        let column: Vec<f32> = out.data().iter().skip(c).step_by(2).copied().collect();
+        let mean = column.iter().sum::<f32>() / 4.0;
+        let var = column.iter().map(|v| (v - mean).powi(2)).sum::<f32>() / 4.0;
+        assert!(
+            mean.abs() < 1e-4 && (var - 1.0).abs() < 1e-3,
+            "{mean} {var}"
+        );
+    }
+
+    // The update mixes 10% of the batch mean and unbiased variance into the buffers.
+    assert_eq!(graph.update_buffers().unwrap(), 2);
+    let state = bn.state_dict(&graph).unwrap();
+    let mean = state["running_mean"].data();
+    assert!((mean[0] - 0.4).abs() < 1e-5 && (mean[1] - 300.0).abs() < 1e-2);
+    let var = state["running_var"].data();
+    assert!((var[0] - (0.9 + 0.1 * 20.0 / 3.0)).abs() < 1e-4);
+
+    // Evaluation mode uses the running statistics and leaves them alone.
+    bn.eval(&mut graph).unwrap();
+    let out = graph.forward(y).unwrap();
+    let expected = (1.0 - mean[0]) / (var[0] + 1e-5).sqrt();
+    assert!((out.data()[0] - expected).abs() < 1e-4);
+    graph.update_buffers().unwrap();
+    assert_eq!(bn.state_dict(&graph).unwrap(), state);
+
+    bn.train(&mut graph, true).unwrap();
+    assert!(graph.forward(y).unwrap().data()[0] < -1.0);
+}
+
+#[test]
+fn norm_layers_nest_in_modules_with_symbolic_batches() {
+    let mut graph = Graph::new();
+    let x = graph.add_input(Tensor::random(vec![6, 3], 7).unwrap());
+    let model = Sequential::new()
+        .with(Linear::new(&mut graph, 3, 4, 1).unwrap())
+        .with(BatchNorm1d::new(&mut graph, 4).unwrap());
+    let y = model.forward(&mut graph, x).unwrap();
+    let names: Vec<String> = model.named_buffers().into_iter().map(|(n, _)| n).collect();
+    assert_eq!(names, ["1.running_mean", "1.running_var"]);
+    assert_eq!(model.state_dict(&graph).unwrap().len(), 6);
+    assert_eq!(model.num_parameters(&graph).unwrap(), 3 * 4 + 4 + 4 + 4);
+
+    // Switching the container switches the nested batch norm.
+    let train_out = graph.forward(y).unwrap();
+    model.eval(&mut graph).unwrap();
+    assert_ne!(graph.forward(y).unwrap(), train_out);
+    model.train(&mut graph, true).unwrap();
+    assert_eq!(graph.forward(y).unwrap(), train_out);
+
+    let seq = graph.add_input(Tensor::random(vec![2, 4, 5], 8).unwrap());
+    let norm = GroupNorm::new(&mut graph, 2, 4).unwrap();
+    let out = norm.forward(&mut graph, seq).unwrap();
+    let symbolic = graph
+        .check_symbolic(out, &[(seq, batched(&[4, 5]))])
+        .unwrap();
+    assert_eq!(symbolic, batched(&[4, 5]));
+    assert!(GroupNorm::new(&mut graph, 3, 4).is_err());
+
+    let loss = graph.apply_op(SumOp { dim: None }, &[out]);
+    graph.backward(loss).unwrap();
+    for (name, idx) in norm.named_parameters() {
+        assert!(graph.get_gradient(idx).is_some(), "{name}");
+    }
+}
+
+#[test]
+fn running_statistics_survive_graph_passes() {
+    let mut graph = Graph::new();
+    let x = graph.add_input(Tensor::random(vec![4, 3], 5).unwrap());
+    let bn = BatchNorm1d::new(&mut graph, 3).unwrap();
+    // Applying the layer again replaces its updates instead of adding more.
+    bn.forward(&mut graph, x).unwrap();
+    let y = bn.forward(&mut graph, x).unwrap();
+    assert_eq!(graph.buffer_updates().len(), 2);
+    let loss = graph.apply_op(SumOp { dim: None }, &[y]);
+
+    // The statistics updates do not feed the loss, but passes keep them as roots.
+    let remap = DeadCodeElimination.run(&mut graph, &[loss]).unwrap();
+    let remap = remap.then(&optimize(&mut graph, &[remap.get(loss).unwrap()]).unwrap());
+    assert_eq!(graph.buffer_updates().len(), 2);
+    assert_eq!(graph.update_buffers().unwrap(), 2);
+    let mean = remap.get(bn.named_buffers()[0].1).unwrap();
+    assert!(graph
+        .parameter(mean)
+        .unwrap()
+        .data()
+        .iter()
+        .any(|v| *v != 0.0));
+}
@@ -0,0 +1,119 @@
+use neuroncore::custom::MacroOp;
+use neuroncore::numeric::NumericPolicy;
+use neuroncore::ops::{DivideOp, InvertibleOp, MultiplyOp, Op, SqrtOp};
+use neuroncore::plan::ExecutionPlan;
+use neuroncore::{ComputeError, Graph, Tensor};
+
+fn singular_index(err: &ComputeError) -> Option<usize> {
+    match err.root_cause() {
+        ComputeError::SingularPoint { index, .. } => Some(*index),
+        _ => None,
+    }
+}
+
+#[test]
+fn divide_follows_the_selected_policy() {
+    let a = Tensor::new(vec![1.0, -1.0, 0.0, 6.0], vec![4]).unwrap();
+    let b = Tensor::new(vec![0.0, 0.0, 0.0, 3.0], vec![4]).unwrap();
+
+    let ieee = a.divide_with(&b, NumericPolicy::Ieee).unwrap();
+    assert_eq!(&ieee.data()[..2], &[f32::INFINITY, f32::NEG_INFINITY]);
+    assert!(ieee.data()[2].is_nan());
+
+    let nan = a.divide_with(&b, NumericPolicy::Nan).unwrap();
+    assert!(nan.data()[..3].iter().all(|v| v.is_nan()));
+    assert_eq!(nan.data()[3], 2.0);
+
+    let err = a.divide_with(&b, NumericPolicy::Error).unwrap_err();
+    assert_eq!(singular_index(&err), Some(0));
+
+    let eps = a.divide_with(&b, NumericPolicy::Epsilon(0.5)).unwrap();
+    assert_eq!(eps.data(), &[2.0, -2.0, 0.0, 2.0]);
+}
+
+#[test]
+fn log_and_sqrt_follow_the_selected_policy() {
+    let x = Tensor::new(vec![4.0, 0.0, -1.0], vec![3]).unwrap();
+
+    let log = x.log_with(NumericPolicy::Ieee).unwrap();
+    assert_eq!(log.data()[1], f32::NEG_INFINITY);
+    assert!(x.log_with(NumericPolicy::Nan).unwrap().data()[1].is_nan());
+    assert_eq!(
+        singular_index(&x.log_with(NumericPolicy::Error).unwrap_err()),
+        Some(1)
+    );
+    assert_eq!(
+        x.log_with(NumericPolicy::Epsilon(1.0)).unwrap().data(),
+        &[4.0f32.ln(), 0.0, 0.0]
+    );
+
+    assert_eq!(
+        singular_index(&x.sqrt_with(NumericPolicy::Error).unwrap_err()),
+        Some(2)
+    );
+    assert_eq!(
+        x.sqrt_with(NumericPolicy::Epsilon(0.25)).unwrap().data(),
+        &[2.0, 0.5, 0.5]
+    );
+
+    let positive = Tensor::new(vec![4.0, 9.0], vec![2]).unwrap();
+    let grad = Tensor::ones_like(&positive);
+    assert_eq!(
+        SqrtOp
+            .forward(std::slice::from_ref(&positive))
+            .unwrap()
+            .data(),
+        &[2.0, 3.0]
+    );
+    let dx = SqrtOp.backward(&[positive], &grad).unwrap();
+    assert_eq!(dx[0].data(), &[0.25, 1.0 / 6.0]);
+}
+
+#[test]
+fn error_policy_reports_singular_points_in_ops_and_inversions() {
+    let b = Tensor::new(vec![2.0, 0.0], vec![2]).unwrap();
+    let out = Tensor::new(vec![4.0, 5.0], vec![2]).unwrap();
+    let err = MultiplyOp
+        .invert_with(&out, &[None, Some(&b)], 0, NumericPolicy::Error)
+        .unwrap_err();
+    assert_eq!(singular_index(&err), Some(1));
+    let ieee = MultiplyOp.invert(&out, &[None, Some(&b)], 0).unwrap();
+    assert_eq!(ieee.data()[1], f32::INFINITY);
+
+    let a = Tensor::new(vec![3.0, 0.0], vec![2]).unwrap();
+    let zero_out = Tensor::new(vec![1.0, 0.0], vec![2]).unwrap();
+    let err = DivideOp
+        .invert_with(&zero_out, &[Some(&a), None], 1, NumericPolicy::Error)
+        .unwrap_err();
+    assert_eq!(singular_index(&err), Some(1));
+
+    // The policy belongs to the graph: a second graph keeps IEEE semantics.
+    let mut strict = Graph::new();
+    strict.set_numeric_policy(NumericPolicy::Error);
+    let x = strict.add_input(out.clone());
+    let y = strict.add_input(b.clone());
+    let q = strict.apply_op(DivideOp, &[x, y]);
+    let err = strict.forward(q).unwrap_err();
+    assert_eq!(err.node_context().unwrap().op, "DivideOp");
+    assert_eq!(singular_index(&err), Some(1));
+
+    let mut lenient = Graph::new();
+    let x = lenient.add_input(out);
+    let y = lenient.add_input(b);
+    let q = lenient.apply_op(DivideOp, &[x, y]);
+    assert_eq!(lenient.numeric_policy(), NumericPolicy::Ieee);
+    assert_eq!(lenient.forward(q).unwrap().data(), &[2.0, f32::INFINITY]);
+
+    // Plans and macro op bodies run under the settings of the graph they belong to.
+    lenient.set_numeric_policy(NumericPolicy::Error);
+    let mut plan = ExecutionPlan::compile(&lenient, q, &[]).unwrap();
+    assert_eq!(
+        singular_index(&plan.run(&lenient, &[]).unwrap_err()),
+        Some(1)
+    );
+    let ratio = MacroOp::new("ratio", 2, |g, p| Ok(g.apply_op(DivideOp, p))).unwrap();
+    let m = lenient.apply_op(ratio, &[x, y]);
+    assert!(lenient.forward(m).is_err());
+    lenient.set_numeric_policy(NumericPolicy::Epsilon(0.5));
+    assert_eq!(lenient.forward(m).unwrap().data(), &[2.0, 10.0]);
+}
@@ -0,0 +1,109 @@
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex};
+use std::thread::ThreadId;
+
+use neuroncore::layers::{Layer, Linear};
+use neuroncore::ops::{AddOp, ReluOp, SumOp};
+use neuroncore::{ComputeError, Graph, Op, Tensor};
+
+/// Identity op that records which thread ran it.
+struct TraceOp(Arc<Mutex<HashSet<ThreadId>>>);
+
+impl Op for TraceOp {
+    fn forward(&self, inputs: &[Tensor]) -> Result<Tensor, ComputeError> {
+        self.0.lock().unwrap().insert(std::thread::current().id());
+        Ok(inputs[0].clone())
+    }
+
+    fn backward(&self, _inputs: &[Tensor], grad: &Tensor) -> Result<Vec<Tensor>, ComputeError> {
+        Ok(vec![grad.clone()])
+    }
+}
+
+/// One small MLP branch per sensor group, summed into a shared head.
+fn build(g: &mut Graph, trace: &Arc<Mutex<HashSet<ThreadId>>>) -> (usize, Vec<usize>) {
+    let mut params = Vec::new();
+    let mut fused = None;
+    for branch in 0..4u32 {
+        let data = (0..6).map(|i| ((i + branch) as f32 * 0.41).cos()).collect();
+        let x = g.add_input(Tensor::new(data, vec![1, 6]).unwrap());
+        let l1 = Linear::new(g, 6, 8, 100 + branch).unwrap();
+        let l2 = Linear::new(g, 8, 4, 200 + branch).unwrap();
+        let h = g.apply_op(TraceOp(trace.clone()), &[x]);
+        let h = l1.forward(g, h).unwrap();
+        let h = g.apply_op(ReluOp, &[h]);
+        let h = l2.forward(g, h).unwrap();
+        params.extend(l1.parameters());
+        params.extend(l2.parameters());
+        fused = Some(match fused {
+            Some(acc) => g.apply_op(AddOp, &[acc, h]),
+            None => h,
+        });
+    }
+    let head = Linear::new(g, 4, 1, 7).unwrap();
+    let out = head.forward(g, fused.unwrap()).unwrap();
+    params.extend(head.parameters());
+    (g.apply_op(SumOp { dim: None }, &[out]), params)
+}
+
+#[test]
+fn parallel_backward_matches_sequential_exactly() {
+    let trace = Arc::new(Mutex::new(HashSet::new()));
+    let mut sequential = Graph::new();
+    let (loss, params) = build(&mut sequential, &trace);
+    sequential.backward(loss).unwrap();
+
+    let mut parallel = Graph::new();
+    parallel.set_num_threads(4);
+    let (p_loss, p_params) = build(&mut parallel, &trace);
+    assert_eq!(
+        parallel.forward(p_loss).unwrap(),
+        sequential.forward(loss).unwrap()
+    );
+    parallel.backward(p_loss).unwrap();
+
+    for (&a, &b) in params.iter().zip(&p_params) {
+        assert_eq!(sequential.get_gradient(a), parallel.get_gradient(b));
+    }
+}
+
+#[test]
+fn independent_branches_run_on_several_threads() {
+    let trace = Arc::new(Mutex::new(HashSet::new()));
+    let mut g = Graph::new();
+    g.set_num_threads(4);
+    let (loss, _) = build(&mut g, &trace);
+    g.forward(loss).unwrap();
+    assert!(trace.lock().unwrap().len() > 1);
+    // Workers persist across calls instead of being spawned for every wave.
+    for _ in 0..5 {
+        g.forward(loss).unwrap();
+        g.backward(loss).unwrap();
+    }
+    assert!(trace.lock().unwrap().len() <= 4);
+    g.set_num_threads(1);
+    assert_eq!(g.num_threads(), 1);
+
+    let sequential_trace = Arc::new(Mutex::new(HashSet::new()));
+    let mut s = Graph::new();
+    let (s_loss, _) = build(&mut s, &sequential_trace);
+    s.forward(s_loss).unwrap();
+    assert_eq!(
+        *sequential_trace.lock().unwrap(),
+        HashSet::from([std::thread::current().id()])
+    );
+}
+
+#[test]
+fn parallel_errors_name_the_first_failing_node() {
+    let mut g = Graph::new();
+    g.set_num_threads(2);
+    let a = g.add_input(Tensor::zeros(vec![2]).unwrap());
+    let b = g.add_input(Tensor::zeros(vec![3]).unwrap());
+    let first = g.apply_op(AddOp, &[a, b]);
+    let second = g.apply_op(AddOp, &[b, a]);
+    let out = g.apply_op(AddOp, &[first, second]);
+
+    let err = g.forward(out).unwrap_err();
+    assert_eq!(err.node_context().map(|c| c.node), Some(first));
+}
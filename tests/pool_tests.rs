@@ -0,0 +1,149 @@
+use neuroncore::conv::Conv2dOp;
+use neuroncore::layers::{AdaptiveAvgPool2d, Conv2d, Layer, MaxPool2d, Relu, Sequential};
+use neuroncore::ops::SumOp;
+use neuroncore::pool::{
+    AdaptiveAvgPool1dOp, AdaptiveAvgPool2dOp, AvgPool1dOp, MaxPool1dOp, MaxPool2dOp,
+};
+use neuroncore::serialize::{self, OpRegistry};
+use neuroncore::shape::Dim;
+use neuroncore::{Graph, Op, Tensor};
+
+#[test]
+fn max_pool_routes_gradients_to_the_argmax() {
+    let x = Tensor::new(
+        vec![
+            1.0, 5.0, 2.0, 0.0, //
+            3.0, 4.0, 9.0, 9.0, //
+            7.0, 0.0, 1.0, 1.0, //
+            0.0, 8.0, 2.0, 6.0,
+        ],
+        vec![1, 1, 4, 4],
+    )
+    .unwrap();
+    let op = MaxPool2dOp::new([2, 2]);
+    let y = op.forward(std::slice::from_ref(&x)).unwrap();
+    assert_eq!(y.shape(), &[1, 1, 2, 2]);
+    assert_eq!(y.data(), &[5.0, 9.0, 8.0, 6.0]);
+
+    let grad = Tensor::new(vec![1.0, 2.0, 3.0, 4.0], vec![1, 1, 2, 2]).unwrap();
+    let gx = op.backward(&[x], &grad).unwrap();
+    // Ties go to the first maximum in row-major order.
+    assert_eq!(
+        gx[0].data(),
+        &[
+            0.0, 1.0, 0.0, 0.0, //
+            0.0, 0.0, 2.0, 0.0, //
+            0.0, 0.0, 0.0, 0.0, //
+            0.0, 3.0, 0.0, 4.0,
+        ]
+    );
+
+    // Overlapping windows accumulate; padding is never selected.
+    let x = Tensor::new(vec![-1.0, -3.0, -2.0], vec![1, 1, 3]).unwrap();
+    let op = MaxPool1dOp {
+        kernel: 2,
+        stride: 1,
+        padding: 1,
+    };
+    let y = op.forward(std::slice::from_ref(&x)).unwrap();
+    assert_eq!(y.data(), &[-1.0, -1.0, -2.0, -2.0]);
+    let gx = op.backward(&[x], &Tensor::ones_like(&y)).unwrap();
+    assert_eq!(gx[0].data(), &[2.0, 0.0, 2.0]);
+}
+
+#[test]
+fn average_pools_divide_by_real_elements() {
+    let x = Tensor::new(vec![1.0, 2.0, 3.0, 4.0, 5.0], vec![1, 1, 5]).unwrap();
+    let padded = AvgPool1dOp {
+        kernel: 2,
+        stride: 2,
+        padding: 1,
+    };
+    let y = padded.forward(std::slice::from_ref(&x)).unwrap();
+    assert_eq!(y.data(), &[1.0, 2.5, 4.5]);
+    let gx = padded
+        .backward(std::slice::from_ref(&x), &Tensor::ones_like(&y))
+        .unwrap();
+    assert_eq!(gx[0].data(), &[1.0, 0.5, 0.5, 0.5, 0.5]);
+
+    // Five inputs onto three outputs: windows [0, 2), [1, 4), [3, 5).
+    let adaptive = AdaptiveAvgPool1dOp { output: 3 };
+    let y = adaptive.forward(std::slice::from_ref(&x)).unwrap();
+    assert_eq!(y.data(), &[1.5, 3.0, 4.5]);
+    let gx = adaptive.backward(&[x], &Tensor::ones_like(&y)).unwrap();
+    let third = 1.0 / 3.0;
+    assert_eq!(gx[0].data(), &[0.5, 0.5 + third, third, third + 0.5, 0.5]);
+
+    let global = AdaptiveAvgPool2dOp { output: [1, 1] };
+    let x = Tensor::random(vec![2, 3, 4, 5], 1).unwrap();
+    let y = global.forward(std::slice::from_ref(&x)).unwrap();
+    assert_eq!(y.shape(), &[2, 3, 1, 1]);
+    let first: f32 = x.data()[..20].iter().sum::<f32>() / 20.0;
+    assert!((y.data()[0] - first).abs() < 1e-6);
+}
+
+#[test]
+fn conv_pool_stack_infers_shapes_and_round_trips() {
+    let mut graph = Graph::new();
+    let x = graph.add_input(Tensor::random(vec![2, 1, 8, 8], 3).unwrap());
+    let features = Sequential::new()
+        .with(
+            Conv2d::new(
+                &mut graph,
+                1,
+                4,
+                [3, 3],
+                Conv2dOp {
+                    padding: [1, 1],
+                    ..Default::default()
+                },
+                4,
+            )
+            .unwrap(),
+        )
+        .with(Relu)
+        .with(MaxPool2d(MaxPool2dOp::new([2, 2])))
+        .with(AdaptiveAvgPool2d(AdaptiveAvgPool2dOp { output: [1, 1] }));
+    let pooled = features.forward(&mut graph, x).unwrap();
+    let symbolic = graph
+        .check_symbolic(
+            pooled,
+            &[(
+                x,
+                vec![
+                    Dim::symbol("batch"),
+                    Dim::Fixed(1),
+                    Dim::Fixed(8),
+                    Dim::Fixed(8),
+                ],
+            )],
+        )
+        .unwrap();
+    assert_eq!(
+        symbolic,
+        vec![
+            Dim::symbol("batch"),
+            Dim::Fixed(4),
+            Dim::Fixed(1),
+            Dim::Fixed(1)
+        ]
+    );
+    assert_eq!(graph.forward(pooled).unwrap().shape(), &[2, 4, 1, 1]);
+
+    let total = graph.apply_op(SumOp { dim: None }, &[pooled]);
+    graph.backward(total).unwrap();
+    assert!(features
+        .parameters()
+        .iter()
+        .all(|&p| graph.get_gradient(p).is_some()));
+
+    let text = serialize::save_to_string(&graph).unwrap();
+    assert!(text.contains("stride=1,1 padding=1,1 dilation=1,1 groups=1"));
+    assert!(text.contains("MaxPool2dOp"));
+    let mut loaded = serialize::load_from_str(&text, &OpRegistry::new()).unwrap();
+    loaded.set_input(x, graph.forward(x).unwrap()).unwrap();
+    assert_eq!(
+        loaded.forward(total).unwrap(),
+        graph.forward(total).unwrap()
+    );
+}
@@ -0,0 +1,138 @@
+use neuroncore::losses::{
+    HuberLoss, HuberLossOp, L1LossOp, LogCoshLossOp, MSELoss, QuantileLoss, QuantileLossOp,
+    Reduction,
+};
+use neuroncore::shape::Dim;
+use neuroncore::{Graph, Op, Tensor};
+
+mod common;
+use common::{batched, check_gradients, round_trip};
+
+#[test]
+fn regression_losses_match_finite_differences() {
+    // Residuals kept away from the kinks of L1, Huber and pinball losses.
+    let predictions = Tensor::new(vec![0.3, -1.2, 2.5, 0.9, -0.4, 1.7], vec![2, 3]).unwrap();
+    let targets = Tensor::new(vec![0.0, 0.1, -0.5, 1.3, 0.6, 1.0], vec![2, 3]).unwrap();
+    let pair = [predictions.clone(), targets];
+    for reduction in [Reduction::Mean, Reduction::Sum, Reduction::None] {
+        check_gradients(&L1LossOp { reduction }, &pair, 2);
+        let huber = HuberLossOp {
+            delta: 0.5,
+            reduction,
+        };
+        check_gradients(&huber, &pair, 2);
+        check_gradients(&LogCoshLossOp { reduction }, &pair, 2);
+
+        // Two quantile levels per target.
+        let levels = Tensor::new(vec![0.1, 0.9], vec![2]).unwrap();
+        let centers = Tensor::new(vec![0.0, 0.5, -1.0], vec![3]).unwrap();
+        let quantile = [
+            Tensor::new(predictions.data().to_vec(), vec![3, 2]).unwrap(),
+            centers,
+            levels,
+        ];
+        check_gradients(&QuantileLossOp { reduction }, &quantile, 2);
+    }
+}
+
+#[test]
+fn robust_losses_bound_the_pull_of_outliers() {
+    let predictions = Tensor::new(vec![0.0, 0.5, 100.0], vec![3]).unwrap();
+    let targets = Tensor::zeros(vec![3]).unwrap();
+    let inputs = [predictions, targets];
+    let none = Reduction::None;
+
+    let l1 = L1LossOp { reduction: none }.forward(&inputs).unwrap();
+    assert_eq!(l1.data(), &[0.0, 0.5, 100.0]);
+    let huber = HuberLossOp {
+        delta: 1.0,
+        reduction: none,
+    };
+    assert_eq!(huber.forward(&inputs).unwrap().data(), &[0.0, 0.125, 99.5]);
+    let log_cosh = LogCoshLossOp { reduction: none }.forward(&inputs).unwrap();
+    assert!((log_cosh.data()[1] - 0.5f32.cosh().ln()).abs() < 1e-6);
+    // cosh(100) overflows f32; the stable form does not.
+    assert!((log_cosh.data()[2] - (100.0 - std::f32::consts::LN_2)).abs() < 1e-4);
+
+    // The outlier's gradient is capped at delta for Huber and 1 for log-cosh.
+    let ones = Tensor::ones(vec![3]).unwrap();
+    let grads = huber.backward(&inputs, &ones).unwrap();
+    assert_eq!(grads[0].data(), &[0.0, 0.5, 1.0]);
+    assert_eq!(grads[1].data(), &[0.0, -0.5, -1.0]);
+    let grads = LogCoshLossOp { reduction: none }
+        .backward(&inputs, &ones)
+        .unwrap();
+    assert!((grads[0].data()[2] - 1.0).abs() < 1e-6);
+
+    let mean = HuberLossOp::default().forward(&inputs).unwrap();
+    assert!((mean.data()[0] - 99.625 / 3.0).abs() < 1e-4);
+    assert!(HuberLossOp {
+        delta: 0.0,
+        reduction: none
+    }
+    .forward(&inputs)
+    .is_err());
+    let short = [
+        Tensor::zeros(vec![3]).unwrap(),
+        Tensor::zeros(vec![2]).unwrap(),
+    ];
+    assert!(L1LossOp::default().forward(&short).is_err());
+}
+
+#[test]
+fn quantile_loss_fits_intervals_and_round_trips() {
+    // Under-predicting the 0.9 quantile costs 0.9 per unit, over-predicting 0.1.
+    let op = QuantileLossOp {
+        reduction: Reduction::None,
+    };
+    let levels = Tensor::new(vec![0.1, 0.9], vec![2]).unwrap();
+    let predictions = Tensor::new(vec![2.0, 2.0, 0.0, 0.0], vec![2, 2]).unwrap();
+    let targets = Tensor::new(vec![1.0, 1.0], vec![2]).unwrap();
+    let loss = op
+        .forward(&[predictions, targets.clone(), levels.clone()])
+        .unwrap();
+    assert_eq!(loss.shape(), &[2, 2]);
+    let expected = [0.9, 0.1, 0.1, 0.9];
+    for (a, b) in loss.data().iter().zip(expected) {
+        assert!((a - b).abs() < 1e-6);
+    }
+    let bad = Tensor::new(vec![0.5, 1.0], vec![2]).unwrap();
+    let predictions = Tensor::zeros(vec![2, 2]).unwrap();
+    assert!(op.forward(&[predictions, targets, bad]).is_err());
+
+    // MSE no longer evaluates the graph while it is being built.
+    let mut graph = Graph::new();
+    let x = graph.add_input(Tensor::new(vec![1.0, 3.0], vec![2]).unwrap());
+    let y = graph.add_input(Tensor::zeros(vec![2]).unwrap());
+    let mse = MSELoss::compute(&mut graph, x, y).unwrap();
+    assert_eq!(graph.forward(mse).unwrap().data(), &[5.0]);
+    graph
+        .set_input(x, Tensor::new(vec![2.0, 2.0, 2.0], vec![3]).unwrap())
+        .unwrap();
+    graph.set_input(y, Tensor::zeros(vec![3]).unwrap()).unwrap();
+    assert_eq!(graph.forward(mse).unwrap().data(), &[4.0]);
+
+    let mut graph = Graph::new();
+    let forecast = graph.add_input(Tensor::random(vec![4, 3], 2).unwrap());
+    let wear = graph.add_input(Tensor::random(vec![4], 3).unwrap());
+    let pinball = QuantileLoss::compute(
+        &mut graph,
+        forecast,
+        wear,
+        &[0.1, 0.5, 0.9],
+        Reduction::Mean,
+    )
+    .unwrap();
+    let huber = HuberLoss::compute(&mut graph, wear, wear, 2.0, Reduction::Sum).unwrap();
+    let symbolic = graph
+        .check_symbolic(pinball, &[(forecast, batched(&[3])), (wear, batched(&[]))])
+        .unwrap();
+    assert_eq!(symbolic, vec![Dim::Fixed(1)]);
+    let value = graph.forward(pinball).unwrap().data()[0];
+
+    // The quantile levels are kept.
+    let (text, loaded) = round_trip(&graph, &[forecast, wear]);
+    assert!(text.contains("delta=2"));
+    assert_eq!(loaded.forward(pinball).unwrap().data()[0], value);
+    assert_eq!(loaded.forward(huber).unwrap().data(), &[0.0]);
+}
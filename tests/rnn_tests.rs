@@ -0,0 +1,137 @@
+use neuroncore::layers::Layer;
+use neuroncore::module::Module;
+use neuroncore::rnn::{Gru, Lstm, RnnConfig};
+use neuroncore::serialize;
+use neuroncore::timeseries::{windows_2d, windows_to_tensor};
+use neuroncore::{Graph, Tensor};
+
+mod common;
+use common::{batched, check_parameter_gradients};
+
+#[test]
+fn stacked_bidirectional_layers_have_expected_shapes_and_names() {
+    let series: Vec<Vec<f32>> = (0..12)
+        .map(|t| vec![(t as f32 * 0.3).sin(), (t as f32 * 0.2).cos()])
+        .collect();
+    let windows = windows_2d(&series, 5, 2).unwrap();
+    let batch = windows_to_tensor(&windows).unwrap();
+    assert_eq!(batch.shape(), &[4, 5, 2]);
+
+    let config = RnnConfig {
+        num_layers: 2,
+        bidirectional: true,
+        ..RnnConfig::new(2, 3)
+    };
+    let mut graph = Graph::new();
+    let x = graph.add_input(batch);
+    let lstm = Lstm::new(&mut graph, config, 1).unwrap();
+    let out = lstm.forward_with_state(&mut graph, x, None).unwrap();
+    assert_eq!(graph.forward(out.output).unwrap().shape(), &[4, 5, 6]);
+    assert_eq!(graph.forward(out.state).unwrap().shape(), &[4, 4, 6]);
+    assert_eq!(lstm.state_size(), 6);
+
+    let names: Vec<String> = lstm
+        .named_parameters()
+        .into_iter()
+        .map(|(n, _)| n)
+        .collect();
+    assert_eq!(names.len(), 16);
+    assert_eq!(
+        &names[..5],
+        [
+            "weight_ih_l0",
+            "weight_hh_l0",
+            "bias_ih_l0",
+            "bias_hh_l0",
+            "weight_ih_l0_reverse"
+        ]
+    );
+    // Layer 1 reads both directions of layer 0: [6, 4 * 3].
+    let w = lstm.named_parameters()[8].1;
+    assert_eq!(graph.parameter(w).unwrap().shape(), &[6, 12]);
+
+    // The zero initial state saves; the scanned cells are opaque.
+    let err = serialize::save_to_string(&graph).unwrap_err();
+    assert!(err.to_string().contains("(ScanOp) is opaque"), "{err}");
+
+    let gru = Gru::new(&mut graph, RnnConfig::new(2, 4), 2).unwrap();
+    let hidden = gru.forward(&mut graph, x).unwrap();
+    let symbolic = graph
+        .check_symbolic(hidden, &[(x, batched(&[5, 2]))])
+        .unwrap();
+    assert_eq!(symbolic, batched(&[5, 4]));
+    assert_eq!(
+        gru.num_parameters(&graph).unwrap(),
+        2 * 12 + 4 * 12 + 2 * 12
+    );
+}
+
+#[test]
+fn lstm_and_gru_gradients_match_finite_differences() {
+    let config = RnnConfig {
+        bidirectional: true,
+        ..RnnConfig::new(2, 2)
+    };
+
+    let mut graph = Graph::new();
+    let x = graph.add_input(Tensor::random(vec![2, 3, 2], 5).unwrap());
+    let lstm = Lstm::new(&mut graph, config, 6).unwrap();
+    let out = lstm.forward_with_state(&mut graph, x, None).unwrap();
+    check_parameter_gradients(&mut graph, &lstm, out.output);
+
+    let mut graph = Graph::new();
+    let x = graph.add_input(Tensor::random(vec![2, 3, 2], 7).unwrap());
+    let gru = Gru::new(
+        &mut graph,
+        RnnConfig {
+            num_layers: 2,
+            ..config
+        },
+        8,
+    )
+    .unwrap();
+    let out = gru.forward_with_state(&mut graph, x, None).unwrap();
+    check_parameter_gradients(&mut graph, &gru, out.state);
+}
+
+#[test]
+fn carried_state_streams_consecutive_windows() {
+    let config = RnnConfig {
+        num_layers: 2,
+        ..RnnConfig::new(1, 3)
+    };
+    let sequence = Tensor::random(vec![1, 8, 1], 9).unwrap();
+    let halves = [
+        Tensor::new(sequence.data()[..4].to_vec(), vec![1, 4, 1]).unwrap(),
+        Tensor::new(sequence.data()[4..].to_vec(), vec![1, 4, 1]).unwrap(),
+    ];
+
+    // One pass over all eight steps.
+    let mut full = Graph::new();
+    let x = full.add_input(sequence);
+    let lstm = Lstm::new(&mut full, config, 10).unwrap();
+    let whole = lstm.forward_with_state(&mut full, x, None).unwrap();
+    let expected_state = full.forward(whole.state).unwrap();
+    let expected_last = full.forward(whole.output).unwrap().select(1, 7).unwrap();
+
+    // Two windows of four, carrying the state between them.
+    let mut stream = Graph::new();
+    let window = stream.add_input(halves[0].clone());
+    let streaming = Lstm::new(&mut stream, config, 10).unwrap();
+    let state = stream.add_input(streaming.initial_state(1).unwrap());
+    let step = streaming
+        .forward_with_state(&mut stream, window, Some(state))
+        .unwrap();
+    let carried = stream.forward(step.state).unwrap();
+    stream.set_input(state, carried).unwrap();
+    stream.set_input(window, halves[1].clone()).unwrap();
+
+    let state = stream.forward(step.state).unwrap();
+    let last = stream.forward(step.output).unwrap().select(1, 3).unwrap();
+    for (a, b) in state.data().iter().zip(expected_state.data()) {
+        assert!((a - b).abs() < 1e-6);
+    }
+    for (a, b) in last.data().iter().zip(expected_last.data()) {
+        assert!((a - b).abs() < 1e-6);
+    }
+}
@@ -0,0 +1,87 @@
+use neuroncore::layers::{Layer, Linear, Relu, Sequential, Sigmoid, Tanh};
+use neuroncore::losses::MSELoss;
+use neuroncore::module::Module;
+use neuroncore::ops::{SigmoidOp, SumOp, TanhOp};
+use neuroncore::optim::{Optimizer, SGD};
+use neuroncore::{Graph, Op, Tensor};
+
+#[test]
+fn sequential_aggregates_named_parameters() {
+    let mut graph = Graph::new();
+    let mlp = Sequential::new()
+        .with(Linear::new(&mut graph, 2, 3, 1).unwrap())
+        .with(Tanh)
+        .with(Linear::new(&mut graph, 3, 1, 2).unwrap())
+        .with(Sigmoid);
+    assert_eq!(mlp.len(), 4);
+    assert_eq!(mlp.parameters(), vec![0, 1, 2, 3]);
+    let names: Vec<String> = mlp.named_parameters().into_iter().map(|(n, _)| n).collect();
+    assert_eq!(names, ["0.weight", "0.bias", "2.weight", "2.bias"]);
+    assert_eq!(mlp.num_parameters(&graph).unwrap(), 6 + 3 + 3 + 1);
+
+    let x = graph.add_input(Tensor::new(vec![0.5, -0.5], vec![1, 2]).unwrap());
+    let out = mlp.forward(&mut graph, x).unwrap();
+    let y = graph.forward(out).unwrap();
+    assert_eq!(y.shape(), &[1, 1]);
+    assert!(y.data()[0] > 0.0 && y.data()[0] < 1.0);
+}
+
+#[test]
+fn batched_training_reduces_bias_gradients_to_bias_shape() {
+    let mut graph = Graph::new();
+    let x = graph
+        .add_input(Tensor::new(vec![0.0, 0.0, 0.0, 1.0, 1.0, 0.0, 1.0, 1.0], vec![4, 2]).unwrap());
+    let y = graph.add_input(Tensor::new(vec![0.0, 1.0, 1.0, 2.0], vec![4, 1]).unwrap());
+    let mlp = Sequential::new()
+        .with(Linear::new(&mut graph, 2, 8, 3).unwrap())
+        .with(Relu)
+        .with(Linear::new(&mut graph, 8, 1, 4).unwrap());
+    let out = mlp.forward(&mut graph, x).unwrap();
+    let loss = MSELoss::compute(&mut graph, out, y).unwrap();
+
+    let mut opt = SGD::new(mlp.parameters(), 0.05, None);
+    let initial = graph.forward(loss).unwrap().data()[0];
+    for _ in 0..200 {
+        opt.zero_grad(&mut graph);
+        graph.backward(loss).unwrap();
+        for (name, idx) in mlp.named_parameters() {
+            let grad = graph.get_gradient(idx).unwrap();
+            assert_eq!(
+                grad.shape(),
+                graph.parameter(idx).unwrap().shape(),
+                "{name}"
+            );
+        }
+        opt.step(&mut graph).unwrap();
+    }
+    let trained = graph.forward(loss).unwrap().data()[0];
+    assert!(trained < initial * 0.1, "{initial} -> {trained}");
+}
+
+#[test]
+fn sigmoid_and_tanh_gradients_match_finite_differences() {
+    let x = Tensor::new(vec![-3.0, -0.5, 0.0, 0.7, 4.0], vec![5]).unwrap();
+    let ops: [&dyn Op; 2] = [&SigmoidOp, &TanhOp];
+    for op in ops {
+        let ones = Tensor::ones_like(&x);
+        let grad = op.backward(std::slice::from_ref(&x), &ones).unwrap();
+        for i in 0..5 {
+            let eps = 1e-3;
+            let mut hi = x.clone();
+            hi.data_mut()[i] += eps;
+            let mut lo = x.clone();
+            lo.data_mut()[i] -= eps;
+            let f = |t: Tensor| op.forward(&[t]).unwrap().data()[i];
+            let numeric = (f(hi) - f(lo)) / (2.0 * eps);
+            assert!((grad[0].data()[i] - numeric).abs() < 1e-3, "{}", op.name());
+        }
+    }
+
+    // Large inputs saturate without overflowing to NaN.
+    let extreme = Tensor::new(vec![-100.0, 100.0], vec![2]).unwrap();
+    let mut graph = Graph::new();
+    let a = graph.add_input(extreme);
+    let s = graph.apply_op(SigmoidOp, &[a]);
+    let total = graph.apply_op(SumOp { dim: None }, &[s]);
+    assert_eq!(graph.forward(total).unwrap().data(), &[1.0]);
+}
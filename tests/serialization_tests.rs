@@ -0,0 +1,175 @@
+use neuroncore::custom::FnOp;
+use neuroncore::dropout::Dropout;
+use neuroncore::layers::{Layer, Linear};
+use neuroncore::norm::BatchNorm1d;
+use neuroncore::numeric::NumericPolicy;
+use neuroncore::ops::{ReluOp, SumOp};
+use neuroncore::serialize::{self, OpRegistry};
+use neuroncore::{ComputeError, Graph, Tensor};
+
+fn mlp() -> (Graph, usize, usize) {
+    let mut graph = Graph::new();
+    let x = graph.add_input(Tensor::new(vec![0.5, -1.0, 2.0], vec![1, 3]).unwrap());
+    let l1 = Linear::new(&mut graph, 3, 4, 7).unwrap();
+    let l2 = Linear::new(&mut graph, 4, 2, 11).unwrap();
+    let h = l1.forward(&mut graph, x).unwrap();
+    let h = graph.apply_op(ReluOp, &[h]);
+    let out = l2.forward(&mut graph, h).unwrap();
+    let total = graph.apply_op(SumOp { dim: Some(1) }, &[out]);
+    graph.set_label(total, "row total");
+    (graph, x, total)
+}
+
+#[test]
+fn round_trip_preserves_parameters_ops_and_labels() {
+    let (graph, x, total) = mlp();
+    let text = serialize::save_to_string(&graph).unwrap();
+    assert!(text.starts_with("neuroncore-graph 2\n"));
+    assert!(text.contains("op SumOp"));
+    assert!(text.contains("dim=Some(1)"));
+
+    let mut loaded = serialize::load_from_str(&text, &OpRegistry::new()).unwrap();
+    assert_eq!(loaded.len(), graph.len());
+    assert_eq!(loaded.label(total), Some("row total"));
+
+    // Inputs are placeholders: feed the original value before comparing outputs.
+    assert!(loaded.forward(x).unwrap().data().iter().all(|v| *v == 0.0));
+    loaded.set_input(x, graph.forward(x).unwrap()).unwrap();
+    assert_eq!(
+        loaded.forward(total).unwrap().data(),
+        graph.forward(total).unwrap().data()
+    );
+    assert_eq!(serialize::save_to_string(&loaded).unwrap(), text);
+}
+
+#[test]
+fn unknown_ops_and_versions_are_parse_errors() {
+    let (graph, _, _) = mlp();
+    let text = serialize::save_to_string(&graph).unwrap();
+
+    let err = serialize::load_from_str(&text, &OpRegistry::empty())
+        .err()
+        .expect("MatMulOp is not registered");
+    match err {
+        ComputeError::Parse { context, message } => {
+            assert!(context.starts_with("graph line "));
+            assert!(message.contains("MatMulOp"));
+        }
+        other => panic!("unexpected error {other:?}"),
+    }
+
+    let future = text.replacen("neuroncore-graph 2", "neuroncore-graph 99", 1);
+    let err = serialize::load_from_str(&future, &OpRegistry::new())
+        .err()
+        .expect("version 99 is unsupported");
+    assert!(err.to_string().contains("version 99"));
+}
+
+#[test]
+fn custom_ops_load_through_registered_constructors() {
+    let mut graph = Graph::new();
+    let x = graph.add_input(Tensor::new(vec![1.0, 2.0], vec![2]).unwrap());
+    let y = graph.apply_op(
+        FnOp::new("Double", 1, |i| i[0].add(&i[0]), |_, g| Ok(vec![g.add(g)?])),
+        &[x],
+    );
+
+    // Closure ops are opaque and refuse to be saved.
+    let err = serialize::save_to_string(&graph).unwrap_err();
+    assert!(err.to_string().contains("opaque"));
+
+    let mut graph = Graph::new();
+    let x = graph.add_input(Tensor::new(vec![1.0, 2.0], vec![2]).unwrap());
+    let y2 = graph.apply_op(ReluOp, &[x]);
+    assert_eq!(y, y2);
+    let text = serialize::save_to_string(&graph)
+        .unwrap()
+        .replace("ReluOp", "Double");
+
+    let mut registry = OpRegistry::new();
+    registry.register("Double", |_| {
+        Ok(Box::new(FnOp::new(
+            "Double",
+            1,
+            |i| i[0].add(&i[0]),
+            |_, g| Ok(vec![g.add(g)?]),
+        )))
+    });
+    let mut loaded = serialize::load_from_str(&text, &registry).unwrap();
+    loaded
+        .set_input(x, Tensor::new(vec![1.0, 2.0], vec![2]).unwrap())
+        .unwrap();
+    assert_eq!(loaded.forward(y).unwrap().data(), &[2.0, 4.0]);
+}
+
+#[test]
+fn graph_state_round_trips_and_bad_references_fail() {
+    let mut graph = Graph::new();
+    let x = graph.add_input(Tensor::random(vec![4, 3], 1).unwrap());
+    let bn = BatchNorm1d::new(&mut graph, 3).unwrap();
+    let h = bn.forward(&mut graph, x).unwrap();
+    let dropout = Dropout::new(&mut graph, 0.5).unwrap();
+    let start = graph.len();
+    let y = dropout.forward(&mut graph, h).unwrap();
+    graph.checkpoint(start..graph.len());
+    graph.set_seed(42);
+
+    let text = serialize::save_to_string(&graph).unwrap();
+    let mut loaded = serialize::load_from_str(&text, &OpRegistry::new()).unwrap();
+    assert_eq!(loaded.buffer_updates(), graph.buffer_updates());
+    assert_eq!(loaded.checkpoints(), graph.checkpoints());
+    assert_eq!(serialize::save_to_string(&loaded).unwrap(), text);
+
+    // Seeds keep advancing in step with the original graph.
+    loaded.set_input(x, graph.forward(x).unwrap()).unwrap();
+    graph.advance_seeds();
+    loaded.advance_seeds();
+    assert_eq!(loaded.forward(y).unwrap(), graph.forward(y).unwrap());
+    assert_eq!(loaded.update_buffers().unwrap(), 2);
+
+    // Version 1 files, without graph state records, still load.
+    let (plain, _, total) = mlp();
+    let v1 = serialize::save_to_string(&plain).unwrap().replacen(
+        "neuroncore-graph 2",
+        "neuroncore-graph 1",
+        1,
+    );
+    let loaded = serialize::load_from_str(&v1, &OpRegistry::new()).unwrap();
+    assert_eq!(loaded.label(total), Some("row total"));
+
+    for bad in ["label 99 oops", "buffer 0 1", "seed 99", "checkpoint 3 99"] {
+        let corrupt = format!("{text}{bad}\n");
+        match serialize::load_from_str(&corrupt, &OpRegistry::new()) {
+            Err(ComputeError::Parse { .. }) => {}
+            other => panic!("{bad}: expected a parse error, got {:?}", other.err()),
+        }
+    }
+}
+
+#[test]
+fn numeric_policy_and_anomaly_detection_round_trip() {
+    let (mut graph, _, total) = mlp();
+    let text = serialize::save_to_string(&graph).unwrap();
+    assert!(!text.contains("policy") && !text.contains("detect_anomalies"));
+
+    for policy in [
+        NumericPolicy::Error,
+        NumericPolicy::Nan,
+        NumericPolicy::Epsilon(1e-6),
+    ] {
+        graph.set_numeric_policy(policy);
+        graph.set_detect_anomalies(true);
+        let text = serialize::save_to_string(&graph).unwrap();
+        let loaded = serialize::load_from_str(&text, &OpRegistry::new()).unwrap();
+        assert_eq!(loaded.numeric_policy(), policy);
+        assert!(loaded.detect_anomalies());
+        assert_eq!(loaded.label(total), Some("row total"));
+    }
+
+    let (header, nodes) = text.split_once("\ninput").unwrap();
+    let bad = format!("{header}\npolicy lenient\ninput{nodes}");
+    match serialize::load_from_str(&bad, &OpRegistry::new()) {
+        Err(ComputeError::Parse { message, .. }) => assert!(message.contains("lenient")),
+        other => panic!("expected a parse error, got {:?}", other.err()),
+    }
+}
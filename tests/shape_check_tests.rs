@@ -0,0 +1,59 @@
+use neuroncore::layers::{Layer, Linear};
+use neuroncore::ops::{AddOp, MatMulOp, ReluOp, SumOp};
+use neuroncore::shape::Dim;
+use neuroncore::{ComputeError, Graph, Tensor};
+
+#[test]
+fn check_infers_output_shape_without_running() {
+    let mut g = Graph::new();
+    let x = g.add_input(Tensor::zeros(vec![4, 3]).unwrap());
+    let layer = Linear::new(&mut g, 3, 5, 1).unwrap();
+    let h = layer.forward(&mut g, x).unwrap();
+    let h = g.apply_op(ReluOp, &[h]);
+    let out = g.apply_op(SumOp { dim: Some(1) }, &[h]);
+
+    assert_eq!(g.check(out).unwrap(), vec![Dim::Fixed(4), Dim::Fixed(1)]);
+}
+
+#[test]
+fn check_reports_first_inconsistent_node() {
+    let mut g = Graph::new();
+    let a = g.add_input(Tensor::zeros(vec![2, 3]).unwrap());
+    let b = g.add_input(Tensor::zeros(vec![4, 1]).unwrap());
+    let bad = g.apply_op(MatMulOp, &[a, b]);
+    let out = g.apply_op(ReluOp, &[bad]);
+
+    let err = g.check(out).unwrap_err();
+    let message = err.to_string();
+    assert!(matches!(
+        err.root_cause(),
+        ComputeError::DimensionError { .. }
+    ));
+    assert_eq!(err.node_context().map(|c| c.node), Some(bad));
+    assert!(message.contains(&format!("node {bad}")), "{message}");
+    assert!(message.contains("MatMulOp"), "{message}");
+    assert!(message.contains("[2, 3], [4, 1]"), "{message}");
+}
+
+#[test]
+fn check_propagates_symbolic_batch_dimension() {
+    let mut g = Graph::new();
+    let x = g.add_input(Tensor::zeros(vec![1, 3]).unwrap());
+    let layer = Linear::new(&mut g, 3, 2, 1).unwrap();
+    let out = layer.forward(&mut g, x).unwrap();
+
+    let batch = vec![Dim::symbol("batch"), Dim::Fixed(3)];
+    let shape = g.check_symbolic(out, &[(x, batch)]).unwrap();
+    assert_eq!(shape, vec![Dim::symbol("batch"), Dim::Fixed(2)]);
+}
+
+#[test]
+fn check_rejects_unrelated_symbols() {
+    let mut g = Graph::new();
+    let a = g.add_input(Tensor::zeros(vec![2]).unwrap());
+    let b = g.add_input(Tensor::zeros(vec![2]).unwrap());
+    let out = g.apply_op(AddOp, &[a, b]);
+
+    let overrides = [(a, vec![Dim::symbol("n")]), (b, vec![Dim::symbol("m")])];
+    assert!(g.check_symbolic(out, &overrides).is_err());
+}
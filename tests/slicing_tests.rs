@@ -0,0 +1,103 @@
+use neuroncore::shape::Dim;
+use neuroncore::slicing::{ConcatOp, FlipOp, NarrowOp, SelectOp, StackOp};
+use neuroncore::{Op, Tensor};
+
+fn arange(shape: Vec<usize>) -> Tensor {
+    let n = shape.iter().product::<usize>();
+    Tensor::new((0..n).map(|i| i as f32).collect(), shape).unwrap()
+}
+
+#[test]
+fn slicing_ops_round_trip_values_and_gradients() {
+    let x = arange(vec![2, 3, 2]);
+    let narrow = NarrowOp {
+        axis: 1,
+        start: 1,
+        len: 2,
+    };
+    let y = narrow.forward(std::slice::from_ref(&x)).unwrap();
+    assert_eq!(y.data(), &[2.0, 3.0, 4.0, 5.0, 8.0, 9.0, 10.0, 11.0]);
+    let g = narrow.backward(std::slice::from_ref(&x), &y).unwrap();
+    assert_eq!(
+        g[0].data(),
+        &[0.0, 0.0, 2.0, 3.0, 4.0, 5.0, 0.0, 0.0, 8.0, 9.0, 10.0, 11.0]
+    );
+
+    // Narrowing both halves and concatenating them restores the input.
+    let head = NarrowOp {
+        axis: 2,
+        start: 0,
+        len: 1,
+    };
+    let tail = NarrowOp {
+        axis: 2,
+        start: 1,
+        len: 1,
+    };
+    let parts = [
+        head.forward(std::slice::from_ref(&x)).unwrap(),
+        tail.forward(std::slice::from_ref(&x)).unwrap(),
+    ];
+    let concat = ConcatOp { axis: 2 };
+    assert_eq!(concat.forward(&parts).unwrap(), x);
+    let grads = concat.backward(&parts, &x).unwrap();
+    assert_eq!(grads[0], parts[0]);
+    assert_eq!(grads[1], parts[1]);
+
+    let last = SelectOp { axis: 1, index: -1 };
+    let y = last.forward(std::slice::from_ref(&x)).unwrap();
+    assert_eq!(y.data(), &[4.0, 5.0, 10.0, 11.0]);
+    assert!(SelectOp { axis: 1, index: -4 }
+        .forward(std::slice::from_ref(&x))
+        .is_err());
+    let stacked = StackOp { axis: 1 }
+        .forward(&[y.clone(), y.clone()])
+        .unwrap();
+    assert_eq!(stacked.shape(), &[2, 2, 2]);
+
+    let flip = FlipOp { axis: 1 };
+    let y = flip.forward(std::slice::from_ref(&x)).unwrap();
+    assert_eq!(&y.data()[..6], &[4.0, 5.0, 2.0, 3.0, 0.0, 1.0]);
+    assert_eq!(flip.backward(std::slice::from_ref(&x), &y).unwrap()[0], x);
+}
+
+#[test]
+fn slicing_ops_infer_symbolic_shapes() {
+    let batch = Dim::symbol("batch");
+    let x = vec![batch.clone(), Dim::Fixed(5), Dim::Fixed(8)];
+    let narrow = NarrowOp {
+        axis: 2,
+        start: 4,
+        len: 4,
+    };
+    assert_eq!(
+        narrow.infer_shape(std::slice::from_ref(&x)).unwrap(),
+        vec![batch.clone(), Dim::Fixed(5), Dim::Fixed(4)]
+    );
+    assert!(NarrowOp {
+        axis: 2,
+        start: 6,
+        len: 4
+    }
+    .infer_shape(std::slice::from_ref(&x))
+    .is_err());
+    assert_eq!(
+        ConcatOp { axis: 2 }
+            .infer_shape(&[x.clone(), x.clone()])
+            .unwrap(),
+        vec![batch.clone(), Dim::Fixed(5), Dim::Fixed(16)]
+    );
+    assert!(ConcatOp { axis: 0 }
+        .infer_shape(&[x.clone(), x.clone()])
+        .is_err());
+    assert_eq!(
+        SelectOp { axis: 1, index: -1 }
+            .infer_shape(std::slice::from_ref(&x))
+            .unwrap(),
+        vec![batch.clone(), Dim::Fixed(8)]
+    );
+    assert_eq!(
+        StackOp { axis: 0 }.infer_shape(&[x.clone(), x]).unwrap(),
+        vec![Dim::Fixed(2), batch, Dim::Fixed(5), Dim::Fixed(8)]
+    );
+}